@@ -17,7 +17,23 @@ use std::cmp::Ordering;
 use std::collections::{BTreeMap, BTreeSet, VecDeque};
 
 use crate::catalogue::pattern::{Adjacency, Pattern};
-use crate::catalogue::{DynIter, PatternId, PatternLabelId};
+use crate::catalogue::{DynIter, PatternDirection, PatternId, PatternLabelId};
+
+/// The algorithm to refine the vertex groups in vertex grouping
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum GroupingAlgo {
+    /// Compare every pair of vertices in a group, which is O(n^2) in the size of the group
+    #[default]
+    Pairwise,
+    /// 1-dimensional Weisfeiler-Lehman color refinement, which sorts the signatures of the vertices in a
+    /// group instead, and yields the same groups as `Pairwise`
+    WeisfeilerLehman,
+}
+
+/// The signature of a vertex in Weisfeiler-Lehman color refinement:
+/// its degrees and the sorted adjacency info tuples with the groups of the adjacent vertices
+type VertexSignature =
+    ((usize, usize, usize), Vec<((bool, PatternDirection, PatternLabelId, PatternLabelId), PatternId)>);
 
 #[derive(Debug, Clone)]
 pub(crate) struct CanonicalLabelManager {
@@ -40,6 +56,20 @@ pub(crate) struct CanonicalLabelManager {
     vertex_groups: BTreeMap<(PatternLabelId, PatternId), Vec<PatternId>>,
     /// Indicates whether vertex grouping has been stable currently. If so, we say the vertices have been well grouped.
     has_converged: bool,
+    /// Vertices whose adjacent vertices changed their groups in the last round of refinement
+    /// - None means all the vertices, as in the first round
+    ///
+    /// A group without such vertices cannot be split in the next round, so it is left untouched
+    dirty_vertices: Option<BTreeSet<PatternId>>,
+    /// Map for Vertex Representatives
+    /// - Key: Vertex ID
+    /// - Value: ID of the vertex representing the given vertex in vertex grouping
+    ///
+    /// Vertices known to be equivalent share a representative, and only the representatives are compared.
+    /// A vertex absent from the map represents itself.
+    vertex_representative_map: BTreeMap<PatternId, PatternId>,
+    /// The number of vertices represented by each representative, which is 1 if absent
+    represented_vertices_nums: BTreeMap<PatternId, usize>,
     /// Map for Vertex Ranking
     /// - Key: Vertex ID
     /// - Value: Rank of the given vertex
@@ -49,10 +79,18 @@ pub(crate) struct CanonicalLabelManager {
     /// - Value: Rank of the given edge
     /// Edge ranks are used for the order of pattern encoding.
     vertex_rank_map: BTreeMap<PatternId, Option<PatternId>>,
+    /// The algorithm to refine the vertex groups
+    grouping_algo: GroupingAlgo,
 }
 
 impl From<&Pattern> for CanonicalLabelManager {
     fn from(pattern: &Pattern) -> Self {
+        CanonicalLabelManager::new(pattern, GroupingAlgo::default())
+    }
+}
+
+impl CanonicalLabelManager {
+    pub fn new(pattern: &Pattern, grouping_algo: GroupingAlgo) -> Self {
         // Initialize the map from vertex ID to its adjacencies list.
         // Filling the data into the map is delayed after the manager is initialized since the cmp_adjacencies method is needed.
         let mut vertex_adjacencies_map: BTreeMap<PatternId, Vec<Adjacency>> = BTreeMap::new();
@@ -96,8 +134,12 @@ impl From<&Pattern> for CanonicalLabelManager {
             vertex_group_map,
             vertex_groups,
             has_converged,
+            dirty_vertices: None,
+            vertex_representative_map: BTreeMap::new(),
+            represented_vertices_nums: BTreeMap::new(),
             edge_rank_map,
             vertex_rank_map,
+            grouping_algo,
         };
         // Sort the adjacencies for each vertex and fill the data into the vertex adjacency map
         manager.update_vertex_adjacencies_order();
@@ -143,10 +185,128 @@ impl CanonicalLabelManager {
     pub fn get_vertex_rank(&self, vertex_id: PatternId) -> Option<PatternId> {
         *self.vertex_rank_map.get(&vertex_id).unwrap()
     }
+
+    /// Given vertex ID, return its (out degree, in degree, undirected degree)
+    ///
+    /// Undirected edges are not counted in out/in degree
+    fn get_vertex_degrees(&self, vertex_id: PatternId) -> (usize, usize, usize) {
+        let mut degrees = (0, 0, 0);
+        for adjacency in self
+            .vertex_adjacencies_map
+            .get(&vertex_id)
+            .expect("Invalid Vertex ID")
+        {
+            if adjacency.is_bidirectional() {
+                degrees.2 += 1;
+            } else if let PatternDirection::Out = adjacency.get_direction() {
+                degrees.0 += 1;
+            } else {
+                degrees.1 += 1;
+            }
+        }
+        degrees
+    }
 }
 
 /// Methods for Vertex Grouping
 impl CanonicalLabelManager {
+    /// Seed the vertex grouping with the converged groups of a sub-pattern, e.g., the pattern before it is
+    /// extended, whose vertices keep their IDs and labels in the given pattern.
+    ///
+    /// The vertices of the sub-pattern start from their groups in it, and every other vertex starts from a
+    /// new group on its own. Only the groups with vertices whose adjacencies changed are refined at first,
+    /// which converges to groups finer than (or the same as) the ones grouped from scratch. Vertices sharing
+    /// a seeded group therefore share a group in every round of grouping from scratch, so each seeded group
+    /// is represented by one of its vertices there, and the resulting groups stay the same.
+    ///
+    /// Nothing is seeded if some vertex of the sub-pattern is not in the pattern or has not been grouped.
+    pub fn seed_vertex_groups(&mut self, pattern: &Pattern, sub_pattern: &Pattern) {
+        let is_sub_pattern_grouped = sub_pattern.vertices_iter().all(|sub_vertex| {
+            let v_id = sub_vertex.get_id();
+            pattern
+                .get_vertex(v_id)
+                .map(|vertex| vertex.get_label() == sub_vertex.get_label())
+                .unwrap_or(false)
+                && sub_pattern.get_vertex_group(v_id).is_some()
+        });
+        if !is_sub_pattern_grouped {
+            return;
+        }
+        let mut vertex_group_map: BTreeMap<PatternId, PatternId> = BTreeMap::new();
+        let mut vertex_groups: BTreeMap<(PatternLabelId, PatternId), Vec<PatternId>> = BTreeMap::new();
+        let mut dirty_vertices: BTreeSet<PatternId> = BTreeSet::new();
+        // The groups of a label range in [0, number of vertices with the label), so new groups start after
+        let mut next_free_groups: BTreeMap<PatternLabelId, PatternId> = BTreeMap::new();
+        for vertex in sub_pattern.vertices_iter() {
+            *next_free_groups
+                .entry(vertex.get_label())
+                .or_insert(0) += 1;
+        }
+        for vertex in pattern.vertices_iter() {
+            let (v_id, v_label) = (vertex.get_id(), vertex.get_label());
+            let v_group = match sub_pattern.get_vertex_group(v_id) {
+                Some(v_group) => {
+                    if pattern.get_vertex_degree(v_id) != sub_pattern.get_vertex_degree(v_id) {
+                        dirty_vertices.insert(v_id);
+                    }
+                    v_group
+                }
+                None => {
+                    dirty_vertices.insert(v_id);
+                    let next_free_group = next_free_groups.entry(v_label).or_insert(0);
+                    *next_free_group += 1;
+                    *next_free_group - 1
+                }
+            };
+            vertex_group_map.insert(v_id, v_group);
+            vertex_groups
+                .entry((v_label, v_group))
+                .and_modify(|vertices| vertices.push(v_id))
+                .or_insert(vec![v_id]);
+        }
+
+        let mut seeded_manager = self.clone();
+        seeded_manager.vertex_representative_map.clear();
+        seeded_manager.represented_vertices_nums.clear();
+        seeded_manager.vertex_group_map = vertex_group_map;
+        seeded_manager.vertex_groups = vertex_groups;
+        seeded_manager.has_converged = false;
+        seeded_manager.dirty_vertices = Some(dirty_vertices);
+        seeded_manager.update_vertex_adjacencies_order();
+        while !seeded_manager.has_converged {
+            seeded_manager.refine_vertex_groups(pattern);
+        }
+
+        // Take the vertex with the smallest ID in each seeded group as the representative
+        self.vertex_representative_map.clear();
+        self.represented_vertices_nums.clear();
+        for vertex_group in seeded_manager.vertex_groups.values() {
+            let representative = *vertex_group.iter().min().unwrap();
+            for &v_id in vertex_group.iter() {
+                self.vertex_representative_map
+                    .insert(v_id, representative);
+            }
+            self.represented_vertices_nums
+                .insert(representative, vertex_group.len());
+        }
+    }
+
+    /// Given vertex ID, return the ID of the vertex representing it in vertex grouping
+    fn get_vertex_representative(&self, vertex_id: PatternId) -> PatternId {
+        self.vertex_representative_map
+            .get(&vertex_id)
+            .cloned()
+            .unwrap_or(vertex_id)
+    }
+
+    /// Given the ID of a representative, return the number of vertices it represents
+    fn get_represented_vertices_num(&self, representative: PatternId) -> usize {
+        self.represented_vertices_nums
+            .get(&representative)
+            .cloned()
+            .unwrap_or(1)
+    }
+
     /// Group vertices that are identical in graph structure together.
     ///
     /// The idea of vertex groups is very similar to the ordered partition in canonical labeling.
@@ -154,7 +314,10 @@ impl CanonicalLabelManager {
     /// Basic Idea: All vertices with the same label are initially in the same group, and iteratively refine the groups with updated grouping information until the grouping is stable.
     pub fn vertex_grouping(&mut self, pattern: &Pattern) {
         while !self.has_converged {
-            self.refine_vertex_groups(pattern);
+            match self.grouping_algo {
+                GroupingAlgo::Pairwise => self.refine_vertex_groups(pattern),
+                GroupingAlgo::WeisfeilerLehman => self.refine_vertex_groups_by_colors(),
+            }
         }
     }
 
@@ -167,24 +330,135 @@ impl CanonicalLabelManager {
             BTreeMap::new();
         let mut has_converged = true;
         for (&(v_label, initial_group), vertex_group) in self.vertex_groups.iter() {
-            // Temporarily record the group for each vertex
-            let mut vertex_group_tmp_vec: Vec<PatternId> = vec![initial_group; vertex_group.len()];
+            // The vertices in the group have been equal in the last round, and so are they in this round
+            // if none of their adjacent vertices has changed its group
+            if !self.is_vertex_group_dirty(vertex_group) {
+                for &v_id in vertex_group.iter() {
+                    updated_vertex_group_map.insert(v_id, initial_group);
+                }
+                updated_vertex_groups.insert((v_label, initial_group), vertex_group.clone());
+                continue;
+            }
+            // Only the representatives are compared, each counting for all the vertices it represents
+            let representatives: Vec<PatternId> = vertex_group
+                .iter()
+                .cloned()
+                .filter(|&v_id| self.get_vertex_representative(v_id) == v_id)
+                .collect();
+            // Temporarily record the group for each representative
+            let mut representative_group_tmp_vec: Vec<PatternId> =
+                vec![initial_group; representatives.len()];
             // To find out the exact group of a vertex, compare it with all vertices with the same label
-            for i in 0..vertex_group.len() {
-                let current_v_id: PatternId = vertex_group[i];
-                for j in (i + 1)..vertex_group.len() {
-                    match self.cmp_vertices(pattern, current_v_id, vertex_group[j]) {
-                        Ordering::Greater => vertex_group_tmp_vec[i] += 1,
-                        Ordering::Less => vertex_group_tmp_vec[j] += 1,
+            for i in 0..representatives.len() {
+                for j in (i + 1)..representatives.len() {
+                    match self.cmp_vertices(pattern, representatives[i], representatives[j]) {
+                        Ordering::Greater => {
+                            representative_group_tmp_vec[i] +=
+                                self.get_represented_vertices_num(representatives[j])
+                        }
+                        Ordering::Less => {
+                            representative_group_tmp_vec[j] +=
+                                self.get_represented_vertices_num(representatives[i])
+                        }
                         Ordering::Equal => (),
                     }
                 }
+            }
+            let representative_groups: BTreeMap<PatternId, PatternId> = representatives
+                .into_iter()
+                .zip(representative_group_tmp_vec.into_iter())
+                .collect();
+
+            for &current_v_id in vertex_group.iter() {
+                let v_group: PatternId =
+                    representative_groups[&self.get_vertex_representative(current_v_id)];
+                if v_group != initial_group {
+                    has_converged = false;
+                }
+
+                updated_vertex_group_map.insert(current_v_id, v_group);
+                updated_vertex_groups
+                    .entry((v_label, v_group))
+                    .and_modify(|vertex_group| vertex_group.push(current_v_id))
+                    .or_insert(vec![current_v_id]);
+            }
+        }
+
+        // Update vertex group manager
+        self.dirty_vertices = Some(self.get_dirty_vertices(&updated_vertex_group_map));
+        self.vertex_group_map = updated_vertex_group_map;
+        self.vertex_groups = updated_vertex_groups;
+        self.has_converged = has_converged;
+
+        // Update the order of vertex adjacencies
+        self.update_vertex_adjacencies_order();
+    }
+
+    /// Whether any vertex in the group is dirty, i.e., the group may be split in this round
+    fn is_vertex_group_dirty(&self, vertex_group: &[PatternId]) -> bool {
+        match &self.dirty_vertices {
+            Some(dirty_vertices) => vertex_group
+                .iter()
+                .any(|v_id| dirty_vertices.contains(v_id)),
+            None => true,
+        }
+    }
+
+    /// Given the updated vertex groups, return the vertices adjacent to a vertex whose group changes
+    fn get_dirty_vertices(
+        &self, updated_vertex_group_map: &BTreeMap<PatternId, PatternId>,
+    ) -> BTreeSet<PatternId> {
+        let mut dirty_vertices = BTreeSet::new();
+        for (v_id, v_group) in updated_vertex_group_map.iter() {
+            if self.vertex_group_map.get(v_id) != Some(v_group) {
+                for adjacency in self
+                    .vertex_adjacencies_map
+                    .get(v_id)
+                    .expect("Invalid Vertex ID")
+                {
+                    dirty_vertices.insert(adjacency.get_adj_vertex().get_id());
+                }
+            }
+        }
+        dirty_vertices
+    }
 
-                let v_group: PatternId = vertex_group_tmp_vec[i];
+    /// Refine all the vertex groups by a round of 1-dimensional Weisfeiler-Lehman color refinement.
+    ///
+    /// Instead of comparing the vertices in a group pairwise, the vertices are sorted by their signatures,
+    /// and each vertex is put to the initial group plus the number of vertices with smaller signatures in
+    /// the group. As the signatures are ordered the same as `cmp_vertices`, the groups are the same as
+    /// those of `refine_vertex_groups`.
+    fn refine_vertex_groups_by_colors(&mut self) {
+        let mut updated_vertex_group_map: BTreeMap<PatternId, PatternId> = BTreeMap::new();
+        let mut updated_vertex_groups: BTreeMap<(PatternLabelId, PatternId), Vec<PatternId>> =
+            BTreeMap::new();
+        let mut has_converged = true;
+        for (&(v_label, initial_group), vertex_group) in self.vertex_groups.iter() {
+            // Vertices share the signature of their representative
+            let mut representative_signatures: BTreeMap<PatternId, VertexSignature> = BTreeMap::new();
+            let mut vertex_signatures: Vec<(VertexSignature, PatternId)> = vertex_group
+                .iter()
+                .map(|&v_id| {
+                    let representative = self.get_vertex_representative(v_id);
+                    let signature = representative_signatures
+                        .entry(representative)
+                        .or_insert_with(|| self.get_vertex_signature(representative))
+                        .clone();
+                    (signature, v_id)
+                })
+                .collect();
+            vertex_signatures.sort();
+            let mut v_group = initial_group;
+            for i in 0..vertex_signatures.len() {
+                if i > 0 && vertex_signatures[i].0 != vertex_signatures[i - 1].0 {
+                    v_group = initial_group + i as PatternId;
+                }
                 if v_group != initial_group {
                     has_converged = false;
                 }
 
+                let current_v_id = vertex_signatures[i].1;
                 updated_vertex_group_map.insert(current_v_id, v_group);
                 updated_vertex_groups
                     .entry((v_label, v_group))
@@ -201,11 +475,32 @@ impl CanonicalLabelManager {
         // Update the order of vertex adjacencies
         self.update_vertex_adjacencies_order();
     }
+
+    /// Given vertex ID, return its signature in Weisfeiler-Lehman color refinement
+    fn get_vertex_signature(&self, vertex_id: PatternId) -> VertexSignature {
+        let mut adjacency_colors: Vec<_> = self
+            .vertex_adjacencies_map
+            .get(&vertex_id)
+            .expect("Invalid Vertex ID")
+            .iter()
+            .map(|adjacency| {
+                let adj_v_group = self
+                    .get_vertex_group(adjacency.get_adj_vertex().get_id())
+                    .unwrap();
+                (get_adjacency_info_tuple(adjacency), adj_v_group)
+            })
+            .collect();
+        adjacency_colors.sort();
+        (self.get_vertex_degrees(vertex_id), adjacency_colors)
+    }
 }
 
 /// Methods for Pattern Ranking
 impl CanonicalLabelManager {
     /// Set unique ranks to each vertex and edge
+    ///
+    /// For a disconnected pattern, the connected components are ranked one after another,
+    /// and the ranks of each component start right after those of the previous ones.
     pub fn pattern_ranking(&mut self, pattern: &mut Pattern) {
         let mut start_v_id;
         if let Some(value) = self.get_pattern_ranking_start_vertex(pattern) {
@@ -214,8 +509,14 @@ impl CanonicalLabelManager {
             return;
         }
 
+        let mut next_free_vertex_rank: PatternId = 0;
+        let mut next_free_edge_rank: PatternId = 0;
         loop {
-            self.pattern_ranking_from_vertex(start_v_id);
+            self.pattern_ranking_from_vertex(
+                start_v_id,
+                &mut next_free_vertex_rank,
+                &mut next_free_edge_rank,
+            );
 
             // Find another starting vertex to deal with disconnected pattern
             let start_vertex = pattern
@@ -257,12 +558,15 @@ impl CanonicalLabelManager {
     }
 
     /// Given a starting vertex, rank all vertices and edges that are reachable from this vertex.
-    fn pattern_ranking_from_vertex(&mut self, start_v_id: PatternId) {
-        let mut next_free_vertex_rank: PatternId = 0;
-        let mut next_free_edge_rank: PatternId = 0;
+    ///
+    /// The ranks are assigned from the given next free ranks, which are advanced accordingly.
+    fn pattern_ranking_from_vertex(
+        &mut self, start_v_id: PatternId, next_free_vertex_rank: &mut PatternId,
+        next_free_edge_rank: &mut PatternId,
+    ) {
         self.vertex_rank_map
-            .insert(start_v_id, Some(next_free_vertex_rank));
-        next_free_vertex_rank += 1;
+            .insert(start_v_id, Some(*next_free_vertex_rank));
+        *next_free_vertex_rank += 1;
         let mut visited_edges: BTreeSet<PatternId> = BTreeSet::new();
         // Initialize Stack for adjacencies
         let mut adjacency_stack: VecDeque<Adjacency> =
@@ -276,8 +580,8 @@ impl CanonicalLabelManager {
             }
             visited_edges.insert(adj_edge_id);
             self.edge_rank_map
-                .insert(adj_edge_id, Some(next_free_edge_rank));
-            next_free_edge_rank += 1;
+                .insert(adj_edge_id, Some(*next_free_edge_rank));
+            *next_free_edge_rank += 1;
 
             // Set dfs id to the vertex if it has not been set before
             let current_v_id: PatternId = adjacency.get_adj_vertex().get_id();
@@ -288,8 +592,8 @@ impl CanonicalLabelManager {
                 .is_some();
             if !is_vertex_visited {
                 self.vertex_rank_map
-                    .insert(current_v_id, Some(next_free_vertex_rank));
-                next_free_vertex_rank += 1;
+                    .insert(current_v_id, Some(*next_free_vertex_rank));
+                *next_free_vertex_rank += 1;
             }
 
             // Update the order of vertex adjacencies with the updated ranks
@@ -330,12 +634,15 @@ impl CanonicalLabelManager {
     /// - Data of Adjacency Itself: (Edge Direction, End Vertex Label and Edge Label)
     /// - Group ID of end vertex
     /// - Rank of end vertex
+    /// - Rank of the edge
+    ///
+    /// Only parallel edges with the same direction and label reach the edge ranks. Such edges are
+    /// interchangeable, so the order between the unranked ones takes no effect on the pattern code, and the
+    /// ranked ones keep the order in which they are visited in pattern ranking.
     fn cmp_adjacencies(&self, adj1: &Adjacency, adj2: &Adjacency) -> Ordering {
         // Compare the information stored inside adjacencies: label and edge direction
-        let adj1_info_tuple =
-            (adj1.get_direction(), adj1.get_adj_vertex().get_label(), adj1.get_edge_label());
-        let adj2_info_tuple =
-            (adj2.get_direction(), adj2.get_adj_vertex().get_label(), adj2.get_edge_label());
+        let adj1_info_tuple = get_adjacency_info_tuple(adj1);
+        let adj2_info_tuple = get_adjacency_info_tuple(adj2);
         match adj1_info_tuple.cmp(&adj2_info_tuple) {
             Ordering::Less => return Ordering::Less,
             Ordering::Greater => return Ordering::Greater,
@@ -355,10 +662,24 @@ impl CanonicalLabelManager {
 
         // Compare vertex ranks
         // Adjacency will be given high priority if its adjacent vertex has no or smaller rank
-        // Since vertices in the same pattern will never be given the same rank, two adjacencies cannot be equal.
         let adj1_v_rank = self.get_vertex_rank(adj1_v_id);
         let adj2_v_rank = self.get_vertex_rank(adj2_v_id);
-        adj1_v_rank.cmp(&adj2_v_rank)
+        match adj1_v_rank.cmp(&adj2_v_rank) {
+            Ordering::Less => return Ordering::Less,
+            Ordering::Greater => return Ordering::Greater,
+            Ordering::Equal => (),
+        }
+
+        // Compare edge ranks, which only happens to parallel edges
+        let adj1_e_rank = self
+            .edge_rank_map
+            .get(&adj1.get_edge_id())
+            .unwrap();
+        let adj2_e_rank = self
+            .edge_rank_map
+            .get(&adj2.get_edge_id())
+            .unwrap();
+        adj1_e_rank.cmp(adj2_e_rank)
     }
 
     /// Compare the ranks of two PatternVertices
@@ -376,19 +697,11 @@ impl CanonicalLabelManager {
             Ordering::Equal => (),
         }
 
-        // Compare Out Degree
-        let v1_out_degree = pattern.get_vertex_out_degree(v1_id);
-        let v2_out_degree = pattern.get_vertex_out_degree(v2_id);
-        match v1_out_degree.cmp(&v2_out_degree) {
-            Ordering::Less => return Ordering::Less,
-            Ordering::Greater => return Ordering::Greater,
-            Ordering::Equal => (),
-        }
-
-        // Compare In Degree
-        let v1_in_degree = pattern.get_vertex_in_degree(v1_id);
-        let v2_in_degree = pattern.get_vertex_in_degree(v2_id);
-        match v1_in_degree.cmp(&v2_in_degree) {
+        // Compare Out, In and Undirected Degree
+        // Undirected edges are counted separately so that their storage orientation takes no effect
+        let v1_degrees = self.get_vertex_degrees(v1_id);
+        let v2_degrees = self.get_vertex_degrees(v2_id);
+        match v1_degrees.cmp(&v2_degrees) {
             Ordering::Less => return Ordering::Less,
             Ordering::Greater => return Ordering::Greater,
             Ordering::Equal => (),
@@ -425,15 +738,14 @@ impl CanonicalLabelManager {
         // Take two maps out as immutable reference
         let vertex_group_map = &self.vertex_group_map;
         let vertex_rank_map = &self.vertex_rank_map;
+        let edge_rank_map = &self.edge_rank_map;
         self.vertex_adjacencies_map
             .values_mut()
             .for_each(|adjacencies| {
                 adjacencies.sort_by(|adj1, adj2| {
                     // Compare the information stored inside adjacencies: label and edge direction
-                    let adj1_info_tuple =
-                        (adj1.get_direction(), adj1.get_adj_vertex().get_label(), adj1.get_edge_label());
-                    let adj2_info_tuple =
-                        (adj2.get_direction(), adj2.get_adj_vertex().get_label(), adj2.get_edge_label());
+                    let adj1_info_tuple = get_adjacency_info_tuple(adj1);
+                    let adj2_info_tuple = get_adjacency_info_tuple(adj2);
                     match adj1_info_tuple.cmp(&adj2_info_tuple) {
                         Ordering::Less => return Ordering::Less,
                         Ordering::Greater => return Ordering::Greater,
@@ -453,11 +765,37 @@ impl CanonicalLabelManager {
 
                     // Compare vertex ranks
                     // Adjacency will be given high priority if its adjacent vertex has no or smaller rank
-                    // Since vertices in the same pattern will never be given the same rank, two adjacencies cannot be equal.
                     let adj1_v_rank = vertex_rank_map.get(&adj1_v_id);
                     let adj2_v_rank = vertex_rank_map.get(&adj2_v_id);
-                    adj1_v_rank.cmp(&adj2_v_rank)
+                    match adj1_v_rank.cmp(&adj2_v_rank) {
+                        Ordering::Less => return Ordering::Less,
+                        Ordering::Greater => return Ordering::Greater,
+                        Ordering::Equal => (),
+                    }
+
+                    // Compare edge ranks, which only happens to parallel edges
+                    let adj1_e_rank = edge_rank_map.get(&adj1.get_edge_id()).unwrap();
+                    let adj2_e_rank = edge_rank_map.get(&adj2.get_edge_id()).unwrap();
+                    adj1_e_rank.cmp(adj2_e_rank)
                 });
             });
     }
 }
+
+/// The information stored inside an adjacency used for comparison:
+/// (is undirected, direction, adjacent vertex label, edge label, hop range)
+///
+/// Adjacencies of undirected edges are ordered after directed ones, and their direction is ignored
+fn get_adjacency_info_tuple(
+    adjacency: &Adjacency,
+) -> (bool, PatternDirection, PatternLabelId, PatternLabelId, Option<(usize, usize)>) {
+    let direction =
+        if adjacency.is_bidirectional() { PatternDirection::Out } else { adjacency.get_direction() };
+    (
+        adjacency.is_bidirectional(),
+        direction,
+        adjacency.get_adj_vertex().get_label(),
+        adjacency.get_edge_label(),
+        adjacency.get_hop_range(),
+    )
+}
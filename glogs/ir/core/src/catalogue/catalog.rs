@@ -13,12 +13,15 @@
 //! See the License for the specific language governing permissions and
 //! limitations under the License.
 
+use std::collections::hash_map::DefaultHasher;
 use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque};
 use std::convert::TryFrom;
 use std::fmt::{self, Display};
 use std::fs::File;
+use std::hash::{Hash, Hasher};
 use std::io::{BufReader, BufWriter};
 use std::path::Path;
+use std::sync::Arc;
 
 use bincode::Result as BincodeResult;
 use bincode::{deserialize_from, serialize_into};
@@ -30,6 +33,7 @@ use petgraph::Direction;
 use rand::rngs::StdRng;
 use rand::seq::IteratorRandom;
 use rand::SeedableRng;
+use rayon::prelude::*;
 use ron::ser::PrettyConfig;
 use serde::de::Visitor;
 use serde::{Deserialize, Serialize};
@@ -38,6 +42,7 @@ use crate::catalogue::extend_step::{get_subsets, ExtendEdge, ExtendStep};
 use crate::catalogue::join_step::BinaryJoinPlan;
 use crate::catalogue::pattern::{Adjacency, Pattern, PatternEdge, PatternVertex};
 use crate::catalogue::pattern_meta::PatternMeta;
+use crate::catalogue::sample::PatternCountInfo;
 use crate::catalogue::{DynIter, PatternDirection, PatternId, PatternLabelId};
 
 /// In Catalog Graph, Vertex Represents a Pattern
@@ -50,6 +55,10 @@ pub struct PatternWeight {
     best_approach: Option<Approach>,
     ///
     out_extend_map: HashMap<Vec<u8>, Approach>,
+    /// How many sampled records the count is estimated from
+    sample_size: usize,
+    /// Variance of the estimated count, if it is estimated by sampling
+    count_variance: Option<f64>,
 }
 
 impl PatternWeight {
@@ -65,6 +74,14 @@ impl PatternWeight {
         self.best_approach
     }
 
+    pub fn get_sample_size(&self) -> usize {
+        self.sample_size
+    }
+
+    pub fn get_count_variance(&self) -> Option<f64> {
+        self.count_variance
+    }
+
     pub fn get_extend_approach(&self, extend_code: &Vec<u8>) -> Option<Approach> {
         self.out_extend_map.get(extend_code).cloned()
     }
@@ -87,6 +104,18 @@ impl PatternWeight {
         self.best_approach = Some(best_approach)
     }
 
+    pub fn clear_best_approach(&mut self) {
+        self.best_approach = None
+    }
+
+    pub fn set_sample_size(&mut self, sample_size: usize) {
+        self.sample_size = sample_size
+    }
+
+    pub fn set_count_variance(&mut self, count_variance: f64) {
+        self.count_variance = Some(count_variance)
+    }
+
     pub fn add_out_extend(&mut self, extend_code: Vec<u8>, approach: Approach) {
         self.out_extend_map
             .insert(extend_code, approach);
@@ -254,7 +283,7 @@ impl Approach {
     }
 }
 
-#[derive(Debug, Clone, Copy, Default)]
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
 pub enum PatMatPlanSpace {
     ExtendWithIntersection,
     BinaryJoin,
@@ -263,6 +292,37 @@ pub enum PatMatPlanSpace {
     Hybrid,
 }
 
+impl PatMatPlanSpace {
+    /// The minimum number of disjoint vertices in both build and probe patterns of a binary join
+    ///
+    /// Pure binary join plans decompose patterns until single edges, while joins with only one disjoint
+    /// vertex are left to extend steps in the hybrid plan space
+    pub fn get_min_join_disjoint_vertices_num(&self) -> usize {
+        match self {
+            PatMatPlanSpace::BinaryJoin => 1,
+            _ => 2,
+        }
+    }
+}
+
+/// The order to intersect the adjacencies of the extend edges in an extend step
+///
+/// The first extend edge is expanded without intersection, and each following one is intersected with
+/// the intermediate results, so the order decides the sizes of the intermediate intersections
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum IntersectOrder {
+    /// Ascending by the estimated adjacency count of each extend edge
+    #[default]
+    AscendingCount,
+    /// Descending by the estimated adjacency count of each extend edge
+    DescendingCount,
+    /// Ascending by the edge label of each extend edge
+    EdgeLabel,
+    /// Ascending by the selectivity of each extend edge, i.e., its estimated adjacency count divided
+    /// by the count of the single edge pattern of its edge label
+    Selectivity,
+}
+
 #[derive(Debug, Clone, Default)]
 pub struct Catalogue {
     /// Catalog Graph
@@ -275,6 +335,12 @@ pub struct Catalogue {
     entries: Vec<NodeIndex>,
     /// Pattern Match Plan Space
     plan_space: PatMatPlanSpace,
+    /// The order to intersect extend edges when planning the extend steps of patterns not in catalog
+    intersect_order: IntersectOrder,
+    /// The sampled records of patterns kept by the last estimation, reused to estimate new patterns
+    pub(crate) sampled_records: HashMap<NodeIndex, Arc<PatternCountInfo>>,
+    /// The sampled records of patterns keyed by their codes, cached across estimations with reuse_records
+    pub(crate) cached_records: HashMap<Vec<u8>, Arc<PatternCountInfo>>,
 }
 
 impl Catalogue {
@@ -320,13 +386,25 @@ impl Catalogue {
         catalog
     }
 
-    /// Build a catalog from a pattern dedicated for its optimization
+    /// Build a catalog from a pattern dedicated for its optimization,
+    /// where the extend steps of each level of sub-patterns are generated in parallel
     pub fn build_from_pattern(pattern: &Pattern, plan_space: PatMatPlanSpace) -> Catalogue {
-        // Initialize Catalog
         let mut catalog = Catalogue::default();
         catalog.set_plan_space(plan_space);
-        // Update catalog with a given pattern to add necessary optimization info
-        catalog.update_catalog_by_pattern(pattern);
+        match catalog.plan_space {
+            PatMatPlanSpace::ExtendWithIntersection => {
+                catalog.update_extend_steps_by_pattern_parallel(pattern);
+            }
+            PatMatPlanSpace::BinaryJoin => {
+                catalog.update_join_steps_by_pattern(pattern);
+            }
+            PatMatPlanSpace::Hybrid => {
+                catalog.update_extend_steps_by_pattern_parallel(pattern);
+                catalog.update_join_steps_by_pattern(pattern);
+            }
+        }
+        info!("Number of patterns after building: {}", catalog.get_patterns_num());
+        info!("Number of approaches after building: {}", catalog.get_approaches_num());
         catalog
     }
 
@@ -386,36 +464,18 @@ impl Catalogue {
                 .map(|v| v.get_id())
                 .collect();
             if !relaxed_patterns.contains(&relaxed_pattern_vertices) {
-                // Store already added target vertex of extend step
-                for adj_vertex_id in get_adj_vertex_id_candies(pattern, &relaxed_pattern_vertices) {
-                    // the adj_vertex is regarded as target vertex
-                    // back link to the relaxed pattern to find all edges to be added
-                    let final_back_links =
-                        get_adjacencies_to_src_vertices(pattern, &relaxed_pattern_vertices, adj_vertex_id);
-                    let back_links_collection = get_subsets(final_back_links.clone(), |_, _| false);
-                    for back_links in back_links_collection {
-                        let extend_step = new_extend_step_from_adjacencies(
-                            &relaxed_pattern,
-                            pattern,
-                            &back_links,
-                            adj_vertex_id,
-                        );
-                        let new_pattern = relaxed_pattern
-                            .extend_by_edges(new_pattern_edges_from_adjacencies(pattern, &back_links))
-                            .unwrap();
-                        let (_, new_pattern_index) = self.add_pattern(new_pattern.clone());
-                        let adj_vertex_rank = new_pattern
-                            .get_vertex_rank(adj_vertex_id)
-                            .unwrap();
-                        self.add_extend_step(
-                            extend_step,
-                            relaxed_pattern_index,
-                            new_pattern_index,
-                            adj_vertex_rank,
-                        );
-                        if back_links == final_back_links {
-                            queue.push_back((new_pattern, new_pattern_index));
-                        }
+                for (extend_step, new_pattern, adj_vertex_rank, is_final) in
+                    get_relaxed_pattern_branches(pattern, &relaxed_pattern, &relaxed_pattern_vertices)
+                {
+                    let (_, new_pattern_index) = self.add_pattern(new_pattern.clone());
+                    self.add_extend_step(
+                        extend_step,
+                        relaxed_pattern_index,
+                        new_pattern_index,
+                        adj_vertex_rank,
+                    );
+                    if is_final {
+                        queue.push_back((new_pattern, new_pattern_index));
                     }
                 }
                 relaxed_patterns.insert(relaxed_pattern_vertices);
@@ -423,6 +483,56 @@ impl Catalogue {
         }
     }
 
+    /// Parallel version of `update_extend_steps_by_pattern`
+    ///
+    /// The sub-patterns are relaxed level by level (i.e., by the number of vertices).
+    /// Within a level, the extend steps and extended patterns of all sub-patterns are computed in parallel,
+    /// and then inserted to the catalog in the order of the level, so the result is deterministic
+    pub fn update_extend_steps_by_pattern_parallel(&mut self, pattern: &Pattern) {
+        // one-vertex patterns are the first level
+        let mut level: Vec<(BTreeSet<PatternId>, Pattern, NodeIndex)> = vec![];
+        for vertex in pattern.vertices_iter() {
+            let new_pattern = Pattern::from(*vertex);
+            let (_, new_pattern_index) = self.add_pattern(new_pattern.clone());
+            level.push((BTreeSet::from([vertex.get_id()]), new_pattern, new_pattern_index));
+        }
+        while !level.is_empty() {
+            let branches: Vec<(NodeIndex, Vec<(ExtendStep, Pattern, usize, bool)>)> = level
+                .par_iter()
+                .map(|(relaxed_pattern_vertices, relaxed_pattern, relaxed_pattern_index)| {
+                    (
+                        *relaxed_pattern_index,
+                        get_relaxed_pattern_branches(pattern, relaxed_pattern, relaxed_pattern_vertices),
+                    )
+                })
+                .collect();
+            // the shared pattern and approach maps are only updated here
+            let mut next_level_vertices = BTreeSet::new();
+            let mut next_level = vec![];
+            for (relaxed_pattern_index, branch) in branches {
+                for (extend_step, new_pattern, adj_vertex_rank, is_final) in branch {
+                    let (_, new_pattern_index) = self.add_pattern(new_pattern.clone());
+                    self.add_extend_step(
+                        extend_step,
+                        relaxed_pattern_index,
+                        new_pattern_index,
+                        adj_vertex_rank,
+                    );
+                    if is_final {
+                        let new_pattern_vertices: BTreeSet<PatternId> = new_pattern
+                            .vertices_iter()
+                            .map(|v| v.get_id())
+                            .collect();
+                        if next_level_vertices.insert(new_pattern_vertices.clone()) {
+                            next_level.push((new_pattern_vertices, new_pattern, new_pattern_index));
+                        }
+                    }
+                }
+            }
+            level = next_level;
+        }
+    }
+
     /// Usage: Given a pattern, find out all (build pattern, binary join steo) pairs that join to get the given pattern, and store them in catalogue.
     fn update_join_steps_by_pattern(&mut self, pattern: &Pattern) {
         let pattern_code: Vec<u8> = pattern.encode_to();
@@ -435,15 +545,27 @@ impl Catalogue {
                     count: OrderedFloat::default(),
                     best_approach: None,
                     out_extend_map: HashMap::new(),
+                    sample_size: 0,
+                    count_variance: None,
                 });
                 self.pattern_locate_map
                     .insert(pattern_code, node_index);
                 node_index
             };
+        let binary_join_plans = pattern
+            .binary_join_decomposition_with_min_disjoint_vertices(
+                self.plan_space
+                    .get_min_join_disjoint_vertices_num(),
+            )
+            .expect("Failed to decompose binary join plans");
+        // Patterns which cannot be decomposed (e.g., single edges) are materialized by minimal extend steps
+        if binary_join_plans.is_empty() {
+            if let PatMatPlanSpace::BinaryJoin = self.plan_space {
+                self.update_extend_steps_by_pattern(pattern);
+            }
+        }
         // Iterate through all binary join plans
-        pattern
-            .binary_join_decomposition()
-            .expect("Failed to decompose binary join plans")
+        binary_join_plans
             .into_iter()
             .for_each(|binary_join_plan| {
                 // Build nodes for both build and probe patterns if no existing patterns can be found
@@ -472,6 +594,37 @@ impl Catalogue {
     }
 }
 
+/// Get the extend steps from the relaxed sub-pattern to the sub-patterns with one more vertex of the pattern
+///
+/// Each branch is (extend step, new pattern, target vertex rank in the new pattern, whether the new pattern
+/// has all the back links of the target vertex and is to be relaxed further). The catalog is not touched,
+/// so that the branches of different sub-patterns can be computed in parallel
+fn get_relaxed_pattern_branches(
+    pattern: &Pattern, relaxed_pattern: &Pattern, relaxed_pattern_vertices: &BTreeSet<PatternId>,
+) -> Vec<(ExtendStep, Pattern, usize, bool)> {
+    let mut branches = vec![];
+    for adj_vertex_id in get_adj_vertex_id_candies(pattern, relaxed_pattern_vertices) {
+        // the adj_vertex is regarded as target vertex
+        // back link to the relaxed pattern to find all edges to be added
+        let final_back_links =
+            get_adjacencies_to_src_vertices(pattern, relaxed_pattern_vertices, adj_vertex_id);
+        let back_links_collection = get_subsets(final_back_links.clone(), |_, _| false);
+        for back_links in back_links_collection {
+            let extend_step =
+                new_extend_step_from_adjacencies(relaxed_pattern, pattern, &back_links, adj_vertex_id);
+            let new_pattern = relaxed_pattern
+                .extend_by_edges(new_pattern_edges_from_adjacencies(pattern, &back_links))
+                .unwrap();
+            let adj_vertex_rank = new_pattern
+                .get_vertex_rank(adj_vertex_id)
+                .unwrap();
+            let is_final = back_links == final_back_links;
+            branches.push((extend_step, new_pattern, adj_vertex_rank, is_final));
+        }
+    }
+    branches
+}
+
 fn get_adj_vertex_id_candies(
     pattern: &Pattern, pre_pattern_vertices: &BTreeSet<PatternId>,
 ) -> BTreeSet<PatternId> {
@@ -508,6 +661,7 @@ fn new_extend_step_from_adjacencies(
                 adj.get_edge_label(),
                 adj.get_direction().reverse(),
             )
+            .with_undirected(adj.is_bidirectional())
         })
         .collect();
     let target_v_label = pattern
@@ -541,6 +695,8 @@ impl Catalogue {
                 count: OrderedFloat::default(),
                 best_approach: None,
                 out_extend_map: HashMap::new(),
+                sample_size: 0,
+                count_variance: None,
             });
             self.pattern_locate_map
                 .insert(pattern_code, pattern_index);
@@ -601,14 +757,45 @@ impl Catalogue {
             .cloned()
     }
 
+    /// Get the subpatterns required for the plan generation of the pattern (see `generate_subpatterns`),
+    /// including the pattern itself, which are not found in the catalog
+    pub fn get_missing_subpatterns(&self, pattern: &Pattern) -> Vec<Pattern> {
+        pattern
+            .generate_subpatterns()
+            .into_iter()
+            .filter(|subpattern| {
+                self.get_pattern_index(&subpattern.encode_to())
+                    .is_none()
+            })
+            .collect()
+    }
+
     pub fn get_pattern_weight(&self, pattern_index: NodeIndex) -> Option<&PatternWeight> {
         self.store.node_weight(pattern_index)
     }
 
+    /// Mutable access to the whole weight of a pattern, for updating its estimation infos in place
+    ///
+    /// Callers must not change the structure of the pattern inside, as its code is the key
+    /// of the pattern in `pattern_locate_map`
     pub fn get_pattern_weight_mut(&mut self, pattern_index: NodeIndex) -> Option<&mut PatternWeight> {
         self.store.node_weight_mut(pattern_index)
     }
 
+    /// Confidence interval of the estimated count of a pattern, as `count ± z * standard deviation`
+    ///
+    /// The lower bound is clamped to 0, and the interval collapses to the count itself
+    /// if the variance of the count is unknown.
+    pub fn get_pattern_count_ci(&self, pattern_index: NodeIndex, z: f64) -> Option<(f64, f64)> {
+        let pattern_weight = self.get_pattern_weight(pattern_index)?;
+        let count = pattern_weight.get_count().into_inner();
+        let margin = pattern_weight
+            .get_count_variance()
+            .map(|variance| z * variance.sqrt())
+            .unwrap_or(0.0);
+        Some(((count - margin).max(0.0), count + margin))
+    }
+
     pub fn get_approach_weight(&self, approach_index: EdgeIndex) -> Option<&ApproachWeight> {
         self.store.edge_weight(approach_index)
     }
@@ -641,6 +828,25 @@ impl Catalogue {
         Box::new(self.entries.iter().cloned())
     }
 
+    /// Iterate the entries whose single vertex has the given label
+    pub fn entries_iter_by_label(&self, vertex_label: PatternLabelId) -> DynIter<NodeIndex> {
+        Box::new(
+            self.entries
+                .iter()
+                .cloned()
+                .filter(move |&entry_index| {
+                    self.get_pattern_weight(entry_index)
+                        .map(|pattern_weight| {
+                            pattern_weight
+                                .get_pattern()
+                                .vertices_iter()
+                                .all(|vertex| vertex.get_label() == vertex_label)
+                        })
+                        .unwrap_or(false)
+                }),
+        )
+    }
+
     pub fn pattern_indices_iter(&self) -> DynIter<NodeIndex> {
         Box::new(self.store.node_indices())
     }
@@ -671,6 +877,14 @@ impl Catalogue {
         self.plan_space = plan_space;
     }
 
+    pub fn get_intersect_order(&self) -> IntersectOrder {
+        self.intersect_order
+    }
+
+    pub fn set_intersect_order(&mut self, intersect_order: IntersectOrder) {
+        self.intersect_order = intersect_order;
+    }
+
     pub fn set_pattern_count_with_index(&mut self, pattern_index: NodeIndex, count: OrderedFloat<f64>) {
         if let Some(pattern_weight) = self.get_pattern_weight_mut(pattern_index) {
             pattern_weight.set_count(count)
@@ -856,6 +1070,427 @@ impl Catalogue {
     }
 }
 
+/// Methods for pruning catalog
+impl Catalogue {
+    /// Remove the patterns with more than `max_vertices` vertices, or with an estimated count below
+    /// `min_count` if it is given, together with the approaches to or from them
+    ///
+    /// The patterns no longer reachable from the entries through the remaining approaches are removed
+    /// as well, and so are the join approaches whose probe patterns are removed. As the catalog graph
+    /// is rebuilt, the node and edge indices of the remaining patterns and approaches are reassigned.
+    pub fn prune(&mut self, max_vertices: usize, min_count: Option<f64>) {
+        let mut kept_patterns: BTreeSet<NodeIndex> = self
+            .pattern_indices_iter()
+            .filter(|&pattern_index| {
+                let pattern_weight = self.get_pattern_weight(pattern_index).unwrap();
+                pattern_weight.get_pattern().get_vertices_num() <= max_vertices
+                    && min_count.map_or(true, |min_count| pattern_weight.get_count().0 >= min_count)
+            })
+            .collect();
+        // Keep the patterns reachable from the entries until none is removed any more
+        loop {
+            let mut reachable_patterns: BTreeSet<NodeIndex> = self
+                .entries_iter()
+                .filter(|entry| kept_patterns.contains(entry))
+                .collect();
+            let mut queue: VecDeque<NodeIndex> = reachable_patterns.iter().cloned().collect();
+            while let Some(pattern_index) = queue.pop_front() {
+                for approach in self.pattern_out_approaches_iter(pattern_index) {
+                    let target_pattern_index = approach.get_target_pattern_index();
+                    if kept_patterns.contains(&target_pattern_index)
+                        && self.is_approach_kept(approach.get_approach_index(), &kept_patterns)
+                        && reachable_patterns.insert(target_pattern_index)
+                    {
+                        queue.push_back(target_pattern_index);
+                    }
+                }
+            }
+            if reachable_patterns.len() == kept_patterns.len() {
+                break;
+            }
+            kept_patterns = reachable_patterns;
+        }
+        // Rebuild the catalog graph with the kept patterns and approaches
+        let mut store = Graph::new();
+        let mut pattern_index_map: HashMap<NodeIndex, NodeIndex> = HashMap::new();
+        for &pattern_index in kept_patterns.iter() {
+            let pattern_weight = self
+                .get_pattern_weight(pattern_index)
+                .unwrap()
+                .clone();
+            pattern_index_map.insert(pattern_index, store.add_node(pattern_weight));
+        }
+        let mut approach_index_map: HashMap<EdgeIndex, EdgeIndex> = HashMap::new();
+        for approach_index in self.store.edge_indices() {
+            let (src_pattern_index, target_pattern_index) = self
+                .store
+                .edge_endpoints(approach_index)
+                .unwrap();
+            if kept_patterns.contains(&src_pattern_index)
+                && kept_patterns.contains(&target_pattern_index)
+                && self.is_approach_kept(approach_index, &kept_patterns)
+            {
+                let mut approach_weight = self
+                    .get_approach_weight(approach_index)
+                    .unwrap()
+                    .clone();
+                if let Some(join_weight) = approach_weight.get_join_weight_mut() {
+                    let probe_pattern_index =
+                        pattern_index_map[&join_weight.get_probe_pattern_node_index()];
+                    join_weight.set_probe_pattern_node_index(probe_pattern_index);
+                }
+                let new_approach_index = store.add_edge(
+                    pattern_index_map[&src_pattern_index],
+                    pattern_index_map[&target_pattern_index],
+                    approach_weight,
+                );
+                approach_index_map.insert(approach_index, new_approach_index);
+            }
+        }
+        let remap_approach = |approach: Approach| {
+            approach_index_map
+                .get(&approach.get_approach_index())
+                .map(|&approach_index| {
+                    Approach::new(
+                        pattern_index_map[&approach.get_src_pattern_index()],
+                        pattern_index_map[&approach.get_target_pattern_index()],
+                        approach_index,
+                    )
+                })
+        };
+        for pattern_weight in store.node_weights_mut() {
+            match pattern_weight
+                .best_approach
+                .and_then(remap_approach)
+            {
+                Some(best_approach) => pattern_weight.set_best_approach(best_approach),
+                None => pattern_weight.clear_best_approach(),
+            }
+            pattern_weight.out_extend_map = pattern_weight
+                .out_extend_map
+                .drain()
+                .filter_map(|(extend_code, approach)| {
+                    remap_approach(approach).map(|approach| (extend_code, approach))
+                })
+                .collect();
+        }
+        self.store = store;
+        self.pattern_locate_map = self
+            .store
+            .node_indices()
+            .map(|pattern_index| {
+                (
+                    self.store[pattern_index]
+                        .get_pattern()
+                        .encode_to(),
+                    pattern_index,
+                )
+            })
+            .collect();
+        self.entries = self
+            .entries
+            .iter()
+            .filter_map(|entry| pattern_index_map.get(entry).cloned())
+            .collect();
+        self.sampled_records = self
+            .sampled_records
+            .drain()
+            .filter_map(|(pattern_index, records)| {
+                pattern_index_map
+                    .get(&pattern_index)
+                    .map(|&pattern_index| (pattern_index, records))
+            })
+            .collect();
+    }
+
+    /// A join approach is only kept if its probe pattern is kept
+    fn is_approach_kept(&self, approach_index: EdgeIndex, kept_patterns: &BTreeSet<NodeIndex>) -> bool {
+        self.get_join_weight(approach_index)
+            .map_or(true, |join_weight| kept_patterns.contains(&join_weight.get_probe_pattern_node_index()))
+    }
+}
+
+/// How to combine the estimated counts of a pattern or an approach from two catalogs
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MergeMode {
+    /// Sum the counts up, e.g., for catalogs estimated on disjoint partitions of a graph
+    Sum,
+    /// Average the counts, e.g., for catalogs estimated on different samples of the same graph
+    Mean,
+}
+
+impl MergeMode {
+    fn merge_counts(&self, count1: OrderedFloat<f64>, count2: OrderedFloat<f64>) -> OrderedFloat<f64> {
+        match self {
+            MergeMode::Sum => count1 + count2,
+            MergeMode::Mean => (count1 + count2) / OrderedFloat::from(2.0),
+        }
+    }
+
+    /// Merge the variances of two independent estimations in the same way as their counts
+    fn merge_count_variances(&self, variance1: Option<f64>, variance2: Option<f64>) -> Option<f64> {
+        let (variance1, variance2) = (variance1?, variance2?);
+        match self {
+            MergeMode::Sum => Some(variance1 + variance2),
+            MergeMode::Mean => Some((variance1 + variance2) / 4.0),
+        }
+    }
+}
+
+/// Methods for merging catalogs
+impl Catalogue {
+    /// Merge the estimations of another catalog into the current one
+    ///
+    /// Patterns are matched by their codes, and extend approaches by their source patterns and extend
+    /// steps. The counts of the patterns and approaches present in both catalogs are combined
+    /// by the merge mode, while those only in the other catalog are added with their counts as they are.
+    /// The best approaches are not updated, as they depend on the merged counts.
+    pub fn merge_estimates(&mut self, other: &Catalogue, merge_mode: MergeMode) {
+        let mut pattern_index_map: HashMap<NodeIndex, NodeIndex> = HashMap::new();
+        for other_pattern_index in other.pattern_indices_iter() {
+            let other_pattern_weight = other
+                .get_pattern_weight(other_pattern_index)
+                .unwrap();
+            let (existed, pattern_index) = self.add_pattern(other_pattern_weight.get_pattern().clone());
+            let pattern_weight = self
+                .get_pattern_weight_mut(pattern_index)
+                .unwrap();
+            if existed {
+                pattern_weight.set_count(
+                    merge_mode.merge_counts(pattern_weight.get_count(), other_pattern_weight.get_count()),
+                );
+                pattern_weight.set_sample_size(
+                    pattern_weight.get_sample_size() + other_pattern_weight.get_sample_size(),
+                );
+                pattern_weight.count_variance = merge_mode.merge_count_variances(
+                    pattern_weight.get_count_variance(),
+                    other_pattern_weight.get_count_variance(),
+                );
+            } else {
+                pattern_weight.set_count(other_pattern_weight.get_count());
+                pattern_weight.set_sample_size(other_pattern_weight.get_sample_size());
+                pattern_weight.count_variance = other_pattern_weight.get_count_variance();
+            }
+            pattern_index_map.insert(other_pattern_index, pattern_index);
+        }
+        for other_approach_index in other.store.edge_indices() {
+            let (other_src_pattern_index, other_target_pattern_index) = other
+                .store
+                .edge_endpoints(other_approach_index)
+                .unwrap();
+            let src_pattern_index = pattern_index_map[&other_src_pattern_index];
+            let target_pattern_index = pattern_index_map[&other_target_pattern_index];
+            match other
+                .get_approach_weight(other_approach_index)
+                .unwrap()
+            {
+                ApproachWeight::ExtendStep(other_extend_weight) => {
+                    let (existed, approach) = self.add_extend_step(
+                        other_extend_weight.get_extend_step().clone(),
+                        src_pattern_index,
+                        target_pattern_index,
+                        other_extend_weight.get_target_vertex_rank(),
+                    );
+                    let extend_weight = self
+                        .get_extend_weight_mut(approach.get_approach_index())
+                        .unwrap();
+                    if existed {
+                        extend_weight.set_adjacency_count(merge_mode.merge_counts(
+                            extend_weight.get_adjacency_count(),
+                            other_extend_weight.get_adjacency_count(),
+                        ));
+                        extend_weight.set_intersect_count(merge_mode.merge_counts(
+                            extend_weight.get_intersect_count(),
+                            other_extend_weight.get_intersect_count(),
+                        ));
+                    } else {
+                        extend_weight.set_adjacency_count(other_extend_weight.get_adjacency_count());
+                        extend_weight.set_intersect_count(other_extend_weight.get_intersect_count());
+                    }
+                }
+                ApproachWeight::BinaryJoinStep(other_join_weight) => {
+                    let probe_pattern_index =
+                        pattern_index_map[&other_join_weight.get_probe_pattern_node_index()];
+                    // Join approaches have no counts, so they are only added if missing
+                    let existed = self
+                        .store
+                        .edges_connecting(src_pattern_index, target_pattern_index)
+                        .any(|approach_ref| {
+                            approach_ref
+                                .weight()
+                                .get_join_weight()
+                                .map_or(false, |join_weight| {
+                                    join_weight.get_probe_pattern_node_index() == probe_pattern_index
+                                })
+                        });
+                    if !existed {
+                        self.store.add_edge(
+                            src_pattern_index,
+                            target_pattern_index,
+                            ApproachWeight::BinaryJoinStep(JoinWeight {
+                                probe_pattern_node_index: probe_pattern_index,
+                                join_plan: other_join_weight.get_join_plan().clone(),
+                            }),
+                        );
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Methods for verifying the canonical codes of patterns in catalog
+impl Catalogue {
+    /// Verify that no two non-isomorphic patterns are merged in the catalog by sharing a canonical code
+    ///
+    /// Catalog nodes are keyed by code, so the collisions are looked for among the source pattern of
+    /// every extend approach extended by its extend step, which should be isomorphic to the target
+    /// pattern when they share a code.
+    ///
+    /// Isomorphism is checked by exhaustive search, so it is meant for debugging only.
+    /// Return the pairs of non-isomorphic patterns with the same code if any.
+    pub fn verify_no_code_collisions(&self) -> Result<(), Vec<(Pattern, Pattern)>> {
+        let mut collisions = vec![];
+        for approach_index in self.store.edge_indices() {
+            if let Some(extend_weight) = self.get_extend_weight(approach_index) {
+                let (src_pattern_index, target_pattern_index) = self
+                    .store
+                    .edge_endpoints(approach_index)
+                    .unwrap();
+                let src_pattern = self
+                    .get_pattern_weight(src_pattern_index)
+                    .unwrap()
+                    .get_pattern();
+                let target_pattern = self
+                    .get_pattern_weight(target_pattern_index)
+                    .unwrap()
+                    .get_pattern();
+                if let Some(extended_pattern) = src_pattern.extend(extend_weight.get_extend_step()) {
+                    if extended_pattern.encode_to() == target_pattern.encode_to()
+                        && !extended_pattern.is_structurally_isomorphic_to(target_pattern)
+                    {
+                        collisions.push((extended_pattern, target_pattern.clone()));
+                    }
+                }
+            }
+        }
+        if collisions.is_empty() {
+            Ok(())
+        } else {
+            Err(collisions)
+        }
+    }
+}
+
+/// The number of WL iterations to compute the structural similarity of patterns when comparing catalogs
+const DIFF_WL_ITERATIONS: usize = 3;
+
+/// The estimated counts of a pattern and its matched pattern in another catalog
+#[derive(Debug, Clone)]
+pub struct PatternCountDiff {
+    pattern: Pattern,
+    other_pattern: Pattern,
+    count: f64,
+    other_count: f64,
+    similarity: f64,
+}
+
+impl PatternCountDiff {
+    pub fn get_pattern(&self) -> &Pattern {
+        &self.pattern
+    }
+
+    pub fn get_other_pattern(&self) -> &Pattern {
+        &self.other_pattern
+    }
+
+    pub fn get_count(&self) -> f64 {
+        self.count
+    }
+
+    pub fn get_other_count(&self) -> f64 {
+        self.other_count
+    }
+
+    /// The structural similarity of the two patterns by the WL kernel, which is 1.0 for the same pattern
+    pub fn get_similarity(&self) -> f64 {
+        self.similarity
+    }
+
+    /// The ratio of the count to the other count, which is 1.0 when both counts are 0
+    pub fn get_ratio(&self) -> f64 {
+        if self.count == self.other_count {
+            1.0
+        } else {
+            self.count / self.other_count
+        }
+    }
+
+    /// How far the two counts diverge, i.e., the ratio of the larger count to the smaller one
+    pub fn get_divergence(&self) -> f64 {
+        let ratio = self.get_ratio();
+        ratio.max(1.0 / ratio)
+    }
+}
+
+/// Methods for comparing catalogs
+impl Catalogue {
+    /// Compare the estimated counts of the patterns in the catalog with those in the other catalog
+    ///
+    /// A pattern is matched with the pattern of the same code in the other catalog. If there is no such
+    /// pattern and `min_similarity` is given, it is matched with the structurally most similar pattern
+    /// in the other catalog by the WL kernel (see `Pattern::hash_wl`), if their similarity reaches
+    /// `min_similarity`.
+    /// The differences are sorted by their divergences in descending order, so that the most diverged
+    /// patterns come first.
+    pub fn diff(&self, other: &Catalogue, min_similarity: Option<f64>) -> Vec<PatternCountDiff> {
+        let mut diffs: Vec<PatternCountDiff> = self
+            .pattern_indices_iter()
+            .filter_map(|pattern_index| {
+                let pattern_weight = self.get_pattern_weight(pattern_index).unwrap();
+                let pattern = pattern_weight.get_pattern();
+                let (other_pattern_index, similarity) =
+                    if let Some(other_pattern_index) = other.get_pattern_index(&pattern.encode_to()) {
+                        (other_pattern_index, 1.0)
+                    } else {
+                        let min_similarity = min_similarity?;
+                        other
+                            .get_most_similar_pattern_index(pattern)
+                            .filter(|&(_, similarity)| similarity >= min_similarity)?
+                    };
+                let other_pattern_weight = other
+                    .get_pattern_weight(other_pattern_index)
+                    .unwrap();
+                Some(PatternCountDiff {
+                    pattern: pattern.clone(),
+                    other_pattern: other_pattern_weight.get_pattern().clone(),
+                    count: pattern_weight.get_count().into_inner(),
+                    other_count: other_pattern_weight.get_count().into_inner(),
+                    similarity,
+                })
+            })
+            .collect();
+        diffs.sort_by(|diff1, diff2| {
+            OrderedFloat(diff2.get_divergence()).cmp(&OrderedFloat(diff1.get_divergence()))
+        });
+        diffs
+    }
+
+    /// Get the index of the pattern in the catalog that is structurally most similar to the given pattern,
+    /// together with their similarity by the WL kernel
+    fn get_most_similar_pattern_index(&self, pattern: &Pattern) -> Option<(NodeIndex, f64)> {
+        self.pattern_indices_iter()
+            .map(|pattern_index| {
+                let other_pattern = self
+                    .get_pattern_weight(pattern_index)
+                    .unwrap()
+                    .get_pattern();
+                (pattern_index, Pattern::hash_wl(pattern, other_pattern, DIFF_WL_ITERATIONS))
+            })
+            .max_by_key(|&(_, similarity)| OrderedFloat(similarity))
+    }
+}
+
 fn get_common_vertex_of_edges(edge_0: &PatternEdge, edge_1: &PatternEdge) -> Option<PatternVertex> {
     let start_vertex_0 = edge_0.get_start_vertex();
     let end_vertex_0 = edge_0.get_end_vertex();
@@ -997,6 +1632,41 @@ impl Catalogue {
         let mut reader = BufReader::new(File::open(path)?);
         deserialize_from(&mut reader)
     }
+
+    /// Save the full catalogue, including the catalog graph as it is, the pattern codes, the plan space and
+    /// the intersect order, so that an estimated catalogue can be reloaded by `load` without sampling the
+    /// graph again
+    ///
+    /// Different from `export`, the node indices and the patterns without any approach are kept
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> BincodeResult<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        serialize_into(
+            &mut writer,
+            &(
+                &self.store,
+                &self.pattern_locate_map,
+                &self.entries,
+                &self.plan_space,
+                &self.intersect_order,
+            ),
+        )
+    }
+
+    /// Load the full catalogue saved by `save`
+    pub fn load<P: AsRef<Path>>(path: P) -> BincodeResult<Self> {
+        let mut reader = BufReader::new(File::open(path)?);
+        let (store, pattern_locate_map, entries, plan_space, intersect_order) =
+            deserialize_from(&mut reader)?;
+        Ok(Catalogue {
+            store,
+            pattern_locate_map,
+            entries,
+            plan_space,
+            intersect_order,
+            sampled_records: HashMap::new(),
+            cached_records: HashMap::new(),
+        })
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -1195,6 +1865,58 @@ fn get_vec_arranges<T: Clone>(vector: &[T], len: usize) -> Vec<Vec<T>> {
     vec_arranges
 }
 
+/// Methods for Catalogue Visualization
+impl Catalogue {
+    /// Export the catalogue as a Graphviz digraph
+    ///
+    /// Each pattern node is labeled by the hash of its code, its numbers of vertices and edges,
+    /// and its estimated count; each approach edge is labeled by whether it is an extend or a join,
+    /// together with the adjacency and intersect counts of an extend or the probe pattern of a join.
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("digraph catalog {\n");
+        for pattern_index in self.pattern_indices_iter() {
+            let pattern_weight = self.get_pattern_weight(pattern_index).unwrap();
+            let pattern = pattern_weight.get_pattern();
+            let mut hasher = DefaultHasher::new();
+            pattern.encode_to().hash(&mut hasher);
+            dot.push_str(&format!(
+                "    p{} [label=\"p{}\\ncode={:016x}\\nvertices={}, edges={}\\ncount={}\"];\n",
+                pattern_index.index(),
+                pattern_index.index(),
+                hasher.finish(),
+                pattern.get_vertices_num(),
+                pattern.get_edges_num(),
+                pattern_weight.get_count()
+            ));
+        }
+        for approach_ref in self.store.edge_references() {
+            let approach_label = match approach_ref.weight() {
+                ApproachWeight::ExtendStep(extend_weight) => format!(
+                    "extend\\nadjacency_count={}\\nintersect_count={}",
+                    extend_weight.get_adjacency_count(),
+                    extend_weight.get_intersect_count()
+                ),
+                ApproachWeight::BinaryJoinStep(join_weight) => {
+                    format!(
+                        "join\\nprobe=p{}",
+                        join_weight
+                            .get_probe_pattern_node_index()
+                            .index()
+                    )
+                }
+            };
+            dot.push_str(&format!(
+                "    p{} -> p{} [label=\"{}\"];\n",
+                approach_ref.source().index(),
+                approach_ref.target().index(),
+                approach_label
+            ));
+        }
+        dot.push_str("}\n");
+        dot
+    }
+}
+
 impl Display for Catalogue {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "{}", ron::ser::to_string_pretty(self, PrettyConfig::default()).unwrap())
@@ -19,9 +19,47 @@ use crate::catalogue::extend_step::{ExtendEdge, ExtendStep};
 use crate::catalogue::pattern::{Pattern, PatternEdge, PatternVertex};
 use crate::catalogue::{PatternDirection, PatternId, PatternLabelId};
 
+/// Edge labels are non-negative, so the highest bit of an encoded edge label
+/// is used to mark undirected edges
+const UNDIRECTED_EDGE_LABEL_FLAG: u32 = 0x80000000;
+/// The second highest bit of an encoded edge label marks path edges, whose code is followed by
+/// the lower and upper bounds of their hop ranges
+const PATH_EDGE_LABEL_FLAG: u32 = 0x40000000;
+/// The third highest bit marks the code of an isolated vertex, which is followed by its rank and label
+/// instead of the end vertices of an edge
+const ISOLATED_VERTEX_FLAG: u32 = 0x20000000;
+/// The direction of an undirected extend edge in the code of an extend step, besides 0 (out) and 1 (in)
+const UNDIRECTED_EXTEND_EDGE_DIRECTION: u8 = 2;
+
 impl Pattern {
     pub fn encode_to(&self) -> Vec<u8> {
-        if self.get_edges_num() > 0 {
+        self.get_code_cache()
+            .get_or_insert_with(|| self.encode_to_uncached())
+    }
+
+    /// Encode the pattern as if all its edges are undirected, so that the patterns identical up to the
+    /// directions of their edges share the same code, which differs from the one given by `encode_to`
+    pub fn encode_undirected(&self) -> Vec<u8> {
+        if self
+            .edges_iter()
+            .all(|edge| edge.is_undirected())
+        {
+            return self.encode_to();
+        }
+        let undirected_edges: Vec<PatternEdge> = self
+            .edges_iter()
+            .map(|edge| edge.clone().with_undirected(true))
+            .collect();
+        let vertices: Vec<PatternVertex> = self.vertices_iter().copied().collect();
+        Pattern::from_parts(vertices, undirected_edges)
+            .unwrap()
+            .encode_to()
+    }
+
+    /// Every edge is encoded in the order of edge ranks, followed by every isolated vertex in the order
+    /// of vertex ranks, except that a pattern of a single vertex is encoded as its label only
+    fn encode_to_uncached(&self) -> Vec<u8> {
+        if self.get_edges_num() > 0 || self.get_vertices_num() > 1 {
             let mut edge_ids: Vec<PatternId> = self
                 .edges_iter()
                 .map(|edge| edge.get_id())
@@ -35,21 +73,49 @@ impl Pattern {
             for edge_id in edge_ids {
                 let edge = self.get_edge(edge_id).unwrap();
                 let edge_label = edge.get_label();
-                let start_vertex = edge.get_start_vertex();
-                let start_vertex_rank = self
+                let mut start_vertex = edge.get_start_vertex();
+                let mut start_vertex_rank = self
                     .get_vertex_rank(start_vertex.get_id())
                     .unwrap();
-                let start_vertex_label = start_vertex.get_label();
-                let end_vertex = edge.get_end_vertex();
-                let end_vertex_rank = self
+                let mut end_vertex = edge.get_end_vertex();
+                let mut end_vertex_rank = self
                     .get_vertex_rank(end_vertex.get_id())
                     .unwrap();
+                // Undirected edges are always encoded from the vertex with smaller rank,
+                // so that the code does not depend on the orientation the edge is given
+                if edge.is_undirected() && start_vertex_rank > end_vertex_rank {
+                    std::mem::swap(&mut start_vertex, &mut end_vertex);
+                    std::mem::swap(&mut start_vertex_rank, &mut end_vertex_rank);
+                }
+                let start_vertex_label = start_vertex.get_label();
                 let end_vertex_label = end_vertex.get_label();
-                pattern_code.extend_from_slice(&label_to_u8_array(edge_label));
+                let mut edge_label_code = edge_label as u32;
+                if edge.is_undirected() {
+                    edge_label_code |= UNDIRECTED_EDGE_LABEL_FLAG;
+                }
+                if edge.is_path() {
+                    edge_label_code |= PATH_EDGE_LABEL_FLAG;
+                }
+                pattern_code.extend_from_slice(&u32_to_u8_array(edge_label_code));
                 pattern_code.extend_from_slice(&id_to_u8_array(start_vertex_rank));
                 pattern_code.extend_from_slice(&label_to_u8_array(start_vertex_label));
                 pattern_code.extend_from_slice(&id_to_u8_array(end_vertex_rank));
                 pattern_code.extend_from_slice(&label_to_u8_array(end_vertex_label));
+                if let Some((hop_min, hop_max)) = edge.get_hop_range() {
+                    pattern_code.extend_from_slice(&u32_to_u8_array(hop_min as u32));
+                    pattern_code.extend_from_slice(&u32_to_u8_array(hop_max as u32));
+                }
+            }
+            let mut isolated_vertices: Vec<(PatternId, PatternLabelId)> = self
+                .vertices_iter()
+                .filter(|vertex| self.get_vertex_degree(vertex.get_id()) == 0)
+                .map(|vertex| (self.get_vertex_rank(vertex.get_id()).unwrap(), vertex.get_label()))
+                .collect();
+            isolated_vertices.sort();
+            for (vertex_rank, vertex_label) in isolated_vertices {
+                pattern_code.extend_from_slice(&u32_to_u8_array(ISOLATED_VERTEX_FLAG));
+                pattern_code.extend_from_slice(&id_to_u8_array(vertex_rank));
+                pattern_code.extend_from_slice(&label_to_u8_array(vertex_label));
             }
             pattern_code
         } else if self.get_vertices_num() == 1 {
@@ -62,12 +128,39 @@ impl Pattern {
     pub fn decode_from(code: &[u8]) -> Option<Pattern> {
         if code.is_empty() {
             None
-        } else if code.len() % 20 == 0 {
-            let mut pattern_edges = Vec::with_capacity(code.len() / 20);
-            for i in 0..(code.len() / 20) {
-                let edge_id = i;
-                let k = i * 20;
-                let edge_label = u8_array_to_label(&code[k..k + 4]);
+        } else if code.len() == 4 {
+            let pattern_label = u8_array_to_label(code);
+            Some(Pattern::from(PatternVertex::new(0, pattern_label)))
+        } else {
+            // Every edge takes 20 bytes, a path edge takes 8 more bytes for its hop range, and every
+            // isolated vertex takes 12 bytes
+            let mut pattern_vertices = vec![];
+            let mut pattern_edges = vec![];
+            let mut k = 0;
+            while k < code.len() {
+                if k + 4 > code.len() {
+                    return None;
+                }
+                let edge_label_code = u8_array_to_u32(&code[k..k + 4]);
+                if edge_label_code == ISOLATED_VERTEX_FLAG {
+                    if k + 12 > code.len() {
+                        return None;
+                    }
+                    pattern_vertices.push(PatternVertex::new(
+                        u8_array_to_id(&code[k + 4..k + 8]),
+                        u8_array_to_label(&code[k + 8..k + 12]),
+                    ));
+                    k += 12;
+                    continue;
+                }
+                if k + 20 > code.len() {
+                    return None;
+                }
+                let edge_id = pattern_edges.len();
+                let is_undirected = edge_label_code & UNDIRECTED_EDGE_LABEL_FLAG != 0;
+                let is_path = edge_label_code & PATH_EDGE_LABEL_FLAG != 0;
+                let edge_label_flags = UNDIRECTED_EDGE_LABEL_FLAG | PATH_EDGE_LABEL_FLAG;
+                let edge_label = (edge_label_code & !edge_label_flags) as PatternLabelId;
                 let start_vertex = PatternVertex::new(
                     u8_array_to_id(&code[k + 4..k + 8]),
                     u8_array_to_label(&code[k + 8..k + 12]),
@@ -76,14 +169,29 @@ impl Pattern {
                     u8_array_to_id(&code[k + 12..k + 16]),
                     u8_array_to_label(&code[k + 16..k + 20]),
                 );
-                pattern_edges.push(PatternEdge::new(edge_id, edge_label, start_vertex, end_vertex));
+                k += 20;
+                let hop_range = if is_path {
+                    if k + 8 > code.len() {
+                        return None;
+                    }
+                    let hop_min = u8_array_to_u32(&code[k..k + 4]) as usize;
+                    let hop_max = u8_array_to_u32(&code[k + 4..k + 8]) as usize;
+                    k += 8;
+                    Some((hop_min, hop_max))
+                } else {
+                    None
+                };
+                pattern_edges.push(
+                    PatternEdge::new(edge_id, edge_label, start_vertex, end_vertex)
+                        .with_undirected(is_undirected)
+                        .with_hop_range(hop_range),
+                );
+            }
+            if pattern_vertices.is_empty() {
+                Pattern::try_from(pattern_edges).ok()
+            } else {
+                Pattern::from_parts(pattern_vertices, pattern_edges).ok()
             }
-            Some(Pattern::try_from(pattern_edges).unwrap())
-        } else if code.len() == 4 {
-            let pattern_label = u8_array_to_label(code);
-            Some(Pattern::from(PatternVertex::new(0, pattern_label)))
-        } else {
-            None
         }
     }
 }
@@ -95,7 +203,12 @@ impl ExtendStep {
         for extend_edge in extend_edges {
             extend_code.extend_from_slice(&id_to_u8_array(extend_edge.get_src_vertex_rank()));
             extend_code.extend_from_slice(&label_to_u8_array(extend_edge.get_edge_label()));
-            extend_code.push(extend_edge.get_direction().into())
+            // The direction of an undirected extend edge is encoded as 2 (both directions)
+            if extend_edge.is_undirected() {
+                extend_code.push(UNDIRECTED_EXTEND_EDGE_DIRECTION)
+            } else {
+                extend_code.push(extend_edge.get_direction().into())
+            }
         }
         extend_code.extend_from_slice(&label_to_u8_array(self.get_target_vertex_label()));
         extend_code
@@ -109,14 +222,17 @@ impl ExtendStep {
                 let k = i * 9;
                 let src_vertex_rank = u8_array_to_id(&code[k..k + 4]);
                 let edge_label = u8_array_to_label(&code[k + 4..k + 8]);
-                let dir = if code[k + 8] == 0 {
-                    PatternDirection::Out
+                let (dir, is_undirected) = if code[k + 8] == 0 {
+                    (PatternDirection::Out, false)
                 } else if code[k + 8] == 1 {
-                    PatternDirection::In
+                    (PatternDirection::In, false)
+                } else if code[k + 8] == UNDIRECTED_EXTEND_EDGE_DIRECTION {
+                    (PatternDirection::Out, true)
                 } else {
                     return None;
                 };
-                extend_edges.push(ExtendEdge::new(src_vertex_rank, edge_label, dir));
+                extend_edges
+                    .push(ExtendEdge::new(src_vertex_rank, edge_label, dir).with_undirected(is_undirected));
             }
             let target_vertex_label = u8_array_to_label(&code[code.len() - 4..code.len()]);
             Some(ExtendStep::new(target_vertex_label, extend_edges))
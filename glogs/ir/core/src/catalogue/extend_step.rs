@@ -19,6 +19,7 @@ use std::convert::TryFrom;
 use std::iter::Iterator;
 
 use ir_common::generated::algebra as pb;
+use ir_common::generated::common as common_pb;
 use serde::{Deserialize, Serialize};
 
 use crate::catalogue::pattern::{Pattern, PatternVertex};
@@ -30,12 +31,24 @@ pub struct ExtendEdge {
     src_vertex_rank: PatternId,
     edge_label: PatternLabelId,
     dir: PatternDirection,
+    /// Whether the edge to extend is undirected, in which case dir is always Out
+    is_undirected: bool,
 }
 
 /// Initializer of ExtendEdge
 impl ExtendEdge {
     pub fn new(src_vertex_rank: usize, edge_label: PatternLabelId, dir: PatternDirection) -> ExtendEdge {
-        ExtendEdge { src_vertex_rank, edge_label, dir }
+        ExtendEdge { src_vertex_rank, edge_label, dir, is_undirected: false }
+    }
+
+    /// Mark the edge to extend as undirected (or not), where the direction of an undirected edge is
+    /// taken as Out, so that the extend edges which differ only in orientation are the same
+    pub fn with_undirected(mut self, is_undirected: bool) -> ExtendEdge {
+        self.is_undirected = is_undirected;
+        if is_undirected {
+            self.dir = PatternDirection::Out;
+        }
+        self
     }
 }
 
@@ -55,6 +68,11 @@ impl ExtendEdge {
     pub fn get_direction(&self) -> PatternDirection {
         self.dir
     }
+
+    #[inline]
+    pub fn is_undirected(&self) -> bool {
+        self.is_undirected
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -107,6 +125,10 @@ pub struct DefiniteExtendEdge {
     edge_id: PatternId,
     edge_label: PatternLabelId,
     dir: PatternDirection,
+    /// Whether the edge to extend is undirected, in which case dir is ignored
+    is_undirected: bool,
+    /// The predicate on the edge to extend if any
+    predicate: Option<common_pb::Expression>,
 }
 
 /// Initializer of DefiniteExtendEdge
@@ -114,7 +136,17 @@ impl DefiniteExtendEdge {
     pub fn new(
         src_vertex: PatternVertex, edge_id: PatternId, edge_label: PatternLabelId, dir: PatternDirection,
     ) -> DefiniteExtendEdge {
-        DefiniteExtendEdge { src_vertex, edge_id, edge_label, dir }
+        DefiniteExtendEdge { src_vertex, edge_id, edge_label, dir, is_undirected: false, predicate: None }
+    }
+
+    pub fn with_undirected(mut self, is_undirected: bool) -> DefiniteExtendEdge {
+        self.is_undirected = is_undirected;
+        self
+    }
+
+    pub fn with_predicate(mut self, predicate: Option<common_pb::Expression>) -> DefiniteExtendEdge {
+        self.predicate = predicate;
+        self
     }
 
     pub fn from_extend_edge(extend_edge: &ExtendEdge, pattern: &Pattern) -> Option<DefiniteExtendEdge> {
@@ -122,7 +154,10 @@ impl DefiniteExtendEdge {
             let edge_id = pattern.get_max_edge_id() + 1;
             let edge_label = extend_edge.get_edge_label();
             let dir = extend_edge.get_direction();
-            Some(DefiniteExtendEdge { src_vertex, edge_id, edge_label, dir })
+            Some(
+                DefiniteExtendEdge::new(src_vertex, edge_id, edge_label, dir)
+                    .with_undirected(extend_edge.is_undirected()),
+            )
         } else {
             None
         }
@@ -145,6 +180,14 @@ impl DefiniteExtendEdge {
     pub fn get_direction(&self) -> PatternDirection {
         self.dir
     }
+
+    pub fn is_undirected(&self) -> bool {
+        self.is_undirected
+    }
+
+    pub fn get_predicate(&self) -> Option<&common_pb::Expression> {
+        self.predicate.as_ref()
+    }
 }
 
 /// Given a DefiniteExtendStep, we can uniquely find which part of the pattern to extend
@@ -152,6 +195,7 @@ impl DefiniteExtendEdge {
 pub struct DefiniteExtendStep {
     target_vertex: PatternVertex,
     extend_edges: Vec<DefiniteExtendEdge>,
+    target_vertex_predicate: Option<common_pb::Expression>,
 }
 
 /// Transform a one-vertex pattern to DefiniteExtendStep
@@ -162,7 +206,11 @@ impl TryFrom<Pattern> for DefiniteExtendStep {
     fn try_from(pattern: Pattern) -> IrResult<Self> {
         if pattern.get_vertices_num() == 1 {
             let target_vertex = *pattern.vertices_iter().last().unwrap();
-            Ok(DefiniteExtendStep { target_vertex, extend_edges: vec![] })
+            let target_vertex_predicate = pattern
+                .get_vertex_predicate(target_vertex.get_id())
+                .cloned();
+            Ok(DefiniteExtendStep::new(target_vertex, vec![])
+                .with_target_vertex_predicate(target_vertex_predicate))
         } else {
             Err(IrError::Unsupported(
                 "Can only convert pattern with one vertex to Definite Extend Step".to_string(),
@@ -173,7 +221,14 @@ impl TryFrom<Pattern> for DefiniteExtendStep {
 
 impl DefiniteExtendStep {
     pub fn new(target_vertex: PatternVertex, extend_edges: Vec<DefiniteExtendEdge>) -> DefiniteExtendStep {
-        DefiniteExtendStep { target_vertex, extend_edges }
+        DefiniteExtendStep { target_vertex, extend_edges, target_vertex_predicate: None }
+    }
+
+    pub fn with_target_vertex_predicate(
+        mut self, target_vertex_predicate: Option<common_pb::Expression>,
+    ) -> DefiniteExtendStep {
+        self.target_vertex_predicate = target_vertex_predicate;
+        self
     }
 
     /// Given a target pattern with a vertex id, pick all its neiboring edges and vertices to generate a definite extend step
@@ -184,23 +239,40 @@ impl DefiniteExtendStep {
                 let edge_id = adjacency.get_edge_id();
                 let dir = adjacency.get_direction();
                 let edge = target_pattern.get_edge(edge_id).unwrap();
+                let edge_predicate = target_pattern
+                    .get_edge_predicate(edge_id)
+                    .cloned();
                 if let PatternDirection::In = dir {
-                    extend_edges.push(DefiniteExtendEdge::new(
-                        edge.get_start_vertex(),
-                        edge_id,
-                        edge.get_label(),
-                        PatternDirection::Out,
-                    ));
+                    extend_edges.push(
+                        DefiniteExtendEdge::new(
+                            edge.get_start_vertex(),
+                            edge_id,
+                            edge.get_label(),
+                            PatternDirection::Out,
+                        )
+                        .with_undirected(edge.is_undirected())
+                        .with_predicate(edge_predicate),
+                    );
                 } else {
-                    extend_edges.push(DefiniteExtendEdge::new(
-                        edge.get_end_vertex(),
-                        edge_id,
-                        edge.get_label(),
-                        PatternDirection::In,
-                    ));
+                    extend_edges.push(
+                        DefiniteExtendEdge::new(
+                            edge.get_end_vertex(),
+                            edge_id,
+                            edge.get_label(),
+                            PatternDirection::In,
+                        )
+                        .with_undirected(edge.is_undirected())
+                        .with_predicate(edge_predicate),
+                    );
                 }
             }
-            Some(DefiniteExtendStep { target_vertex, extend_edges })
+            let target_vertex_predicate = target_pattern
+                .get_vertex_predicate(target_vertex_id)
+                .cloned();
+            Some(
+                DefiniteExtendStep::new(target_vertex, extend_edges)
+                    .with_target_vertex_predicate(target_vertex_predicate),
+            )
         } else {
             None
         }
@@ -230,12 +302,15 @@ impl DefiniteExtendStep {
                         ))
                         .cloned()
                     {
-                        definite_extend_edges.push(DefiniteExtendEdge::new(
-                            src_vertex_candidate,
-                            edge_id_to_assign,
-                            extend_edge.get_edge_label(),
-                            extend_edge.get_direction(),
-                        ));
+                        definite_extend_edges.push(
+                            DefiniteExtendEdge::new(
+                                src_vertex_candidate,
+                                edge_id_to_assign,
+                                extend_edge.get_edge_label(),
+                                extend_edge.get_direction(),
+                            )
+                            .with_undirected(extend_edge.is_undirected()),
+                        );
                         found_src_vertex = true;
                         break;
                     }
@@ -248,7 +323,7 @@ impl DefiniteExtendStep {
             }
         }
         let target_vertex = PatternVertex::new(vertex_id_to_assign, extend_step.get_target_vertex_label());
-        Some(DefiniteExtendStep { target_vertex, extend_edges: definite_extend_edges })
+        Some(DefiniteExtendStep::new(target_vertex, definite_extend_edges))
     }
 }
 
@@ -264,6 +339,11 @@ impl DefiniteExtendStep {
         self.extend_edges.len()
     }
 
+    #[inline]
+    pub fn get_target_vertex_predicate(&self) -> Option<&common_pb::Expression> {
+        self.target_vertex_predicate.as_ref()
+    }
+
     pub fn iter(&self) -> DynIter<&DefiniteExtendEdge> {
         Box::new(self.extend_edges.iter())
     }
@@ -300,6 +380,63 @@ impl DefiniteExtendStep {
         expand_operators
     }
 
+    /// Generate the PathExpand operator if the DefiniteExtendStep extends a variable-length path edge,
+    /// followed by the GetV operator to tag the end vertex of the path as the target vertex
+    ///
+    /// A path edge can only be extended alone, as its end vertices cannot be intersected with others.
+    pub fn generate_path_expand_operators(
+        &self, origin_pattern: &Pattern,
+    ) -> IrResult<Option<(pb::PathExpand, pb::GetV)>> {
+        let has_path_edge = self.extend_edges.iter().any(|extend_edge| {
+            origin_pattern
+                .get_edge(extend_edge.edge_id)
+                .map(|edge| edge.is_path())
+                .unwrap_or(false)
+        });
+        if !has_path_edge {
+            return Ok(None);
+        } else if self.extend_edges.len() > 1 {
+            return Err(IrError::Unsupported(
+                "Build logical plan error: path edge cannot be extended with other edges".to_string(),
+            ));
+        }
+        let extend_edge = &self.extend_edges[0];
+        let (hop_min, hop_max) = origin_pattern
+            .get_edge(extend_edge.edge_id)
+            .and_then(|edge| edge.get_hop_range())
+            .unwrap();
+        if hop_min == 0 || hop_min > hop_max {
+            return Err(IrError::InvalidRange(hop_min as i32, hop_max as i32));
+        }
+        let edge_predicate = origin_pattern
+            .get_edge_predicate(extend_edge.edge_id)
+            .cloned();
+        let path_expand = pb::PathExpand {
+            base: Some(pb::EdgeExpand {
+                v_tag: None,
+                direction: extend_edge.dir as i32,
+                params: Some(query_params(vec![extend_edge.edge_label.into()], vec![], edge_predicate)),
+                expand_opt: pb::edge_expand::ExpandOpt::Vertex as i32,
+                alias: None,
+            }),
+            // use start vertex id as tag
+            start_tag: Some((extend_edge.get_src_vertex().get_id() as i32).into()),
+            alias: None,
+            // the hop range of PathExpand is [lower, upper)
+            hop_range: Some(pb::Range { lower: hop_min as i32, upper: hop_max as i32 + 1 }),
+            path_opt: pb::path_expand::PathOpt::Arbitrary as i32,
+            result_opt: pb::path_expand::ResultOpt::EndV as i32,
+        };
+        let get_v = pb::GetV {
+            tag: None,
+            opt: pb::get_v::VOpt::End as i32,
+            params: None,
+            // use target vertex id as alias
+            alias: Some((self.get_target_vertex().get_id() as i32).into()),
+        };
+        Ok(Some((path_expand, get_v)))
+    }
+
     /// Generate the intersect operator for DefiniteExtendStep;s target vertex
     /// It needs its parent EdgeExpand Operator's node ids
     pub fn generate_intersect_operator(&self, parents: Vec<i32>) -> pb::Intersect {
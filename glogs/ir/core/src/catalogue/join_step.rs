@@ -276,17 +276,17 @@ impl BinaryJoinPlan {
 impl BinaryJoinPlan {
     /// Determine whether a decomposition plan is valid or not
     /// This function is used to filter some useless decompoistion plans, and the criteria are as follow:
-    /// - **There must be at least one disjoint vertex in both build and probe patterns.**
-    /// (Only one disjoint vertex is equivalent to Extend Step)
+    /// - **There must be at least min_disjoint_vertices_num disjoint vertices in both patterns.**
+    /// (Only one disjoint vertex is equivalent to Extend Step, useful only if no extend step is allowed)
     /// - **Build Pattern must be larger in size than probe pattern.**
     /// The reason is that when we perform bottom-up DP algorithm, we can always find the best plan of the smaller probe pattern when the larger build pattern is considered.
-    fn is_valid(&self, target_pattern: &Pattern) -> bool {
+    fn is_valid(&self, target_pattern: &Pattern, min_disjoint_vertices_num: usize) -> bool {
         let build_pattern_vertices_num = self.build_pattern.get_vertices_num();
         let probe_pattern_vertices_num = self.probe_pattern.get_vertices_num();
         let shared_vertices_num = self.shared_vertices.len();
         let is_plan_valid: bool = shared_vertices_num > 0
-            && build_pattern_vertices_num > shared_vertices_num + 1
-            && probe_pattern_vertices_num > shared_vertices_num + 1;
+            && build_pattern_vertices_num >= shared_vertices_num + min_disjoint_vertices_num
+            && probe_pattern_vertices_num >= shared_vertices_num + min_disjoint_vertices_num;
         if !is_plan_valid {
             return false;
         }
@@ -406,6 +406,16 @@ impl Pattern {
     ///
     /// Shared vertices are those used as the criteria of join operation, therefore existing in both patterns, and disjoint vertices can only exist in one of the patterns.
     pub fn binary_join_decomposition(&self) -> IrResult<Vec<BinaryJoinPlan>> {
+        self.binary_join_decomposition_with_min_disjoint_vertices(2)
+    }
+
+    /// Decompose the pattern into join plans whose build and probe patterns both have
+    /// at least min_disjoint_vertices_num disjoint vertices
+    ///
+    /// With 1, a pattern can be decomposed until single edges, as the pure binary join plan space does
+    pub fn binary_join_decomposition_with_min_disjoint_vertices(
+        &self, min_disjoint_vertices_num: usize,
+    ) -> IrResult<Vec<BinaryJoinPlan>> {
         // Pattern with less than 5 vertices has no binary join plan
         // if self.get_vertices_num() < 5 {
         //     return Ok(vec![]);
@@ -438,7 +448,10 @@ impl Pattern {
                 });
             // candidate_plan_queue.append(&mut self.extend_decomposition_plans(&candidate_plan));
 
-            if candidate_plan.plan.is_valid(self) {
+            if candidate_plan
+                .plan
+                .is_valid(self, min_disjoint_vertices_num)
+            {
                 let mut build_pattern: Pattern = candidate_plan.plan.build_pattern;
                 let mut probe_pattern: Pattern = candidate_plan.plan.probe_pattern;
                 if build_pattern.get_vertices_num() < probe_pattern.get_vertices_num() {
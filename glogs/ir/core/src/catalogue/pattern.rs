@@ -17,25 +17,32 @@ use std::collections::{BTreeMap, BTreeSet, HashMap, VecDeque};
 use std::convert::{TryFrom, TryInto};
 use std::fmt::{Debug, Display};
 use std::fs::File;
-use std::io::{self, BufReader, BufWriter};
+use std::hash::{Hash, Hasher};
+use std::io::{self, BufRead, BufReader, BufWriter, Read};
 use std::iter::FromIterator;
 use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering as AtomicOrdering};
+use std::sync::RwLock;
+use std::time::Duration;
 
 use ir_common::generated::algebra as pb;
 use ir_common::generated::common as common_pb;
+use itertools::Itertools;
 use ordered_float::OrderedFloat;
+use prost::Message;
 use serde::de::Visitor;
 use serde::{Deserialize, Serialize};
 use vec_map::VecMap;
 
-use crate::catalogue::canonical_label::CanonicalLabelManager;
+use crate::catalogue::canonical_label::{CanonicalLabelManager, GroupingAlgo};
 use crate::catalogue::extend_step::{
-    get_subsets, limit_repeated_element_num, DefiniteExtendEdge, ExtendEdge, ExtendStep,
+    get_subsets, limit_repeated_element_num, DefiniteExtendEdge, DefiniteExtendStep, ExtendEdge, ExtendStep,
 };
 use crate::catalogue::pattern_meta::PatternMeta;
 use crate::catalogue::{DynIter, PatternDirection, PatternId, PatternLabelId};
 use crate::error::{IrError, IrResult};
 use crate::plan::meta::{PlanMeta, TagId};
+use crate::JsonIO;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct PatternVertex {
@@ -71,25 +78,72 @@ struct PatternVertexData {
     out_adjacencies: Vec<Adjacency>,
     /// Incoming adjacent edges and vertices related to this vertex
     in_adjacencies: Vec<Adjacency>,
+    /// Undirected adjacent edges and vertices related to this vertex, neither outgoing nor incoming
+    bidirectional_adjacencies: Vec<Adjacency>,
     /// Tag (alias) assigned to this vertex by user
     tag: Option<TagId>,
     /// Predicate(filter or other expressions) this vertex has
     predicate: Option<common_pb::Expression>,
 }
 
+impl PatternVertexData {
+    /// Add the adjacency to the outgoing, incoming or bidirectional adjacencies it belongs to
+    fn add_adjacency(&mut self, adjacency: Adjacency) {
+        if adjacency.is_bidirectional() {
+            self.bidirectional_adjacencies.push(adjacency)
+        } else if let PatternDirection::Out = adjacency.get_direction() {
+            self.out_adjacencies.push(adjacency)
+        } else {
+            self.in_adjacencies.push(adjacency)
+        }
+    }
+
+    /// Remove the adjacency through the given edge
+    fn remove_adjacency(&mut self, edge_id: PatternId) {
+        self.out_adjacencies
+            .retain(|adj| adj.get_edge_id() != edge_id);
+        self.in_adjacencies
+            .retain(|adj| adj.get_edge_id() != edge_id);
+        self.bidirectional_adjacencies
+            .retain(|adj| adj.get_edge_id() != edge_id);
+    }
+
+    fn clear_adjacencies(&mut self) {
+        self.out_adjacencies.clear();
+        self.in_adjacencies.clear();
+        self.bidirectional_adjacencies.clear();
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct PatternEdge {
     id: PatternId,
     label: PatternLabelId,
     start_vertex: PatternVertex,
     end_vertex: PatternVertex,
+    /// Whether the edge is undirected (both direction), where start and end vertices are interchangeable
+    is_undirected: bool,
+    /// The range `[hop_min, hop_max]` of hops if the edge is a variable-length path, e.g., `knows*1..3`
+    hop_range: Option<(usize, usize)>,
 }
 
 impl PatternEdge {
     pub fn new(
         id: PatternId, label: PatternLabelId, start_vertex: PatternVertex, end_vertex: PatternVertex,
     ) -> PatternEdge {
-        PatternEdge { id, label, start_vertex, end_vertex }
+        PatternEdge { id, label, start_vertex, end_vertex, is_undirected: false, hop_range: None }
+    }
+
+    /// Mark the edge as undirected (or not), so that its orientation is ignored
+    pub fn with_undirected(mut self, is_undirected: bool) -> PatternEdge {
+        self.is_undirected = is_undirected;
+        self
+    }
+
+    /// Make the edge a variable-length path of `[hop_min, hop_max]` hops (or a single edge if None)
+    pub fn with_hop_range(mut self, hop_range: Option<(usize, usize)>) -> PatternEdge {
+        self.hop_range = hop_range;
+        self
     }
 
     /// If the given direction is incoming, reverse the start and end vertex
@@ -119,6 +173,21 @@ impl PatternEdge {
     pub fn get_end_vertex(&self) -> PatternVertex {
         self.end_vertex
     }
+
+    #[inline]
+    pub fn is_undirected(&self) -> bool {
+        self.is_undirected
+    }
+
+    #[inline]
+    pub fn get_hop_range(&self) -> Option<(usize, usize)> {
+        self.hop_range
+    }
+
+    #[inline]
+    pub fn is_path(&self) -> bool {
+        self.hop_range.is_some()
+    }
 }
 
 /// Each PatternEdge of a Pattern has a related PatternEdgeData struct
@@ -144,9 +213,17 @@ pub struct Adjacency {
     adj_vertex: PatternVertex,
     /// the connecting direction: outgoing or incoming
     direction: PatternDirection,
+    /// whether the connecting edge is undirected, in which case the direction is only
+    /// a storage detail and should be ignored when comparing adjacencies
+    is_bidirectional: bool,
+    /// the hop range of the connecting edge if it is a path
+    hop_range: Option<(usize, usize)>,
 }
 
 impl Adjacency {
+    /// The adjacency of an undirected edge is flagged as bidirectional, and is kept apart from the
+    /// outgoing and incoming adjacencies of the vertex, while its direction only records how the
+    /// edge is stored
     fn new(src_vertex: &PatternVertex, edge: &PatternEdge) -> Option<Adjacency> {
         let start_vertex = edge.get_start_vertex();
         let end_vertex = edge.get_end_vertex();
@@ -156,6 +233,8 @@ impl Adjacency {
                 edge_label: edge.get_label(),
                 adj_vertex: edge.get_end_vertex(),
                 direction: PatternDirection::Out,
+                is_bidirectional: edge.is_undirected(),
+                hop_range: edge.get_hop_range(),
             })
         } else if (src_vertex.id, src_vertex.label) == (end_vertex.id, end_vertex.label) {
             Some(Adjacency {
@@ -163,6 +242,8 @@ impl Adjacency {
                 edge_label: edge.get_label(),
                 adj_vertex: edge.get_start_vertex(),
                 direction: PatternDirection::In,
+                is_bidirectional: edge.is_undirected(),
+                hop_range: edge.get_hop_range(),
             })
         } else {
             None
@@ -188,6 +269,58 @@ impl Adjacency {
     pub fn get_direction(&self) -> PatternDirection {
         self.direction
     }
+
+    #[inline]
+    pub fn get_hop_range(&self) -> Option<(usize, usize)> {
+        self.hop_range
+    }
+
+    #[inline]
+    pub fn is_bidirectional(&self) -> bool {
+        self.is_bidirectional
+    }
+}
+
+/// Lazily computed canonical code of a pattern
+///
+/// The cache is reset whenever the pattern is relabeled, i.e., whenever the pattern is edited
+#[derive(Default)]
+pub(crate) struct PatternCodeCache {
+    code: RwLock<Option<Vec<u8>>>,
+    /// Number of times the cached code is reused instead of being recomputed
+    hits: AtomicUsize,
+}
+
+impl PatternCodeCache {
+    /// Return the cached code, or compute it by `encode` and cache it if it is absent
+    pub(crate) fn get_or_insert_with<F: FnOnce() -> Vec<u8>>(&self, encode: F) -> Vec<u8> {
+        if let Some(code) = self.code.read().unwrap().as_ref() {
+            self.hits.fetch_add(1, AtomicOrdering::Relaxed);
+            return code.clone();
+        }
+        let code = encode();
+        *self.code.write().unwrap() = Some(code.clone());
+        code
+    }
+
+    pub(crate) fn get_hits(&self) -> usize {
+        self.hits.load(AtomicOrdering::Relaxed)
+    }
+}
+
+/// A cloned pattern is the same as the original one, so it also takes the cached code
+impl Clone for PatternCodeCache {
+    fn clone(&self) -> Self {
+        PatternCodeCache { code: RwLock::new(self.code.read().unwrap().clone()), hits: AtomicUsize::new(0) }
+    }
+}
+
+impl Debug for PatternCodeCache {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PatternCodeCache")
+            .field("code", &*self.code.read().unwrap())
+            .finish()
+    }
 }
 
 #[derive(Debug, Clone, Default)]
@@ -214,6 +347,8 @@ pub struct Pattern {
     /// Key: vertex's Tag info, Value: vertex id
     /// - use a Tag to locate a vertex
     tag_vertex_map: BTreeMap<TagId, PatternId>,
+    /// Cache of the canonical code of the pattern
+    code_cache: PatternCodeCache,
 }
 
 /// Initialze a Pattern from just a single Pattern Vertex
@@ -228,6 +363,7 @@ impl From<PatternVertex> for Pattern {
             rank_vertex_map: VecMap::from_iter([(0, vertex.id)]),
             tag_edge_map: BTreeMap::new(),
             tag_vertex_map: BTreeMap::new(),
+            code_cache: PatternCodeCache::default(),
         }
     }
 }
@@ -257,8 +393,7 @@ impl TryFrom<Vec<PatternEdge>> for Pattern {
                     .vertices_data
                     .entry(start_vertex.get_id())
                     .or_insert(PatternVertexData::default())
-                    .out_adjacencies
-                    .push(Adjacency::new(start_vertex, &edge).unwrap());
+                    .add_adjacency(Adjacency::new(start_vertex, &edge).unwrap());
                 // Add or update the end vertex to the new Pattern
                 let end_vertex = new_pattern
                     .vertices
@@ -269,8 +404,7 @@ impl TryFrom<Vec<PatternEdge>> for Pattern {
                     .vertices_data
                     .entry(end_vertex.get_id())
                     .or_insert(PatternVertexData::default())
-                    .in_adjacencies
-                    .push(Adjacency::new(end_vertex, &edge).unwrap());
+                    .add_adjacency(Adjacency::new(end_vertex, &edge).unwrap());
             }
             new_pattern.canonical_labeling();
             Ok(new_pattern)
@@ -280,14 +414,201 @@ impl TryFrom<Vec<PatternEdge>> for Pattern {
     }
 }
 
+/// Initialize a Pattern from both Pattern Vertices and Pattern Edges
+impl Pattern {
+    /// Build a pattern from the given vertices and edges
+    /// - the end vertices of the edges need not be listed in `vertices`
+    /// - vertices not connected by any edge are kept as isolated vertices,
+    ///   so the pattern can be disconnected
+    pub fn from_parts(vertices: Vec<PatternVertex>, edges: Vec<PatternEdge>) -> IrResult<Pattern> {
+        if vertices.is_empty() && edges.is_empty() {
+            return Err(IrError::InvalidPattern("Empty pattern".to_string()));
+        }
+        let mut new_pattern = Pattern::default();
+        let edge_vertices = edges
+            .iter()
+            .flat_map(|edge| vec![edge.get_start_vertex(), edge.get_end_vertex()]);
+        for vertex in vertices.into_iter().chain(edge_vertices) {
+            let existing_vertex = *new_pattern
+                .vertices
+                .entry(vertex.get_id())
+                .or_insert(vertex);
+            if existing_vertex.get_label() != vertex.get_label() {
+                return Err(IrError::InvalidPattern(format!(
+                    "vertex {} has conflicting labels {} and {}",
+                    vertex.get_id(),
+                    existing_vertex.get_label(),
+                    vertex.get_label()
+                )));
+            }
+            new_pattern
+                .vertices_data
+                .entry(vertex.get_id())
+                .or_insert(PatternVertexData::default());
+        }
+        for edge in edges {
+            if new_pattern.edges.contains_key(edge.get_id()) {
+                return Err(IrError::InvalidPattern(format!("duplicate edge id {}", edge.get_id())));
+            }
+            let start_vertex = edge.get_start_vertex();
+            let end_vertex = edge.get_end_vertex();
+            new_pattern
+                .vertices_data
+                .get_mut(start_vertex.get_id())
+                .unwrap()
+                .add_adjacency(Adjacency::new(&start_vertex, &edge).unwrap());
+            new_pattern
+                .vertices_data
+                .get_mut(end_vertex.get_id())
+                .unwrap()
+                .add_adjacency(Adjacency::new(&end_vertex, &edge).unwrap());
+            new_pattern
+                .edges_data
+                .insert(edge.get_id(), PatternEdgeData::default());
+            new_pattern.edges.insert(edge.get_id(), edge);
+        }
+        new_pattern.canonical_labeling();
+        Ok(new_pattern)
+    }
+}
+
+/// Initialize a Pattern from an edge list
+impl Pattern {
+    /// Read a pattern from lines of `edge_id,edge_label,src_id,src_label,dst_id,dst_label`
+    /// - fields can be separated by either comma or tab
+    /// - blank lines are skipped
+    pub fn from_edge_list<R: Read>(reader: R) -> IrResult<Pattern> {
+        let mut pattern_edges = vec![];
+        for (line_no, line) in BufReader::new(reader).lines().enumerate() {
+            let line = line.map_err(|err| {
+                IrError::InvalidPattern(format!(
+                    "failed to read edge list at line {}: {}",
+                    line_no + 1,
+                    err
+                ))
+            })?;
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+            let fields: Vec<&str> = line
+                .split(|c| c == ',' || c == '\t')
+                .map(|field| field.trim())
+                .collect();
+            if fields.len() != 6 {
+                return Err(IrError::InvalidPattern(format!(
+                    "line {} of edge list should have 6 fields, but got {}: {}",
+                    line_no + 1,
+                    fields.len(),
+                    line
+                )));
+            }
+            let parse_id = |idx: usize| -> IrResult<PatternId> {
+                fields[idx].parse().map_err(|_| {
+                    IrError::InvalidPattern(format!(
+                        "invalid id `{}` at line {} of edge list",
+                        fields[idx],
+                        line_no + 1
+                    ))
+                })
+            };
+            let parse_label = |idx: usize| -> IrResult<PatternLabelId> {
+                fields[idx].parse().map_err(|_| {
+                    IrError::InvalidPattern(format!(
+                        "invalid label `{}` at line {} of edge list",
+                        fields[idx],
+                        line_no + 1
+                    ))
+                })
+            };
+            let start_vertex = PatternVertex::new(parse_id(2)?, parse_label(3)?);
+            let end_vertex = PatternVertex::new(parse_id(4)?, parse_label(5)?);
+            pattern_edges.push(PatternEdge::new(parse_id(0)?, parse_label(1)?, start_vertex, end_vertex));
+        }
+        Pattern::try_from(pattern_edges)
+    }
+}
+
 /// Initialize a Pattern from a protobuf Pattern
 impl Pattern {
     pub fn from_pb_pattern(
         pb_pattern: &pb::Pattern, pattern_meta: &PatternMeta, plan_meta: &mut PlanMeta,
     ) -> IrResult<Pattern> {
+        let mut patterns = Pattern::from_pb_fuzzy_pattern(pb_pattern, pattern_meta, plan_meta)?;
+        if patterns.len() > 1 {
+            return Err(IrError::Unsupported("Fuzzy Pattern".to_string()));
+        }
+        Ok(patterns.remove(0))
+    }
+
+    /// Build patterns from the pb pattern for every vertex label assignment consistent with the schema
+    ///
+    /// A pb pattern is fuzzy if some of its vertices have more than one candidate label,
+    /// and then one pattern is materialized for each candidate label assignment
+    ///
+    /// An edge expand with multiple labels is also fuzzy, and every candidate edge label is tried
+    pub fn from_pb_fuzzy_pattern(
+        pb_pattern: &pb::Pattern, pattern_meta: &PatternMeta, plan_meta: &mut PlanMeta,
+    ) -> IrResult<Vec<Pattern>> {
+        use pb::pattern::binder::Item as BinderItem;
+        // record the candidate labels of each edge expand in the order of edge id assignment
+        let mut edges_candidate_labels: Vec<Vec<PatternLabelId>> = vec![];
+        for sentence in &pb_pattern.sentences {
+            for binder in &sentence.binders {
+                if let Some(BinderItem::Edge(edge_expand)) = binder.item.as_ref() {
+                    let edge_labels = get_edge_expand_label(edge_expand)?;
+                    for &edge_label in edge_labels.iter() {
+                        if pattern_meta
+                            .get_edge_label_name(edge_label)
+                            .is_none()
+                        {
+                            return Err(IrError::InvalidPattern(format!(
+                                "edge expand label {} doesn't exist in schema",
+                                edge_label
+                            )));
+                        }
+                    }
+                    edges_candidate_labels.push(edge_labels);
+                }
+            }
+        }
+        let edge_label_choices: Vec<Vec<PatternLabelId>> = if edges_candidate_labels.is_empty() {
+            vec![vec![]]
+        } else {
+            edges_candidate_labels
+                .into_iter()
+                .multi_cartesian_product()
+                .collect()
+        };
+        let max_tag_id = plan_meta.get_max_tag_id() as PatternId;
+        let mut next_vertex_id = max_tag_id;
+        let mut patterns = vec![];
+        for edge_labels in edge_label_choices {
+            let (choice_patterns, choice_next_vertex_id) = Pattern::from_pb_pattern_with_edge_labels(
+                pb_pattern,
+                pattern_meta,
+                max_tag_id,
+                &edge_labels,
+            )?;
+            patterns.extend(choice_patterns);
+            next_vertex_id = choice_next_vertex_id;
+        }
+        if patterns.is_empty() {
+            return Err(IrError::InvalidPattern("The pattern is illegal according to schema".to_string()));
+        }
+        plan_meta.set_max_tag_id(next_vertex_id as TagId);
+        Ok(patterns)
+    }
+
+    /// Build patterns from the pb pattern with the label of every edge expand fixed,
+    /// and return them together with the next vertex id to assign
+    fn from_pb_pattern_with_edge_labels(
+        pb_pattern: &pb::Pattern, pattern_meta: &PatternMeta, max_tag_id: PatternId,
+        edge_labels: &[PatternLabelId],
+    ) -> IrResult<(Vec<Pattern>, PatternId)> {
         use pb::pattern::binder::Item as BinderItem;
         // next vertex id assign to the vertex picked from the pb pattern
-        let mut next_vertex_id = plan_meta.get_max_tag_id() as PatternId;
+        let mut next_vertex_id = max_tag_id;
         // next edge id assign to the edge picked from the pb pattern
         let mut next_edge_id = 0;
         // record the vertices from the pb pattern having tags
@@ -331,10 +652,10 @@ impl Pattern {
             // iterate over the binders
             for (i, binder) in sentence.binders.iter().enumerate() {
                 if let Some(BinderItem::Edge(edge_expand)) = binder.item.as_ref() {
-                    // get edge label's id
-                    let edge_label = get_edge_expand_label(edge_expand)?;
                     // assign the new pattern edge with a new id
                     let edge_id = assign_id(&mut next_edge_id, None);
+                    // get edge label's id
+                    let edge_label = edge_labels[edge_id];
                     // get edge direction
                     let edge_direction = PatternDirection::try_from(edge_expand.direction)?;
                     // add edge predicate
@@ -382,38 +703,37 @@ impl Pattern {
                 }
             }
         }
-        if v_id_label_maps.is_empty() {
-            return Err(IrError::InvalidPattern("The pattern is illegal according to schema".to_string()));
-        } else if v_id_label_maps.len() > 1 {
-            return Err(IrError::Unsupported("Fuzzy Pattern".to_string()));
-        }
-        let v_id_label_map = v_id_label_maps.remove(0);
-        let pattern_edges: Vec<PatternEdge> = edges
+        let patterns: IrResult<Vec<Pattern>> = v_id_label_maps
             .into_iter()
-            .map(|(e_id, e_label, start_v_id, end_v_id)| {
-                let start_v_label = *v_id_label_map.get(&start_v_id).unwrap();
-                let end_v_label = *v_id_label_map.get(&end_v_id).unwrap();
-                PatternEdge::new(
-                    e_id,
-                    e_label,
-                    PatternVertex::new(start_v_id, start_v_label),
-                    PatternVertex::new(end_v_id, end_v_label),
-                )
+            .map(|v_id_label_map| {
+                let pattern_edges: Vec<PatternEdge> = edges
+                    .iter()
+                    .map(|&(e_id, e_label, start_v_id, end_v_id)| {
+                        let start_v_label = *v_id_label_map.get(&start_v_id).unwrap();
+                        let end_v_label = *v_id_label_map.get(&end_v_id).unwrap();
+                        PatternEdge::new(
+                            e_id,
+                            e_label,
+                            PatternVertex::new(start_v_id, start_v_label),
+                            PatternVertex::new(end_v_id, end_v_label),
+                        )
+                    })
+                    .collect();
+                Pattern::try_from(pattern_edges).map(|mut pattern| {
+                    for &tag in tag_set.iter() {
+                        pattern.set_vertex_tag(tag as PatternId, tag);
+                    }
+                    for (&v_id, predicate) in v_id_predicate_map.iter() {
+                        pattern.set_vertex_predicate(v_id, predicate.clone());
+                    }
+                    for (&e_id, predicate) in e_id_predicate_map.iter() {
+                        pattern.set_edge_predicate(e_id, predicate.clone());
+                    }
+                    pattern
+                })
             })
             .collect();
-        plan_meta.set_max_tag_id(next_vertex_id as TagId);
-        Pattern::try_from(pattern_edges).map(|mut pattern| {
-            for tag in tag_set {
-                pattern.set_vertex_tag(tag as PatternId, tag);
-            }
-            for (v_id, predicate) in v_id_predicate_map {
-                pattern.set_vertex_predicate(v_id, predicate);
-            }
-            for (e_id, predicate) in e_id_predicate_map {
-                pattern.set_edge_predicate(e_id, predicate);
-            }
-            pattern
-        })
+        Ok((patterns?, next_vertex_id))
     }
 }
 
@@ -463,25 +783,26 @@ fn get_sentence_last_expand_index(sentence: &pb::pattern::Sentence) -> Option<us
         .map(|(id, _)| id)
 }
 
-/// Get the edge expand's label
-/// - in current realization, edge_expand only allows to have one label
-/// - if it has no label or more than one label, give Error
-fn get_edge_expand_label(edge_expand: &pb::EdgeExpand) -> IrResult<PatternLabelId> {
+/// Get the edge expand's candidate labels
+/// - edge_expand is allowed to have multiple labels, each of which is a candidate label of the edge
+/// - if it has no label, give Error
+fn get_edge_expand_label(edge_expand: &pb::EdgeExpand) -> IrResult<Vec<PatternLabelId>> {
     if edge_expand.expand_opt != pb::edge_expand::ExpandOpt::Vertex as i32 {
         return Err(IrError::Unsupported("Expand only edge in pattern".to_string()));
     }
     if let Some(params) = edge_expand.params.as_ref() {
-        // TODO: Support Fuzzy Pattern
         if params.tables.is_empty() {
             return Err(IrError::Unsupported("FuzzyPattern: no specific edge expand label".to_string()));
-        } else if params.tables.len() > 1 {
-            return Err(IrError::Unsupported("FuzzyPattern: more than 1 edge expand label".to_string()));
-        }
-        // get edge label's id
-        match params.tables[0].item.as_ref() {
-            Some(common_pb::name_or_id::Item::Id(e_label_id)) => Ok(*e_label_id),
-            _ => Err(IrError::InvalidPattern("edge expand doesn't have valid label".to_string())),
         }
+        // get edge labels' ids
+        params
+            .tables
+            .iter()
+            .map(|table| match table.item.as_ref() {
+                Some(common_pb::name_or_id::Item::Id(e_label_id)) => Ok(*e_label_id),
+                _ => Err(IrError::InvalidPattern("edge expand doesn't have valid label".to_string())),
+            })
+            .collect()
     } else {
         Err(IrError::MissingData("pb::EdgeExpand.params".to_string()))
     }
@@ -552,14 +873,16 @@ fn get_src_dst_vertex_id_label_maps(
 ) -> Vec<BTreeMap<PatternId, PatternLabelId>> {
     match edge_direction {
         PatternDirection::Out => pattern_meta
-            .associated_vlabels_iter_by_elabel(edge_label)
-            .map(|(start_v_label, end_v_label)| {
+            .associated_vlabels(edge_label)
+            .iter()
+            .map(|&(start_v_label, end_v_label)| {
                 BTreeMap::from_iter([(src_vertex_id, start_v_label), (dst_vertex_id, end_v_label)])
             })
             .collect(),
         PatternDirection::In => pattern_meta
-            .associated_vlabels_iter_by_elabel(edge_label)
-            .map(|(start_v_label, end_v_label)| {
+            .associated_vlabels(edge_label)
+            .iter()
+            .map(|&(start_v_label, end_v_label)| {
                 BTreeMap::from_iter([(src_vertex_id, end_v_label), (dst_vertex_id, start_v_label)])
             })
             .collect(),
@@ -695,6 +1018,7 @@ impl Pattern {
         self.edges.len()
     }
 
+    /// Get the minimum edge id of the pattern, or 0 if the pattern has no edges
     #[inline]
     pub fn get_min_edge_id(&self) -> PatternId {
         self.edges
@@ -704,6 +1028,7 @@ impl Pattern {
             .unwrap_or(0)
     }
 
+    /// Get the maximum edge id of the pattern, or 0 if the pattern has no edges
     #[inline]
     pub fn get_max_edge_id(&self) -> PatternId {
         self.edges
@@ -783,22 +1108,24 @@ impl Pattern {
         self.vertices.len()
     }
 
+    /// Get the minimum vertex id of the pattern, or 0 if the pattern has no vertices
     #[inline]
     pub fn get_min_vertex_id(&self) -> PatternId {
         self.vertices
             .iter()
             .map(|(vertex_id, _)| vertex_id)
             .next()
-            .unwrap()
+            .unwrap_or(0)
     }
 
+    /// Get the maximum vertex id of the pattern, or 0 if the pattern has no vertices
     #[inline]
     pub fn get_max_vertex_id(&self) -> PatternId {
         self.vertices
             .iter()
             .map(|(vertex_id, _)| vertex_id)
             .last()
-            .unwrap()
+            .unwrap_or(0)
     }
 
     /// Get the minimum vertex label id of the current pattern
@@ -834,6 +1161,30 @@ impl Pattern {
             .map(|vertex_data| vertex_data.rank)
     }
 
+    /// Get the maps from vertex ranks to vertex ids, and from edge ranks to edge ids
+    ///
+    /// They are the inverses of get_vertex_rank and get_edge_rank, e.g., to align the vertices and
+    /// edges of patterns with the same code
+    pub fn rank_to_id_maps(&self) -> (BTreeMap<PatternId, PatternId>, BTreeMap<PatternId, PatternId>) {
+        let vertex_rank_id_map = self
+            .rank_vertex_map
+            .iter()
+            .map(|(vertex_rank, &vertex_id)| (vertex_rank, vertex_id))
+            .collect();
+        let edge_rank_id_map = self
+            .rank_edge_map
+            .iter()
+            .map(|(edge_rank, &edge_id)| (edge_rank, edge_id))
+            .collect();
+        (vertex_rank_id_map, edge_rank_id_map)
+    }
+
+    /// Get the cache of the pattern's canonical code
+    #[inline]
+    pub(crate) fn get_code_cache(&self) -> &PatternCodeCache {
+        &self.code_cache
+    }
+
     /// Get a PatternVertex's Tag info
     #[inline]
     pub fn get_vertex_tag(&self, vertex_id: PatternId) -> Option<TagId> {
@@ -868,10 +1219,21 @@ impl Pattern {
             .unwrap_or(0)
     }
 
+    /// Count how many undirected edges connect to this vertex
+    #[inline]
+    pub fn get_vertex_bidirectional_degree(&self, vertex_id: PatternId) -> usize {
+        self.vertices_data
+            .get(vertex_id)
+            .map(|vertex_data| vertex_data.bidirectional_adjacencies.len())
+            .unwrap_or(0)
+    }
+
     /// Count how many edges connect to this vertex
     #[inline]
     pub fn get_vertex_degree(&self, vertex_id: PatternId) -> usize {
-        self.get_vertex_out_degree(vertex_id) + self.get_vertex_in_degree(vertex_id)
+        self.get_vertex_out_degree(vertex_id)
+            + self.get_vertex_in_degree(vertex_id)
+            + self.get_vertex_bidirectional_degree(vertex_id)
     }
 
     #[inline]
@@ -884,7 +1246,7 @@ impl Pattern {
     #[inline]
     pub fn get_edges_predicate_num(&self) -> usize {
         self.edges_iter()
-            .map(|e| if self.get_vertex_predicate(e.get_id()).is_some() { 1 } else { 0 })
+            .map(|e| if self.get_edge_predicate(e.get_id()).is_some() { 1 } else { 0 })
             .sum()
     }
 
@@ -962,13 +1324,70 @@ impl Pattern {
         }
     }
 
-    /// Iterate both outgoing and incoming edges of the given vertex
+    /// Iterate all undirected edges of the given vertex
+    pub fn bidirectional_adjacencies_iter(&self, vertex_id: PatternId) -> DynIter<&Adjacency> {
+        if let Some(vertex_data) = self.vertices_data.get(vertex_id) {
+            Box::new(vertex_data.bidirectional_adjacencies.iter())
+        } else {
+            Box::new(std::iter::empty())
+        }
+    }
+
+    /// Iterate outgoing, incoming and undirected edges of the given vertex
     pub fn adjacencies_iter(&self, vertex_id: PatternId) -> DynIter<&Adjacency> {
         Box::new(
             self.out_adjacencies_iter(vertex_id)
-                .chain(self.in_adjacencies_iter(vertex_id)),
+                .chain(self.in_adjacencies_iter(vertex_id))
+                .chain(self.bidirectional_adjacencies_iter(vertex_id)),
+        )
+    }
+
+    /// Iterate the edges of the given vertex, where the vertex is the stored start vertex
+    ///
+    /// Every edge of the pattern is visited exactly once by iterating this over all vertices
+    fn stored_out_adjacencies_iter(&self, vertex_id: PatternId) -> DynIter<&Adjacency> {
+        Box::new(
+            self.out_adjacencies_iter(vertex_id).chain(
+                self.bidirectional_adjacencies_iter(vertex_id)
+                    .filter(|adj| adj.get_direction() == PatternDirection::Out),
+            ),
         )
     }
+
+    /// Iterate the adjacent vertices of the given vertex regardless of edge directions
+    ///
+    /// Each adjacent vertex is returned exactly once, even if it is connected by multiple edges
+    pub fn undirected_neighbors_iter(&self, vertex_id: PatternId) -> DynIter<PatternVertex> {
+        let neighbors: BTreeMap<PatternId, PatternVertex> = self
+            .adjacencies_iter(vertex_id)
+            .map(|adjacency| {
+                let adj_vertex = adjacency.get_adj_vertex();
+                (adj_vertex.get_id(), adj_vertex)
+            })
+            .collect();
+        Box::new(neighbors.into_values())
+    }
+
+    /// Get all edges between the two given vertices in either direction, ordered by edge ids
+    ///
+    /// More than one edge is returned when there are multi-edges between the vertices
+    pub fn edges_between(&self, vertex1_id: PatternId, vertex2_id: PatternId) -> Vec<&PatternEdge> {
+        let edge_ids: BTreeSet<PatternId> = self
+            .adjacencies_iter(vertex1_id)
+            .filter(|adjacency| adjacency.get_adj_vertex().get_id() == vertex2_id)
+            .map(|adjacency| adjacency.get_edge_id())
+            .collect();
+        edge_ids
+            .into_iter()
+            .map(|edge_id| self.get_edge(edge_id).unwrap())
+            .collect()
+    }
+
+    /// Determine whether the two given vertices are connected by an edge in either direction
+    pub fn are_adjacent(&self, vertex1_id: PatternId, vertex2_id: PatternId) -> bool {
+        self.adjacencies_iter(vertex1_id)
+            .any(|adjacency| adjacency.get_adj_vertex().get_id() == vertex2_id)
+    }
 }
 
 impl Display for PatternVertex {
@@ -1003,6 +1422,304 @@ impl Display for Pattern {
     }
 }
 
+impl PatternVertex {
+    /// Format the vertex like its Display, but with the label name from the pattern meta
+    pub fn display_with_meta(&self, pattern_meta: &PatternMeta) -> String {
+        let label = pattern_meta
+            .get_vertex_label_interner()
+            .display_label(self.label);
+        format!("({}:{})", self.id, label)
+    }
+}
+
+impl PatternEdge {
+    /// Format the edge like its Display, but with the label names from the pattern meta
+    pub fn display_with_meta(&self, pattern_meta: &PatternMeta) -> String {
+        let label = pattern_meta
+            .get_edge_label_interner()
+            .display_label(self.label);
+        format!(
+            "{}-[{}:{}]->{}",
+            self.start_vertex
+                .display_with_meta(pattern_meta),
+            self.id,
+            label,
+            self.end_vertex.display_with_meta(pattern_meta)
+        )
+    }
+}
+
+/// Methods for Pattern Visualization
+impl Pattern {
+    /// Format the pattern like its Display, but with the label names from the pattern meta
+    ///
+    /// Labels unknown to the pattern meta are printed as their ids.
+    pub fn display_with_meta(&self, pattern_meta: &PatternMeta) -> String {
+        match self.get_vertices_num() {
+            0 => String::new(),
+            1 => {
+                let (_, v) = self.vertices.iter().next().unwrap();
+                v.display_with_meta(pattern_meta)
+            }
+            _ => {
+                let edges: Vec<String> = self
+                    .edges
+                    .values()
+                    .map(|e| e.display_with_meta(pattern_meta))
+                    .collect();
+                edges.join(", ")
+            }
+        }
+    }
+
+    /// Export the pattern as a Graphviz digraph
+    ///
+    /// Each vertex node is labeled by `id:label` together with its canonical rank and group,
+    /// as well as its tag and predicate if it has; each edge is labeled by `id:label`.
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("digraph pattern {\n");
+        for vertex in self.vertices_iter() {
+            let v_id = vertex.get_id();
+            let mut node_label = format!(
+                "{}:{}\\nrank={}, group={}",
+                v_id,
+                vertex.get_label(),
+                format_dot_option(self.get_vertex_rank(v_id)),
+                format_dot_option(self.get_vertex_group(v_id)),
+            );
+            if let Some(tag) = self.get_vertex_tag(v_id) {
+                node_label.push_str(&format!("\\ntag={}", tag));
+            }
+            if let Some(predicate) = self.get_vertex_predicate(v_id) {
+                node_label
+                    .push_str(&format!("\\npredicate={}", escape_dot_label(&format!("{:?}", predicate))));
+            }
+            dot.push_str(&format!("    v{} [label=\"{}\"];\n", v_id, node_label));
+        }
+        for vertex in self.vertices_iter() {
+            for adjacency in self.stored_out_adjacencies_iter(vertex.get_id()) {
+                let e_id = adjacency.get_edge_id();
+                let mut edge_label = format!("{}:{}", e_id, adjacency.get_edge_label());
+                if let Some(tag) = self.get_edge_tag(e_id) {
+                    edge_label.push_str(&format!("\\ntag={}", tag));
+                }
+                if let Some(predicate) = self.get_edge_predicate(e_id) {
+                    edge_label.push_str(&format!(
+                        "\\npredicate={}",
+                        escape_dot_label(&format!("{:?}", predicate))
+                    ));
+                }
+                let edge_attrs = if adjacency.is_bidirectional() { ", dir=none" } else { "" };
+                dot.push_str(&format!(
+                    "    v{} -> v{} [label=\"{}\"{}];\n",
+                    vertex.get_id(),
+                    adjacency.get_adj_vertex().get_id(),
+                    edge_label,
+                    edge_attrs
+                ));
+            }
+        }
+        dot.push_str("}\n");
+        dot
+    }
+
+    /// Render the pattern as a Cypher query counting its matches, e.g.,
+    /// `MATCH (v0:Person)-[e0:KNOWS]->(v1:Person) RETURN count(*)`
+    ///
+    /// Each vertex (edge) is named by `v` (`e`) with its id, and labeled by its name in the pattern meta.
+    /// The predicates are rendered in the WHERE clause if they can be expressed in Cypher, otherwise they
+    /// are omitted with a comment noting so ahead of the query.
+    pub fn to_cypher(&self, pattern_meta: &PatternMeta) -> String {
+        let vertex_labels = pattern_meta.get_vertex_label_interner();
+        let edge_labels = pattern_meta.get_edge_label_interner();
+        let mut notes = vec![];
+        let mut conditions = vec![];
+        let mut add_predicate = |alias: String, predicate: Option<&common_pb::Expression>| {
+            if let Some(predicate) = predicate {
+                match expr_to_cypher(predicate, &alias) {
+                    Some(condition) => conditions.push(format!("({})", condition)),
+                    None => notes.push(format!("// the predicate of {} is omitted", alias)),
+                }
+            }
+        };
+        // A vertex is labeled when it first appears in the match clause
+        let mut labeled_vertices = BTreeSet::new();
+        let mut render_vertex = |vertex: &PatternVertex| {
+            if labeled_vertices.insert(vertex.get_id()) {
+                let label = cypher_label(&vertex_labels.display_label(vertex.get_label()));
+                format!("(v{}:{})", vertex.get_id(), label)
+            } else {
+                format!("(v{})", vertex.get_id())
+            }
+        };
+        let mut paths = vec![];
+        for vertex in self.vertices_iter() {
+            add_predicate(format!("v{}", vertex.get_id()), self.get_vertex_predicate(vertex.get_id()));
+            for adjacency in self.stored_out_adjacencies_iter(vertex.get_id()) {
+                let edge = self.get_edge(adjacency.get_edge_id()).unwrap();
+                add_predicate(format!("e{}", edge.get_id()), self.get_edge_predicate(edge.get_id()));
+                let hops = edge
+                    .get_hop_range()
+                    .map(|(hop_min, hop_max)| format!("*{}..{}", hop_min, hop_max))
+                    .unwrap_or_default();
+                let start_vertex = render_vertex(vertex);
+                let end_vertex = render_vertex(&adjacency.get_adj_vertex());
+                paths.push(format!(
+                    "{}-[e{}:{}{}]-{}{}",
+                    start_vertex,
+                    edge.get_id(),
+                    cypher_label(&edge_labels.display_label(edge.get_label())),
+                    hops,
+                    if edge.is_undirected() { "" } else { ">" },
+                    end_vertex
+                ));
+            }
+        }
+        // The isolated vertices, e.g., the only vertex of a single-vertex pattern
+        for vertex in self.vertices_iter() {
+            if self.get_vertex_degree(vertex.get_id()) == 0 {
+                paths.push(render_vertex(vertex));
+            }
+        }
+        let mut cypher = String::new();
+        for note in notes {
+            cypher.push_str(&note);
+            cypher.push('\n');
+        }
+        cypher.push_str(&format!("MATCH {}", paths.join(", ")));
+        if !conditions.is_empty() {
+            cypher.push_str(&format!(" WHERE {}", conditions.join(" AND ")));
+        }
+        cypher.push_str(" RETURN count(*)");
+        cypher
+    }
+}
+
+fn format_dot_option(value: Option<PatternId>) -> String {
+    value
+        .map(|value| value.to_string())
+        .unwrap_or_else(|| "none".to_string())
+}
+
+fn escape_dot_label(label: &str) -> String {
+    label.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+/// Quote the label by backticks unless it is a plain identifier in Cypher
+fn cypher_label(label: &str) -> String {
+    let is_identifier = label
+        .chars()
+        .next()
+        .map(|c| c.is_ascii_alphabetic() || c == '_')
+        .unwrap_or(false)
+        && label
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_');
+    if is_identifier {
+        label.to_string()
+    } else {
+        format!("`{}`", label.replace('`', "``"))
+    }
+}
+
+/// Render the predicate on the pattern vertex or edge named by alias in Cypher
+///
+/// Return None if the predicate cannot be expressed in Cypher, e.g., it refers to other tags
+fn expr_to_cypher(expr: &common_pb::Expression, alias: &str) -> Option<String> {
+    use common_pb::expr_opr::Item;
+    use common_pb::{Arithmetic, Logical};
+    let mut cypher = String::new();
+    for opr in expr.operators.iter() {
+        let token = match opr.item.as_ref()? {
+            Item::Const(value) => value_to_cypher(value)?,
+            Item::Var(var) => var_to_cypher(var, alias)?,
+            Item::Logical(logical) => match Logical::from_i32(*logical)? {
+                Logical::Eq => "=",
+                Logical::Ne => "<>",
+                Logical::Lt => "<",
+                Logical::Le => "<=",
+                Logical::Gt => ">",
+                Logical::Ge => ">=",
+                Logical::Within => "IN",
+                Logical::Startswith => "STARTS WITH",
+                Logical::Endswith => "ENDS WITH",
+                Logical::And => "AND",
+                Logical::Or => "OR",
+                Logical::Not => "NOT",
+                Logical::Without => return None,
+            }
+            .to_string(),
+            Item::Arith(arith) => match Arithmetic::from_i32(*arith)? {
+                Arithmetic::Add => "+",
+                Arithmetic::Sub => "-",
+                Arithmetic::Mul => "*",
+                Arithmetic::Div => "/",
+                Arithmetic::Mod => "%",
+                Arithmetic::Exp => "^",
+                _ => return None,
+            }
+            .to_string(),
+            Item::Brace(0) => "(".to_string(),
+            Item::Brace(_) => ")".to_string(),
+            _ => return None,
+        };
+        // No spaces inside the braces
+        if !cypher.is_empty() && !cypher.ends_with('(') && token != ")" {
+            cypher.push(' ');
+        }
+        cypher.push_str(&token);
+    }
+    Some(cypher)
+}
+
+fn value_to_cypher(value: &common_pb::Value) -> Option<String> {
+    use common_pb::value::Item;
+    fn list<T: ToString>(items: &[T]) -> String {
+        format!("[{}]", items.iter().map(T::to_string).join(", "))
+    }
+    let quote = |s: &String| format!("'{}'", s.replace('\\', "\\\\").replace('\'', "\\'"));
+    match value.item.as_ref()? {
+        Item::Boolean(b) => Some(b.to_string()),
+        Item::I32(i) => Some(i.to_string()),
+        Item::I64(i) => Some(i.to_string()),
+        Item::F64(f) => Some(format!("{:?}", f)),
+        Item::Str(s) => Some(quote(s)),
+        Item::I32Array(array) => Some(list(&array.item)),
+        Item::I64Array(array) => Some(list(&array.item)),
+        Item::F64Array(array) => Some(format!(
+            "[{}]",
+            array
+                .item
+                .iter()
+                .map(|f| format!("{:?}", f))
+                .join(", ")
+        )),
+        Item::StrArray(array) => Some(format!("[{}]", array.item.iter().map(quote).join(", "))),
+        _ => None,
+    }
+}
+
+/// Only the variables of the current vertex or edge, or its id or property, are supported
+fn var_to_cypher(var: &common_pb::Variable, alias: &str) -> Option<String> {
+    use common_pb::property::Item;
+    if var.tag.is_some() {
+        return None;
+    }
+    match var
+        .property
+        .as_ref()
+        .map(|property| property.item.as_ref())
+    {
+        None => Some(alias.to_string()),
+        Some(Some(Item::Id(_))) => Some(format!("id({})", alias)),
+        Some(Some(Item::Key(key))) => match key.item.as_ref()? {
+            common_pb::name_or_id::Item::Name(name) => Some(format!("{}.{}", alias, name)),
+            common_pb::name_or_id::Item::Id(_) => None,
+        },
+        _ => None,
+    }
+}
+
 /// Setters of fields of Pattern
 impl Pattern {
     /// Assign a PatternEdge with the given group
@@ -1086,13 +1803,35 @@ impl Pattern {
     /// - Vertex Grouping (Partition): vertices in the same group (partition) are equivalent in structure.
     /// - Pattern Ranking: given the vertex groups, rank each vertex and edge with a unique ID.
     fn canonical_labeling(&mut self) {
+        self.canonical_labeling_from(None)
+    }
+
+    /// Canonical labeling of the pattern extended from the given sub-pattern, if any, where the vertex
+    /// grouping is seeded with the groups of the sub-pattern. The labels are the same as from scratch.
+    fn canonical_labeling_from(&mut self, sub_pattern: Option<&Pattern>) {
+        // the ranks are about to change, so the cached code is no longer valid
+        self.code_cache = PatternCodeCache::default();
         let mut canonical_label_manager = CanonicalLabelManager::from(&*self);
+        if let Some(sub_pattern) = sub_pattern {
+            canonical_label_manager.seed_vertex_groups(self, sub_pattern);
+        }
         canonical_label_manager.vertex_grouping(self);
         canonical_label_manager.pattern_ranking(self);
         self.update_vertex_groups(&canonical_label_manager);
         self.update_pattern_ranks(&canonical_label_manager);
     }
 
+    /// Group the vertices of the pattern by the given algorithm, without changing the pattern
+    ///
+    /// Return the map from vertex IDs to their groups
+    pub fn get_vertex_groups_by(&self, grouping_algo: GroupingAlgo) -> BTreeMap<PatternId, PatternId> {
+        let mut canonical_label_manager = CanonicalLabelManager::new(self, grouping_algo);
+        canonical_label_manager.vertex_grouping(self);
+        canonical_label_manager
+            .vertex_groups_iter()
+            .collect()
+    }
+
     /// Update vertex groups
     fn update_vertex_groups(&mut self, canonical_label_manager: &CanonicalLabelManager) {
         canonical_label_manager
@@ -1131,10 +1870,32 @@ impl Pattern {
     ///
     /// Pattern is disconnected if number of connected components is bigger than 1
     pub fn get_connected_component_num(&self) -> usize {
-        self.vertices_iter()
-            .map(|vertex| vertex.get_id())
-            .filter(|&v_id| self.get_vertex_rank(v_id).unwrap() == 0)
-            .count()
+        self.get_component_start_vertices().len()
+    }
+
+    /// Return the vertex where the ranking of each connected component starts from
+    ///
+    /// The components are ranked one after another, so that the start vertex of a component
+    /// is the first vertex of the component in the order of vertex ranks.
+    fn get_component_start_vertices(&self) -> Vec<PatternId> {
+        let mut visited_vertices: BTreeSet<PatternId> = BTreeSet::new();
+        let mut start_vertices = vec![];
+        for (_, &v_id) in self.rank_vertex_map.iter() {
+            if !visited_vertices.insert(v_id) {
+                continue;
+            }
+            start_vertices.push(v_id);
+            let mut vertices_queue: VecDeque<PatternId> = VecDeque::from(vec![v_id]);
+            while let Some(current_v_id) = vertices_queue.pop_front() {
+                for adj in self.adjacencies_iter(current_v_id) {
+                    let adj_v_id = adj.get_adj_vertex().get_id();
+                    if visited_vertices.insert(adj_v_id) {
+                        vertices_queue.push_back(adj_v_id);
+                    }
+                }
+            }
+        }
+        start_vertices
     }
 
     /// Determine whther a pattern is connected or not
@@ -1144,6 +1905,10 @@ impl Pattern {
         self.get_connected_component_num() == 1
     }
 
+    /// Get all connected components of the pattern
+    ///
+    /// The components are sorted by their canonical codes, so that the same pattern built in
+    /// different orders always yields its components in the same order.
     pub fn get_connected_components(&self) -> Vec<Pattern> {
         // // ---debug---
         // self.vertices_iter()
@@ -1155,10 +1920,9 @@ impl Pattern {
         //     });
         // // ---debug---
         let mut visited_vertices: BTreeSet<PatternId> = BTreeSet::new();
-        let connected_components: Vec<Pattern> = self
-            .vertices_iter()
-            .map(|vertex| vertex.get_id())
-            .filter(|&v_id| self.get_vertex_rank(v_id).unwrap() == 0)
+        let mut connected_components: Vec<Pattern> = self
+            .get_component_start_vertices()
+            .into_iter()
             .map(|v_id| {
                 // BFS to traverse the connected component
                 let mut vertices_queue: VecDeque<PatternId> = VecDeque::new();
@@ -1188,7 +1952,10 @@ impl Pattern {
                             };
                             let start_vertex = PatternVertex::new(start_v_id, start_v_label);
                             let end_vertex = PatternVertex::new(end_v_id, end_v_label);
-                            pattern_edges.push(PatternEdge::new(e_id, e_label, start_vertex, end_vertex));
+                            pattern_edges.push(
+                                PatternEdge::new(e_id, e_label, start_vertex, end_vertex)
+                                    .with_undirected(adj.is_bidirectional()),
+                            );
                             // Push unvisited vertex to vertices queue
                             vertices_queue.push_back(adj_v_id);
                             current_v_id
@@ -1200,23 +1967,87 @@ impl Pattern {
                     });
                 }
 
-                // Build pattern
-                if pattern_edges.is_empty() {
-                    // Case-1: Single Vertex
-                    let v_label: PatternLabelId = self
-                        .get_vertex(v_id)
-                        .expect("Vertex Not Found in Pattern")
-                        .get_label();
-                    Pattern::from(PatternVertex::new(v_id, v_label))
-                } else {
-                    // Case-2: Pattern Edges
-                    Pattern::try_from(pattern_edges).expect("Failed to build pattern from a single vertex")
-                }
+                // Build pattern, where a component without edges is a single vertex
+                let start_vertex = *self
+                    .get_vertex(v_id)
+                    .expect("Vertex Not Found in Pattern");
+                Pattern::from_parts(vec![start_vertex], pattern_edges)
+                    .expect("Failed to build pattern of a connected component")
             })
             .collect();
-
+        connected_components.sort_by_cached_key(|component| component.encode_to());
         connected_components
     }
+
+    /// Get all simple cycles of the pattern regardless of edge directions
+    ///
+    /// Each cycle is a list of vertex ids in traversal order, starting from its smallest vertex id.
+    /// Multiple edges between two vertices are not regarded as a cycle.
+    pub fn extract_cycles(&self) -> Vec<Vec<PatternId>> {
+        let mut cycles = vec![];
+        for start_vertex_id in self
+            .vertices_iter()
+            .map(|vertex| vertex.get_id())
+        {
+            let mut path = vec![start_vertex_id];
+            self.extract_cycles_by_dfs(&mut path, &mut cycles);
+        }
+        cycles
+    }
+
+    /// Extend the path by DFS to find the cycles back to its start vertex.
+    ///
+    /// Only vertices with larger ids than the start vertex are visited, and a cycle is recorded
+    /// in only one of its two traversal directions.
+    fn extract_cycles_by_dfs(&self, path: &mut Vec<PatternId>, cycles: &mut Vec<Vec<PatternId>>) {
+        let start_vertex_id = path[0];
+        let current_vertex_id = *path.last().unwrap();
+        for adj_vertex_id in self
+            .undirected_neighbors_iter(current_vertex_id)
+            .map(|vertex| vertex.get_id())
+        {
+            if adj_vertex_id == start_vertex_id {
+                if path.len() >= 3 && path[1] < current_vertex_id {
+                    cycles.push(path.clone());
+                }
+            } else if adj_vertex_id > start_vertex_id && !path.contains(&adj_vertex_id) {
+                path.push(adj_vertex_id);
+                self.extract_cycles_by_dfs(path, cycles);
+                path.pop();
+            }
+        }
+    }
+
+    /// Determine whether the pattern itself is exactly one cycle regardless of edge directions
+    pub fn is_single_cycle(&self) -> bool {
+        self.get_vertices_num() >= 3
+            && self.get_edges_num() == self.get_vertices_num()
+            && self.is_connected()
+            && self.vertices_iter().all(|vertex| {
+                self.undirected_neighbors_iter(vertex.get_id())
+                    .count()
+                    == 2
+            })
+    }
+
+    /// Determine whether two patterns are isomorphic by comparing their canonical codes
+    pub fn is_isomorphic_to(&self, other: &Pattern) -> bool {
+        self.get_vertices_num() == other.get_vertices_num()
+            && self.get_edges_num() == other.get_edges_num()
+            && self.encode_to() == other.encode_to()
+    }
+
+    /// Determine whether two patterns are isomorphic by searching for a vertex mapping between them
+    ///
+    /// Unlike `is_isomorphic_to`, it does not rely on the canonical codes, so it can be used to check
+    /// the canonical codes themselves. It takes exponential time in the worst case.
+    ///
+    /// With the same numbers of vertices and edges, a subpattern mapping is one-to-one on both.
+    pub fn is_structurally_isomorphic_to(&self, other: &Pattern) -> bool {
+        self.get_vertices_num() == other.get_vertices_num()
+            && self.get_edges_num() == other.get_edges_num()
+            && self.get_subpattern_mapping(other).is_some()
+    }
 }
 
 /// Methods for Pattern Edit
@@ -1264,22 +2095,21 @@ impl Pattern {
                     std::mem::swap(&mut start_vertex, &mut end_vertex);
                 }
                 let new_pattern_edge =
-                    PatternEdge::new(new_pattern_edge_id, new_pattern_edge_label, start_vertex, end_vertex);
+                    PatternEdge::new(new_pattern_edge_id, new_pattern_edge_label, start_vertex, end_vertex)
+                        .with_undirected(extend_edge.is_undirected());
                 // Update start vertex and end vertex's adjacency info
                 let start_vertex_new_adjacency = Adjacency::new(&start_vertex, &new_pattern_edge).unwrap();
                 new_pattern
                     .vertices_data
                     .get_mut(start_vertex.get_id())
                     .unwrap()
-                    .out_adjacencies
-                    .push(start_vertex_new_adjacency);
+                    .add_adjacency(start_vertex_new_adjacency);
                 let end_vertex_new_adjacency = Adjacency::new(&end_vertex, &new_pattern_edge).unwrap();
                 new_pattern
                     .vertices_data
                     .get_mut(end_vertex.get_id())
                     .unwrap()
-                    .in_adjacencies
-                    .push(end_vertex_new_adjacency);
+                    .add_adjacency(end_vertex_new_adjacency);
                 new_pattern
                     .edges
                     .insert(new_pattern_edge_id, new_pattern_edge);
@@ -1291,13 +2121,230 @@ impl Pattern {
             }
         }
 
-        new_pattern.canonical_labeling();
+        new_pattern.canonical_labeling_from(Some(self));
         Some(new_pattern)
     }
 
+    /// Extend the current Pattern to a new Pattern with the given ExtendStep as `extend`, but return an
+    /// error telling which source vertex rank of the ExtendStep is missing in the current Pattern
+    pub fn try_extend(&self, extend_step: &ExtendStep) -> IrResult<Pattern> {
+        if let Some(extend_edge) = extend_step.iter().find(|extend_edge| {
+            self.get_vertex_from_rank(extend_edge.get_src_vertex_rank())
+                .is_none()
+        }) {
+            return Err(IrError::InvalidPattern(format!(
+                "source vertex of rank {} does not exist in the pattern of {} vertices",
+                extend_edge.get_src_vertex_rank(),
+                self.get_vertices_num()
+            )));
+        }
+        self.extend(extend_step)
+            .ok_or_else(|| IrError::InvalidPattern("failed to extend the pattern".to_string()))
+    }
+
+    /// Extend the current Pattern as `try_extend`, and also check that the newly extended edges are
+    /// allowed by the given pattern meta, where the first offending edge is reported in the error
+    pub fn try_extend_with_meta(
+        &self, extend_step: &ExtendStep, pattern_meta: &PatternMeta,
+    ) -> IrResult<Pattern> {
+        let new_pattern = self.try_extend(extend_step)?;
+        let target_vertex_id = new_pattern.get_max_vertex_id();
+        for adjacency in new_pattern.adjacencies_iter(target_vertex_id) {
+            let edge = new_pattern
+                .get_edge(adjacency.get_edge_id())
+                .unwrap();
+            validate_edge_against_meta(edge, pattern_meta)?;
+        }
+        Ok(new_pattern)
+    }
+
+    /// Remap the labels of all the vertices and edges in the pattern by the given maps, e.g., to import a
+    /// pattern produced against a different schema
+    /// - The ids, tags and predicates of the vertices and edges are kept, and the pattern is canonically
+    ///   labeled again, as the new labels may change the ranks
+    /// - Return an error if the label of any vertex or edge is missing in the maps
+    pub fn relabel(
+        &self, vertex_label_map: &HashMap<PatternLabelId, PatternLabelId>,
+        edge_label_map: &HashMap<PatternLabelId, PatternLabelId>,
+    ) -> IrResult<Pattern> {
+        let relabel_vertex = |vertex: &PatternVertex| -> IrResult<PatternVertex> {
+            vertex_label_map
+                .get(&vertex.get_label())
+                .map(|&label| PatternVertex::new(vertex.get_id(), label))
+                .ok_or_else(|| {
+                    IrError::InvalidPattern(format!(
+                        "vertex label {} not found in the label map",
+                        vertex.get_label()
+                    ))
+                })
+        };
+        let mut new_pattern = self.clone();
+        for vertex in self.vertices_iter() {
+            new_pattern
+                .vertices
+                .insert(vertex.get_id(), relabel_vertex(vertex)?);
+        }
+        for vertex_data in new_pattern.vertices_data.values_mut() {
+            vertex_data.clear_adjacencies();
+        }
+        for edge in self.edges_iter() {
+            let edge_label = *edge_label_map
+                .get(&edge.get_label())
+                .ok_or_else(|| {
+                    IrError::InvalidPattern(format!(
+                        "edge label {} not found in the label map",
+                        edge.get_label()
+                    ))
+                })?;
+            let start_vertex = relabel_vertex(&edge.get_start_vertex())?;
+            let end_vertex = relabel_vertex(&edge.get_end_vertex())?;
+            let new_edge = PatternEdge::new(edge.get_id(), edge_label, start_vertex, end_vertex)
+                .with_undirected(edge.is_undirected())
+                .with_hop_range(edge.get_hop_range());
+            new_pattern
+                .vertices_data
+                .get_mut(start_vertex.get_id())
+                .unwrap()
+                .add_adjacency(Adjacency::new(&start_vertex, &new_edge).unwrap());
+            new_pattern
+                .vertices_data
+                .get_mut(end_vertex.get_id())
+                .unwrap()
+                .add_adjacency(Adjacency::new(&end_vertex, &new_edge).unwrap());
+            new_pattern
+                .edges
+                .insert(edge.get_id(), new_edge);
+        }
+        new_pattern.canonical_labeling();
+        Ok(new_pattern)
+    }
+
+    /// Merge the other pattern into the current one, where the vertices carrying the same shared tag
+    /// in both patterns are identified as one vertex
+    /// - The shared vertices keep their ids in the current pattern, while the other vertices and all the
+    ///   edges of the other pattern are given new ids after the current pattern's maximum ones
+    /// - The tags and predicates of the other pattern are carried over
+    /// - Return an error if a shared tag is missing or tags vertices of different labels in the two
+    ///   patterns, or if any other tag or predicate of the two patterns conflicts
+    pub fn merge(&self, other: &Pattern, shared_tags: &[TagId]) -> IrResult<Pattern> {
+        let mut new_pattern = self.clone();
+        // Map the vertex ids of the other pattern to the ones of the merged pattern
+        let mut vertex_id_map: HashMap<PatternId, PatternId> = HashMap::new();
+        for &tag in shared_tags {
+            let vertex = self.get_vertex_from_tag(tag).ok_or_else(|| {
+                IrError::InvalidPattern(format!("shared tag {} not found in the pattern", tag))
+            })?;
+            let other_vertex = other.get_vertex_from_tag(tag).ok_or_else(|| {
+                IrError::InvalidPattern(format!("shared tag {} not found in the pattern to merge", tag))
+            })?;
+            if vertex.get_label() != other_vertex.get_label() {
+                return Err(IrError::InvalidPattern(format!(
+                    "shared tag {} is on vertices of different labels {} and {}",
+                    tag,
+                    vertex.get_label(),
+                    other_vertex.get_label()
+                )));
+            }
+            vertex_id_map.insert(other_vertex.get_id(), vertex.get_id());
+        }
+        let mut next_vertex_id = self.get_max_vertex_id() + 1;
+        for other_vertex in other.vertices_iter() {
+            let other_vertex_id = other_vertex.get_id();
+            if !vertex_id_map.contains_key(&other_vertex_id) {
+                let new_vertex = PatternVertex::new(next_vertex_id, other_vertex.get_label());
+                new_pattern
+                    .vertices
+                    .insert(next_vertex_id, new_vertex);
+                new_pattern
+                    .vertices_data
+                    .insert(next_vertex_id, PatternVertexData::default());
+                vertex_id_map.insert(other_vertex_id, next_vertex_id);
+                next_vertex_id += 1;
+            }
+            let vertex_id = vertex_id_map[&other_vertex_id];
+            if let Some(tag) = other.get_vertex_tag(other_vertex_id) {
+                match new_pattern.get_vertex_from_tag(tag) {
+                    Some(vertex) if vertex.get_id() != vertex_id => {
+                        return Err(IrError::InvalidPattern(format!(
+                            "tag {} is on different vertices of the patterns to merge",
+                            tag
+                        )))
+                    }
+                    _ => new_pattern.set_vertex_tag(vertex_id, tag),
+                }
+            }
+            if let Some(predicate) = other.get_vertex_predicate(other_vertex_id) {
+                match new_pattern.get_vertex_predicate(vertex_id) {
+                    Some(old_predicate) if old_predicate != predicate => {
+                        return Err(IrError::InvalidPattern(format!(
+                            "vertex {} has different predicates in the patterns to merge",
+                            vertex_id
+                        )))
+                    }
+                    _ => new_pattern.set_vertex_predicate(vertex_id, predicate.clone()),
+                }
+            }
+        }
+        let mut next_edge_id = self.get_max_edge_id() + 1;
+        for other_edge in other.edges_iter() {
+            let start_vertex = new_pattern
+                .get_vertex(vertex_id_map[&other_edge.get_start_vertex().get_id()])
+                .cloned()
+                .unwrap();
+            let end_vertex = new_pattern
+                .get_vertex(vertex_id_map[&other_edge.get_end_vertex().get_id()])
+                .cloned()
+                .unwrap();
+            let new_edge = PatternEdge::new(next_edge_id, other_edge.get_label(), start_vertex, end_vertex)
+                .with_undirected(other_edge.is_undirected())
+                .with_hop_range(other_edge.get_hop_range());
+            new_pattern
+                .vertices_data
+                .get_mut(start_vertex.get_id())
+                .unwrap()
+                .add_adjacency(Adjacency::new(&start_vertex, &new_edge).unwrap());
+            new_pattern
+                .vertices_data
+                .get_mut(end_vertex.get_id())
+                .unwrap()
+                .add_adjacency(Adjacency::new(&end_vertex, &new_edge).unwrap());
+            new_pattern.edges.insert(next_edge_id, new_edge);
+            new_pattern
+                .edges_data
+                .insert(next_edge_id, PatternEdgeData::default());
+            if let Some(tag) = other.get_edge_tag(other_edge.get_id()) {
+                if new_pattern.get_edge_from_tag(tag).is_some() {
+                    return Err(IrError::InvalidPattern(format!(
+                        "tag {} is on edges of both patterns to merge",
+                        tag
+                    )));
+                }
+                new_pattern.set_edge_tag(tag, next_edge_id);
+            }
+            if let Some(predicate) = other.get_edge_predicate(other_edge.get_id()) {
+                new_pattern.set_edge_predicate(next_edge_id, predicate.clone());
+            }
+            next_edge_id += 1;
+        }
+        new_pattern.canonical_labeling();
+        Ok(new_pattern)
+    }
+
     /// Find all possible ExtendSteps of current pattern based on the given Pattern Meta
     pub fn get_extend_steps(
         &self, pattern_meta: &PatternMeta, same_label_vertex_limit: usize,
+    ) -> Vec<ExtendStep> {
+        self.get_extend_steps_with_symmetric(pattern_meta, same_label_vertex_limit, false)
+    }
+
+    /// Find all possible ExtendSteps of current pattern based on the given Pattern Meta
+    ///
+    /// Every source vertex is connected to the target vertex by at most one extend edge. If
+    /// `include_symmetric` is set, the symmetric extend steps are also generated, where a source vertex
+    /// can be connected to the target vertex by two extend edges of opposite directions, i.e., the target
+    /// vertex is both a successor and a predecessor of the source vertex.
+    pub fn get_extend_steps_with_symmetric(
+        &self, pattern_meta: &PatternMeta, same_label_vertex_limit: usize, include_symmetric: bool,
     ) -> Vec<ExtendStep> {
         let mut extend_steps = vec![];
         // Get all vertex labels from pattern meta as the possible extend target vertex
@@ -1325,9 +2372,9 @@ impl Pattern {
             for (_, src_vertex) in &self.vertices {
                 // check whether there are some edges between the target vertex and the current source vertex
                 let adjacent_edges =
-                    pattern_meta.associated_elabels_iter_by_vlabel(src_vertex.get_label(), target_v_label);
+                    pattern_meta.associated_elabels(src_vertex.get_label(), target_v_label);
                 // Transform all the adjacent edges to ExtendEdge and add to extend_edges_with_src_id
-                for (adjacent_edge_label, adjacent_edge_dir) in adjacent_edges {
+                for &(adjacent_edge_label, adjacent_edge_dir) in adjacent_edges {
                     let extend_edge = ExtendEdge::new(
                         self.get_vertex_rank(src_vertex.get_id())
                             .unwrap(),
@@ -1339,14 +2386,25 @@ impl Pattern {
             }
             // Get the subsets of extend_edges_with_src_id, and add every subset to the extend_edges_set_collection
             // The algorithm is BFS Search
-            let extend_edges_set_collection =
-                get_subsets(extend_edges_with_src_id, |(_, src_id_for_check), extend_edges_set| {
-                    limit_repeated_element_num(
-                        src_id_for_check,
-                        extend_edges_set.iter().map(|(_, v_id)| v_id),
-                        1,
-                    )
-                });
+            let extend_edges_set_collection = get_subsets(
+                extend_edges_with_src_id,
+                |(extend_edge_for_check, src_id_for_check), extend_edges_set| {
+                    if include_symmetric {
+                        extend_edges_set
+                            .iter()
+                            .any(|(extend_edge, v_id)| {
+                                v_id == src_id_for_check
+                                    && extend_edge.get_direction() == extend_edge_for_check.get_direction()
+                            })
+                    } else {
+                        limit_repeated_element_num(
+                            src_id_for_check,
+                            extend_edges_set.iter().map(|(_, v_id)| v_id),
+                            1,
+                        )
+                    }
+                },
+            );
             for extend_edges in extend_edges_set_collection {
                 let extend_step = ExtendStep::new(
                     target_v_label,
@@ -1398,15 +2456,11 @@ impl Pattern {
             .vertices_data
             .get_mut(start_vertex.get_id())
         {
-            start_vertex_data
-                .out_adjacencies
-                .push(Adjacency::new(&start_vertex, edge).unwrap());
+            start_vertex_data.add_adjacency(Adjacency::new(&start_vertex, edge).unwrap());
         }
         // update end vertex's connection info
         if let Some(end_vertex_data) = self.vertices_data.get_mut(end_vertex.get_id()) {
-            end_vertex_data
-                .in_adjacencies
-                .push(Adjacency::new(&end_vertex, edge).unwrap());
+            end_vertex_data.add_adjacency(Adjacency::new(&end_vertex, edge).unwrap());
         }
         // add edge to the pattern
         self.edges.insert(edge.get_id(), edge.clone());
@@ -1424,7 +2478,7 @@ impl Pattern {
         for edge in edges {
             new_pattern.add_edge(edge)?;
         }
-        new_pattern.canonical_labeling();
+        new_pattern.canonical_labeling_from(Some(self));
         Ok(new_pattern)
     }
 
@@ -1445,10 +2499,14 @@ impl Pattern {
                 target_vetex,
                 extend_edge.get_src_vertex(),
             )
-        };
+        }
+        .with_undirected(extend_edge.is_undirected());
         let mut new_pattern = self.clone();
         if new_pattern.add_edge(&pattern_edge).is_ok() {
-            new_pattern.canonical_labeling();
+            if let Some(predicate) = extend_edge.get_predicate() {
+                new_pattern.set_edge_predicate(extend_edge.get_edge_id(), predicate.clone());
+            }
+            new_pattern.canonical_labeling_from(Some(self));
             Some(new_pattern)
         } else {
             None
@@ -1466,14 +2524,24 @@ impl Pattern {
             if self.get_vertex_degree(target_v_cand.get_id()) != extend_step.get_extend_edges_num() {
                 continue;
             }
-            // compare whether the candidate vertex has the same connection info as the extend step
-            let cand_e_label_dir_set: BTreeSet<(PatternLabelId, PatternDirection)> = self
+            // compare whether the candidate vertex has the same connection info as the extend step,
+            // where the direction of an undirected edge is taken as Out
+            let cand_e_label_dir_set: BTreeSet<(PatternLabelId, bool, PatternDirection)> = self
                 .adjacencies_iter(target_v_cand.get_id())
-                .map(|adjacency| (adjacency.get_edge_label(), adjacency.get_direction().reverse()))
+                .map(|adjacency| {
+                    let direction = if adjacency.is_bidirectional() {
+                        PatternDirection::Out
+                    } else {
+                        adjacency.get_direction().reverse()
+                    };
+                    (adjacency.get_edge_label(), adjacency.is_bidirectional(), direction)
+                })
                 .collect();
-            let extend_e_label_dir_set: BTreeSet<(PatternLabelId, PatternDirection)> = extend_step
+            let extend_e_label_dir_set: BTreeSet<(PatternLabelId, bool, PatternDirection)> = extend_step
                 .iter()
-                .map(|extend_edge| (extend_edge.get_edge_label(), extend_edge.get_direction()))
+                .map(|extend_edge| {
+                    (extend_edge.get_edge_label(), extend_edge.is_undirected(), extend_edge.get_direction())
+                })
                 .collect();
             // if has the same connection info, check whether the pattern after the removing the target vertex
             // has the same code with the target pattern code
@@ -1505,28 +2573,12 @@ impl Pattern {
                 let adjacent_vertex_id = adjacency.get_adj_vertex().get_id();
                 let adjacent_edge_id = adjacency.get_edge_id();
                 // delete adjacent edges
-                // delete in edges
-                self.edges.remove(adjacent_edge_id);
-                // delete in edge tag map
-                if let Some(tag) = self.get_edge_tag(adjacent_edge_id) {
-                    self.tag_edge_map.remove(&tag);
-                }
-                // delete in edges data
-                self.edges_data.remove(adjacent_edge_id);
+                self.remove_edge_internal(adjacent_edge_id);
                 // update adjcent vertices's info
-                if let PatternDirection::Out = adjacency.get_direction() {
-                    self.vertices_data
-                        .get_mut(adjacent_vertex_id)
-                        .unwrap()
-                        .in_adjacencies
-                        .retain(|adj| adj.get_edge_id() != adjacent_edge_id)
-                } else {
-                    self.vertices_data
-                        .get_mut(adjacent_vertex_id)
-                        .unwrap()
-                        .out_adjacencies
-                        .retain(|adj| adj.get_edge_id() != adjacent_edge_id)
-                }
+                self.vertices_data
+                    .get_mut(adjacent_vertex_id)
+                    .unwrap()
+                    .remove_adjacency(adjacent_edge_id);
             }
             self.canonical_labeling();
             if self.is_connected() {
@@ -1547,40 +2599,17 @@ impl Pattern {
                 .cloned()
                 .collect();
             // delete target vertex
-            // delete in vertices
-            self.vertices.remove(vertex_id);
-            // delete in vertex tag map
-            if let Some(tag) = self.get_vertex_tag(vertex_id) {
-                self.tag_vertex_map.remove(&tag);
-            }
-            // delete in vertices data
-            self.vertices_data.remove(vertex_id);
+            self.remove_vertex_internal(vertex_id);
             for adjacency in adjacencies {
                 let adjacent_vertex_id = adjacency.get_adj_vertex().get_id();
                 let adjacent_edge_id = adjacency.get_edge_id();
                 // delete adjacent edges
-                // delete in edges
-                self.edges.remove(adjacent_edge_id);
-                // delete in edge tag map
-                if let Some(tag) = self.get_edge_tag(adjacent_edge_id) {
-                    self.tag_edge_map.remove(&tag);
-                }
-                // delete in edges data
-                self.edges_data.remove(adjacent_edge_id);
+                self.remove_edge_internal(adjacent_edge_id);
                 // update adjcent vertices's info
-                if let PatternDirection::Out = adjacency.get_direction() {
-                    self.vertices_data
-                        .get_mut(adjacent_vertex_id)
-                        .unwrap()
-                        .in_adjacencies
-                        .retain(|adj| adj.get_edge_id() != adjacent_edge_id)
-                } else {
-                    self.vertices_data
-                        .get_mut(adjacent_vertex_id)
-                        .unwrap()
-                        .out_adjacencies
-                        .retain(|adj| adj.get_edge_id() != adjacent_edge_id)
-                }
+                self.vertices_data
+                    .get_mut(adjacent_vertex_id)
+                    .unwrap()
+                    .remove_adjacency(adjacent_edge_id);
             }
 
             self.canonical_labeling();
@@ -1589,15 +2618,23 @@ impl Pattern {
 
     /// Delete a extend step from current pattern to get a new pattern
     ///
-    /// The code of the new pattern should be the same as the target pattern code
+    /// The code of the new pattern should be the same as the target pattern code. It is checked again after
+    /// the removal, and a mismatch, which is a bug, panics in debug builds and results in None otherwise.
     pub fn de_extend(&self, extend_step: &ExtendStep, target_pattern_code: &Vec<u8>) -> Option<Pattern> {
-        if let Some(target_vertex_id) = self.locate_vertex(extend_step, target_pattern_code) {
-            self.clone().remove_vertex(target_vertex_id)
+        let target_vertex_id = self.locate_vertex(extend_step, target_pattern_code)?;
+        let de_extended_pattern = self.clone().remove_vertex(target_vertex_id)?;
+        let is_target_pattern = de_extended_pattern.encode_to() == *target_pattern_code;
+        debug_assert!(is_target_pattern, "the de-extended pattern does not match the target pattern code");
+        if is_target_pattern {
+            Some(de_extended_pattern)
         } else {
             None
         }
     }
 
+    /// Remove an edge from the current pattern, together with its end vertices left isolated
+    ///
+    /// Return None if the edge does not exist, or the remaining pattern is disconnected
     pub fn remove_edge(mut self, edge_id: PatternId) -> Option<Pattern> {
         if let Some(edge) = self.get_edge(edge_id).cloned() {
             self.remove_edge_internal(edge_id);
@@ -1607,22 +2644,25 @@ impl Pattern {
             self.vertices_data
                 .get_mut(start_vertex)
                 .unwrap()
-                .out_adjacencies
-                .retain(|adj| adj.get_edge_id() != edge_id);
-            if self.get_vertex_degree(start_vertex) == 0 && self.get_vertices_num() > 1 {
-                self.remove_vertex_internal(start_vertex)
-            }
+                .remove_adjacency(edge_id);
             // update end vertex's info
             self.vertices_data
                 .get_mut(end_vertex)
                 .unwrap()
-                .in_adjacencies
-                .retain(|adj| adj.get_edge_id() != edge_id);
-            if self.get_vertex_degree(end_vertex) == 0 && self.get_vertices_num() > 1 {
-                self.remove_vertex_internal(end_vertex)
+                .remove_adjacency(edge_id);
+            // delete the end vertices left isolated, as long as the pattern is not empty
+            for vertex_id in [start_vertex, end_vertex] {
+                if self.get_vertex(vertex_id).is_some()
+                    && self.get_vertex_degree(vertex_id) == 0
+                    && self.get_vertices_num() > 1
+                {
+                    self.remove_vertex_internal(vertex_id)
+                }
             }
+            // the ranks of the remaining vertices and edges are reassigned before the
+            // connectivity check, which relies on them
+            self.canonical_labeling();
             if self.is_connected() {
-                self.canonical_labeling();
                 Some(self)
             } else {
                 None
@@ -1640,6 +2680,12 @@ impl Pattern {
         if let Some(tag) = self.get_vertex_tag(vertex_id) {
             self.tag_vertex_map.remove(&tag);
         }
+        // delete in vertex rank map
+        if let Some(rank) = self.get_vertex_rank(vertex_id) {
+            if self.rank_vertex_map.get(rank) == Some(&vertex_id) {
+                self.rank_vertex_map.remove(rank);
+            }
+        }
         // delete in vertices data
         self.vertices_data.remove(vertex_id);
     }
@@ -1652,6 +2698,12 @@ impl Pattern {
         if let Some(tag) = self.get_edge_tag(edge_id) {
             self.tag_edge_map.remove(&tag);
         }
+        // delete in edge rank map
+        if let Some(rank) = self.get_edge_rank(edge_id) {
+            if self.rank_edge_map.get(rank) == Some(&edge_id) {
+                self.rank_edge_map.remove(rank);
+            }
+        }
         // delete in edges data
         self.edges_data.remove(edge_id);
     }
@@ -1674,6 +2726,392 @@ impl Pattern {
     // }
 }
 
+/// Generate all the distinct patterns with at most `max_vertices` vertices and `max_edges` edges
+/// on the given Pattern Meta
+///
+/// The generation starts from every single vertex pattern and iteratively extends the patterns
+/// by their extend steps, so that no more than `same_label_vertex_limit` vertices share a label.
+/// Patterns are deduplicated by their canonical codes.
+///
+/// As an extend step always connects its target vertex to the pattern, disconnected candidates
+/// only arise from extending a pattern with an isolated vertex. They are skipped if
+/// `connected_only` is set; otherwise they are kept, but not extended any further, since the
+/// vertex ranks used by extend steps are only unique within a connected pattern.
+///
+/// If `trees_only` is set, only the acyclic connected patterns are generated. A pattern with a
+/// cycle never shrinks back to a tree by extension, so it is skipped without being extended.
+pub fn generate_patterns(
+    pattern_meta: &PatternMeta, max_vertices: usize, max_edges: usize, same_label_vertex_limit: usize,
+    connected_only: bool, trees_only: bool,
+) -> Vec<Pattern> {
+    let mut patterns = vec![];
+    let mut pattern_codes = BTreeSet::new();
+    if max_vertices == 0 || same_label_vertex_limit == 0 {
+        return patterns;
+    }
+    // Use BFS to generate the patterns, and the one-vertex patterns are the starting points
+    let mut queue = VecDeque::new();
+    for vertex_label in pattern_meta.vertex_label_ids_iter() {
+        let new_pattern = Pattern::from(PatternVertex::new(0, vertex_label));
+        if pattern_codes.insert(get_generated_pattern_code(&new_pattern)) {
+            patterns.push(new_pattern.clone());
+            queue.push_back(new_pattern);
+        }
+    }
+    while let Some(relaxed_pattern) = queue.pop_front() {
+        if relaxed_pattern.get_vertices_num() >= max_vertices {
+            continue;
+        }
+        let mut extend_steps = relaxed_pattern.get_extend_steps(pattern_meta, same_label_vertex_limit);
+        if !connected_only && !trees_only {
+            // Extend steps without any extend edge add isolated vertices to the pattern
+            for vertex_label in pattern_meta.vertex_label_ids_iter() {
+                if relaxed_pattern
+                    .vertices_iter_by_label(vertex_label)
+                    .count()
+                    < same_label_vertex_limit
+                {
+                    extend_steps.push(ExtendStep::new(vertex_label, vec![]));
+                }
+            }
+        }
+        for extend_step in extend_steps.iter() {
+            let new_pattern = match relaxed_pattern.extend(extend_step) {
+                Some(new_pattern) => new_pattern,
+                None => continue,
+            };
+            if new_pattern.get_edges_num() > max_edges {
+                continue;
+            }
+            let is_connected = new_pattern.is_connected();
+            if connected_only && !is_connected {
+                continue;
+            }
+            if trees_only && !is_tree_pattern(&new_pattern) {
+                continue;
+            }
+            if pattern_codes.insert(get_generated_pattern_code(&new_pattern)) {
+                patterns.push(new_pattern.clone());
+                if is_connected {
+                    queue.push_back(new_pattern);
+                }
+            }
+        }
+    }
+    patterns
+}
+
+/// A pattern is a tree if it is connected and has one less edge than vertices
+fn is_tree_pattern(pattern: &Pattern) -> bool {
+    pattern.get_edges_num() + 1 == pattern.get_vertices_num() && pattern.is_connected()
+}
+
+/// The code of a pattern used for deduplication in pattern generation
+///
+/// The pattern code only covers the edges of a pattern with more than one vertex, so the labels of
+/// the isolated vertices are appended to tell the disconnected patterns apart.
+fn get_generated_pattern_code(pattern: &Pattern) -> Vec<u8> {
+    let mut pattern_code = pattern.encode_to();
+    if pattern.get_vertices_num() > 1 {
+        let isolated_vertex_labels: Vec<PatternLabelId> = pattern
+            .vertices_iter()
+            .filter(|vertex| pattern.get_vertex_degree(vertex.get_id()) == 0)
+            .map(|vertex| vertex.get_label())
+            .sorted()
+            .collect();
+        for vertex_label in isolated_vertex_labels {
+            pattern_code.extend_from_slice(&vertex_label.to_be_bytes());
+        }
+    }
+    pattern_code
+}
+
+/// Methods for Operator Generation
+impl Pattern {
+    /// Generate the EdgeExpand operators which extend to the given vertex from all its neighbors,
+    /// the same as the ones generated when the vertex is the target of a DefiniteExtendStep in a plan
+    ///
+    /// Return an empty vec if the vertex is not in the pattern
+    pub fn extend_operators_for_vertex(&self, vertex_id: PatternId) -> Vec<pb::EdgeExpand> {
+        DefiniteExtendStep::from_target_pattern(self, vertex_id)
+            .map(|extend_step| extend_step.generate_expand_operators(self))
+            .unwrap_or_default()
+    }
+}
+
+/// Methods for Pattern Validation
+impl Pattern {
+    /// Check whether the pattern is realizable under the given pattern meta
+    ///
+    /// Every vertex label must be a known vertex label, and every edge's
+    /// (start vertex label, edge label, end vertex label) must be allowed by the pattern meta.
+    /// The first offending vertex or edge is reported in the error.
+    pub fn validate_against_meta(&self, pattern_meta: &PatternMeta) -> IrResult<()> {
+        let vertex_labels: BTreeSet<PatternLabelId> = pattern_meta.vertex_label_ids_iter().collect();
+        if let Some(vertex) = self
+            .vertices_iter()
+            .find(|vertex| !vertex_labels.contains(&vertex.get_label()))
+        {
+            return Err(IrError::InvalidPattern(format!("vertex {} has an unknown label", vertex)));
+        }
+        self.edges_iter()
+            .try_for_each(|edge| validate_edge_against_meta(edge, pattern_meta))
+    }
+}
+
+/// Check whether the edge's (start vertex label, edge label, end vertex label) is allowed by the meta
+fn validate_edge_against_meta(edge: &PatternEdge, pattern_meta: &PatternMeta) -> IrResult<()> {
+    let vertex_labels_pair = (edge.get_start_vertex().get_label(), edge.get_end_vertex().get_label());
+    if pattern_meta
+        .associated_vlabels(edge.get_label())
+        .contains(&vertex_labels_pair)
+    {
+        Ok(())
+    } else {
+        Err(IrError::InvalidPattern(format!("edge {} is not allowed by the schema", edge)))
+    }
+}
+
+/// Methods for Pattern Similarity
+impl Pattern {
+    /// Compute the normalized Weisfeiler-Lehman subtree kernel of two patterns, which lies in [0, 1].
+    ///
+    /// Identical patterns get 1.0, and patterns sharing no vertex label get 0.0
+    pub fn hash_wl(p1: &Pattern, p2: &Pattern, iterations: usize) -> f64 {
+        let mut color_dict: BTreeMap<Vec<i64>, usize> = BTreeMap::new();
+        let mut colors1 = p1.init_wl_colors(&mut color_dict);
+        let mut colors2 = p2.init_wl_colors(&mut color_dict);
+        let mut color_counts1 = BTreeMap::new();
+        let mut color_counts2 = BTreeMap::new();
+        count_wl_colors(&colors1, &mut color_counts1);
+        count_wl_colors(&colors2, &mut color_counts2);
+        for iteration in 1..=iterations {
+            colors1 = p1.refine_wl_colors(&colors1, iteration, &mut color_dict);
+            colors2 = p2.refine_wl_colors(&colors2, iteration, &mut color_dict);
+            count_wl_colors(&colors1, &mut color_counts1);
+            count_wl_colors(&colors2, &mut color_counts2);
+        }
+        let kernel_12 = wl_kernel(&color_counts1, &color_counts2);
+        let kernel_11 = wl_kernel(&color_counts1, &color_counts1);
+        let kernel_22 = wl_kernel(&color_counts2, &color_counts2);
+        if kernel_11 == 0.0 || kernel_22 == 0.0 {
+            0.0
+        } else {
+            kernel_12 / (kernel_11 * kernel_22).sqrt()
+        }
+    }
+
+    /// Initial WL colors of vertices are compressed from their labels
+    fn init_wl_colors(&self, color_dict: &mut BTreeMap<Vec<i64>, usize>) -> BTreeMap<PatternId, usize> {
+        self.vertices_iter()
+            .map(|vertex| {
+                let signature = vec![0, vertex.get_label() as i64];
+                (vertex.get_id(), get_wl_color(color_dict, signature))
+            })
+            .collect()
+    }
+
+    /// New WL color of a vertex is compressed from its old color and the sorted (direction, edge label, color) of its adjacencies
+    fn refine_wl_colors(
+        &self, colors: &BTreeMap<PatternId, usize>, iteration: usize,
+        color_dict: &mut BTreeMap<Vec<i64>, usize>,
+    ) -> BTreeMap<PatternId, usize> {
+        let mut new_colors = BTreeMap::new();
+        for (&vertex_id, &color) in colors.iter() {
+            let mut adjacency_signatures: Vec<(i64, i64, i64)> = self
+                .adjacencies_iter(vertex_id)
+                .map(|adjacency| {
+                    (
+                        adjacency.get_direction() as i64,
+                        adjacency.get_edge_label() as i64,
+                        colors[&adjacency.get_adj_vertex().get_id()] as i64,
+                    )
+                })
+                .collect();
+            adjacency_signatures.sort();
+            let mut signature = vec![iteration as i64, color as i64];
+            for (direction, edge_label, adj_color) in adjacency_signatures {
+                signature.extend([direction, edge_label, adj_color]);
+            }
+            new_colors.insert(vertex_id, get_wl_color(color_dict, signature));
+        }
+        new_colors
+    }
+}
+
+fn get_wl_color(color_dict: &mut BTreeMap<Vec<i64>, usize>, signature: Vec<i64>) -> usize {
+    let next_color = color_dict.len();
+    *color_dict
+        .entry(signature)
+        .or_insert(next_color)
+}
+
+fn count_wl_colors(colors: &BTreeMap<PatternId, usize>, color_counts: &mut BTreeMap<usize, usize>) {
+    for &color in colors.values() {
+        *color_counts.entry(color).or_insert(0) += 1;
+    }
+}
+
+fn wl_kernel(color_counts1: &BTreeMap<usize, usize>, color_counts2: &BTreeMap<usize, usize>) -> f64 {
+    color_counts1
+        .iter()
+        .filter_map(|(color, &count1)| {
+            color_counts2
+                .get(color)
+                .map(|&count2| (count1 * count2) as f64)
+        })
+        .sum()
+}
+
+/// Methods for Subpattern Matching
+impl Pattern {
+    /// Determine whether the given pattern appears in the current pattern as a subgraph
+    ///
+    /// Unlike generate_subpatterns, the matched part is not required to be an induced subpattern
+    pub fn contains_subpattern(&self, sub_pattern: &Pattern) -> bool {
+        self.get_subpattern_mapping(sub_pattern)
+            .is_some()
+    }
+
+    /// Find a mapping from the vertex ids of the given pattern to the vertex ids of the current pattern,
+    /// under which the given pattern appears in the current pattern as a subgraph
+    ///
+    /// Vertex labels, edge labels and edge directions are all respected.
+    /// Return None if no such mapping exists.
+    pub fn get_subpattern_mapping(&self, sub_pattern: &Pattern) -> Option<BTreeMap<PatternId, PatternId>> {
+        if sub_pattern.get_vertices_num() > self.get_vertices_num()
+            || sub_pattern.get_edges_num() > self.get_edges_num()
+        {
+            return None;
+        }
+        let matching_order = sub_pattern.get_subpattern_matching_order();
+        let mut vertex_mapping = BTreeMap::new();
+        let mut used_vertices = BTreeSet::new();
+        if self.match_subpattern_by_backtracking(
+            sub_pattern,
+            &matching_order,
+            &mut vertex_mapping,
+            &mut used_vertices,
+        ) {
+            Some(vertex_mapping)
+        } else {
+            None
+        }
+    }
+
+    /// Order the vertices by BFS on each connected component,
+    /// so that every vertex except the first one of its component is adjacent to some vertex before it
+    fn get_subpattern_matching_order(&self) -> Vec<PatternId> {
+        let mut matching_order = Vec::with_capacity(self.get_vertices_num());
+        let mut visited_vertices = BTreeSet::new();
+        for vertex in self.vertices_iter() {
+            if !visited_vertices.insert(vertex.get_id()) {
+                continue;
+            }
+            let mut vertices_queue = VecDeque::from(vec![vertex.get_id()]);
+            while let Some(v_id) = vertices_queue.pop_front() {
+                matching_order.push(v_id);
+                for adj_vertex in self.undirected_neighbors_iter(v_id) {
+                    if visited_vertices.insert(adj_vertex.get_id()) {
+                        vertices_queue.push_back(adj_vertex.get_id());
+                    }
+                }
+            }
+        }
+        matching_order
+    }
+
+    /// Map the vertices of the sub pattern one by one in the matching order, and backtrack on conflicts
+    fn match_subpattern_by_backtracking(
+        &self, sub_pattern: &Pattern, matching_order: &[PatternId],
+        vertex_mapping: &mut BTreeMap<PatternId, PatternId>, used_vertices: &mut BTreeSet<PatternId>,
+    ) -> bool {
+        let sub_v_id = match matching_order.get(vertex_mapping.len()) {
+            Some(&sub_v_id) => sub_v_id,
+            None => return true,
+        };
+        let sub_v_label = sub_pattern
+            .get_vertex(sub_v_id)
+            .unwrap()
+            .get_label();
+        let candidates: Vec<PatternId> = self
+            .vertices_iter_by_label(sub_v_label)
+            .map(|vertex| vertex.get_id())
+            .filter(|v_id| !used_vertices.contains(v_id))
+            .filter(|&v_id| self.get_vertex_degree(v_id) >= sub_pattern.get_vertex_degree(sub_v_id))
+            .collect();
+        for v_id in candidates {
+            vertex_mapping.insert(sub_v_id, v_id);
+            if self.is_subpattern_vertex_matched(sub_pattern, sub_v_id, vertex_mapping) {
+                used_vertices.insert(v_id);
+                if self.match_subpattern_by_backtracking(
+                    sub_pattern,
+                    matching_order,
+                    vertex_mapping,
+                    used_vertices,
+                ) {
+                    return true;
+                }
+                used_vertices.remove(&v_id);
+            }
+            vertex_mapping.remove(&sub_v_id);
+        }
+        false
+    }
+
+    /// Check whether every edge between the newly mapped vertex and the already mapped vertices
+    /// of the sub pattern has a distinct counterpart in the current pattern
+    fn is_subpattern_vertex_matched(
+        &self, sub_pattern: &Pattern, sub_v_id: PatternId, vertex_mapping: &BTreeMap<PatternId, PatternId>,
+    ) -> bool {
+        let v_id = *vertex_mapping.get(&sub_v_id).unwrap();
+        let mut required_adjacencies: BTreeMap<_, usize> = BTreeMap::new();
+        for adjacency in sub_pattern.adjacencies_iter(sub_v_id) {
+            if let Some(&adj_v_id) = vertex_mapping.get(&adjacency.get_adj_vertex().get_id()) {
+                *required_adjacencies
+                    .entry((adj_v_id, get_adjacency_match_key(adjacency)))
+                    .or_insert(0) += 1;
+            }
+        }
+        for adjacency in self.adjacencies_iter(v_id) {
+            if let Some(count) = required_adjacencies
+                .get_mut(&(adjacency.get_adj_vertex().get_id(), get_adjacency_match_key(adjacency)))
+            {
+                *count = count.saturating_sub(1);
+            }
+        }
+        required_adjacencies
+            .values()
+            .all(|&count| count == 0)
+    }
+}
+
+/// The information an adjacency must agree on to be matched:
+/// (is undirected, direction, edge label, hop range)
+fn get_adjacency_match_key(
+    adjacency: &Adjacency,
+) -> (bool, PatternDirection, PatternLabelId, Option<(usize, usize)>) {
+    let direction =
+        if adjacency.is_bidirectional() { PatternDirection::Out } else { adjacency.get_direction() };
+    (adjacency.is_bidirectional(), direction, adjacency.get_edge_label(), adjacency.get_hop_range())
+}
+
+/// Two patterns are regarded as equal if they are isomorphic
+impl PartialEq for Pattern {
+    fn eq(&self, other: &Self) -> bool {
+        self.is_isomorphic_to(other)
+    }
+}
+
+impl Eq for Pattern {}
+
+/// Hash a pattern by its canonical code, which is consistent with its equality
+impl Hash for Pattern {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.encode_to().hash(state)
+    }
+}
+
 impl Serialize for Pattern {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
@@ -1712,6 +3150,12 @@ impl<'de> Deserialize<'de> for Pattern {
 struct GCardPatternVertex {
     tag_id: u8,
     label_id: u32,
+    /// Tag (alias) assigned to the vertex by user
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    tag: Option<TagId>,
+    /// Base64 encoded protobuf bytes of the vertex predicate
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    predicate: Option<String>,
 }
 
 #[derive(Serialize, Deserialize)]
@@ -1720,6 +3164,29 @@ struct GCardPatternEdge {
     src: u8,
     dst: u8,
     label_id: u32,
+    /// Whether the edge is undirected
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    undirected: bool,
+    /// The (min, max) hops of the edge if it is a variable-length path
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    hop_range: Option<(usize, usize)>,
+    /// Tag (alias) assigned to the edge by user
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    tag: Option<TagId>,
+    /// Base64 encoded protobuf bytes of the edge predicate
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    predicate: Option<String>,
+}
+
+fn encode_predicate(predicate: &common_pb::Expression) -> String {
+    base64::encode(predicate.encode_to_vec())
+}
+
+fn decode_predicate(predicate: &str) -> IrResult<common_pb::Expression> {
+    let bytes = base64::decode(predicate)
+        .map_err(|err| IrError::InvalidPattern(format!("invalid predicate encoding: {}", err)))?;
+    common_pb::Expression::decode(bytes.as_slice())
+        .map_err(|err| IrError::InvalidPattern(format!("invalid predicate: {}", err)))
 }
 
 #[derive(Serialize, Deserialize)]
@@ -1734,7 +3201,14 @@ impl From<Pattern> for PatternWithCount {
         let vertices = value
             .vertices
             .values()
-            .map(|v| GCardPatternVertex { tag_id: v.get_id() as _, label_id: v.get_label() as _ })
+            .map(|v| GCardPatternVertex {
+                tag_id: v.get_id() as _,
+                label_id: v.get_label() as _,
+                tag: value.get_vertex_tag(v.get_id()),
+                predicate: value
+                    .get_vertex_predicate(v.get_id())
+                    .map(encode_predicate),
+            })
             .collect();
         let edges = value
             .edges
@@ -1744,6 +3218,12 @@ impl From<Pattern> for PatternWithCount {
                 src: e.get_start_vertex().get_id() as _,
                 dst: e.get_end_vertex().get_id() as _,
                 label_id: e.get_label() as _,
+                undirected: e.is_undirected(),
+                hop_range: e.get_hop_range(),
+                tag: value.get_edge_tag(e.get_id()),
+                predicate: value
+                    .get_edge_predicate(e.get_id())
+                    .map(encode_predicate),
             })
             .collect();
         let count = None;
@@ -1756,34 +3236,54 @@ impl TryFrom<PatternWithCount> for Pattern {
 
     fn try_from(value: PatternWithCount) -> IrResult<Self> {
         let PatternWithCount { vertices, edges, count: _ } = value;
-        if edges.is_empty() {
+        let mut pattern = if edges.is_empty() {
             if vertices.len() != 1 {
                 return Err(IrError::InvalidPattern(
                     "Pattern without edges has more than one vertex".into(),
                 ));
             }
-            let GCardPatternVertex { tag_id, label_id } = vertices.into_iter().next().unwrap();
-            let vertex = PatternVertex::new(tag_id as _, label_id as _);
-            Ok(Pattern::from(vertex))
+            let GCardPatternVertex { tag_id, label_id, .. } = vertices.iter().next().unwrap();
+            let vertex = PatternVertex::new(*tag_id as _, *label_id as _);
+            Pattern::from(vertex)
         } else {
             let vertex_map: HashMap<_, _> = vertices
-                .into_iter()
+                .iter()
                 .map(|v| (v.tag_id, PatternVertex::new(v.tag_id as _, v.label_id as _)))
                 .collect();
             let pattern_edges: Vec<_> = edges
-                .into_iter()
-                .map(|GCardPatternEdge { tag_id, src, dst, label_id }| -> IrResult<_> {
+                .iter()
+                .map(|edge| -> IrResult<_> {
+                    let &GCardPatternEdge { tag_id, src, dst, label_id, undirected, hop_range, .. } = edge;
                     let start = vertex_map.get(&src).copied().ok_or_else(|| {
                         IrError::InvalidPattern(format!("start vertex {src} does not exist."))
                     })?;
                     let end = vertex_map.get(&dst).copied().ok_or_else(|| {
                         IrError::InvalidPattern(format!("end vertex {dst} does not exist."))
                     })?;
-                    Ok(PatternEdge::new(tag_id as _, label_id as _, start, end))
+                    Ok(PatternEdge::new(tag_id as _, label_id as _, start, end)
+                        .with_undirected(undirected)
+                        .with_hop_range(hop_range))
                 })
                 .try_collect()?;
-            Pattern::try_from(pattern_edges)
+            Pattern::try_from(pattern_edges)?
+        };
+        for vertex in vertices {
+            if let Some(tag) = vertex.tag {
+                pattern.set_vertex_tag(vertex.tag_id as _, tag);
+            }
+            if let Some(predicate) = vertex.predicate {
+                pattern.set_vertex_predicate(vertex.tag_id as _, decode_predicate(&predicate)?);
+            }
         }
+        for edge in edges {
+            if let Some(tag) = edge.tag {
+                pattern.set_edge_tag(tag, edge.tag_id as _);
+            }
+            if let Some(predicate) = edge.predicate {
+                pattern.set_edge_predicate(edge.tag_id as _, decode_predicate(&predicate)?);
+            }
+        }
+        Ok(pattern)
     }
 }
 
@@ -1805,6 +3305,76 @@ impl PatternWithCount {
     }
 }
 
+/// The count of a pattern together with its size, its canonical code and the time spent on counting,
+/// which is printed as JSON by the count command for the downstream tools
+#[derive(Serialize, Deserialize)]
+pub struct PatternCountRecord {
+    /// Base64 encoded canonical code of the pattern
+    pattern_code: String,
+    vertices: usize,
+    edges: usize,
+    count: f64,
+    elapsed_ms: f64,
+    /// The q-error of the count against the ground-truth count, if it is given
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    q_error: Option<f64>,
+    /// The pattern itself, so that it can be read back from the record
+    pattern: PatternWithCount,
+}
+
+impl PatternCountRecord {
+    pub fn new(pattern: &Pattern, count: f64, elapsed: Duration) -> Self {
+        PatternCountRecord {
+            pattern_code: base64::encode(pattern.encode_to()),
+            vertices: pattern.get_vertices_num(),
+            edges: pattern.get_edges_num(),
+            count,
+            elapsed_ms: elapsed.as_secs_f64() * 1000.0,
+            q_error: None,
+            pattern: PatternWithCount::from(pattern.clone()),
+        }
+    }
+
+    pub fn with_q_error(mut self, q_error: f64) -> Self {
+        self.q_error = Some(q_error);
+        self
+    }
+
+    pub fn get_pattern_code(&self) -> &str {
+        &self.pattern_code
+    }
+
+    pub fn get_count(&self) -> f64 {
+        self.count
+    }
+
+    pub fn get_elapsed_ms(&self) -> f64 {
+        self.elapsed_ms
+    }
+
+    pub fn get_q_error(&self) -> Option<f64> {
+        self.q_error
+    }
+
+    pub fn into_pattern(self) -> PatternWithCount {
+        self.pattern
+    }
+}
+
+/// The record is written in a single line, so that each of them can be piped as a JSON line
+impl JsonIO for PatternCountRecord {
+    fn into_json<W: io::Write>(self, writer: W) -> io::Result<()> {
+        Ok(serde_json::to_writer(writer, &self)?)
+    }
+
+    fn from_json<R: io::Read>(reader: R) -> io::Result<Self>
+    where
+        Self: Sized,
+    {
+        Ok(serde_json::from_reader(reader)?)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use std::{collections::BTreeMap, iter::FromIterator};
@@ -1823,4 +3393,31 @@ mod tests {
         let joined_maps = join_id_label_maps(left_maps, right_maps, &mut false);
         assert_eq!(joined_maps.len(), 3);
     }
+
+    #[test]
+    fn test_pattern_code_cache() {
+        let v0 = PatternVertex::new(0, 0);
+        let v1 = PatternVertex::new(1, 0);
+        let v2 = PatternVertex::new(2, 1);
+        let pattern =
+            Pattern::try_from(vec![PatternEdge::new(0, 0, v0, v1), PatternEdge::new(1, 1, v1, v2)])
+                .unwrap();
+        let code = pattern.encode_to();
+        assert_eq!(pattern.get_code_cache().get_hits(), 0);
+        assert_eq!(pattern.encode_to(), code);
+        assert_eq!(pattern.encode_to(), code);
+        assert_eq!(pattern.get_code_cache().get_hits(), 2);
+        let sub_pattern = pattern.remove_vertex(2).unwrap();
+        assert_eq!(sub_pattern.get_code_cache().get_hits(), 0);
+        let sub_code = sub_pattern.encode_to();
+        assert_ne!(sub_code, code);
+        assert_eq!(
+            sub_code,
+            Pattern::try_from(vec![PatternEdge::new(0, 0, v0, v1)])
+                .unwrap()
+                .encode_to()
+        );
+        assert_eq!(sub_pattern.encode_to(), sub_code);
+        assert_eq!(sub_pattern.get_code_cache().get_hits(), 1);
+    }
 }
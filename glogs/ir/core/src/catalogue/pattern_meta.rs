@@ -16,21 +16,66 @@
 use std::collections::{BTreeMap, BTreeSet};
 
 use bimap::BiBTreeMap;
+use graph_store::prelude::{DefaultId, GlobalStoreTrait, InternalId, LabelId, LargeGraphDB};
 
 use crate::catalogue::{DynIter, PatternDirection, PatternLabelId};
 use crate::plan::meta::KeyType;
 use crate::plan::meta::Schema;
 
+/// A two-way mapping between the label names in the schema and the label ids used in patterns
+#[derive(Debug, Clone, Default)]
+pub struct LabelInterner {
+    /// Left: label name, Right: label id
+    labels: BiBTreeMap<String, PatternLabelId>,
+}
+
+impl LabelInterner {
+    pub fn insert(&mut self, label_name: String, label_id: PatternLabelId) {
+        self.labels.insert(label_name, label_id);
+    }
+
+    pub fn name_to_id(&self, label_name: &str) -> Option<PatternLabelId> {
+        self.labels.get_by_left(label_name).cloned()
+    }
+
+    pub fn id_to_name(&self, label_id: PatternLabelId) -> Option<&str> {
+        self.labels
+            .get_by_right(&label_id)
+            .map(String::as_str)
+    }
+
+    /// Get the name of the label, or the label id itself if it is not interned
+    pub fn display_label(&self, label_id: PatternLabelId) -> String {
+        self.id_to_name(label_id)
+            .map(str::to_string)
+            .unwrap_or_else(|| label_id.to_string())
+    }
+
+    /// Iterate over the label names, ordered by names
+    pub fn names_iter(&self) -> DynIter<&String> {
+        Box::new(self.labels.iter().map(|(name, _)| name))
+    }
+
+    /// Iterate over the label ids, ordered by their names
+    pub fn ids_iter(&self) -> DynIter<PatternLabelId> {
+        Box::new(self.labels.iter().map(|(_, id)| *id))
+    }
+
+    pub fn len(&self) -> usize {
+        self.labels.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.labels.is_empty()
+    }
+}
+
 #[derive(Debug, Clone, Default)]
 pub struct PatternMeta {
-    /// Key: vertex label name, Value: vertex labal id
-    ///
-    /// Usage: get a vertex label id from its label name
-    vertex_label_map: BiBTreeMap<String, PatternLabelId>,
-    /// Key: edge label name, Value: edge label id
-    ///
-    /// Usage: get an edge label id from its label name
-    edge_label_map: BiBTreeMap<String, PatternLabelId>,
+    /// Usage: get a vertex label id from its label name, and vice versa
+    vertex_labels: LabelInterner,
+    /// Usage: get an edge label id from its label name, and vice versa
+    edge_labels: LabelInterner,
     /// Key: vertex label id, Value: BTreeSet<(edge label id, direction)>
     ///
     /// Usage: given a vertex(label id), find all its adjacent edges(label id) with directions
@@ -45,6 +90,11 @@ pub struct PatternMeta {
     /// Usage: given a (src vertex label id, dst vertex label id) pair:
     ///        find all possible edges(label id) between them
     vv2edges_meta: BTreeMap<(PatternLabelId, PatternLabelId), Vec<(PatternLabelId, PatternDirection)>>,
+    /// Key: vertex label id, Value: the number of vertices with the label in the graph
+    ///
+    /// Usage: statistics to prefer the vertices of rare labels in plan generation, which are not in
+    ///        the schema and have to be set explicitly
+    vertex_label_counts: BTreeMap<PatternLabelId, usize>,
 }
 
 /// Initializer of PatternMeta
@@ -53,11 +103,12 @@ impl From<Schema> for PatternMeta {
     fn from(src_schema: Schema) -> PatternMeta {
         let (table_map, relation_labels) = src_schema.get_pattern_meta_info();
         let mut pattern_meta = PatternMeta {
-            vertex_label_map: BiBTreeMap::new(),
-            edge_label_map: BiBTreeMap::new(),
+            vertex_labels: LabelInterner::default(),
+            edge_labels: LabelInterner::default(),
             v2edges_meta: BTreeMap::new(),
             e2vertices_meta: BTreeMap::new(),
             vv2edges_meta: BTreeMap::new(),
+            vertex_label_counts: BTreeMap::new(),
         };
         for (name, (key_type, id)) in &table_map {
             if let KeyType::Relation = key_type {
@@ -66,7 +117,7 @@ impl From<Schema> for PatternMeta {
                     .get(id)
                     .expect("Schema relation_bound_labels doesn't store edge info");
                 pattern_meta
-                    .edge_label_map
+                    .edge_labels
                     .insert(name.clone(), *id);
                 for (start_v_meta, end_v_meta) in connections {
                     let start_v_name = start_v_meta.get_name();
@@ -103,7 +154,7 @@ impl From<Schema> for PatternMeta {
             } else if let KeyType::Entity = key_type {
                 // Case that this is an vertex label
                 pattern_meta
-                    .vertex_label_map
+                    .vertex_labels
                     .insert(name.clone(), *id);
             }
         }
@@ -114,31 +165,19 @@ impl From<Schema> for PatternMeta {
 /// Iterators of fields in PatternMeta
 impl PatternMeta {
     pub fn vertex_label_names_iter(&self) -> DynIter<&String> {
-        Box::new(
-            self.vertex_label_map
-                .iter()
-                .map(|(name, _)| name),
-        )
+        self.vertex_labels.names_iter()
     }
 
     pub fn edge_label_names_iter(&self) -> DynIter<&String> {
-        Box::new(self.edge_label_map.iter().map(|(name, _)| name))
+        self.edge_labels.names_iter()
     }
 
     pub fn vertex_label_ids_iter(&self) -> DynIter<PatternLabelId> {
-        Box::new(
-            self.vertex_label_map
-                .iter()
-                .map(|(_, vertex_id)| *vertex_id),
-        )
+        self.vertex_labels.ids_iter()
     }
 
     pub fn edge_label_ids_iter(&self) -> DynIter<PatternLabelId> {
-        Box::new(
-            self.edge_label_map
-                .iter()
-                .map(|(_, edge_id)| *edge_id),
-        )
+        self.edge_labels.ids_iter()
     }
 
     /// Given a soruce vertex label, iterate over all its neighboring adjacent vertices(label)
@@ -177,59 +216,105 @@ impl PatternMeta {
     pub fn associated_vlabels_iter_by_elabel(
         &self, src_e_label: PatternLabelId,
     ) -> DynIter<(PatternLabelId, PatternLabelId)> {
-        match self.e2vertices_meta.get(&src_e_label) {
-            Some(associations) => Box::new(associations.iter().cloned()),
-            None => Box::new(std::iter::empty()),
-        }
+        Box::new(
+            self.associated_vlabels(src_e_label)
+                .iter()
+                .cloned(),
+        )
     }
     /// Given a src vertex label and a dst vertex label, iterate over all possible edges(label) between them with directions
     pub fn associated_elabels_iter_by_vlabel(
         &self, src_v_label: PatternLabelId, dst_v_label: PatternLabelId,
     ) -> DynIter<(PatternLabelId, PatternDirection)> {
-        match self
-            .vv2edges_meta
-            .get(&(src_v_label, dst_v_label))
-        {
-            Some(edges) => Box::new(edges.iter().cloned()),
-            None => Box::new(std::iter::empty()),
-        }
+        Box::new(
+            self.associated_elabels(src_v_label, dst_v_label)
+                .iter()
+                .cloned(),
+        )
     }
 }
 
 /// Methods for access some fields of PatternMeta or get some info from PatternMeta
 impl PatternMeta {
+    /// Given an edge label, get all possible pairs of its (src vertex label, dst vertex label)
+    ///
+    /// The pairs are precomputed from the schema, so it is cheaper than collecting
+    /// associated_vlabels_iter_by_elabel in hot loops
+    pub fn associated_vlabels(&self, e_label: PatternLabelId) -> &[(PatternLabelId, PatternLabelId)] {
+        self.e2vertices_meta
+            .get(&e_label)
+            .map(Vec::as_slice)
+            .unwrap_or(&[])
+    }
+
+    /// Given a src vertex label and a dst vertex label, get all possible edges(label) between them
+    /// with directions
+    pub fn associated_elabels(
+        &self, src_v_label: PatternLabelId, dst_v_label: PatternLabelId,
+    ) -> &[(PatternLabelId, PatternDirection)] {
+        self.vv2edges_meta
+            .get(&(src_v_label, dst_v_label))
+            .map(Vec::as_slice)
+            .unwrap_or(&[])
+    }
+
     /// Get the number of vertex labels in the graph schema
     pub fn get_vertex_types_num(&self) -> usize {
-        self.vertex_label_map.len()
+        self.vertex_labels.len()
     }
 
     /// Get the number of edge labels in the graph schema
     pub fn get_edge_types_num(&self) -> usize {
-        self.edge_label_map.len()
+        self.edge_labels.len()
+    }
+
+    /// Get the interner between the vertex label names and ids
+    pub fn get_vertex_label_interner(&self) -> &LabelInterner {
+        &self.vertex_labels
+    }
+
+    /// Get the interner between the edge label names and ids
+    pub fn get_edge_label_interner(&self) -> &LabelInterner {
+        &self.edge_labels
     }
 
     pub fn get_vertex_label_id(&self, label_name: &str) -> Option<PatternLabelId> {
-        self.vertex_label_map
-            .get_by_left(label_name)
-            .cloned()
+        self.vertex_labels.name_to_id(label_name)
     }
 
     pub fn get_edge_label_id(&self, label_name: &str) -> Option<PatternLabelId> {
-        self.edge_label_map
-            .get_by_left(label_name)
-            .cloned()
+        self.edge_labels.name_to_id(label_name)
     }
 
     pub fn get_vertex_label_name(&self, label_id: PatternLabelId) -> Option<String> {
-        self.vertex_label_map
-            .get_by_right(&label_id)
-            .cloned()
+        self.vertex_labels
+            .id_to_name(label_id)
+            .map(str::to_string)
     }
 
     pub fn get_edge_label_name(&self, label_id: PatternLabelId) -> Option<String> {
-        self.edge_label_map
-            .get_by_right(&label_id)
-            .cloned()
+        self.edge_labels
+            .id_to_name(label_id)
+            .map(str::to_string)
+    }
+
+    /// Get the number of vertices with the label in the graph, if it is known
+    pub fn get_vertex_label_count(&self, label_id: PatternLabelId) -> Option<usize> {
+        self.vertex_label_counts.get(&label_id).cloned()
+    }
+
+    pub fn set_vertex_label_count(&mut self, label_id: PatternLabelId, count: usize) {
+        self.vertex_label_counts.insert(label_id, count);
+    }
+
+    /// Set the vertex counts of all the vertex labels by the vertices in the graph
+    pub fn with_vertex_label_counts(mut self, graph: &LargeGraphDB<DefaultId, InternalId>) -> Self {
+        let vertex_label_ids: Vec<PatternLabelId> = self.vertex_label_ids_iter().collect();
+        for vertex_label in vertex_label_ids {
+            let vertex_count = graph.count_all_vertices(Some(&vec![vertex_label as LabelId]));
+            self.set_vertex_label_count(vertex_label, vertex_count);
+        }
+        self
     }
 
     /// Get the maximum vertex label id of the current pattern
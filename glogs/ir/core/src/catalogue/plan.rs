@@ -17,10 +17,11 @@ use core::ops::{Add, AddAssign};
 use itertools::Itertools;
 use ordered_float::{Float, OrderedFloat};
 use std::borrow::BorrowMut;
-use std::cmp::Ordering;
-use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::cmp::{Ordering, Reverse};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque};
 use std::convert::{TryFrom, TryInto};
 use std::fmt::Display;
+use std::sync::atomic::{AtomicUsize, Ordering as AtomicOrdering};
 use std::sync::RwLock;
 
 use ir_common::expr_parse::str_to_expr_pb;
@@ -28,37 +29,122 @@ use ir_common::generated::algebra::{self as pb};
 use ir_common::generated::common::{self as common_pb, Variable};
 use lazy_static::lazy_static;
 use petgraph::graph::NodeIndex;
+use rayon::prelude::*;
 
 use crate::catalogue::catalog::{
-    Approach, ApproachWeight, Catalogue, ExtendWeight, JoinWeight, PatMatPlanSpace,
+    Approach, ApproachWeight, Catalogue, ExtendWeight, IntersectOrder, JoinWeight, PatMatPlanSpace,
 };
-use crate::catalogue::extend_step::DefiniteExtendStep;
+use crate::catalogue::extend_step::{DefiniteExtendEdge, DefiniteExtendStep};
 use crate::catalogue::join_step::BinaryJoinPlan;
-use crate::catalogue::pattern::{Pattern, PatternVertex};
+use crate::catalogue::pattern::{Pattern, PatternEdge, PatternVertex};
 use crate::catalogue::pattern_meta::PatternMeta;
 use crate::catalogue::PatternDirection;
 use crate::catalogue::{PatternId, PatternLabelId};
 use crate::error::{IrError, IrResult};
+use crate::plan::meta::TagId;
+
+/// The default inflation of the count of a pattern per extra hop of its path edges
+const DEFAULT_PATH_HOP_INFLATION: f64 = 10.0;
 
 lazy_static! {
-    static ref ALPHA: RwLock<f64> = RwLock::new(0.15);
-    static ref BETA: RwLock<f64> = RwLock::new(0.1);
-    static ref W1: RwLock<f64> = RwLock::new(6.0);
-    static ref W2: RwLock<f64> = RwLock::new(3.0);
+    static ref DEFAULT_COST_MODEL: RwLock<CostModel> = RwLock::new(CostModel::new(0.15, 0.1, 6.0, 3.0));
 }
 
 /// Methods for Pattern to generate pb Logical plan of pattern matching
 impl Pattern {
     /// Get all required subpatterns (i.e., whose cardinalities should be estimated) for plan generation.
     pub fn generate_subpatterns(&self) -> Vec<Pattern> {
+        let patterns: BTreeMap<Vec<u8>, Pattern> = self
+            .generate_subpatterns_iter()
+            .map(|pattern| (pattern.encode_to(), pattern))
+            .collect();
+        patterns.into_values().collect()
+    }
+
+    /// Lazily yield the same subpatterns as generate_subpatterns, deduplicated by their codes
+    ///
+    /// Only the codes of the yielded subpatterns are kept for deduplication, so the memory is bounded
+    /// for large patterns, whose subpatterns are consumed one by one.
+    pub fn generate_subpatterns_iter(&self) -> impl Iterator<Item = Pattern> {
+        SubpatternsIter::new(self.clone(), None)
+    }
+
+    /// Generate the subpatterns as generate_subpatterns, but stop once the budget is exceeded
+    ///
+    /// The subpatterns collected so far are returned with a flag indicating whether the generation is
+    /// truncated by the budget.
+    pub fn generate_subpatterns_with_budget(&self, budget: &SubpatternsBudget) -> (Vec<Pattern>, bool) {
+        let mut subpatterns_iter = SubpatternsIter::new(self.clone(), budget.max_depth);
+        let mut patterns = BTreeMap::new();
+        while budget
+            .max_patterns
+            .map(|max_patterns| patterns.len() < max_patterns)
+            .unwrap_or(true)
+        {
+            if let Some(pattern) = subpatterns_iter.next() {
+                patterns.insert(pattern.encode_to(), pattern);
+            } else {
+                return (patterns.into_values().collect(), subpatterns_iter.truncated);
+            }
+        }
+        let truncated = subpatterns_iter.next().is_some() || subpatterns_iter.truncated;
+        (patterns.into_values().collect(), truncated)
+    }
+
+    /// Get all connected subpatterns with exactly n vertices (deduplicated by their codes).
+    ///
+    /// Vertices are removed level by level, so only `get_vertices_num() - n` levels are enumerated.
+    /// Edges are then removed from the induced subpatterns as long as they keep connecting all n vertices,
+    /// so that the non-induced subpatterns are returned as well.
+    pub fn subpatterns_of_size(&self, n: usize) -> Vec<Pattern> {
+        let vertices_num = self.get_vertices_num();
+        if n == 0 || n > vertices_num {
+            return vec![];
+        }
         let mut patterns = BTreeMap::new();
-        generate_subpatterns_recursive(self, &mut patterns);
+        patterns.insert(self.encode_to(), self.clone());
+        for _ in n..vertices_num {
+            let mut sub_patterns = BTreeMap::new();
+            for pattern in patterns.values() {
+                for vertex_id in pattern
+                    .vertices_iter()
+                    .map(|vertex| vertex.get_id())
+                {
+                    if let Some(sub_pattern) = pattern.clone().remove_vertex(vertex_id) {
+                        sub_patterns
+                            .entry(sub_pattern.encode_to())
+                            .or_insert(sub_pattern);
+                    }
+                }
+            }
+            patterns = sub_patterns;
+        }
+        let mut removing_edge_patterns = patterns.clone();
+        while !removing_edge_patterns.is_empty() {
+            let mut sub_patterns = BTreeMap::new();
+            for pattern in removing_edge_patterns.values() {
+                for edge_id in pattern.edges_iter().map(|edge| edge.get_id()) {
+                    if let Some(sub_pattern) = pattern.clone().remove_edge(edge_id) {
+                        let sub_pattern_code = sub_pattern.encode_to();
+                        if sub_pattern.get_vertices_num() == n && !patterns.contains_key(&sub_pattern_code)
+                        {
+                            sub_patterns
+                                .entry(sub_pattern_code)
+                                .or_insert(sub_pattern);
+                        }
+                    }
+                }
+            }
+            patterns.extend(sub_patterns.clone());
+            removing_edge_patterns = sub_patterns;
+        }
         patterns.into_values().collect()
     }
 
-    /// Generate a naive extend based pattern match plan
+    /// Generate a naive extend based pattern match plan, whose matches are aggregated and limited by
+    /// the config
     pub fn generate_simple_extend_match_plan(
-        &self, pattern_meta: &PatternMeta, is_distributed: bool,
+        &self, pattern_meta: &PatternMeta, is_distributed: bool, config: &PlanConfig,
     ) -> IrResult<pb::LogicalPlan> {
         let mut trace_pattern = self.clone();
         let mut definite_extend_steps = vec![];
@@ -85,7 +171,8 @@ impl Pattern {
                 .expect("Failed to build stand-alone pattern match plan")
         };
         match_pb_plan_add_source(&mut pb_plan);
-        pb_plan_add_count_sink_operator(&mut pb_plan);
+        pb_plan_add_limit_operator(&mut pb_plan, config.limit)?;
+        pb_plan_add_aggregate_sink_operator(&mut pb_plan, self, config.aggregate)?;
         Ok(pb_plan)
     }
 
@@ -94,6 +181,7 @@ impl Pattern {
     /// The basic idea is to put vertex with predicate or lowest cost to be executed earlier.
     pub fn generate_heuristic_match_plan(
         &self, catalog: &mut Catalogue, pattern_meta: &PatternMeta, is_distributed: bool,
+        config: &PlanConfig,
     ) -> IrResult<pb::LogicalPlan> {
         // let (mut extend_steps, _) = get_definite_extend_steps(self.clone(), catalog);
         // extend_steps.reverse();
@@ -103,8 +191,14 @@ impl Pattern {
         //     build_stand_alone_match_plan(self, extend_steps, pattern_meta)
         // }
 
-        let (mut extend_steps, _) = get_definite_extend_steps(self.clone(), catalog);
-        extend_steps.reverse();
+        let extend_steps = if self.is_single_cycle() {
+            get_cycle_definite_extend_steps(self, pattern_meta)?
+        } else {
+            let (mut extend_steps, _) =
+                get_definite_extend_steps_parallel(self.clone(), catalog, Some(pattern_meta));
+            extend_steps.reverse();
+            extend_steps
+        };
         let mut pb_plan = if is_distributed {
             build_distributed_match_plan(self, extend_steps, pattern_meta)
                 .expect("Failed to build distributed pattern match plan")
@@ -113,93 +207,307 @@ impl Pattern {
                 .expect("Failed to build distributed pattern match plan")
         };
         match_pb_plan_add_source(&mut pb_plan);
-        pb_plan_add_count_sink_operator(&mut pb_plan);
+        pb_plan_add_limit_operator(&mut pb_plan, config.limit)?;
+        pb_plan_add_aggregate_sink_operator(&mut pb_plan, self, config.aggregate)?;
         Ok(pb_plan)
     }
 
+    /// Generate the optimized plan, whose approaches are chosen under the cost model of the config
     pub fn generate_optimized_match_plan(
         &self, catalog: &mut Catalogue, pattern_meta: &PatternMeta, is_distributed: bool,
+        config: &PlanConfig,
     ) -> IrResult<pb::LogicalPlan> {
         // If pattern not found in catalogue, use heuristic plan
         if catalog
             .get_pattern_index(&self.encode_to())
             .is_none()
         {
-            return self.generate_heuristic_match_plan(catalog, pattern_meta, is_distributed);
+            return self.generate_heuristic_match_plan(catalog, pattern_meta, is_distributed, config);
         }
 
         // If pattern is in catalogue, optimizatized plan is generated.
-        match catalog.get_plan_space() {
-            PatMatPlanSpace::BinaryJoin => Err(IrError::Unsupported(
-                "Do not support pure binary join plan with no extend steps".to_string(),
-            )),
-            _ => {
-                catalog.set_best_approach_by_pattern(self);
-                PlanGenerator::new(self, catalog, pattern_meta, is_distributed)
-                    .generate_pattern_match_plan()
-            }
-        }
+        PlanGenerator::new(self, catalog, pattern_meta, is_distributed)
+            .with_config(config)
+            .generate_pattern_match_plan()
     }
 }
 
-fn generate_subpatterns_recursive(pattern: &Pattern, patterns: &mut BTreeMap<Vec<u8>, Pattern>) {
-    patterns
-        .entry(pattern.encode_to())
-        .or_insert_with(|| pattern.clone());
+/// The budget to bound the generation of subpatterns
+///
+/// max_depth: the maximum number of vertices removed from the pattern to generate a subpattern
+///
+/// max_patterns: the maximum number of subpatterns to generate
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SubpatternsBudget {
+    max_depth: Option<usize>,
+    max_patterns: Option<usize>,
+}
+
+impl SubpatternsBudget {
+    pub fn with_max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = Some(max_depth);
+        self
+    }
 
-    let mut sub_patterns_extend_steps = vec![];
-    for vertex_id in pattern
-        .vertices_iter()
-        .map(|vertex| vertex.get_id())
-    {
-        if let Some(sub_pattern) = pattern.clone().remove_vertex(vertex_id) {
-            let extend_step = DefiniteExtendStep::from_target_pattern(pattern, vertex_id).unwrap();
-            sub_patterns_extend_steps.push((sub_pattern, extend_step));
-        }
-    }
-    for (sub_pattern, extend_step) in sub_patterns_extend_steps {
-        patterns
-            .entry(sub_pattern.encode_to())
-            .or_insert_with(|| sub_pattern.clone());
-        let target_vertex = extend_step.get_target_vertex();
-        for extend_edge in extend_step.iter() {
-            let adjacency_pattern = sub_pattern
-                .extend_definitely(extend_edge, target_vertex)
-                .unwrap();
-            patterns
-                .entry(adjacency_pattern.encode_to())
-                .or_insert(adjacency_pattern);
+    pub fn with_max_patterns(mut self, max_patterns: usize) -> Self {
+        self.max_patterns = Some(max_patterns);
+        self
+    }
+
+    pub fn get_max_depth(&self) -> Option<usize> {
+        self.max_depth
+    }
+
+    pub fn get_max_patterns(&self) -> Option<usize> {
+        self.max_patterns
+    }
+}
+
+/// The iterator of generate_subpatterns_iter
+///
+/// Patterns are expanded depth-first. The codes of the yielded and expanded patterns are kept to
+/// deduplicate them, but the patterns themselves are dropped once yielded.
+struct SubpatternsIter {
+    /// Patterns to be yielded
+    pending_patterns: VecDeque<Pattern>,
+    /// Patterns whose subpatterns are to be generated, with their depths
+    patterns_to_expand: Vec<(Pattern, usize)>,
+    yielded_codes: HashSet<Vec<u8>>,
+    expanded_codes: HashSet<Vec<u8>>,
+    /// Patterns deeper than max_depth are yielded but not expanded
+    max_depth: Option<usize>,
+    /// Whether some pattern is not expanded due to max_depth
+    truncated: bool,
+}
+
+impl SubpatternsIter {
+    fn new(pattern: Pattern, max_depth: Option<usize>) -> Self {
+        SubpatternsIter {
+            pending_patterns: VecDeque::from(vec![pattern.clone()]),
+            patterns_to_expand: vec![(pattern, 0)],
+            yielded_codes: HashSet::new(),
+            expanded_codes: HashSet::new(),
+            max_depth,
+            truncated: false,
         }
-        for extend_edges in extend_step
-            .iter()
-            .permutations(extend_step.get_extend_edges_num())
+    }
+
+    /// Generate the subpatterns removing one vertex from the pattern, and the patterns that
+    /// extend them by part of the edges to the removed vertex
+    ///
+    /// Patterns with the same code have the same subpatterns, so every code is expanded only once
+    fn expand(&mut self, pattern: Pattern, depth: usize) {
+        if self
+            .max_depth
+            .map(|max_depth| depth >= max_depth)
+            .unwrap_or(false)
+        {
+            self.truncated = self.truncated || pattern.get_vertices_num() > 1;
+            return;
+        }
+        if !self.expanded_codes.insert(pattern.encode_to()) {
+            return;
+        }
+        let mut sub_patterns = vec![];
+        for vertex_id in pattern
+            .vertices_iter()
+            .map(|vertex| vertex.get_id())
         {
-            let mut adjacency_pattern = sub_pattern.clone();
-            for extend_edge in extend_edges {
-                adjacency_pattern = adjacency_pattern
-                    .extend_definitely(extend_edge, target_vertex)
-                    .unwrap();
-                patterns
-                    .entry(adjacency_pattern.encode_to())
-                    .or_insert_with(|| adjacency_pattern.clone());
+            if let Some(sub_pattern) = pattern.clone().remove_vertex(vertex_id) {
+                let extend_step = DefiniteExtendStep::from_target_pattern(&pattern, vertex_id).unwrap();
+                self.pending_patterns
+                    .push_back(sub_pattern.clone());
+                let target_vertex = extend_step.get_target_vertex();
+                for extend_edges in extend_step
+                    .iter()
+                    .permutations(extend_step.get_extend_edges_num())
+                {
+                    let mut adjacency_pattern = sub_pattern.clone();
+                    for extend_edge in extend_edges {
+                        adjacency_pattern = adjacency_pattern
+                            .extend_definitely(extend_edge, target_vertex)
+                            .unwrap();
+                        if !self
+                            .yielded_codes
+                            .contains(&adjacency_pattern.encode_to())
+                        {
+                            self.pending_patterns
+                                .push_back(adjacency_pattern.clone());
+                        }
+                    }
+                }
+                sub_patterns.push((sub_pattern, depth + 1));
+            }
+        }
+        // Sub patterns are pushed reversely, so they are expanded in the order they are generated
+        self.patterns_to_expand
+            .extend(sub_patterns.into_iter().rev());
+    }
+}
+
+impl Iterator for SubpatternsIter {
+    type Item = Pattern;
+
+    fn next(&mut self) -> Option<Pattern> {
+        loop {
+            while let Some(pattern) = self.pending_patterns.pop_front() {
+                if self.yielded_codes.insert(pattern.encode_to()) {
+                    return Some(pattern);
+                }
             }
+            let (pattern, depth) = self.patterns_to_expand.pop()?;
+            self.expand(pattern, depth);
         }
-        generate_subpatterns_recursive(&sub_pattern, patterns);
     }
 }
 
 impl Catalogue {
+    /// Set the best approaches to reach the pattern under the default cost model
+    ///
+    /// Best approaches which are already set in the catalogue are kept
     pub fn set_best_approach_by_pattern(&mut self, pattern: &Pattern) {
         let node_index = self
             .get_pattern_index(&pattern.encode_to())
             .expect("Pattern not found in catalogue");
-        self.set_node_best_approach_recursively(node_index)
+        let mut best_approaches: HashMap<NodeIndex, Approach> = self
+            .pattern_indices_iter()
+            .filter_map(|index| {
+                self.get_pattern_weight(index)
+                    .and_then(|pattern_weight| pattern_weight.get_best_approach())
+                    .map(|best_approach| (index, best_approach))
+            })
+            .collect();
+        self.get_node_best_approach_recursively(node_index, &CostModel::default(), &mut best_approaches)
             .expect("Failed to set node best approach recursively");
+        for (index, best_approach) in best_approaches {
+            self.set_pattern_best_approach(index, best_approach);
+        }
+    }
+
+    /// Measure how sensitive the chosen plan of the pattern is to the estimated count of each of its subpatterns.
+    ///
+    /// Every subpattern's count is perturbed by -10% and +10% in turn, together with the extend counts
+    /// derived from it, and the best approaches are re-selected.
+    /// The score of a subpattern is the fraction of perturbations that change the chosen plan, keyed by its code.
+    /// Counts and best approaches are restored afterwards.
+    pub fn sensitivity_analysis(&mut self, pattern: &Pattern) -> IrResult<HashMap<Vec<u8>, f64>> {
+        const PERTURBATION_FACTORS: [f64; 2] = [0.9, 1.1];
+        let node_index = self
+            .get_pattern_index(&pattern.encode_to())
+            .ok_or_else(|| IrError::Unsupported("Pattern not found in catalog".to_string()))?;
+        let subpattern_indices = self.collect_subpattern_indices(node_index);
+        let original_best_approaches: Vec<(NodeIndex, Option<Approach>)> = subpattern_indices
+            .iter()
+            .map(|&index| {
+                (
+                    index,
+                    self.get_pattern_weight(index)
+                        .unwrap()
+                        .get_best_approach(),
+                )
+            })
+            .collect();
+        self.clear_best_approaches(&subpattern_indices);
+        self.set_best_approach_by_pattern(pattern);
+        let baseline_plan = self.collect_chosen_plan_approaches(node_index);
+        let mut sensitivity_scores = HashMap::new();
+        for &subpattern_index in subpattern_indices.iter() {
+            let subpattern_weight = self
+                .get_pattern_weight(subpattern_index)
+                .unwrap();
+            let subpattern_code = subpattern_weight.get_pattern().encode_to();
+            let original_count = subpattern_weight.get_count();
+            let mut changed_num = 0;
+            for factor in PERTURBATION_FACTORS {
+                self.set_pattern_count_with_index(subpattern_index, (original_count.0 * factor).into());
+                self.update_extend_count_infos_to(subpattern_index);
+                self.clear_best_approaches(&subpattern_indices);
+                self.set_best_approach_by_pattern(pattern);
+                if self.collect_chosen_plan_approaches(node_index) != baseline_plan {
+                    changed_num += 1;
+                }
+            }
+            self.set_pattern_count_with_index(subpattern_index, original_count);
+            self.update_extend_count_infos_to(subpattern_index);
+            sensitivity_scores
+                .insert(subpattern_code, changed_num as f64 / PERTURBATION_FACTORS.len() as f64);
+        }
+        // restore the best approaches before the analysis
+        for (index, best_approach) in original_best_approaches {
+            let pattern_weight = self.get_pattern_weight_mut(index).unwrap();
+            if let Some(best_approach) = best_approach {
+                pattern_weight.set_best_approach(best_approach)
+            } else {
+                pattern_weight.clear_best_approach()
+            }
+        }
+        Ok(sensitivity_scores)
     }
 
-    /// Given a node in catalogue, find the best approach and the lowest cost to reach to it
-    fn set_node_best_approach_recursively(
-        &mut self, node_index: NodeIndex,
+    /// Update the extend counts of the patterns extended to the given one, which derive from its count
+    fn update_extend_count_infos_to(&mut self, node_index: NodeIndex) {
+        let src_indices: BTreeSet<NodeIndex> = self
+            .pattern_in_approaches_iter(node_index)
+            .map(|approach| approach.get_src_pattern_index())
+            .collect();
+        for src_index in src_indices {
+            self.set_extend_count_infos(src_index);
+        }
+    }
+
+    /// Collect the given node and all nodes which can reach to it in the catalogue
+    fn collect_subpattern_indices(&self, node_index: NodeIndex) -> Vec<NodeIndex> {
+        let mut visited = BTreeSet::from([node_index]);
+        let mut stack = vec![node_index];
+        while let Some(index) = stack.pop() {
+            for approach in self.pattern_in_approaches_iter(index) {
+                let mut pre_indices = vec![approach.get_src_pattern_index()];
+                if let Some(join_weight) = self.get_join_weight(approach.get_approach_index()) {
+                    pre_indices.push(join_weight.get_probe_pattern_node_index());
+                }
+                for pre_index in pre_indices {
+                    if visited.insert(pre_index) {
+                        stack.push(pre_index);
+                    }
+                }
+            }
+        }
+        visited.into_iter().collect()
+    }
+
+    fn clear_best_approaches(&mut self, node_indices: &[NodeIndex]) {
+        for &index in node_indices {
+            if let Some(pattern_weight) = self.get_pattern_weight_mut(index) {
+                pattern_weight.clear_best_approach()
+            }
+        }
+    }
+
+    /// Collect the best approaches which the plan of the given node consists of
+    fn collect_chosen_plan_approaches(&self, node_index: NodeIndex) -> Vec<Approach> {
+        let mut chosen_approaches = vec![];
+        let mut stack = vec![node_index];
+        while let Some(index) = stack.pop() {
+            if let Some(best_approach) = self
+                .get_pattern_weight(index)
+                .and_then(|pattern_weight| pattern_weight.get_best_approach())
+            {
+                chosen_approaches.push(best_approach);
+                stack.push(best_approach.get_src_pattern_index());
+                if let Some(join_weight) = self.get_join_weight(best_approach.get_approach_index()) {
+                    stack.push(join_weight.get_probe_pattern_node_index());
+                }
+            }
+        }
+        chosen_approaches
+    }
+
+    /// Given a node in catalogue, find the best approach and the lowest cost to reach it by the cost model
+    ///
+    /// The chosen approaches are memorized in best_approaches, and the memorized ones are reused
+    fn get_node_best_approach_recursively(
+        &self, node_index: NodeIndex, cost_model: &CostModel,
+        best_approaches: &mut HashMap<NodeIndex, Approach>,
     ) -> IrResult<(Option<Approach>, CostCount)> {
         let pattern_weight = self
             .get_pattern_weight(node_index)
@@ -207,13 +515,13 @@ impl Catalogue {
         let pattern = pattern_weight.get_pattern().clone();
         if pattern.get_vertices_num() == 1 {
             Ok((None, CostCount::from_src_pattern(pattern_weight.get_count())))
-        } else if let Some(best_approach) = pattern_weight.get_best_approach() {
-            // Recursively set best approach
+        } else if let Some(&best_approach) = best_approaches.get(&node_index) {
+            // Recursively get the cost of the best approach
             let pre_pattern_index = best_approach.get_src_pattern_index();
             let (_pre_best_approach, mut cost) = self
-                .set_node_best_approach_recursively(pre_pattern_index)
+                .get_node_best_approach_recursively(pre_pattern_index, cost_model, best_approaches)
                 .expect("Failed to set node best approach recursively");
-            let this_step_cost = self.estimate_approach_cost(&best_approach);
+            let this_step_cost = self.estimate_approach_cost(&best_approach, cost_model, best_approaches);
             cost += this_step_cost;
             Ok((Some(best_approach), cost))
         } else {
@@ -228,12 +536,12 @@ impl Catalogue {
             for approach in candidate_approaches {
                 let pre_pattern_index = approach.get_src_pattern_index();
                 let (_pre_best_approach, pre_cost) = self
-                    .set_node_best_approach_recursively(pre_pattern_index)
+                    .get_node_best_approach_recursively(pre_pattern_index, cost_model, best_approaches)
                     .expect("Failed to set node best approach recursively");
-                let this_step_cost = self.estimate_approach_cost(&approach);
+                let this_step_cost = self.estimate_approach_cost(&approach, cost_model, best_approaches);
                 let cost = pre_cost + this_step_cost;
                 cost_counts_vec.push((pre_pattern_index, pre_cost, this_step_cost, cost));
-                if cost < min_cost {
+                if cost.get_cost(cost_model) < min_cost.get_cost(cost_model) {
                     min_cost = cost;
                     best_approach = approach;
                 }
@@ -245,15 +553,24 @@ impl Catalogue {
                 best_approach,
                 min_cost,
                 cost_counts_vec,
+                cost_model,
             );
-            // set best approach in the catalogue
-            self.set_pattern_best_approach(node_index, best_approach);
+            // memorize the best approach
+            best_approaches.insert(node_index, best_approach);
             Ok((Some(best_approach), min_cost))
         }
     }
 
     /// Collect all candidate approaches in plan space of the give node
+    ///
+    /// In the binary join plan space, patterns without any join approach are reached by extend steps
     fn collect_candidate_approaches(&self, node_index: NodeIndex) -> Vec<Approach> {
+        let has_join_approach = self
+            .pattern_in_approaches_iter(node_index)
+            .any(|approach| {
+                self.get_join_weight(approach.get_approach_index())
+                    .is_some()
+            });
         let candidate_approaches: Vec<Approach> = self
             .pattern_in_approaches_iter(node_index)
             .filter(|approach| {
@@ -262,7 +579,7 @@ impl Catalogue {
                     .expect("No such approach exists in catalogue");
                 let is_approach_in_plan_space: bool = match self.get_plan_space() {
                     PatMatPlanSpace::ExtendWithIntersection => approach_weight.is_extend(),
-                    PatMatPlanSpace::BinaryJoin => approach_weight.is_join(),
+                    PatMatPlanSpace::BinaryJoin => approach_weight.is_join() || !has_join_approach,
                     PatMatPlanSpace::Hybrid => true,
                 };
                 is_approach_in_plan_space
@@ -272,14 +589,17 @@ impl Catalogue {
     }
 
     /// Cost Estimation Functions
-    fn estimate_approach_cost(&mut self, approach: &Approach) -> CostCount {
+    fn estimate_approach_cost(
+        &self, approach: &Approach, cost_model: &CostModel,
+        best_approaches: &mut HashMap<NodeIndex, Approach>,
+    ) -> CostCount {
         let approach_weight = self
             .get_approach_weight(approach.get_approach_index())
             .expect("Approach not found in catalogue");
         if let ApproachWeight::ExtendStep(extend_weight) = approach_weight {
             self.estimate_extend_step_cost(approach, extend_weight)
-        } else if let ApproachWeight::BinaryJoinStep(join_weight) = approach_weight.clone() {
-            self.estimate_binary_join_step_cost(approach, &join_weight)
+        } else if let ApproachWeight::BinaryJoinStep(join_weight) = approach_weight {
+            self.estimate_binary_join_step_cost(approach, join_weight, cost_model, best_approaches)
         } else {
             CostCount::max_value()
         }
@@ -311,7 +631,8 @@ impl Catalogue {
 
     /// Cost Estimation Function of Binary Join Step
     fn estimate_binary_join_step_cost(
-        &mut self, approach: &Approach, join_weight: &JoinWeight,
+        &self, approach: &Approach, join_weight: &JoinWeight, cost_model: &CostModel,
+        best_approaches: &mut HashMap<NodeIndex, Approach>,
     ) -> CostCount {
         // Collect data for cost estimation
         let build_pattern_cardinality = self
@@ -327,7 +648,11 @@ impl Catalogue {
             .expect("Cannot find pattern weight in catalogue")
             .get_count();
         let (_, probe_pattern_cost) = self
-            .set_node_best_approach_recursively(join_weight.get_probe_pattern_node_index())
+            .get_node_best_approach_recursively(
+                join_weight.get_probe_pattern_node_index(),
+                cost_model,
+                best_approaches,
+            )
             .unwrap();
         probe_pattern_cost
             + CostCount::from_join(
@@ -347,6 +672,13 @@ impl Catalogue {
 /// target_pattern: the reference of the target pattern, fixed after initialization
 ///
 /// catalog: the reference of the catalogue
+///
+/// cost_model: the cost model to choose the best approaches, which are memorized in best_approaches
+///
+/// node_steps: the catalogue step which each node in the plan is generated from, aligned with plan.nodes
+///
+/// node_cardinalities: the estimated cardinality flowing out of each node that changes the result
+/// cardinality, i.e., the last node of a step, aligned with plan.nodes
 pub struct PlanGenerator<'a> {
     plan: pb::LogicalPlan,
     vertex_labels_to_scan: BTreeSet<PatternLabelId>,
@@ -355,6 +687,12 @@ pub struct PlanGenerator<'a> {
     catalog: &'a Catalogue,
     pattern_meta: &'a PatternMeta,
     is_distributed: bool,
+    cost_model: CostModel,
+    aggregate: AggregateSpec,
+    limit: Option<usize>,
+    best_approaches: HashMap<NodeIndex, Approach>,
+    node_steps: Vec<Option<PlanStep>>,
+    node_cardinalities: Vec<Option<OrderedFloat<f64>>>,
 }
 
 impl<'a> PlanGenerator<'a> {
@@ -369,9 +707,64 @@ impl<'a> PlanGenerator<'a> {
             target_pattern: pattern,
             plan: pb::LogicalPlan::default(),
             vertex_labels_to_scan: BTreeSet::new(),
+            cost_model: CostModel::default().for_mode(is_distributed),
+            aggregate: AggregateSpec::default(),
+            limit: None,
+            best_approaches: HashMap::new(),
+            node_steps: vec![],
+            node_cardinalities: vec![],
         }
     }
 
+    /// Choose the approaches under the cost model, with its weights for the mode of the generator
+    pub fn with_cost_model(mut self, cost_model: CostModel) -> Self {
+        self.cost_model = cost_model.for_mode(self.is_distributed);
+        self
+    }
+
+    pub fn get_cost_model(&self) -> &CostModel {
+        &self.cost_model
+    }
+
+    pub fn with_aggregate(mut self, aggregate: AggregateSpec) -> Self {
+        self.aggregate = aggregate;
+        self
+    }
+
+    pub fn get_aggregate(&self) -> AggregateSpec {
+        self.aggregate
+    }
+
+    /// Only aggregate the first `limit` matches in the generated plan, if given
+    pub fn with_limit(mut self, limit: Option<usize>) -> Self {
+        self.limit = limit;
+        self
+    }
+
+    pub fn get_limit(&self) -> Option<usize> {
+        self.limit
+    }
+
+    /// Set the cost model, the aggregate and the limit of the config
+    pub fn with_config(self, config: &PlanConfig) -> Self {
+        self.with_cost_model(config.cost_model)
+            .with_aggregate(config.aggregate)
+            .with_limit(config.limit)
+    }
+
+    /// Get the best approach to reach the node in the catalogue under the cost model of the generator
+    ///
+    /// Return None if the node is a single vertex pattern, which is reached by scanning
+    pub fn get_best_approach(&mut self, node_index: NodeIndex) -> IrResult<Option<Approach>> {
+        if let Some(&best_approach) = self.best_approaches.get(&node_index) {
+            return Ok(Some(best_approach));
+        }
+        let (best_approach, _) = self
+            .catalog
+            .get_node_best_approach_recursively(node_index, &self.cost_model, &mut self.best_approaches)?;
+        Ok(best_approach)
+    }
+
     /// Get the pb logical plan
     pub fn get_pb_plan(&self) -> pb::LogicalPlan {
         self.plan.clone()
@@ -382,6 +775,74 @@ impl<'a> PlanGenerator<'a> {
         self.plan.nodes.len()
     }
 
+    /// Explain the generated plan node by node, with the catalogue step each node comes from
+    pub fn explain(&self) -> PlanExplanation {
+        let nodes = self
+            .plan
+            .nodes
+            .iter()
+            .enumerate()
+            .map(|(node_id, node)| PlanExplanationNode {
+                operator: get_operator_kind(node),
+                children: node.children.clone(),
+                step: self.node_steps.get(node_id).cloned().flatten(),
+                estimated_cardinality: self
+                    .node_cardinalities
+                    .get(node_id)
+                    .copied()
+                    .flatten()
+                    .map(|cardinality| cardinality.into_inner()),
+            })
+            .collect();
+        PlanExplanation { roots: self.plan.roots.clone(), nodes, cost_model: self.cost_model }
+    }
+
+    /// Get the estimated cardinalities flowing out of the nodes, aligned with the nodes of the logical plan
+    ///
+    /// Only the nodes that change the result cardinality have estimations, which are the counts of the
+    /// patterns matched after them
+    pub fn get_estimated_cardinalities(&self) -> Vec<Option<f64>> {
+        (0..self.get_node_num())
+            .map(|node_id| {
+                self.node_cardinalities
+                    .get(node_id)
+                    .copied()
+                    .flatten()
+                    .map(|cardinality| cardinality.into_inner())
+            })
+            .collect()
+    }
+
+    /// Align the steps and cardinalities with the nodes, where the nodes pushed without a step get None
+    fn align_node_steps(&mut self) {
+        self.node_steps
+            .resize(self.get_node_num(), None);
+        self.node_cardinalities
+            .resize(self.get_node_num(), None);
+    }
+
+    /// Mark the nodes within the range as generated from the given step
+    fn set_node_steps(&mut self, node_ids: std::ops::Range<usize>, step: &PlanStep) {
+        self.align_node_steps();
+        for node_step in &mut self.node_steps[node_ids] {
+            *node_step = Some(step.clone());
+        }
+    }
+
+    /// Mark the node as outputting the instances of the given pattern
+    fn set_node_cardinality(&mut self, node_id: usize, pattern: &Pattern) {
+        self.align_node_steps();
+        self.node_cardinalities[node_id] = Some(self.get_pattern_count(pattern));
+    }
+
+    fn get_pattern_count(&self, pattern: &Pattern) -> OrderedFloat<f64> {
+        self.catalog
+            .get_pattern_index(&pattern.encode_to())
+            .and_then(|node_index| self.catalog.get_pattern_weight(node_index))
+            .map(|pattern_weight| pattern_weight.get_count())
+            .unwrap_or_default()
+    }
+
     fn insert_node(&mut self, index: usize, node: pb::logical_plan::Node) -> IrResult<()> {
         if self.get_node_num() < index {
             return Err(IrError::Unsupported(
@@ -389,6 +850,9 @@ impl<'a> PlanGenerator<'a> {
             ));
         }
 
+        self.align_node_steps();
+        self.node_steps.insert(index, None);
+        self.node_cardinalities.insert(index, None);
         self.plan.nodes.insert(index, node);
         // Offset the nodes after the inserted one by 1
         self.plan
@@ -425,28 +889,17 @@ impl<'a> PlanGenerator<'a> {
     }
 
     fn remove_node(&mut self, index: usize) -> IrResult<()> {
-        if self.get_node_num() < index {
+        if self.get_node_num() <= index {
             return Err(IrError::Unsupported(
                 "Failed in Plan Generation: Node Removal, Index out of bound".to_string(),
             ));
         }
 
-        // Offset the nodes after the inserted one by -1
-        self.plan
-            .nodes
-            .iter_mut()
-            .enumerate()
-            .filter(|(current_node_idx, _node)| *current_node_idx > index)
-            .for_each(|(_index, current_node)| {
-                current_node
-                    .children
-                    .iter_mut()
-                    .for_each(|child_id| {
-                        *child_id -= 1;
-                    });
-            });
-        // Remove the node at specified index
-        self.plan.nodes.remove(index);
+        // Remove the node at specified index, and offset the nodes after it by -1
+        self.align_node_steps();
+        self.node_steps.remove(index);
+        self.node_cardinalities.remove(index);
+        pb_plan_remove_node(&mut self.plan, index);
 
         Ok(())
     }
@@ -455,7 +908,9 @@ impl<'a> PlanGenerator<'a> {
         self.generate_pattern_match_plan_recursively(self.target_pattern)
             .expect("Failed to generate pattern match plan with catalogue");
         self.match_pb_plan_add_source();
-        self.pb_plan_add_count_sink_operator();
+        self.pb_plan_add_limit_operator()?;
+        self.pb_plan_add_aggregate_sink_operator()?;
+        validate_logical_plan(&self.plan)?;
         Ok(self.plan.clone())
     }
 
@@ -471,11 +926,7 @@ impl<'a> PlanGenerator<'a> {
                 self.generate_pattern_match_plan_for_size_one_pattern(pattern);
             } else {
                 // Get the best approach to reach the node
-                let best_approach_opt = self
-                    .catalog
-                    .get_pattern_weight(node_index)
-                    .expect("Failed to get pattern weight from node index")
-                    .get_best_approach();
+                let best_approach_opt = self.get_best_approach(node_index)?;
                 // Set trace pattern for recursive plan generation
                 self.trace_pattern = pattern.clone();
                 if let Some(best_approach) = best_approach_opt {
@@ -510,16 +961,27 @@ impl<'a> PlanGenerator<'a> {
             .catalog
             .get_pattern_index(&target_pattern.encode_to())
             .expect("Pattern not found in catalog");
-        let (src_pattern, definite_extend_step, _cost) =
+        let (src_pattern, definite_extend_step, cost) =
             pattern_roll_back(self.trace_pattern.clone(), pattern_index, extend_approach, self.catalog);
         // Recursively generate pattern match plan for the source node
         self.generate_pattern_match_plan_recursively(&src_pattern)
             .expect("Failed to generate optimized pattern match plan recursively");
         // Append Extend Operator in logical plan
+        let start_node_id = self.get_node_num();
         self.append_extend_operator(&src_pattern, &target_pattern, definite_extend_step)
             .expect("Failed to append extend operator");
+        let extend_step = PlanStep {
+            kind: PlanStepKind::Extend,
+            src_pattern_code: Some(src_pattern.encode_to()),
+            target_pattern_code: target_pattern.encode_to(),
+            cost_count: cost,
+        };
+        self.set_node_steps(start_node_id..self.get_node_num(), &extend_step);
+        self.set_node_cardinality(self.get_node_num() - 1, &target_pattern);
     }
 
+    /// Both the build and probe patterns are generated recursively by their best approaches,
+    /// so that either side can be a join itself and the resulting join tree can be bushy
     fn generate_pattern_match_plan_recursively_for_join_approach(&mut self, join_approach: Approach) {
         // Collect Join Weight
         let join_weight = self
@@ -529,15 +991,19 @@ impl<'a> PlanGenerator<'a> {
             .get_join_weight()
             .expect("Failed to get join weight");
         // Roll back join plan for exact pattern instances with vertex/edge id cohesion
+        let min_disjoint_vertices_num = self
+            .catalog
+            .get_plan_space()
+            .get_min_join_disjoint_vertices_num();
         let join_plan = self
             .trace_pattern
-            .binary_join_decomposition()
+            .binary_join_decomposition_with_min_disjoint_vertices(min_disjoint_vertices_num)
             .expect("Failed to do binary join decomposition")
             .into_iter()
             .filter(|binary_join_plan| {
                 let build_pattern_in_catalog = join_weight.get_join_plan().get_build_pattern();
-                pattern_equal(build_pattern_in_catalog, binary_join_plan.get_build_pattern())
-                    || pattern_equal(build_pattern_in_catalog, binary_join_plan.get_probe_pattern())
+                build_pattern_in_catalog.is_isomorphic_to(binary_join_plan.get_build_pattern())
+                    || build_pattern_in_catalog.is_isomorphic_to(binary_join_plan.get_probe_pattern())
             })
             .collect::<Vec<BinaryJoinPlan>>()
             .first()
@@ -552,14 +1018,33 @@ impl<'a> PlanGenerator<'a> {
             self.catalog,
             self.pattern_meta,
             self.is_distributed,
-        );
+        )
+        .with_cost_model(self.cost_model);
+        // Share the memorized approaches, so the probe plan follows those by which the join is costed
+        probe_pattern_logical_plan_builder.best_approaches = std::mem::take(&mut self.best_approaches);
         probe_pattern_logical_plan_builder
             .generate_pattern_match_plan_recursively(join_plan.get_probe_pattern())
             .expect("Failed to generate optimized pattern match plan recursively");
+        self.best_approaches = std::mem::take(&mut probe_pattern_logical_plan_builder.best_approaches);
         // Append binary join operator to join two plans
         let join_keys: Vec<Variable> = join_plan.generate_join_keys();
         self.join(probe_pattern_logical_plan_builder, join_keys)
             .expect("Failed to join two logical plans");
+        // The as node inserted ahead and the join node appended are generated from the join step
+        let join_step = PlanStep {
+            kind: PlanStepKind::Join,
+            src_pattern_code: Some(join_plan.get_build_pattern().encode_to()),
+            target_pattern_code: self.trace_pattern.encode_to(),
+            cost_count: CostCount::from_join(
+                self.get_pattern_count(join_plan.get_build_pattern()),
+                self.get_pattern_count(join_plan.get_probe_pattern()),
+                self.get_pattern_count(&self.trace_pattern),
+            ),
+        };
+        self.set_node_steps(0..1, &join_step);
+        self.set_node_steps(self.get_node_num() - 1..self.get_node_num(), &join_step);
+        let trace_pattern = self.trace_pattern.clone();
+        self.set_node_cardinality(self.get_node_num() - 1, &trace_pattern);
     }
 
     fn generate_pattern_match_plan_for_size_one_pattern(&mut self, pattern: &Pattern) {
@@ -577,10 +1062,21 @@ impl<'a> PlanGenerator<'a> {
             pb::logical_plan::Node { opr: Some(opr.into()), children }
         };
         self.plan.nodes.push(select_node);
+        // Append the select node of the vertex predicate if any
+        if let Some(predicate) = self
+            .target_pattern
+            .get_vertex_predicate(vertex.get_id())
+        {
+            let opr = pb::Select { predicate: Some(predicate.clone()) };
+            let children: Vec<i32> = vec![self.get_node_num() as i32 + 1];
+            self.plan
+                .nodes
+                .push(pb::logical_plan::Node { opr: Some(opr.into()), children });
+        }
         // Append as node
         let as_node = {
             let opr = pb::As { alias: Some((vertex.get_id() as i32).into()) };
-            let children: Vec<i32> = vec![2];
+            let children: Vec<i32> = vec![self.get_node_num() as i32 + 1];
             pb::logical_plan::Node { opr: Some(opr.into()), children }
         };
         self.plan.nodes.push(as_node);
@@ -589,6 +1085,14 @@ impl<'a> PlanGenerator<'a> {
             .insert(vertex.get_label());
         // Set root for pb plan
         self.plan.roots = vec![0];
+        let source_step = PlanStep {
+            kind: PlanStepKind::Source,
+            src_pattern_code: None,
+            target_pattern_code: pattern.encode_to(),
+            cost_count: CostCount::from_src_pattern(self.get_pattern_count(pattern)),
+        };
+        self.set_node_steps(0..self.get_node_num(), &source_step);
+        self.set_node_cardinality(self.get_node_num() - 1, pattern);
     }
 
     /// Append logical plan operators for extend step
@@ -622,13 +1126,29 @@ impl<'a> PlanGenerator<'a> {
         let mut child_offset = self.get_node_num() as i32;
         let edge_expands: Vec<pb::EdgeExpand> =
             definite_extend_step.generate_expand_operators(target_pattern);
+        let path_expand_oprs = definite_extend_step.generate_path_expand_operators(target_pattern)?;
         let edge_expands_num = edge_expands.len();
         let edge_expands_ids: Vec<i32> = (0..edge_expands_num as i32)
             .map(|i| i + child_offset)
             .collect();
         // if edge expand num > 1, we need a Intersect Operator
-        match edge_expands_num.cmp(&1) {
-            Ordering::Greater => {
+        match (path_expand_oprs, edge_expands_num.cmp(&1)) {
+            (Some((path_expand, get_v)), _) => {
+                // Set the children of the previous node
+                self.plan.nodes.last_mut().unwrap().children = vec![child_offset];
+                child_offset += 1;
+                // Append path expand and its end vertex nodes
+                self.plan.nodes.push(pb::logical_plan::Node {
+                    opr: Some(path_expand.into()),
+                    children: vec![child_offset],
+                });
+                child_offset += 1;
+                self.plan
+                    .nodes
+                    .push(pb::logical_plan::Node { opr: Some(get_v.into()), children: vec![child_offset] });
+                child_offset += 1;
+            }
+            (None, Ordering::Greater) => {
                 // Set the children of the previous node
                 self.plan.nodes.last_mut().unwrap().children = edge_expands_ids.clone();
                 // Append edge expand nodes
@@ -650,7 +1170,7 @@ impl<'a> PlanGenerator<'a> {
                 self.plan.nodes.push(intersect_node);
                 child_offset += 1;
             }
-            Ordering::Equal => {
+            (None, Ordering::Equal) => {
                 // Set the children of the previous node
                 self.plan.nodes.last_mut().unwrap().children = edge_expands_ids;
                 // Append edge expand node
@@ -717,7 +1237,19 @@ impl<'a> PlanGenerator<'a> {
             definite_extend_step.generate_expand_operators(target_pattern);
         let edge_expands_num = edge_expands.len();
         // Append Edge Expand Nodes
-        if edge_expands_num == 0 {
+        if let Some((path_expand, get_v)) =
+            definite_extend_step.generate_path_expand_operators(target_pattern)?
+        {
+            self.plan.nodes.push(pb::logical_plan::Node {
+                opr: Some(path_expand.into()),
+                children: vec![child_offset],
+            });
+            child_offset += 1;
+            self.plan
+                .nodes
+                .push(pb::logical_plan::Node { opr: Some(get_v.into()), children: vec![child_offset] });
+            child_offset += 1;
+        } else if edge_expands_num == 0 {
             return Err(IrError::InvalidPattern(
                 "Build logical plan error: extend step is not source but has 0 edges".to_string(),
             ));
@@ -816,6 +1348,11 @@ impl<'a> PlanGenerator<'a> {
         }
 
         // Concat two plans to be one
+        self.align_node_steps();
+        other.align_node_steps();
+        self.node_steps.extend(other.node_steps);
+        self.node_cardinalities
+            .extend(other.node_cardinalities);
         self.plan.nodes.extend(other.plan.nodes);
         // Append join node
         let join_node = {
@@ -877,9 +1414,29 @@ impl<'a> PlanGenerator<'a> {
         // });
 
         // If the plan is purely extend-based, the first Select node could be removed, and we only need to scan the first vertex
+        let mut source_predicate = None;
         if let PatMatPlanSpace::ExtendWithIntersection = self.catalog.get_plan_space() {
             self.remove_node(0)
                 .expect("Failed to remove node from pb_plan");
+            // The predicate of the source vertex (if any) follows the label select, which is pushed down
+            // into the scan if possible, and kept as a select otherwise
+            source_predicate = match self
+                .plan
+                .nodes
+                .first()
+                .and_then(|node| node.opr.as_ref())
+                .and_then(|opr| opr.opr.as_ref())
+            {
+                Some(pb::logical_plan::operator::Opr::Select(filter)) => filter
+                    .predicate
+                    .clone()
+                    .filter(is_scan_predicate),
+                _ => None,
+            };
+            if source_predicate.is_some() {
+                self.remove_node(0)
+                    .expect("Failed to remove node from pb_plan");
+            }
         }
 
         // Append Sink Node
@@ -897,7 +1454,7 @@ impl<'a> PlanGenerator<'a> {
                     columns: vec![],
                     is_all_columns: false,
                     limit: None,
-                    predicate: None,
+                    predicate: source_predicate,
                     sample_ratio: 1.0,
                     extra: HashMap::new(),
                 }),
@@ -910,82 +1467,509 @@ impl<'a> PlanGenerator<'a> {
             .expect("Failed to insert node to pb_plan");
     }
 
-    pub fn pb_plan_add_count_sink_operator(&mut self) {
+    /// Append a limit node after the last match node, if a limit is given
+    pub fn pb_plan_add_limit_operator(&mut self) -> IrResult<()> {
+        if let Some(limit) = self.limit {
+            let pb_plan_len = self.get_node_num();
+            // Modify the children ID of the last node
+            self.plan.nodes[pb_plan_len - 1].children = vec![pb_plan_len as i32];
+            let limit_node =
+                pb::logical_plan::Node { opr: Some(build_limit_operator(limit)?.into()), children: vec![] };
+            self.insert_node(pb_plan_len, limit_node)?;
+        }
+        Ok(())
+    }
+
+    pub fn pb_plan_add_aggregate_sink_operator(&mut self) -> IrResult<()> {
         let pb_plan_len = self.plan.nodes.len();
         // Modify the children ID of the last node
         self.plan.nodes[pb_plan_len - 1].children = vec![pb_plan_len as i32];
-        // Append Count Aggregate Node
-        let count_node = {
-            let opr = pb::GroupBy {
-                mappings: vec![],
-                functions: vec![pb::group_by::AggFunc {
-                    vars: vec![],
-                    aggregate: 3, // count
-                    alias: Some(0.into()),
-                }],
-            };
+        // Append Aggregate Node, if any
+        if let Some(group_by) = self
+            .aggregate
+            .to_group_by(self.target_pattern)?
+        {
             let children: Vec<i32> = vec![(pb_plan_len + 1) as i32];
-            pb::logical_plan::Node { opr: Some(opr.into()), children }
-        };
-        self.plan.nodes.push(count_node);
+            self.plan
+                .nodes
+                .push(pb::logical_plan::Node { opr: Some(group_by.into()), children });
+        }
         // Append Sink Node
         let sink_node = {
-            let opr = pb::Sink {
-                tags: vec![common_pb::NameOrIdKey { key: Some(0.into()) }],
-                sink_target: Some(pb::sink::SinkTarget {
-                    inner: Some(pb::sink::sink_target::Inner::SinkDefault(pb::SinkDefault {
-                        id_name_mappings: vec![],
-                    })),
-                }),
-            };
+            let opr = self.aggregate.to_sink(self.target_pattern);
             let children: Vec<i32> = vec![];
             pb::logical_plan::Node { opr: Some(opr.into()), children }
         };
         self.plan.nodes.push(sink_node);
+        Ok(())
+    }
+}
+
+/// The kind of catalogue step that a node in the logical plan is generated from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PlanStepKind {
+    Source,
+    Extend,
+    Join,
+}
+
+impl Display for PlanStepKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PlanStepKind::Source => write!(f, "source"),
+            PlanStepKind::Extend => write!(f, "extend"),
+            PlanStepKind::Join => write!(f, "join"),
+        }
+    }
+}
+
+/// The catalogue step chosen by the plan generator
+///
+/// src_pattern_code: the code of the source pattern (the build pattern of a join), None for a source step
+///
+/// target_pattern_code: the code of the pattern reached by the step
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlanStep {
+    kind: PlanStepKind,
+    src_pattern_code: Option<Vec<u8>>,
+    target_pattern_code: Vec<u8>,
+    cost_count: CostCount,
+}
+
+impl PlanStep {
+    pub fn get_kind(&self) -> PlanStepKind {
+        self.kind
+    }
+
+    pub fn get_src_pattern_code(&self) -> Option<&Vec<u8>> {
+        self.src_pattern_code.as_ref()
+    }
+
+    pub fn get_target_pattern_code(&self) -> &Vec<u8> {
+        &self.target_pattern_code
+    }
+
+    pub fn get_cost_count(&self) -> CostCount {
+        self.cost_count
+    }
+
+    /// Get the cost of the step weighted by the cost model
+    pub fn get_cost(&self, cost_model: &CostModel) -> f64 {
+        self.cost_count
+            .get_cost(cost_model)
+            .into_inner()
+    }
+}
+
+/// A node of the explained plan, aligned with the node of the same index in the logical plan
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlanExplanationNode {
+    operator: String,
+    children: Vec<i32>,
+    step: Option<PlanStep>,
+    estimated_cardinality: Option<f64>,
+}
+
+impl PlanExplanationNode {
+    pub fn get_operator(&self) -> &str {
+        &self.operator
+    }
+
+    pub fn get_children(&self) -> &Vec<i32> {
+        &self.children
+    }
+
+    /// Get the step the node is generated from, None for the scan, count and sink nodes
+    pub fn get_step(&self) -> Option<&PlanStep> {
+        self.step.as_ref()
+    }
+
+    /// Get the estimated cardinality flowing out of the node, None if the node keeps the cardinality
+    pub fn get_estimated_cardinality(&self) -> Option<f64> {
+        self.estimated_cardinality
+    }
+}
+
+/// Structured explanation of the plan generated by PlanGenerator
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlanExplanation {
+    roots: Vec<i32>,
+    nodes: Vec<PlanExplanationNode>,
+    cost_model: CostModel,
+}
+
+impl PlanExplanation {
+    pub fn get_roots(&self) -> &Vec<i32> {
+        &self.roots
+    }
+
+    pub fn get_nodes(&self) -> &Vec<PlanExplanationNode> {
+        &self.nodes
+    }
+
+    pub fn get_nodes_num(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn get_cost_model(&self) -> &CostModel {
+        &self.cost_model
+    }
+
+    fn fmt_node(
+        &self, f: &mut std::fmt::Formatter<'_>, node_id: i32, depth: usize, visited: &mut HashSet<i32>,
+    ) -> std::fmt::Result {
+        let indent = "  ".repeat(depth);
+        let node = match self.nodes.get(node_id as usize) {
+            Some(node) => node,
+            None => return writeln!(f, "{}#{} <missing>", indent, node_id),
+        };
+        // Nodes with several parents (e.g., Intersect) are printed once, and referred to afterwards
+        if !visited.insert(node_id) {
+            return writeln!(f, "{}#{} {} (see above)", indent, node_id, node.operator);
+        }
+        write!(f, "{}#{} {}", indent, node_id, node.operator)?;
+        if let Some(step) = &node.step {
+            write!(f, " [{}", step.kind)?;
+            if let Some(src_pattern_code) = &step.src_pattern_code {
+                write!(f, " from {}", encode_pattern_code_to_hex(src_pattern_code))?;
+            }
+            write!(
+                f,
+                " to {}, cost count: {}, cost: {}]",
+                encode_pattern_code_to_hex(&step.target_pattern_code),
+                step.cost_count,
+                step.get_cost(&self.cost_model)
+            )?;
+        }
+        if let Some(estimated_cardinality) = node.estimated_cardinality {
+            write!(f, " (estimated rows: {})", estimated_cardinality)?;
+        }
+        writeln!(f)?;
+        for &child_id in &node.children {
+            self.fmt_node(f, child_id, depth + 1, visited)?;
+        }
+        Ok(())
+    }
+}
+
+impl Display for PlanExplanation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut visited = HashSet::new();
+        for &root in &self.roots {
+            self.fmt_node(f, root, 0, &mut visited)?;
+        }
+        Ok(())
+    }
+}
+
+fn encode_pattern_code_to_hex(pattern_code: &[u8]) -> String {
+    pattern_code
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+fn get_operator_kind(node: &pb::logical_plan::Node) -> String {
+    node.opr
+        .as_ref()
+        .and_then(|opr| opr.opr.as_ref())
+        .map(|opr| opr.get_name())
+        .unwrap_or_else(|| "Unknown".to_string())
+}
+
+/// The inflation of the count of a pattern due to its path edges, where every path edge of
+/// `[hop_min, hop_max]` hops sums up the inflations of all the hops in the range, and a path edge
+/// of `h` hops is estimated as `path_hop_inflation^(h - 1)` times of a single edge
+fn get_path_inflation(pattern: &Pattern, path_hop_inflation: f64) -> f64 {
+    pattern
+        .edges_iter()
+        .filter_map(|edge| edge.get_hop_range())
+        .map(|(hop_min, hop_max)| {
+            (hop_min.max(1)..=hop_max)
+                .map(|hop| path_hop_inflation.powi(hop as i32 - 1))
+                .sum::<f64>()
+        })
+        .product()
+}
+
+/// The pattern whose path edges are taken as single edges, which is the one estimated by the catalogue
+fn get_single_hop_pattern(pattern: &Pattern) -> Pattern {
+    let single_hop_edges: Vec<PatternEdge> = pattern
+        .edges_iter()
+        .map(|edge| edge.clone().with_hop_range(None))
+        .collect();
+    Pattern::try_from(single_hop_edges).unwrap()
+}
+
+/// The estimated counts of patterns memorized by pattern codes during a plan generation,
+/// so that each distinct pattern is estimated by the catalogue only once
+///
+/// path_hop_inflation: the inflation of the count of a pattern per extra hop of its path edges,
+/// given by the cost model
+#[derive(Debug)]
+pub struct PatternCountCache {
+    pattern_counts: RwLock<HashMap<Vec<u8>, OrderedFloat<f64>>>,
+    lookup_num: AtomicUsize,
+    path_hop_inflation: f64,
+}
+
+/// The default cache inflates the path edges as the default cost model
+impl Default for PatternCountCache {
+    fn default() -> Self {
+        PatternCountCache::new(&CostModel::default())
+    }
+}
+
+impl PatternCountCache {
+    pub fn new(cost_model: &CostModel) -> Self {
+        PatternCountCache {
+            pattern_counts: RwLock::new(HashMap::new()),
+            lookup_num: AtomicUsize::new(0),
+            path_hop_inflation: cost_model.get_path_hop_inflation(),
+        }
+    }
+
+    /// Estimate the count of the pattern, where every path edge is counted as a single edge by the
+    /// catalogue, and then inflated by the path hop inflation per extra hop
+    pub fn estimate_pattern_count(&self, catalog: &Catalogue, pattern: &Pattern) -> OrderedFloat<f64> {
+        self.lookup_num
+            .fetch_add(1, AtomicOrdering::Relaxed);
+        let path_inflation = get_path_inflation(pattern, self.path_hop_inflation);
+        // The counts are cached by the codes of the single hop patterns before the inflation,
+        // as the codes of patterns with path edges carry their hop ranges
+        let single_hop_pattern;
+        let pattern = if pattern.edges_iter().any(|edge| edge.is_path()) {
+            single_hop_pattern = get_single_hop_pattern(pattern);
+            &single_hop_pattern
+        } else {
+            pattern
+        };
+        let pattern_code = pattern.encode_to();
+        if let Some(&pattern_count) = self
+            .pattern_counts
+            .read()
+            .unwrap()
+            .get(&pattern_code)
+        {
+            return pattern_count * path_inflation;
+        }
+        let pattern_count = catalog.estimate_pattern_count(pattern);
+        self.pattern_counts
+            .write()
+            .unwrap()
+            .insert(pattern_code, pattern_count);
+        pattern_count * path_inflation
+    }
+
+    /// The number of times pattern counts are looked up in the cache
+    pub fn get_lookup_num(&self) -> usize {
+        self.lookup_num.load(AtomicOrdering::Relaxed)
+    }
+
+    /// The number of distinct patterns estimated by the catalogue
+    pub fn get_patterns_num(&self) -> usize {
+        self.pattern_counts.read().unwrap().len()
     }
 }
 
 pub fn get_definite_extend_steps(
     pattern: Pattern, catalog: &mut Catalogue,
+) -> (Vec<DefiniteExtendStep>, CostCount) {
+    get_definite_extend_steps_with_cache(pattern, catalog, &PatternCountCache::default())
+}
+
+pub fn get_definite_extend_steps_with_cache(
+    pattern: Pattern, catalog: &mut Catalogue, pattern_count_cache: &PatternCountCache,
+) -> (Vec<DefiniteExtendStep>, CostCount) {
+    let mut best_approaches = HashMap::new();
+    let (extend_steps, cost) = get_definite_extend_steps_read_only(
+        pattern,
+        catalog,
+        pattern_count_cache,
+        &mut best_approaches,
+        false,
+        None,
+    );
+    set_patterns_best_approach(catalog, best_approaches);
+    (extend_steps, cost)
+}
+
+/// Get the definite extend steps of a pattern in the given order of its vertices, instead of the
+/// optimal one, together with the cost estimated by the catalog along exactly these steps
+///
+/// The first vertex in the order is the source, and every following vertex is extended from the
+/// vertices before it. Return error if the order is not a permutation of the vertices in the pattern,
+/// or the vertices extended so far are disconnected at some step.
+pub fn get_definite_extend_steps_in_order(
+    pattern: &Pattern, vertex_order: &[PatternId], catalog: &Catalogue,
+) -> IrResult<(Vec<DefiniteExtendStep>, CostCount)> {
+    let distinct_vertex_ids: BTreeSet<PatternId> = vertex_order.iter().cloned().collect();
+    if vertex_order.len() != pattern.get_vertices_num()
+        || distinct_vertex_ids.len() != vertex_order.len()
+        || distinct_vertex_ids
+            .iter()
+            .any(|&vertex_id| pattern.get_vertex(vertex_id).is_none())
+    {
+        return Err(IrError::InvalidPattern(format!(
+            "extend order {:?} is not a permutation of the pattern vertices",
+            vertex_order
+        )));
+    }
+    let pattern_count_cache = PatternCountCache::default();
+    let mut trace_pattern = pattern.clone();
+    let mut pattern_count = pattern_count_cache.estimate_pattern_count(catalog, &trace_pattern);
+    let mut definite_extend_steps = vec![];
+    let mut cost = CostCount::default();
+    // Roll back the pattern from the last extended vertex to the source
+    for (position, &vertex_id) in vertex_order.iter().enumerate().skip(1).rev() {
+        let mut extend_step = DefiniteExtendStep::from_target_pattern(&trace_pattern, vertex_id)
+            .ok_or(IrError::InvalidPattern(format!("vertex {} not found in pattern", vertex_id)))?;
+        let sub_pattern = trace_pattern
+            .remove_vertex(vertex_id)
+            .ok_or(IrError::InvalidPattern(format!(
+                "vertices {:?} are disconnected before extending vertex {}",
+                &vertex_order[..position],
+                vertex_id
+            )))?;
+        let sub_pattern_count = pattern_count_cache.estimate_pattern_count(catalog, &sub_pattern);
+        let adjacency_count =
+            get_adjacency_count(&sub_pattern, &mut extend_step, catalog, &pattern_count_cache);
+        let intersect_count =
+            get_intersect_count(&sub_pattern, &extend_step, catalog, &pattern_count_cache);
+        cost += CostCount::from_extend(
+            sub_pattern_count,
+            pattern_count,
+            adjacency_count,
+            intersect_count,
+            extend_step.get_extend_edges_num(),
+        );
+        definite_extend_steps.push(extend_step);
+        trace_pattern = sub_pattern;
+        pattern_count = sub_pattern_count;
+    }
+    cost += CostCount::from_src_pattern(pattern_count);
+    definite_extend_steps.push(trace_pattern.try_into()?);
+    definite_extend_steps.reverse();
+    Ok((definite_extend_steps, cost))
+}
+
+/// The parallel version of get_definite_extend_steps
+///
+/// The sub-patterns which are not in catalog are costed in parallel with the catalog read-only,
+/// and the best approaches chosen for the patterns in catalog are set afterwards in a single thread.
+/// If the pattern meta is given, the ties of costs are broken as sort_vertex_ids_with_meta, so that
+/// the vertex of the rarest label is preferred as the source
+pub fn get_definite_extend_steps_parallel(
+    pattern: Pattern, catalog: &mut Catalogue, pattern_meta: Option<&PatternMeta>,
+) -> (Vec<DefiniteExtendStep>, CostCount) {
+    let mut best_approaches = HashMap::new();
+    let (extend_steps, cost) = get_definite_extend_steps_read_only(
+        pattern,
+        catalog,
+        &PatternCountCache::default(),
+        &mut best_approaches,
+        true,
+        pattern_meta,
+    );
+    set_patterns_best_approach(catalog, best_approaches);
+    (extend_steps, cost)
+}
+
+/// Find the definite extend steps of a pattern without modifying the catalog
+///
+/// The best approaches chosen for the patterns in catalog are memorized in best_approaches,
+/// which are left to the caller to be set in catalog. If is_parallel, the sub-pattern branches
+/// are costed in parallel, each memorizing its own best approaches. If the pattern meta is given,
+/// the branches are tried in the order of sort_vertex_ids_with_meta
+fn get_definite_extend_steps_read_only(
+    pattern: Pattern, catalog: &Catalogue, pattern_count_cache: &PatternCountCache,
+    best_approaches: &mut HashMap<NodeIndex, Approach>, is_parallel: bool,
+    pattern_meta: Option<&PatternMeta>,
 ) -> (Vec<DefiniteExtendStep>, CostCount) {
     let pattern_code = pattern.encode_to();
     if let Some(pattern_index) = catalog.get_pattern_index(&pattern_code) {
-        get_definite_extend_steps_in_catalog(catalog, pattern_index, pattern)
+        get_definite_extend_steps_in_catalog_read_only(catalog, pattern_index, pattern, best_approaches)
     } else {
-        let pattern_count = catalog.estimate_pattern_count(&pattern);
-        let mut sub_patterns_extend_steps = vec![];
-        for vertex_id in pattern
+        let pattern_count = pattern_count_cache.estimate_pattern_count(catalog, &pattern);
+        let mut vertex_ids: Vec<PatternId> = pattern
             .vertices_iter()
             .map(|vertex| vertex.get_id())
-        {
+            .collect();
+        // The first branch wins the ties, whose vertex is extended last, so the vertices sorted to the
+        // last are preferred to be matched first
+        if let Some(pattern_meta) = pattern_meta {
+            sort_vertex_ids_with_meta(&mut vertex_ids, &pattern, pattern_meta);
+        }
+        let mut sub_patterns_extend_steps = vec![];
+        for vertex_id in vertex_ids {
             if let Some(sub_pattern) = pattern.clone().remove_vertex(vertex_id) {
                 let extend_step = DefiniteExtendStep::from_target_pattern(&pattern, vertex_id).unwrap();
                 sub_patterns_extend_steps.push((sub_pattern, extend_step));
             }
         }
+        let cost_branch =
+            |sub_pattern: Pattern,
+             mut extend_step: DefiniteExtendStep,
+             branch_best_approaches: &mut HashMap<NodeIndex, Approach>| {
+                let sub_pattern_predicate_num = sub_pattern.get_predicate_num();
+                let sub_pattern_count = pattern_count_cache.estimate_pattern_count(catalog, &sub_pattern);
+                let adjacency_count =
+                    get_adjacency_count(&sub_pattern, &mut extend_step, catalog, pattern_count_cache);
+                let intersect_count =
+                    get_intersect_count(&sub_pattern, &extend_step, catalog, pattern_count_cache);
+                let (mut extend_steps, pre_cost) = get_definite_extend_steps_read_only(
+                    sub_pattern,
+                    catalog,
+                    pattern_count_cache,
+                    branch_best_approaches,
+                    is_parallel,
+                    pattern_meta,
+                );
+                let this_step_cost = CostCount::from_extend(
+                    sub_pattern_count,
+                    pattern_count,
+                    adjacency_count,
+                    intersect_count,
+                    extend_step.get_extend_edges_num(),
+                );
+                extend_steps.push(extend_step);
+                (sub_pattern_predicate_num, extend_steps, pre_cost + this_step_cost)
+            };
+        // The branches keep their order after collected
+        let branches: Vec<(usize, Vec<DefiniteExtendStep>, CostCount)> = if is_parallel {
+            let parallel_branches: Vec<_> = sub_patterns_extend_steps
+                .into_par_iter()
+                .map(|(sub_pattern, extend_step)| {
+                    let mut branch_best_approaches = HashMap::new();
+                    let branch = cost_branch(sub_pattern, extend_step, &mut branch_best_approaches);
+                    (branch, branch_best_approaches)
+                })
+                .collect();
+            parallel_branches
+                .into_iter()
+                .map(|(branch, branch_best_approaches)| {
+                    best_approaches.extend(branch_best_approaches);
+                    branch
+                })
+                .collect()
+        } else {
+            sub_patterns_extend_steps
+                .into_iter()
+                .map(|(sub_pattern, extend_step)| cost_branch(sub_pattern, extend_step, best_approaches))
+                .collect()
+        };
+        // Select the optimal branch in order, so that the ties are broken the same in both modes
+        let cost_model = CostModel::default();
         let mut optimal_extend_steps = vec![];
         let mut min_cost = CostCount::max_value();
         let mut max_predicate_num = usize::MIN;
-        for (sub_pattern, mut extend_step) in sub_patterns_extend_steps {
-            let sub_pattern_predicate_num = sub_pattern.get_predicate_num();
-            let sub_pattern_count = catalog.estimate_pattern_count(&sub_pattern);
-            let adjacency_count = get_adjacency_count(&sub_pattern, &mut extend_step, catalog);
-            let intersect_count = get_intersect_count(&sub_pattern, &extend_step, catalog);
-            let (mut extend_steps, pre_cost) = get_definite_extend_steps(sub_pattern, catalog);
-            let this_step_cost = CostCount::from_extend(
-                sub_pattern_count,
-                pattern_count,
-                adjacency_count,
-                intersect_count,
-                extend_step.get_extend_edges_num(),
-            );
-
+        for (sub_pattern_predicate_num, extend_steps, cost) in branches {
             if sub_pattern_predicate_num > max_predicate_num
-                || (pre_cost + this_step_cost < min_cost && sub_pattern_predicate_num == max_predicate_num)
+                || (cost.get_cost(&cost_model) < min_cost.get_cost(&cost_model)
+                    && sub_pattern_predicate_num == max_predicate_num)
             {
-                extend_steps.push(extend_step);
                 optimal_extend_steps = extend_steps;
-                min_cost = pre_cost + this_step_cost;
+                min_cost = cost;
                 max_predicate_num = sub_pattern_predicate_num;
             }
         }
@@ -993,8 +1977,48 @@ pub fn get_definite_extend_steps(
     }
 }
 
+/// For a pattern which is a single cycle, the vertices are extended one by one along the cycle,
+/// and the cycle is closed by the last extend step with an intersection.
+///
+/// The traversal starts from a vertex with predicate (if any) to make the filter applied earliest.
+fn get_cycle_definite_extend_steps(
+    pattern: &Pattern, pattern_meta: &PatternMeta,
+) -> IrResult<Vec<DefiniteExtendStep>> {
+    let mut cycle = pattern
+        .extract_cycles()
+        .pop()
+        .ok_or(IrError::InvalidPattern("Pattern is not a cycle".to_string()))?;
+    // Start from the most preferred vertex, which is sorted to the last. The vertices are sorted
+    // reversely, so that the first one in the cycle is preferred among the ties
+    let mut sorted_vertex_ids: Vec<PatternId> = cycle.iter().rev().cloned().collect();
+    sort_vertex_ids_with_meta(&mut sorted_vertex_ids, pattern, pattern_meta);
+    if let Some(position) = sorted_vertex_ids
+        .last()
+        .and_then(|source_vertex_id| {
+            cycle
+                .iter()
+                .position(|vertex_id| vertex_id == source_vertex_id)
+        })
+    {
+        cycle.rotate_left(position);
+    }
+    let mut trace_pattern = pattern.clone();
+    let mut definite_extend_steps = vec![];
+    for &vertex_id in cycle.iter().skip(1).rev() {
+        let definite_extend_step = DefiniteExtendStep::from_target_pattern(&trace_pattern, vertex_id)
+            .ok_or(IrError::InvalidPattern("Vertex not found in pattern".to_string()))?;
+        definite_extend_steps.push(definite_extend_step);
+        trace_pattern = trace_pattern
+            .remove_vertex(vertex_id)
+            .ok_or(IrError::InvalidPattern("Pattern is disconnected after removing vertex".to_string()))?;
+    }
+    definite_extend_steps.push(trace_pattern.try_into()?);
+    Ok(definite_extend_steps)
+}
+
 fn get_adjacency_count(
     sub_pattern: &Pattern, extend_step: &mut DefiniteExtendStep, catalog: &Catalogue,
+    pattern_count_cache: &PatternCountCache,
 ) -> OrderedFloat<f64> {
     let target_vertex = extend_step.get_target_vertex();
     let mut adjacency_count_map = HashMap::new();
@@ -1002,19 +2026,67 @@ fn get_adjacency_count(
         let adjacency_pattern = sub_pattern
             .extend_definitely(extend_edge, target_vertex)
             .unwrap();
-        let sub_target_pattern_count = catalog.estimate_pattern_count(&adjacency_pattern);
+        let sub_target_pattern_count =
+            pattern_count_cache.estimate_pattern_count(catalog, &adjacency_pattern);
         adjacency_count_map.insert(extend_edge.get_edge_id(), sub_target_pattern_count);
     }
-    extend_step.sort_by(|extend_edge1, extend_edge2| {
-        adjacency_count_map
-            .get(&extend_edge1.get_edge_id())
-            .cmp(&adjacency_count_map.get(&extend_edge2.get_edge_id()))
-    });
+    // The sorted extend edges decide the order of intersections, in both the intersect count and the plan
+    match catalog.get_intersect_order() {
+        IntersectOrder::AscendingCount => extend_step.sort_by(|extend_edge1, extend_edge2| {
+            adjacency_count_map
+                .get(&extend_edge1.get_edge_id())
+                .cmp(&adjacency_count_map.get(&extend_edge2.get_edge_id()))
+        }),
+        IntersectOrder::DescendingCount => extend_step.sort_by(|extend_edge1, extend_edge2| {
+            adjacency_count_map
+                .get(&extend_edge2.get_edge_id())
+                .cmp(&adjacency_count_map.get(&extend_edge1.get_edge_id()))
+        }),
+        IntersectOrder::EdgeLabel => extend_step.sort_by(|extend_edge1, extend_edge2| {
+            extend_edge1
+                .get_edge_label()
+                .cmp(&extend_edge2.get_edge_label())
+        }),
+        IntersectOrder::Selectivity => {
+            let mut selectivity_map = HashMap::new();
+            for extend_edge in extend_step.iter() {
+                let edge_id = extend_edge.get_edge_id();
+                let adjacency_count = adjacency_count_map[&edge_id];
+                let edge_pattern = get_extend_edge_pattern(extend_edge, target_vertex);
+                let edge_count = pattern_count_cache.estimate_pattern_count(catalog, &edge_pattern);
+                let selectivity = if edge_count > OrderedFloat::default() {
+                    adjacency_count / edge_count
+                } else {
+                    adjacency_count
+                };
+                selectivity_map.insert(edge_id, selectivity);
+            }
+            extend_step.sort_by(|extend_edge1, extend_edge2| {
+                selectivity_map
+                    .get(&extend_edge1.get_edge_id())
+                    .cmp(&selectivity_map.get(&extend_edge2.get_edge_id()))
+            })
+        }
+    }
     adjacency_count_map.values().sum()
 }
 
+/// The pattern with only the edge of the extend edge, together with its source and target vertices
+fn get_extend_edge_pattern(extend_edge: &DefiniteExtendEdge, target_vertex: PatternVertex) -> Pattern {
+    let src_vertex = extend_edge.get_src_vertex();
+    let (start_vertex, end_vertex) = match extend_edge.get_direction() {
+        PatternDirection::Out => (src_vertex, target_vertex),
+        PatternDirection::In => (target_vertex, src_vertex),
+    };
+    let edge =
+        PatternEdge::new(extend_edge.get_edge_id(), extend_edge.get_edge_label(), start_vertex, end_vertex)
+            .with_undirected(extend_edge.is_undirected());
+    Pattern::try_from(vec![edge]).unwrap()
+}
+
 fn get_intersect_count(
     sub_pattern: &Pattern, extend_step: &DefiniteExtendStep, catalog: &Catalogue,
+    pattern_count_cache: &PatternCountCache,
 ) -> OrderedFloat<f64> {
     let target_vertex = extend_step.get_target_vertex();
     let mut intersect_count = OrderedFloat::default();
@@ -1026,34 +2098,48 @@ fn get_intersect_count(
         adjacency_pattern = adjacency_pattern
             .extend_definitely(extend_edge, target_vertex)
             .unwrap();
-        let sub_target_pattern_count = catalog.estimate_pattern_count(&adjacency_pattern);
+        let sub_target_pattern_count =
+            pattern_count_cache.estimate_pattern_count(catalog, &adjacency_pattern);
         intersect_count += sub_target_pattern_count
     }
     intersect_count
 }
 
-fn get_definite_extend_steps_in_catalog(
-    catalog: &mut Catalogue, pattern_index: NodeIndex, pattern: Pattern,
+/// Find the definite extend steps of a pattern in catalog without modifying the catalog
+///
+/// The chosen approaches of patterns without predicates are memorized in best_approaches,
+/// which are left to the caller to be set in catalog
+fn get_definite_extend_steps_in_catalog_read_only(
+    catalog: &Catalogue, pattern_index: NodeIndex, pattern: Pattern,
+    best_approaches: &mut HashMap<NodeIndex, Approach>,
 ) -> (Vec<DefiniteExtendStep>, CostCount) {
     let pattern_weight = catalog
         .get_pattern_weight(pattern_index)
         .unwrap();
     let predicate_num = pattern.get_predicate_num();
+    let memorized_best_approach = pattern_weight
+        .get_best_approach()
+        .or_else(|| best_approaches.get(&pattern_index).copied());
     if pattern.get_vertices_num() == 1 {
         let src_definite_extend_step = DefiniteExtendStep::try_from(pattern).unwrap();
         let cost = CostCount::from_src_pattern(pattern_weight.get_count());
         (vec![src_definite_extend_step], cost)
-    } else if pattern_weight.get_best_approach().is_some() && predicate_num == 0 {
-        let best_approach = pattern_weight.get_best_approach().unwrap();
+    } else if memorized_best_approach.is_some() && predicate_num == 0 {
+        let best_approach = memorized_best_approach.unwrap();
         let (pre_pattern, definite_extend_step, this_step_cost) =
             pattern_roll_back(pattern, pattern_index, best_approach, catalog);
         let pre_pattern_index = best_approach.get_src_pattern_index();
-        let (mut definite_extend_steps, mut cost) =
-            get_definite_extend_steps_in_catalog(catalog, pre_pattern_index, pre_pattern);
+        let (mut definite_extend_steps, mut cost) = get_definite_extend_steps_in_catalog_read_only(
+            catalog,
+            pre_pattern_index,
+            pre_pattern,
+            best_approaches,
+        );
         definite_extend_steps.push(definite_extend_step);
         cost += this_step_cost;
         return (definite_extend_steps, cost);
     } else {
+        let cost_model = CostModel::default();
         let mut optimal_extend_steps = vec![];
         let mut min_cost = CostCount::max_value();
         let mut max_predicate_num = usize::MIN;
@@ -1067,13 +2153,18 @@ fn get_definite_extend_steps_in_catalog(
                 pattern_roll_back(pattern.clone(), pattern_index, approach, catalog);
             let pre_pattern_predicate_num = pre_pattern.get_predicate_num();
             let pre_pattern_index = approach.get_src_pattern_index();
-            let (mut extend_steps, pre_cost) =
-                get_definite_extend_steps_in_catalog(catalog, pre_pattern_index, pre_pattern);
+            let (mut extend_steps, pre_cost) = get_definite_extend_steps_in_catalog_read_only(
+                catalog,
+                pre_pattern_index,
+                pre_pattern,
+                best_approaches,
+            );
             extend_steps.push(definite_extend_step);
             let cost = pre_cost + this_step_cost;
             cost_counts_vec.push((pre_pattern_index, pre_cost, this_step_cost, cost));
             if pre_pattern_predicate_num > max_predicate_num
-                || (cost < min_cost && pre_pattern_predicate_num == max_predicate_num)
+                || (cost.get_cost(&cost_model) < min_cost.get_cost(&cost_model)
+                    && pre_pattern_predicate_num == max_predicate_num)
             {
                 optimal_extend_steps = extend_steps;
                 min_cost = cost;
@@ -1088,14 +2179,21 @@ fn get_definite_extend_steps_in_catalog(
             best_approach,
             min_cost,
             cost_counts_vec,
+            &cost_model,
         );
         if predicate_num == 0 {
-            catalog.set_pattern_best_approach(pattern_index, best_approach);
+            best_approaches.insert(pattern_index, best_approach);
         }
         return (optimal_extend_steps, min_cost);
     }
 }
 
+fn set_patterns_best_approach(catalog: &mut Catalogue, best_approaches: HashMap<NodeIndex, Approach>) {
+    for (pattern_index, best_approach) in best_approaches {
+        catalog.set_pattern_best_approach(pattern_index, best_approach);
+    }
+}
+
 fn pattern_roll_back(
     pattern: Pattern, pattern_index: NodeIndex, approach: Approach, catalog: &Catalogue,
 ) -> (Pattern, DefiniteExtendStep, CostCount) {
@@ -1123,15 +2221,17 @@ fn pattern_roll_back(
         .unwrap()
         .get_id();
     let extend_step = extend_weight.get_extend_step();
+    // The direction of an undirected edge is taken as Out, the same as its extend edge
     let edge_id_map = pattern
         .adjacencies_iter(target_vertex_id)
         .map(|adjacency| {
+            let direction = if adjacency.is_bidirectional() {
+                PatternDirection::Out
+            } else {
+                adjacency.get_direction().reverse()
+            };
             (
-                (
-                    adjacency.get_adj_vertex().get_id(),
-                    adjacency.get_edge_label(),
-                    adjacency.get_direction().reverse(),
-                ),
+                (adjacency.get_adj_vertex().get_id(), adjacency.get_edge_label(), direction),
                 adjacency.get_edge_id(),
             )
         })
@@ -1163,26 +2263,34 @@ fn get_adj_edges_filter_num(pattern: &Pattern, vertex_id: PatternId) -> usize {
 }
 
 fn sort_vertex_ids(vertex_ids: &mut [PatternId], pattern: &Pattern) {
-    vertex_ids.sort_by(|&v1_id, &v2_id| {
-        // compare v1 and v2's vertex predicate
-        let v1_has_predicate = vertex_has_predicate(pattern, v1_id);
-        let v2_has_predicate = vertex_has_predicate(pattern, v2_id);
-        // compare v1 and v2's adjacent edges' predicate num
-        let v1_edges_predicate_num = get_adj_edges_filter_num(pattern, v1_id);
-        let v2_edges_predicate_num = get_adj_edges_filter_num(pattern, v2_id);
-        // compare v1 and v2's degree
-        let v1_degree = pattern.get_vertex_degree(v1_id);
-        let v2_degree = pattern.get_vertex_degree(v2_id);
-        // compare v1 and v2's out degree
-        let v1_out_degree = pattern.get_vertex_out_degree(v1_id);
-        let v2_out_degree = pattern.get_vertex_out_degree(v2_id);
-        (v1_has_predicate, v1_edges_predicate_num, v1_degree, v1_out_degree).cmp(&(
-            v2_has_predicate,
-            v2_edges_predicate_num,
-            v2_degree,
-            v2_out_degree,
-        ))
-    });
+    vertex_ids.sort_by_key(|&vertex_id| get_vertex_sort_key(pattern, vertex_id, None));
+}
+
+/// Sort the vertex ids as sort_vertex_ids, but the ties of degrees are broken by the vertex counts of
+/// their labels in the pattern meta, so that the vertex of the rarest label is preferred as the source
+fn sort_vertex_ids_with_meta(vertex_ids: &mut [PatternId], pattern: &Pattern, pattern_meta: &PatternMeta) {
+    vertex_ids.sort_by_key(|&vertex_id| get_vertex_sort_key(pattern, vertex_id, Some(pattern_meta)));
+}
+
+/// The key to sort the vertices, where the vertices sorted to the last are preferred to be matched first
+///
+/// The vertices are compared by whether they have predicates, the predicate num of their adjacent edges,
+/// their degrees, the vertex counts of their labels (if given by the pattern meta, where rarer is
+/// preferred) and their out degrees in turn
+fn get_vertex_sort_key(
+    pattern: &Pattern, vertex_id: PatternId, pattern_meta: Option<&PatternMeta>,
+) -> (bool, usize, usize, Reverse<usize>, usize) {
+    let label_count = pattern_meta
+        .zip(pattern.get_vertex(vertex_id))
+        .and_then(|(pattern_meta, vertex)| pattern_meta.get_vertex_label_count(vertex.get_label()))
+        .unwrap_or(usize::MAX);
+    (
+        vertex_has_predicate(pattern, vertex_id),
+        get_adj_edges_filter_num(pattern, vertex_id),
+        pattern.get_vertex_degree(vertex_id),
+        Reverse(label_count),
+        pattern.get_vertex_out_degree(vertex_id),
+    )
 }
 
 /// Build logical plan for extend based pattern match plan
@@ -1206,7 +2314,8 @@ pub fn build_distributed_match_plan(
     let mut pre_node = pb::logical_plan::Node { opr: Some(as_opr.into()), children: vec![] };
     for definite_extend_step in definite_extend_steps.into_iter().rev() {
         let edge_expands = definite_extend_step.generate_expand_operators(origin_pattern);
-        let edge_expands_num = edge_expands.len();
+        let path_expand_oprs = definite_extend_step.generate_path_expand_operators(origin_pattern)?;
+        let edge_expands_num = if path_expand_oprs.is_some() { 1 } else { edge_expands.len() };
         let edge_expands_ids: Vec<i32> = (0..edge_expands_num as i32)
             .map(|i| i + child_offset)
             .collect();
@@ -1214,8 +2323,16 @@ pub fn build_distributed_match_plan(
             pre_node.children.push(i);
         }
         match_plan.nodes.push(pre_node);
-        match edge_expands_num.cmp(&1) {
-            Ordering::Greater => {
+        match (path_expand_oprs, edge_expands_num.cmp(&1)) {
+            (Some((path_expand, get_v)), _) => {
+                match_plan.nodes.push(pb::logical_plan::Node {
+                    opr: Some(path_expand.into()),
+                    children: vec![child_offset + 1],
+                });
+                pre_node = pb::logical_plan::Node { opr: Some(get_v.into()), children: vec![] };
+                child_offset += 2;
+            }
+            (None, Ordering::Greater) => {
                 // if edge expand num > 1, we need a Intersect Operator
                 for edge_expand in edge_expands {
                     let node = pb::logical_plan::Node {
@@ -1228,7 +2345,7 @@ pub fn build_distributed_match_plan(
                 pre_node = pb::logical_plan::Node { opr: Some(intersect.into()), children: vec![] };
                 child_offset += (edge_expands_num + 1) as i32;
             }
-            Ordering::Equal => {
+            (None, Ordering::Equal) => {
                 let edge_expand = edge_expands.into_iter().last().unwrap();
                 pre_node = pb::logical_plan::Node { opr: Some(edge_expand.into()), children: vec![] };
                 child_offset += 1;
@@ -1262,6 +2379,7 @@ pub fn build_distributed_match_plan(
     }
     pre_node.children.push(child_offset);
     match_plan.nodes.push(pre_node);
+    match_plan.roots = vec![0];
     Ok(match_plan)
 }
 
@@ -1283,7 +2401,18 @@ pub fn build_stand_alone_match_plan(
     child_offset += 1;
     for definite_extend_step in definite_extend_steps.into_iter().rev() {
         let mut edge_expands = definite_extend_step.generate_expand_operators(origin_pattern);
-        if edge_expands.is_empty() {
+        if let Some((path_expand, get_v)) =
+            definite_extend_step.generate_path_expand_operators(origin_pattern)?
+        {
+            match_plan.nodes.push(pb::logical_plan::Node {
+                opr: Some(path_expand.into()),
+                children: vec![child_offset],
+            });
+            match_plan
+                .nodes
+                .push(pb::logical_plan::Node { opr: Some(get_v.into()), children: vec![child_offset + 1] });
+            child_offset += 2;
+        } else if edge_expands.is_empty() {
             return Err(IrError::InvalidPattern(
                 "Build logical plan error: extend step is not source but has 0 edges".to_string(),
             ));
@@ -1322,19 +2451,20 @@ pub fn build_stand_alone_match_plan(
             child_offset += 1;
         }
     }
+    match_plan.roots = vec![0];
     Ok(match_plan)
 }
 
 /// Cost estimation functions
 pub fn extend_cost_estimate(
     pre_pattern_count: usize, pattern_count: usize, adjacency_count: usize, intersect_count: usize,
-    extend_num: usize,
+    extend_num: usize, cost_model: &CostModel,
 ) -> usize {
     pre_pattern_count
         + pattern_count
         + (if extend_num > 1 {
-            ((adjacency_count as f64) * (*ALPHA.read().unwrap())) as usize
-                + ((intersect_count as f64) * (*BETA.read().unwrap())) as usize
+            ((adjacency_count as f64) * cost_model.get_alpha()) as usize
+                + ((intersect_count as f64) * cost_model.get_beta()) as usize
                 + pre_pattern_count * extend_num
         } else {
             0
@@ -1375,9 +2505,7 @@ fn check_target_vertex_label_num(extend_step: &DefiniteExtendStep, pattern_meta:
         let src_vertex_label = extend_edge.get_src_vertex().get_label();
         let edge_label = extend_edge.get_edge_label();
         let dir = extend_edge.get_direction();
-        for (start_vertex_label, end_vertex_label) in
-            pattern_meta.associated_vlabels_iter_by_elabel(edge_label)
-        {
+        for &(start_vertex_label, end_vertex_label) in pattern_meta.associated_vlabels(edge_label) {
             if dir == PatternDirection::Out && src_vertex_label == start_vertex_label {
                 target_vertex_label_candis.insert(end_vertex_label);
             } else if dir == PatternDirection::In && src_vertex_label == end_vertex_label {
@@ -1429,6 +2557,23 @@ fn match_pb_plan_add_source(pb_plan: &mut pb::LogicalPlan) -> Option<()> {
                 }
             })
             .unwrap();
+        // The predicate of the source vertex (if any) follows the label select, which is pushed down
+        // into the scan if possible, and kept as a select otherwise
+        let source_predicate = match pb_plan
+            .nodes
+            .get(1)
+            .and_then(|node| node.opr.as_ref())
+            .and_then(|opr| opr.opr.as_ref())
+        {
+            Some(pb::logical_plan::operator::Opr::Select(filter)) => filter
+                .predicate
+                .clone()
+                .filter(is_scan_predicate),
+            _ => None,
+        };
+        if source_predicate.is_some() {
+            pb_plan_remove_node(pb_plan, 1);
+        }
         let source = pb::Scan {
             scan_opt: 0,
             alias: None,
@@ -1437,7 +2582,7 @@ fn match_pb_plan_add_source(pb_plan: &mut pb::LogicalPlan) -> Option<()> {
                 columns: vec![],
                 is_all_columns: false,
                 limit: None,
-                predicate: None,
+                predicate: source_predicate,
                 sample_ratio: 1.0,
                 extra: HashMap::new(),
             }),
@@ -1447,46 +2592,316 @@ fn match_pb_plan_add_source(pb_plan: &mut pb::LogicalPlan) -> Option<()> {
         pb_plan
             .nodes
             .insert(0, pb::logical_plan::Node { opr: Some(source.into()), children: vec![1] });
+        pb_plan.roots = vec![0];
         Some(())
     } else {
         None
     }
 }
 
-fn pb_plan_add_count_sink_operator(pb_plan: &mut pb::LogicalPlan) {
-    let pb_plan_len = pb_plan.nodes.len();
-    let count = pb::GroupBy {
-        mappings: vec![],
-        functions: vec![pb::group_by::AggFunc {
-            vars: vec![],
-            aggregate: 3, // count
-            alias: Some(0.into()),
-        }],
-    };
-    pb_plan
+/// Whether the predicate only refers to the properties of the current vertex, so that it can be
+/// evaluated by a scan
+fn is_scan_predicate(predicate: &common_pb::Expression) -> bool {
+    predicate
+        .operators
+        .iter()
+        .all(|opr| match &opr.item {
+            Some(common_pb::expr_opr::Item::Var(var)) => var.tag.is_none(),
+            Some(common_pb::expr_opr::Item::Vars(_)) | Some(common_pb::expr_opr::Item::VarMap(_)) => false,
+            _ => true,
+        })
+}
+
+/// Remove the node from the plan, and offset the child and parent (of Intersect nodes) ids referring to
+/// the nodes after it by -1
+fn pb_plan_remove_node(pb_plan: &mut pb::LogicalPlan, index: usize) {
+    pb_plan.nodes.remove(index);
+    let index = index as i32;
+    for node in pb_plan.nodes.iter_mut() {
+        for child_id in node.children.iter_mut() {
+            if *child_id > index {
+                *child_id -= 1;
+            }
+        }
+        if let Some(pb::logical_plan::operator::Opr::Intersect(intersect)) = node
+            .opr
+            .as_mut()
+            .and_then(|opr| opr.opr.as_mut())
+        {
+            for parent_id in intersect.parents.iter_mut() {
+                if *parent_id > index {
+                    *parent_id -= 1;
+                }
+            }
+        }
+    }
+}
+
+/// Check the wiring of the nodes in the logical plan
+///
+/// Every root, child and parent (of Intersect nodes) id must refer to a node in the plan, no node is its
+/// own child, and there must be exactly one sink, i.e., the node without children.
+pub fn validate_logical_plan(pb_plan: &pb::LogicalPlan) -> IrResult<()> {
+    let nodes_num = pb_plan.nodes.len() as i32;
+    let is_valid_id = |id: i32| id >= 0 && id < nodes_num;
+    if pb_plan.roots.is_empty() {
+        return Err(IrError::InvalidPlan("no root in the plan".to_string()));
+    }
+    if let Some(root) = pb_plan
+        .roots
+        .iter()
+        .find(|&&root| !is_valid_id(root))
+    {
+        return Err(IrError::InvalidPlan(format!("root {} is out of range", root)));
+    }
+    for (node_id, node) in pb_plan.nodes.iter().enumerate() {
+        let node_id = node_id as i32;
+        if let Some(child_id) = node
+            .children
+            .iter()
+            .find(|&&child_id| !is_valid_id(child_id) || child_id == node_id)
+        {
+            return Err(IrError::InvalidPlan(format!("child {} of node {} is invalid", child_id, node_id)));
+        }
+        if let Some(pb::logical_plan::operator::Opr::Intersect(intersect)) = node
+            .opr
+            .as_ref()
+            .and_then(|opr| opr.opr.as_ref())
+        {
+            if let Some(parent_id) = intersect
+                .parents
+                .iter()
+                .find(|&&parent_id| !is_valid_id(parent_id))
+            {
+                return Err(IrError::InvalidPlan(format!(
+                    "parent {} of intersect node {} is out of range",
+                    parent_id, node_id
+                )));
+            }
+        }
+    }
+    let sinks_num = pb_plan
         .nodes
-        .push(pb::logical_plan::Node { opr: Some(count.into()), children: vec![(pb_plan_len + 1) as i32] });
-    let sink = pb::Sink {
-        tags: vec![common_pb::NameOrIdKey { key: Some(0.into()) }],
-        sink_target: Some(pb::sink::SinkTarget {
-            inner: Some(pb::sink::sink_target::Inner::SinkDefault(pb::SinkDefault {
-                id_name_mappings: vec![],
-            })),
-        }),
-    };
+        .iter()
+        .filter(|node| node.children.is_empty())
+        .count();
+    if sinks_num != 1 {
+        return Err(IrError::InvalidPlan(format!("{} sinks in the plan, expected exactly one", sinks_num)));
+    }
+    Ok(())
+}
+
+fn pb_plan_add_limit_operator(pb_plan: &mut pb::LogicalPlan, limit: Option<usize>) -> IrResult<()> {
+    if let Some(limit) = limit {
+        let pb_plan_len = pb_plan.nodes.len();
+        pb_plan.nodes.push(pb::logical_plan::Node {
+            opr: Some(build_limit_operator(limit)?.into()),
+            children: vec![(pb_plan_len + 1) as i32],
+        });
+    }
+    Ok(())
+}
+
+/// The limit operator which only keeps the first `limit` matches
+///
+/// Return an error if the limit does not fit in the i32 range of the operator
+fn build_limit_operator(limit: usize) -> IrResult<pb::Limit> {
+    let upper = i32::try_from(limit)
+        .map_err(|_| IrError::InvalidPlan(format!("limit {} is out of the range of i32", limit)))?;
+    Ok(pb::Limit { range: Some(pb::Range { lower: 0, upper }) })
+}
+
+fn pb_plan_add_aggregate_sink_operator(
+    pb_plan: &mut pb::LogicalPlan, pattern: &Pattern, aggregate: AggregateSpec,
+) -> IrResult<()> {
+    if let Some(group_by) = aggregate.to_group_by(pattern)? {
+        let pb_plan_len = pb_plan.nodes.len();
+        pb_plan.nodes.push(pb::logical_plan::Node {
+            opr: Some(group_by.into()),
+            children: vec![(pb_plan_len + 1) as i32],
+        });
+    }
+    let sink = aggregate.to_sink(pattern);
     pb_plan
         .nodes
         .push(pb::logical_plan::Node { opr: Some(sink.into()), children: vec![] });
+    Ok(())
 }
 
-fn pattern_equal(pattern1: &Pattern, pattern2: &Pattern) -> bool {
-    if pattern1.get_vertices_num() == pattern2.get_vertices_num()
-        && pattern1.get_edges_num() == pattern2.get_edges_num()
-    {
-        return pattern1.encode_to() == pattern2.encode_to();
+/// The configuration of a generated pattern match plan
+///
+/// aggregate: the aggregate applied to the matches
+///
+/// limit: only the first `limit` matches are aggregated, if given
+///
+/// cost_model: the cost model to choose the approaches of an optimized plan
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct PlanConfig {
+    aggregate: AggregateSpec,
+    limit: Option<usize>,
+    cost_model: CostModel,
+}
+
+impl PlanConfig {
+    pub fn with_aggregate(mut self, aggregate: AggregateSpec) -> Self {
+        self.aggregate = aggregate;
+        self
+    }
+
+    pub fn with_limit(mut self, limit: Option<usize>) -> Self {
+        self.limit = limit;
+        self
+    }
+
+    pub fn with_cost_model(mut self, cost_model: CostModel) -> Self {
+        self.cost_model = cost_model;
+        self
+    }
+}
+
+/// The aggregate applied to the matches of a generated pattern match plan before they are sunk
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AggregateSpec {
+    /// Count the matches, which is the cardinality of the pattern
+    Count,
+    /// Count the distinct vertices of the given tag among the matches
+    CountDistinct(TagId),
+    /// No aggregate, the raw matches are sunk with all the pattern vertices
+    None,
+}
+
+impl Default for AggregateSpec {
+    fn default() -> Self {
+        AggregateSpec::Count
+    }
+}
+
+impl AggregateSpec {
+    /// The GroupBy operator of the aggregate, or None if there is no aggregate
+    fn to_group_by(&self, pattern: &Pattern) -> IrResult<Option<pb::GroupBy>> {
+        let (vars, aggregate) = match *self {
+            AggregateSpec::Count => (vec![], 3), // count
+            AggregateSpec::CountDistinct(tag) => {
+                // Pattern vertices are aliased by their ids in the generated plans
+                let vertex_id = pattern
+                    .get_vertex_from_tag(tag)
+                    .ok_or(IrError::TagNotExist((tag as i32).into()))?
+                    .get_id();
+                let var = common_pb::Variable { tag: Some((vertex_id as i32).into()), property: None };
+                (vec![var], 4) // count distinct
+            }
+            AggregateSpec::None => return Ok(None),
+        };
+        Ok(Some(pb::GroupBy {
+            mappings: vec![],
+            functions: vec![pb::group_by::AggFunc { vars, aggregate, alias: Some(0.into()) }],
+        }))
+    }
+
+    /// The Sink operator of the aggregate, which sinks either the aggregate result or the matched vertices
+    fn to_sink(&self, pattern: &Pattern) -> pb::Sink {
+        let tags = match self {
+            AggregateSpec::Count | AggregateSpec::CountDistinct(_) => {
+                vec![common_pb::NameOrIdKey { key: Some(0.into()) }]
+            }
+            AggregateSpec::None => pattern
+                .vertices_iter()
+                .map(|vertex| common_pb::NameOrIdKey { key: Some((vertex.get_id() as i32).into()) })
+                .collect(),
+        };
+        pb::Sink {
+            tags,
+            sink_target: Some(pb::sink::SinkTarget {
+                inner: Some(pb::sink::sink_target::Inner::SinkDefault(pb::SinkDefault {
+                    id_name_mappings: vec![],
+                })),
+            }),
+        }
     }
+}
+
+/// The weights to combine the counts of a plan into its cost
+///
+/// alpha and beta weight the adjacency and intersect counts of extend steps,
+/// and w1 and w2 weight the join counts of binary join steps
+///
+/// distributed_weights: the (alpha, beta, w1, w2) used instead for distributed plans if given, as the
+/// shuffles and intersections cost differently from the stand-alone mode
+///
+/// path_hop_inflation: the inflation of the count of a pattern per extra hop of its path edges,
+/// i.e., a path edge of `h` hops is estimated as `path_hop_inflation^(h - 1)` times of a single edge
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CostModel {
+    alpha: f64,
+    beta: f64,
+    w1: f64,
+    w2: f64,
+    distributed_weights: Option<(f64, f64, f64, f64)>,
+    path_hop_inflation: f64,
+}
 
-    false
+/// The default cost model follows the weights set by the deprecated set_alpha, set_beta, set_w1 and set_w2
+impl Default for CostModel {
+    fn default() -> Self {
+        *DEFAULT_COST_MODEL.read().unwrap()
+    }
+}
+
+impl CostModel {
+    pub fn new(alpha: f64, beta: f64, w1: f64, w2: f64) -> CostModel {
+        CostModel {
+            alpha,
+            beta,
+            w1,
+            w2,
+            distributed_weights: None,
+            path_hop_inflation: DEFAULT_PATH_HOP_INFLATION,
+        }
+    }
+
+    /// Use the given weights for distributed plans, while the ones given by `new` are for stand-alone plans
+    pub fn with_distributed_weights(mut self, alpha: f64, beta: f64, w1: f64, w2: f64) -> CostModel {
+        self.distributed_weights = Some((alpha, beta, w1, w2));
+        self
+    }
+
+    pub fn get_distributed_weights(&self) -> Option<(f64, f64, f64, f64)> {
+        self.distributed_weights
+    }
+
+    pub fn with_path_hop_inflation(mut self, path_hop_inflation: f64) -> CostModel {
+        self.path_hop_inflation = path_hop_inflation;
+        self
+    }
+
+    pub fn get_path_hop_inflation(&self) -> f64 {
+        self.path_hop_inflation
+    }
+
+    /// Get the cost model whose weights are the ones for the distributed or stand-alone mode
+    pub fn for_mode(&self, is_distributed: bool) -> CostModel {
+        let cost_model = match self.distributed_weights {
+            Some((alpha, beta, w1, w2)) if is_distributed => CostModel::new(alpha, beta, w1, w2),
+            _ => CostModel::new(self.alpha, self.beta, self.w1, self.w2),
+        };
+        cost_model.with_path_hop_inflation(self.path_hop_inflation)
+    }
+
+    pub fn get_alpha(&self) -> f64 {
+        self.alpha
+    }
+
+    pub fn get_beta(&self) -> f64 {
+        self.beta
+    }
+
+    pub fn get_w1(&self) -> f64 {
+        self.w1
+    }
+
+    pub fn get_w2(&self) -> f64 {
+        self.w2
+    }
 }
 
 #[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
@@ -1544,6 +2959,11 @@ impl CostCount {
         CostCount { instance_count: pattern_count, left_join_count, right_join_count, ..Default::default() }
     }
 
+    /// The estimated count of intermediate results intersected by the extend steps
+    pub fn get_intersect_count(&self) -> OrderedFloat<f64> {
+        self.intersect_count
+    }
+
     fn max_value() -> CostCount {
         CostCount::new(
             OrderedFloat::max_value(),
@@ -1554,15 +2974,15 @@ impl CostCount {
         )
     }
 
-    fn get_cost(&self) -> OrderedFloat<f64> {
+    fn get_cost(&self, cost_model: &CostModel) -> OrderedFloat<f64> {
         if *self == CostCount::max_value() {
             OrderedFloat::max_value()
         } else {
             self.instance_count
-                + (self.adjacency_count * (*ALPHA.read().unwrap()))
-                + (self.intersect_count * (*BETA.read().unwrap()))
-                + (self.left_join_count * (*W1.read().unwrap()))
-                + (self.right_join_count * (*W1.read().unwrap()))
+                + (self.adjacency_count * cost_model.alpha)
+                + (self.intersect_count * cost_model.beta)
+                + (self.left_join_count * cost_model.w1)
+                + (self.right_join_count * cost_model.w2)
         }
     }
 }
@@ -1576,7 +2996,7 @@ impl Add for CostCount {
             adjacency_count: self.adjacency_count + rhs.adjacency_count,
             intersect_count: self.intersect_count + rhs.intersect_count,
             left_join_count: self.left_join_count + rhs.left_join_count,
-            right_join_count: self.right_join_count + rhs.left_join_count,
+            right_join_count: self.right_join_count + rhs.right_join_count,
         }
     }
 }
@@ -1591,45 +3011,38 @@ impl AddAssign for CostCount {
     }
 }
 
-impl PartialOrd for CostCount {
-    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
-        Some(self.cmp(other))
-    }
-}
-
-impl Ord for CostCount {
-    fn cmp(&self, other: &Self) -> Ordering {
-        self.get_cost().cmp(&other.get_cost())
-    }
-}
-
+#[deprecated(note = "pass a CostModel to the plan generation instead")]
 pub fn set_alpha(alpha: f64) {
-    if let Ok(mut old_alpha) = ALPHA.write() {
-        *old_alpha = alpha;
+    if let Ok(mut default_cost_model) = DEFAULT_COST_MODEL.write() {
+        default_cost_model.alpha = alpha;
     }
 }
 
+#[deprecated(note = "pass a CostModel to the plan generation instead")]
 pub fn set_beta(beta: f64) {
-    if let Ok(mut old_beta) = BETA.write() {
-        *old_beta = beta
+    if let Ok(mut default_cost_model) = DEFAULT_COST_MODEL.write() {
+        default_cost_model.beta = beta
     }
 }
 
+#[deprecated(note = "pass a CostModel to the plan generation instead")]
 pub fn set_w1(w1: f64) {
-    if let Ok(mut old_w1) = W1.write() {
-        *old_w1 = w1
+    if let Ok(mut default_cost_model) = DEFAULT_COST_MODEL.write() {
+        default_cost_model.w1 = w1
     }
 }
 
+#[deprecated(note = "pass a CostModel to the plan generation instead")]
 pub fn set_w2(w2: f64) {
-    if let Ok(mut old_w2) = W2.write() {
-        *old_w2 = w2
+    if let Ok(mut default_cost_model) = DEFAULT_COST_MODEL.write() {
+        default_cost_model.w2 = w2
     }
 }
 
 fn print_pattern_choose_approach_log(
     catalog: &Catalogue, pattern: &Pattern, pattern_index: NodeIndex, best_approach: Approach,
     min_cost: CostCount, cost_counts_vec: Vec<(NodeIndex, CostCount, CostCount, CostCount)>,
+    cost_model: &CostModel,
 ) {
     info!("Current Pattern: {}", pattern);
     info!("Current Pattern Index: {}", pattern_index.index());
@@ -1644,14 +3057,33 @@ fn print_pattern_choose_approach_log(
         info!("Pre Pattern: {}", pre_pattern.get_pattern());
         info!("Pre Pattern Index: {}", pre_pattern_index.index());
         info!("Pre Pattern CostCount: {}", pre_pattern_cost);
-        info!("Pre Pattern Cost: {}", pre_pattern_cost.get_cost());
+        info!("Pre Pattern Cost: {}", pre_pattern_cost.get_cost(cost_model));
         info!("Step CostCount: {}", step_cost);
-        info!("Step Cost: {}", step_cost.get_cost());
+        info!("Step Cost: {}", step_cost.get_cost(cost_model));
         info!("Pattern CostCount: {}", cost);
-        info!("Pattern Cost: {}", cost.get_cost());
+        info!("Pattern Cost: {}", cost.get_cost(cost_model));
         info!("-------------------------------------");
     }
     info!("Chosen Pre Pattern Index: {}", best_approach.get_src_pattern_index().index());
     info!("Pattern Final CostCount: {}", min_cost);
-    info!("Pattern Final Cost: {}\n", min_cost.get_cost());
+    info!("Pattern Final Cost: {}\n", min_cost.get_cost(cost_model));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cost_count_add_join_counts() {
+        let left = CostCount::from_join(1.0.into(), 2.0.into(), 10.0.into());
+        let right = CostCount::from_join(4.0.into(), 8.0.into(), 20.0.into());
+        let sum = left + right;
+        assert_eq!(sum, CostCount::new(30.0.into(), 0.0.into(), 0.0.into(), 5.0.into(), 10.0.into()));
+        let mut sum_assigned = left;
+        sum_assigned += right;
+        assert_eq!(sum_assigned, sum);
+        // the left join count is weighted by w1, and the right join count by w2
+        let cost_model = CostModel::new(0.0, 0.0, 100.0, 1000.0);
+        assert_eq!(sum.get_cost(&cost_model), OrderedFloat(30.0 + 5.0 * 100.0 + 10.0 * 1000.0));
+    }
 }
@@ -16,65 +16,826 @@
 
 use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 use std::convert::TryFrom;
-use std::fs::File;
+use std::fs::{create_dir_all, File};
+use std::io::{BufReader, BufWriter};
 use std::iter::FromIterator;
 use std::path::Path;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicUsize, Ordering as AtomicOrdering};
 use std::sync::{mpsc, mpsc::Sender, Arc};
+use std::time::{Duration, Instant};
 use std::{thread, thread::JoinHandle, vec};
 
+use bincode::Result as BincodeResult;
+use bincode::{deserialize_from, serialize_into};
+use dyn_type::{CastError, Object};
 use graph_store::config::{DIR_GRAPH_SCHEMA, FILE_SCHEMA};
+use graph_store::graph_db::{Direction, LocalEdge};
 use graph_store::prelude::{DefaultId, GlobalStoreTrait, GraphDBConfig, InternalId, LabelId, LargeGraphDB};
+use ir_common::expr_parse::to_suffix_expr;
+use ir_common::generated::common as common_pb;
+use ir_common::NameOrId;
 use log::info;
-use petgraph::graph::NodeIndex;
+use petgraph::graph::{EdgeIndex, NodeIndex};
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
 
-use crate::catalogue::catalog::{Catalogue, TableLogue};
+use crate::catalogue::catalog::{Catalogue, PatMatPlanSpace, TableLogue};
 use crate::catalogue::extend_step::{DefiniteExtendEdge, DefiniteExtendStep, ExtendStep};
-use crate::catalogue::pattern::Pattern;
+use crate::catalogue::pattern::{Pattern, PatternEdge, PatternVertex};
 use crate::catalogue::pattern_meta::PatternMeta;
 use crate::catalogue::plan::get_definite_extend_steps;
-use crate::catalogue::{DynIter, PatternId, PatternLabelId};
+use crate::catalogue::sparsify::get_edge_distribution;
+use crate::catalogue::{DynIter, PatternDirection, PatternId, PatternLabelId};
+use crate::error::{IrError, IrResult};
 use crate::plan::meta::Schema;
 use crate::JsonIO;
 
 type PatternRecord = BTreeMap<PatternId, DefaultId>;
 
+/// How to sample the records of a pattern when estimating the graph
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SamplingStrategy {
+    /// Keep the records picked with a fixed stride, which starts from a random offset if seeded
+    #[default]
+    Stride,
+    /// Keep exactly the expected number of records by reservoir sampling
+    Reservoir,
+    /// Keep each record independently with the same probability
+    Bernoulli,
+}
+
+impl FromStr for SamplingStrategy {
+    type Err = IrError;
+
+    fn from_str(strategy: &str) -> IrResult<Self> {
+        match strategy {
+            "stride" => Ok(SamplingStrategy::Stride),
+            "reservoir" => Ok(SamplingStrategy::Reservoir),
+            "bernoulli" => Ok(SamplingStrategy::Bernoulli),
+            _ => Err(IrError::Unsupported(format!("sampling strategy {:?}", strategy))),
+        }
+    }
+}
+
+/// The configuration to estimate the counts of the patterns in a catalog
+///
+/// rate: the rate to sample the records of each pattern
+///
+/// sparsify_rate: the sparsify rate of each edge triple (src label, edge label, dst label), by which the
+/// counts estimated on a sparsified graph are scaled
+///
+/// limit: the maximum number of records of a pattern to keep, if given
+///
+/// min_seeds_per_label: the minimum number of start records to keep for each vertex label
+///
+/// thread_num: the number of threads to extend the records
+///
+/// seed: the seed of the RNG of random sampling, which makes the estimation reproducible if given
+///
+/// strategy: the strategy to sample the records
+///
+/// keep_sampled_records: whether to keep the sampled records in the catalog for
+/// `update_catalog_by_pattern_and_estimate`, which is off by default as they may take a lot of memory
+///
+/// reuse_records: whether to cache the sampled records of patterns by their codes in the catalog, so that
+/// `estimate_graph` reuses the records of the patterns counted by a previous estimation instead of
+/// extending them again, which trades memory for speed and is off by default
+#[derive(Debug, Clone)]
+pub struct EstimateConfig {
+    rate: f64,
+    sparsify_rate: HashMap<(u8, u8, u8), f64>,
+    limit: Option<usize>,
+    min_seeds_per_label: usize,
+    thread_num: usize,
+    seed: Option<u64>,
+    strategy: SamplingStrategy,
+    keep_sampled_records: bool,
+    reuse_records: bool,
+}
+
+/// By default, all the records are kept without sparsify rates, and extended in a single thread
+impl Default for EstimateConfig {
+    fn default() -> Self {
+        EstimateConfig {
+            rate: 1.0,
+            sparsify_rate: HashMap::new(),
+            limit: None,
+            min_seeds_per_label: 0,
+            thread_num: 1,
+            seed: None,
+            strategy: SamplingStrategy::default(),
+            keep_sampled_records: false,
+            reuse_records: false,
+        }
+    }
+}
+
+impl EstimateConfig {
+    pub fn with_rate(mut self, rate: f64) -> Self {
+        self.rate = rate;
+        self
+    }
+
+    pub fn with_sparsify_rate(mut self, sparsify_rate: HashMap<(u8, u8, u8), f64>) -> Self {
+        self.sparsify_rate = sparsify_rate;
+        self
+    }
+
+    pub fn with_limit(mut self, limit: Option<usize>) -> Self {
+        self.limit = limit;
+        self
+    }
+
+    pub fn with_min_seeds_per_label(mut self, min_seeds_per_label: usize) -> Self {
+        self.min_seeds_per_label = min_seeds_per_label;
+        self
+    }
+
+    pub fn with_thread_num(mut self, thread_num: usize) -> Self {
+        self.thread_num = thread_num;
+        self
+    }
+
+    pub fn with_seed(mut self, seed: Option<u64>) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    pub fn with_strategy(mut self, strategy: SamplingStrategy) -> Self {
+        self.strategy = strategy;
+        self
+    }
+
+    pub fn with_keep_sampled_records(mut self, keep_sampled_records: bool) -> Self {
+        self.keep_sampled_records = keep_sampled_records;
+        self
+    }
+
+    pub fn with_reuse_records(mut self, reuse_records: bool) -> Self {
+        self.reuse_records = reuse_records;
+        self
+    }
+}
+
 impl Catalogue {
+    /// Estimate the count of every pattern in the catalog on the given (sample) graph
+    ///
+    /// Records are sampled by the strategy of the config. If a seed is given, the sampling is driven
+    /// by an RNG seeded with it, which makes the estimation reproducible.
+    ///
+    /// Start records are sampled by the rate stratified by their vertex labels, where a rare label
+    /// keeps at least min_seeds_per_label records (unless the limit is smaller).
+    ///
+    /// If keep_sampled_records of the config is set, the sampled records of the patterns which can be
+    /// extended further are kept in the catalog, and reused by `update_catalog_by_pattern_and_estimate`
+    /// for new patterns. Otherwise, the records kept previously are cleared.
+    ///
+    /// If reuse_records of the config is set, the patterns whose records are cached by a previous
+    /// estimation with reuse_records are not extended again, e.g., when the catalog is estimated again
+    /// after being updated, and the records of the newly counted patterns are cached as well. The cached
+    /// records are only valid for the same graph and config. Otherwise, the cached records are cleared.
+    ///
+    /// Return the report of the time cost, the peak number of records and the number of executed sub
+    /// tasks of each level of patterns.
     pub fn estimate_graph(
-        &mut self, graph: Arc<LargeGraphDB<DefaultId, InternalId>>, rate: f64,
-        sparsify_rate: HashMap<(u8, u8, u8), f64>, limit: Option<usize>, thread_num: usize,
-    ) {
-        // Store the count of patterns
-        let mut pattern_counts_map = HashMap::new();
-        // The start points of the overal estimate graph process
-        let mut pattern_count_infos = self.get_start_pattern_count_infos(&graph, rate, limit);
-        // Store start patterns' count
-        update_pattern_counts_map(&mut pattern_counts_map, &pattern_count_infos);
+        &mut self, graph: Arc<LargeGraphDB<DefaultId, InternalId>>, config: &EstimateConfig,
+    ) -> EstimationReport {
+        self.estimate_graph_with_checkpoint(graph, config, None, None)
+            .expect("estimation without checkpoints should not fail")
+    }
+
+    /// Estimate the count of every pattern in the catalog like `estimate_graph`, which is resumable
+    ///
+    /// If a checkpoint directory is given, a checkpoint is saved to `level_<n>.checkpoint` in it after
+    /// the n-th level of patterns is counted (the start patterns are the level 0). It keeps the counts
+    /// of all the counted patterns and the records of the patterns in the last level.
+    ///
+    /// If a checkpoint to resume from is given, the counts are seeded from it, and the estimation picks
+    /// up from the patterns in its last level instead of the start patterns. The sampled records of
+    /// the patterns before the last level are not kept in the checkpoint, so they are not kept in the
+    /// catalog for `update_catalog_by_pattern_and_estimate` either, even if keep_sampled_records is set.
+    pub fn estimate_graph_with_checkpoint(
+        &mut self, graph: Arc<LargeGraphDB<DefaultId, InternalId>>, config: &EstimateConfig,
+        checkpoint_dir: Option<&Path>, resume_from: Option<&Path>,
+    ) -> BincodeResult<EstimationReport> {
+        // Records sampled previously are replaced by this estimation
+        self.sampled_records.clear();
+        if !config.reuse_records {
+            self.cached_records.clear();
+        }
+        let mut report = EstimationReport::default();
+        // Store the count of patterns, and the start points of the overal estimate graph process,
+        // which are the patterns of the last level if resumed from a checkpoint
+        let (mut pattern_counts_map, mut pattern_count_infos, mut level) = match resume_from {
+            Some(resume_from) => EstimationCheckpoint::load(resume_from)?.restore(self)?,
+            None => {
+                let level_start_time = Instant::now();
+                let pattern_count_infos = self.get_start_pattern_count_infos(&graph, config);
+                report.add_level(0, level_start_time.elapsed(), 0, &pattern_count_infos);
+                (HashMap::new(), pattern_count_infos, 0)
+            }
+        };
+        // Store the variance of patterns' count, which is unknown for the patterns before the last level
+        // of a checkpoint to resume from
+        let mut pattern_count_variances = HashMap::new();
+        update_pattern_count_variances(&mut pattern_count_variances, &pattern_count_infos);
+        if resume_from.is_none() {
+            // Store start patterns' count
+            update_pattern_counts_map(&mut pattern_counts_map, &pattern_count_infos);
+            if let Some(checkpoint_dir) = checkpoint_dir {
+                self.save_estimation_checkpoint(
+                    checkpoint_dir,
+                    level,
+                    &pattern_counts_map,
+                    &pattern_count_infos,
+                )?;
+            }
+        }
+        if config.keep_sampled_records {
+            self.update_sampled_records(&pattern_count_infos);
+        }
         // Count patterns in the catalog level by level
         while !pattern_count_infos.is_empty() {
             // Generate sub tasks to get of count infos of next level's pattern
-            let sub_tasks = self.generate_sub_tasks(pattern_count_infos, &graph);
+            let mut sub_tasks = self.generate_sub_tasks(pattern_count_infos, &graph);
+            // Skip the patterns counted already
+            sub_tasks.retain(|pattern_index, _| !pattern_counts_map.contains_key(pattern_index));
+            // Take the patterns whose records are cached instead of extending them again
+            let cached_pattern_count_infos = if config.reuse_records {
+                self.take_cached_pattern_count_infos(&mut sub_tasks)
+            } else {
+                HashMap::new()
+            };
             // Execute Subtasks
-            pattern_count_infos = self.execcute_sub_tasks(sub_tasks, thread_num, rate, limit);
+            let level_start_time = Instant::now();
+            let sub_tasks_num = sub_tasks.len();
+            pattern_count_infos = self.execcute_sub_tasks(sub_tasks, config);
+            if config.reuse_records {
+                self.update_cached_records(&pattern_count_infos);
+            }
+            pattern_count_infos.extend(cached_pattern_count_infos);
+            let level_elapsed = level_start_time.elapsed();
             // Store patterns' count
             update_pattern_counts_map(&mut pattern_counts_map, &pattern_count_infos);
+            update_pattern_count_variances(&mut pattern_count_variances, &pattern_count_infos);
+            if config.keep_sampled_records {
+                self.update_sampled_records(&pattern_count_infos);
+            }
+            level += 1;
+            if !pattern_count_infos.is_empty() {
+                report.add_level(level, level_elapsed, sub_tasks_num, &pattern_count_infos);
+            }
+            if let Some(checkpoint_dir) = checkpoint_dir {
+                self.save_estimation_checkpoint(
+                    checkpoint_dir,
+                    level,
+                    &pattern_counts_map,
+                    &pattern_count_infos,
+                )?;
+            }
         }
         info!("{:?}", pattern_counts_map);
         // Set pattern count in the catalog with sparsify rate info
         for (&pattern_index, &pattern_count) in pattern_counts_map.iter() {
-            self.set_pattern_count_with_rate(pattern_index, pattern_count, &sparsify_rate);
+            self.set_pattern_count_with_rate(pattern_index, pattern_count, &config.sparsify_rate);
+        }
+        for (&pattern_index, &pattern_count_variance) in pattern_count_variances.iter() {
+            self.set_pattern_count_variance_with_rate(
+                pattern_index,
+                pattern_count_variance,
+                &config.sparsify_rate,
+            );
         }
         // Set extend count in the catalog
         for (&pattern_index, _) in pattern_counts_map.iter() {
             self.set_extend_count_infos(pattern_index)
         }
+        Ok(report)
+    }
+
+    /// Estimate the count of every pattern in the catalog on a graph split into partitions
+    ///
+    /// The sampling pipeline of `estimate_graph` runs on each partition separately, and the counts
+    /// (as well as the variances and the sample sizes) of the patterns are summed across the partitions
+    /// before scaled by the sparsify rates. It assumes that no edge crosses the partitions, i.e., every
+    /// match of a pattern lies in a single partition, as the matches across partitions are not counted.
+    ///
+    /// The sampled records of different partitions cannot be reused together, so none of them is kept
+    /// in the catalog for `update_catalog_by_pattern_and_estimate`.
+    pub fn estimate_graph_partitioned(
+        &mut self, graphs: Vec<Arc<LargeGraphDB<DefaultId, InternalId>>>, config: &EstimateConfig,
+    ) {
+        self.sampled_records.clear();
+        let mut pattern_counts_map: HashMap<NodeIndex, usize> = HashMap::new();
+        let mut pattern_count_variances: HashMap<NodeIndex, f64> = HashMap::new();
+        let mut sample_sizes: HashMap<NodeIndex, usize> = HashMap::new();
+        for graph in graphs {
+            let (partition_counts_map, partition_count_variances) =
+                self.count_patterns_on_partition(&graph, config);
+            for (pattern_index, pattern_count) in partition_counts_map {
+                *pattern_counts_map
+                    .entry(pattern_index)
+                    .or_default() += pattern_count;
+            }
+            // The partitions are sampled independently, so the variances are summed up as well
+            for (pattern_index, pattern_count_variance) in partition_count_variances {
+                *pattern_count_variances
+                    .entry(pattern_index)
+                    .or_default() += pattern_count_variance;
+            }
+            for start_pattern_index in self.get_start_pattern_indices() {
+                *sample_sizes
+                    .entry(start_pattern_index)
+                    .or_default() += self
+                    .get_pattern_weight(start_pattern_index)
+                    .unwrap()
+                    .get_sample_size();
+            }
+        }
+        for (pattern_index, sample_size) in sample_sizes {
+            self.get_pattern_weight_mut(pattern_index)
+                .unwrap()
+                .set_sample_size(sample_size);
+        }
+        for (&pattern_index, &pattern_count) in pattern_counts_map.iter() {
+            self.set_pattern_count_with_rate(pattern_index, pattern_count, &config.sparsify_rate);
+        }
+        for (&pattern_index, &pattern_count_variance) in pattern_count_variances.iter() {
+            self.set_pattern_count_variance_with_rate(
+                pattern_index,
+                pattern_count_variance,
+                &config.sparsify_rate,
+            );
+        }
+        for (&pattern_index, _) in pattern_counts_map.iter() {
+            self.set_extend_count_infos(pattern_index)
+        }
+    }
+
+    /// Count the patterns in the catalog level by level on a single partition of the graph, and return
+    /// the counts and the variances of the patterns
+    fn count_patterns_on_partition(
+        &mut self, graph: &Arc<LargeGraphDB<DefaultId, InternalId>>, config: &EstimateConfig,
+    ) -> (HashMap<NodeIndex, usize>, HashMap<NodeIndex, f64>) {
+        let mut pattern_count_infos = self.get_start_pattern_count_infos(graph, config);
+        let mut pattern_counts_map = HashMap::new();
+        let mut pattern_count_variances = HashMap::new();
+        update_pattern_counts_map(&mut pattern_counts_map, &pattern_count_infos);
+        update_pattern_count_variances(&mut pattern_count_variances, &pattern_count_infos);
+        while !pattern_count_infos.is_empty() {
+            let mut sub_tasks = self.generate_sub_tasks(pattern_count_infos, graph);
+            sub_tasks.retain(|pattern_index, _| !pattern_counts_map.contains_key(pattern_index));
+            pattern_count_infos = self.execcute_sub_tasks(sub_tasks, config);
+            update_pattern_counts_map(&mut pattern_counts_map, &pattern_count_infos);
+            update_pattern_count_variances(&mut pattern_count_variances, &pattern_count_infos);
+        }
+        (pattern_counts_map, pattern_count_variances)
+    }
+
+    fn save_estimation_checkpoint(
+        &self, checkpoint_dir: &Path, level: usize, pattern_counts_map: &HashMap<NodeIndex, usize>,
+        pattern_count_infos: &HashMap<NodeIndex, Arc<PatternCountInfo>>,
+    ) -> BincodeResult<()> {
+        create_dir_all(checkpoint_dir)?;
+        let pattern_counts = pattern_counts_map
+            .iter()
+            .map(|(&pattern_index, &pattern_count)| {
+                let pattern_code = self
+                    .get_pattern_weight(pattern_index)
+                    .unwrap()
+                    .get_pattern()
+                    .encode_to();
+                (pattern_code, pattern_count)
+            })
+            .collect();
+        let frontier = pattern_count_infos
+            .values()
+            .map(|pattern_count_info| {
+                let pattern = &pattern_count_info.pattern;
+                let ranked_records = pattern_count_info
+                    .pattern_records
+                    .iter()
+                    .map(|pattern_record| {
+                        pattern_record
+                            .iter()
+                            .map(|(&v_id, &graph_vertex_id)| {
+                                (pattern.get_vertex_rank(v_id).unwrap(), graph_vertex_id)
+                            })
+                            .collect()
+                    })
+                    .collect();
+                (pattern.encode_to(), pattern_count_info.pattern_count, ranked_records)
+            })
+            .collect();
+        let checkpoint = EstimationCheckpoint { level, pattern_counts, frontier };
+        checkpoint.save(checkpoint_dir.join(format!("level_{}.checkpoint", level)))
+    }
+
+    /// Update the catalog with the given pattern, and estimate the counts of the newly added patterns only
+    ///
+    /// Like `update_catalog_by_pattern`, only the missing patterns and approaches are added, so the counts
+    /// of the patterns already in the catalog are left unchanged. A new pattern is estimated by extending
+    /// the sampled records of one of its source patterns, where the records are reused if
+    /// - they are kept by the last `estimate_graph` on this catalog with keep_sampled_records, i.e., the
+    ///   source pattern could be extended further at that time (the records of the largest patterns are
+    ///   not kept), or
+    /// - they are sampled by a previous update of this catalog with keep_sampled_records.
+    ///
+    /// Otherwise, the records of the source pattern are sampled again from its own source patterns,
+    /// down to the single vertices if necessary. A catalog loaded from disk keeps no records at all.
+    /// The single vertices sampled again are not stratified by min_seeds_per_label of the config.
+    /// Without keep_sampled_records, the records sampled by the update are cleared afterwards.
+    ///
+    /// Return the indices of the new patterns, sorted by their sizes
+    pub fn update_catalog_by_pattern_and_estimate(
+        &mut self, pattern: &Pattern, graph: Arc<LargeGraphDB<DefaultId, InternalId>>,
+        config: &EstimateConfig,
+    ) -> Vec<NodeIndex> {
+        let pattern_indices: HashSet<NodeIndex> = self.pattern_indices_iter().collect();
+        let approaches_num = self.get_approaches_num();
+        self.update_catalog_by_pattern(pattern);
+        let mut new_pattern_indices: Vec<NodeIndex> = self
+            .pattern_indices_iter()
+            .filter(|pattern_index| !pattern_indices.contains(pattern_index))
+            .collect();
+        new_pattern_indices.sort_by_key(|&pattern_index| {
+            let pattern = self
+                .get_pattern_weight(pattern_index)
+                .unwrap()
+                .get_pattern();
+            (pattern.get_vertices_num(), pattern.get_edges_num(), pattern_index)
+        });
+        for &pattern_index in new_pattern_indices.iter() {
+            if let Some(pattern_count_info) =
+                self.get_or_sample_pattern_count_info(pattern_index, &graph, config)
+            {
+                if pattern_count_info.pattern.get_vertices_num() == 1 {
+                    self.get_pattern_weight_mut(pattern_index)
+                        .unwrap()
+                        .set_sample_size(pattern_count_info.pattern_records.len());
+                }
+                self.set_pattern_count_with_rate(
+                    pattern_index,
+                    pattern_count_info.pattern_count,
+                    &config.sparsify_rate,
+                );
+                self.set_pattern_count_variance_with_rate(
+                    pattern_index,
+                    pattern_count_info.pattern_count_variance,
+                    &config.sparsify_rate,
+                );
+            }
+        }
+        // Extend counts are set on the source patterns of the new approaches, which may be old ones
+        let src_pattern_indices: BTreeSet<NodeIndex> = self
+            .pattern_indices_iter()
+            .flat_map(|pattern_index| self.pattern_out_approaches_iter(pattern_index))
+            .filter(|approach| approach.get_approach_index().index() >= approaches_num)
+            .map(|approach| approach.get_src_pattern_index())
+            .chain(new_pattern_indices.iter().cloned())
+            .collect();
+        for pattern_index in src_pattern_indices {
+            self.set_extend_count_infos(pattern_index)
+        }
+        if !config.keep_sampled_records {
+            self.sampled_records.clear();
+        }
+        new_pattern_indices
+    }
+
+    /// Estimate the count of every pattern in the catalog on the given (sample) graph by random walks
+    ///
+    /// Instead of materializing the records of patterns, each walk starts from a random vertex and
+    /// extends the pattern one vertex at a time, picking a random vertex among the candidates.
+    /// The count of a pattern is the average of the Horvitz–Thompson estimates of the walks, i.e., the
+    /// inverse of the probability that a walk is picked, or 0 if the walk fails to reach the whole pattern.
+    pub fn estimate_graph_random_walk(
+        &mut self, graph: Arc<LargeGraphDB<DefaultId, InternalId>>, walks: usize, seed: Option<u64>,
+    ) {
+        let walk_steps_map = self.get_random_walk_steps();
+        let mut src_vertices_map: HashMap<PatternLabelId, Vec<DefaultId>> = HashMap::new();
+        for (&pattern_index, walk_steps) in walk_steps_map.iter() {
+            let src_vertex_label = walk_steps[0].get_target_vertex().get_label();
+            let src_vertices = src_vertices_map
+                .entry(src_vertex_label)
+                .or_insert_with(|| {
+                    graph
+                        .get_all_vertices(Some(&vec![src_vertex_label as LabelId]))
+                        .map(|graph_vertex| graph_vertex.get_id())
+                        .collect()
+                });
+            let mut rng = if let Some(seed) = get_pattern_seed(seed, pattern_index) {
+                StdRng::seed_from_u64(seed)
+            } else {
+                StdRng::from_entropy()
+            };
+            let edge_predicates: Vec<Vec<Option<PropertyPredicate>>> = walk_steps
+                .iter()
+                .map(compile_edge_predicates)
+                .collect();
+            let pattern_count = if walks == 0 {
+                0.0
+            } else {
+                (0..walks)
+                    .map(|_| random_walk(&graph, src_vertices, walk_steps, &edge_predicates, &mut rng))
+                    .sum::<f64>()
+                    / walks as f64
+            };
+            self.set_pattern_count_with_index(pattern_index, pattern_count.into());
+        }
+        for &pattern_index in walk_steps_map.keys() {
+            self.set_extend_count_infos(pattern_index)
+        }
+    }
+
+    /// Get the extend steps for random walks to reach each pattern in the catalog
+    ///
+    /// Like estimate_graph, patterns are visited level by level from the start patterns
+    fn get_random_walk_steps(&self) -> HashMap<NodeIndex, Vec<DefiniteExtendStep>> {
+        let mut walk_steps_map = HashMap::new();
+        let mut patterns = vec![];
+        for start_pattern_index in self.get_start_pattern_indices() {
+            let pattern = self
+                .get_pattern_weight(start_pattern_index)
+                .unwrap()
+                .get_pattern()
+                .clone();
+            walk_steps_map
+                .insert(start_pattern_index, vec![DefiniteExtendStep::try_from(pattern.clone()).unwrap()]);
+            patterns.push((start_pattern_index, pattern));
+        }
+        while !patterns.is_empty() {
+            let mut next_patterns = vec![];
+            for (pattern_index, pattern) in patterns {
+                for approach in self.pattern_out_approaches_iter(pattern_index) {
+                    let target_pattern_index = approach.get_target_pattern_index();
+                    if walk_steps_map.contains_key(&target_pattern_index) {
+                        continue;
+                    }
+                    if let Some(extend_weight) = self.get_extend_weight(approach.get_approach_index()) {
+                        let extend_step = extend_weight.get_extend_step();
+                        let target_vertex = PatternVertex::new(
+                            pattern.get_max_vertex_id() + 1,
+                            extend_step.get_target_vertex_label(),
+                        );
+                        let extend_edges = extend_step
+                            .iter()
+                            .map(|extend_edge| {
+                                DefiniteExtendEdge::from_extend_edge(extend_edge, &pattern).unwrap()
+                            })
+                            .collect();
+                        let mut walk_steps = walk_steps_map[&pattern_index].clone();
+                        walk_steps.push(DefiniteExtendStep::new(target_vertex, extend_edges));
+                        walk_steps_map.insert(target_pattern_index, walk_steps);
+                        next_patterns.push((target_pattern_index, pattern.extend(extend_step).unwrap()));
+                    }
+                }
+            }
+            patterns = next_patterns;
+        }
+        walk_steps_map
+    }
+
+    /// Evaluate the estimation of the catalog by k-fold cross validation.
+    ///
+    /// The start vertices' records are split into k folds. For each fold, pattern counts are
+    /// estimated from the other k - 1 folds, and compared with the counts on the held-out fold,
+    /// both scaled up to the whole graph. Return the average Q-error of each pattern by its code.
+    pub fn cross_validate(
+        &mut self, graph: Arc<LargeGraphDB<DefaultId, InternalId>>, k: usize, rate: f64,
+    ) -> HashMap<Vec<u8>, f64> {
+        if k < 2 {
+            return HashMap::new();
+        }
+        let mut q_error_sums: HashMap<NodeIndex, f64> = HashMap::new();
+        for fold in 0..k {
+            let train_counts = self.get_pattern_counts_in_folds(
+                &graph,
+                rate,
+                |record_index| record_index % k != fold,
+                k as f64 / (k - 1) as f64,
+            );
+            let test_counts = self.get_pattern_counts_in_folds(
+                &graph,
+                rate,
+                |record_index| record_index % k == fold,
+                k as f64,
+            );
+            // Both counts are lifted to at least 1 to keep the Q-error finite for empty folds
+            for (pattern_index, &test_count) in test_counts.iter() {
+                let train_count = train_counts
+                    .get(pattern_index)
+                    .cloned()
+                    .unwrap_or(0.0);
+                *q_error_sums
+                    .entry(*pattern_index)
+                    .or_insert(0.0) += q_error(train_count.max(1.0), test_count.max(1.0));
+            }
+        }
+        q_error_sums
+            .into_iter()
+            .map(|(pattern_index, q_error_sum)| {
+                let pattern_code = self
+                    .get_pattern_weight(pattern_index)
+                    .unwrap()
+                    .get_pattern()
+                    .encode_to();
+                (pattern_code, q_error_sum / k as f64)
+            })
+            .collect()
+    }
+
+    /// Count patterns in the catalog level by level, with the start records filtered by their indices
+    fn get_pattern_counts_in_folds<F: Fn(usize) -> bool>(
+        &mut self, graph: &Arc<LargeGraphDB<DefaultId, InternalId>>, rate: f64, fold_filter: F, scale: f64,
+    ) -> HashMap<NodeIndex, f64> {
+        let mut pattern_counts_map = HashMap::new();
+        let mut pattern_count_infos = HashMap::new();
+        for start_pattern_index in self.get_start_pattern_indices() {
+            let pattern = self
+                .get_pattern_weight(start_pattern_index)
+                .unwrap()
+                .get_pattern()
+                .clone();
+            let (extend_steps, _) = get_definite_extend_steps(pattern.clone(), self);
+            let pattern_records: Vec<PatternRecord> = get_src_records(graph, extend_steps, None)
+                .into_iter()
+                .enumerate()
+                .filter(|(record_index, _)| fold_filter(*record_index))
+                .map(|(_, pattern_record)| pattern_record)
+                .collect();
+            let pattern_count = pattern_records.len();
+            pattern_count_infos.insert(
+                start_pattern_index,
+                Arc::new(PatternCountInfo::new(
+                    pattern,
+                    sample_records(pattern_records, rate, None),
+                    pattern_count,
+                )),
+            );
+        }
+        update_pattern_counts_map(&mut pattern_counts_map, &pattern_count_infos);
+        while !pattern_count_infos.is_empty() {
+            let sub_tasks = self.generate_sub_tasks(pattern_count_infos, graph);
+            pattern_count_infos =
+                self.execcute_sub_tasks(sub_tasks, &EstimateConfig::default().with_rate(rate));
+            update_pattern_counts_map(&mut pattern_counts_map, &pattern_count_infos);
+        }
+        pattern_counts_map
+            .into_iter()
+            .map(|(pattern_index, pattern_count)| (pattern_index, pattern_count as f64 * scale))
+            .collect()
     }
 
     fn get_start_pattern_indices(&self) -> Vec<NodeIndex> {
         self.entries_iter().collect()
     }
 
+    /// Keep the sampled records of the patterns which can be extended further
+    fn update_sampled_records(&mut self, pattern_count_infos: &HashMap<NodeIndex, Arc<PatternCountInfo>>) {
+        for (&pattern_index, pattern_count_info) in pattern_count_infos.iter() {
+            if self
+                .pattern_out_approaches_iter(pattern_index)
+                .next()
+                .is_some()
+            {
+                self.sampled_records
+                    .insert(pattern_index, Arc::clone(pattern_count_info));
+            }
+        }
+    }
+
+    /// Cache the sampled records of the patterns by their codes for the estimations with reuse_records
+    fn update_cached_records(&mut self, pattern_count_infos: &HashMap<NodeIndex, Arc<PatternCountInfo>>) {
+        for (&pattern_index, pattern_count_info) in pattern_count_infos.iter() {
+            let pattern_code = self
+                .get_pattern_weight(pattern_index)
+                .unwrap()
+                .get_pattern()
+                .encode_to();
+            self.cached_records
+                .insert(pattern_code, Arc::clone(pattern_count_info));
+        }
+    }
+
+    /// Remove the sub tasks of the patterns whose records are cached, and return their cached count infos
+    ///
+    /// The records of an end pattern are not kept by its sub task, so its cached count info is not reused
+    /// if the pattern can be extended further now, unless its count is 0.
+    fn take_cached_pattern_count_infos(
+        &self, sub_tasks: &mut HashMap<NodeIndex, SubTask>,
+    ) -> HashMap<NodeIndex, Arc<PatternCountInfo>> {
+        let mut cached_pattern_count_infos = HashMap::new();
+        sub_tasks.retain(|&pattern_index, _| {
+            let pattern_code = self
+                .get_pattern_weight(pattern_index)
+                .unwrap()
+                .get_pattern()
+                .encode_to();
+            let is_end = self
+                .pattern_out_approaches_iter(pattern_index)
+                .next()
+                .is_none();
+            match self.cached_records.get(&pattern_code) {
+                Some(pattern_count_info)
+                    if is_end
+                        || pattern_count_info.pattern_count == 0
+                        || !pattern_count_info.pattern_records.is_empty() =>
+                {
+                    cached_pattern_count_infos.insert(pattern_index, Arc::clone(pattern_count_info));
+                    false
+                }
+                _ => true,
+            }
+        });
+        cached_pattern_count_infos
+    }
+
+    /// Get the sampled records of a pattern, which are sampled (recursively from the source pattern
+    /// with the least count) and kept if not sampled yet
+    ///
+    /// Return None if the pattern cannot be reached by extend steps
+    fn get_or_sample_pattern_count_info(
+        &mut self, pattern_index: NodeIndex, graph: &Arc<LargeGraphDB<DefaultId, InternalId>>,
+        config: &EstimateConfig,
+    ) -> Option<Arc<PatternCountInfo>> {
+        if let Some(pattern_count_info) = self.sampled_records.get(&pattern_index) {
+            return Some(Arc::clone(pattern_count_info));
+        }
+        let pattern = self
+            .get_pattern_weight(pattern_index)?
+            .get_pattern()
+            .clone();
+        let pattern_count_info = if pattern.get_vertices_num() == 1 {
+            let (extend_steps, _) = get_definite_extend_steps(pattern.clone(), self);
+            let vertex_count = count_start_vertices_exact(graph, &extend_steps);
+            let pattern_records = get_src_records(graph, extend_steps, config.limit);
+            let pattern_count = vertex_count.unwrap_or(pattern_records.len());
+            let pattern_records = sample_records_by_strategy(
+                pattern_records,
+                config.rate,
+                config.limit,
+                config.strategy,
+                get_pattern_seed(config.seed, pattern_index),
+            );
+            PatternCountInfo::new(pattern, pattern_records, pattern_count)
+        } else {
+            let extend_approach = self
+                .pattern_in_approaches_iter(pattern_index)
+                .filter(|approach| {
+                    self.get_extend_weight(approach.get_approach_index())
+                        .is_some()
+                })
+                .min_by_key(|approach| {
+                    self.get_pattern_weight(approach.get_src_pattern_index())
+                        .unwrap()
+                        .get_count()
+                })?;
+            let src_pattern_count_info = self.get_or_sample_pattern_count_info(
+                extend_approach.get_src_pattern_index(),
+                graph,
+                config,
+            )?;
+            let extend_step = Arc::new(
+                self.get_extend_weight(extend_approach.get_approach_index())
+                    .unwrap()
+                    .get_extend_step()
+                    .clone(),
+            );
+            let target_vertex_predicate =
+                self.get_target_vertex_predicate(pattern_index, extend_approach.get_approach_index());
+            let sub_task_result = SubTask::new(&src_pattern_count_info, &extend_step, graph)
+                .with_target_vertex_predicate(target_vertex_predicate)
+                .execute(
+                    config.thread_num,
+                    config.rate,
+                    config.limit,
+                    false,
+                    config.strategy,
+                    get_pattern_seed(config.seed, pattern_index),
+                );
+            let target_pattern = src_pattern_count_info
+                .pattern
+                .extend(&extend_step)
+                .unwrap();
+            PatternCountInfo::new(
+                target_pattern,
+                sub_task_result.target_pattern_records,
+                sub_task_result.target_pattern_count,
+            )
+            .with_pattern_count_variance(sub_task_result.target_pattern_count_variance)
+        };
+        let pattern_count_info = Arc::new(pattern_count_info);
+        self.sampled_records
+            .insert(pattern_index, Arc::clone(&pattern_count_info));
+        Some(pattern_count_info)
+    }
+
     fn get_start_pattern_count_infos(
-        &mut self, graph: &LargeGraphDB<DefaultId, InternalId>, rate: f64, limit: Option<usize>,
+        &mut self, graph: &LargeGraphDB<DefaultId, InternalId>, config: &EstimateConfig,
     ) -> HashMap<NodeIndex, Arc<PatternCountInfo>> {
         let mut pattern_nodes = HashMap::new();
         for start_pattern_index in self.get_start_pattern_indices() {
@@ -84,9 +845,22 @@ impl Catalogue {
                 .get_pattern()
                 .clone();
             let (extend_steps, _) = get_definite_extend_steps(pattern.clone(), self);
-            let mut pattern_records = get_src_records(graph, extend_steps, limit);
-            let pattern_count = pattern_records.len();
-            pattern_records = sample_records(pattern_records, rate, limit);
+            let vertex_count = count_start_vertices_exact(graph, &extend_steps);
+            let mut pattern_records = get_src_records(graph, extend_steps, config.limit);
+            let pattern_count = vertex_count.unwrap_or(pattern_records.len());
+            // Start patterns are single vertices, so the rate is stratified by the vertex label
+            let label_rate =
+                get_stratified_sample_rate(pattern_count, config.rate, config.min_seeds_per_label);
+            pattern_records = sample_records_by_strategy(
+                pattern_records,
+                label_rate,
+                config.limit,
+                config.strategy,
+                get_pattern_seed(config.seed, start_pattern_index),
+            );
+            self.get_pattern_weight_mut(start_pattern_index)
+                .unwrap()
+                .set_sample_size(pattern_records.len());
             pattern_nodes.insert(
                 start_pattern_index,
                 Arc::new(PatternCountInfo::new(pattern, pattern_records, pattern_count)),
@@ -95,6 +869,13 @@ impl Catalogue {
         pattern_nodes
     }
 
+    /// Generate a single sub task for each pattern of the next level, which extends the records of its
+    /// predecessor with the smallest count
+    ///
+    /// A pattern reached by multiple approaches is still extended only once, and the patterns counted
+    /// already are skipped by the caller, so the records of a pattern are never computed twice in an
+    /// estimation. The patterns counted by previous estimations are skipped by the caller as well if
+    /// their records are cached.
     fn generate_sub_tasks(
         &self, pattern_count_infos: HashMap<NodeIndex, Arc<PatternCountInfo>>,
         graph: &Arc<LargeGraphDB<DefaultId, InternalId>>,
@@ -114,6 +895,7 @@ impl Catalogue {
             let mut min_count = usize::MAX;
             let mut pre_pattern_count_min = None;
             let mut extend_step = None;
+            let mut target_vertex_predicate = None;
             for approach in self.pattern_in_approaches_iter(next_pattern_index) {
                 if let Some(pre_pattern_count) = pattern_count_infos.get(&approach.get_src_pattern_index())
                 {
@@ -127,19 +909,41 @@ impl Catalogue {
                                 .unwrap()
                                 .get_extend_step()
                                 .clone(),
-                        ))
+                        ));
+                        target_vertex_predicate = self
+                            .get_target_vertex_predicate(next_pattern_index, approach.get_approach_index());
                     }
                 }
             }
             if let Some(count) = pre_pattern_count_min {
-                sub_tasks.insert(next_pattern_index, SubTask::new(&count, &extend_step.unwrap(), graph));
+                sub_tasks.insert(
+                    next_pattern_index,
+                    SubTask::new(&count, &extend_step.unwrap(), graph)
+                        .with_target_vertex_predicate(target_vertex_predicate),
+                );
             }
         }
         sub_tasks
     }
 
+    /// Get the predicate of the vertex that the extend approach extends to in the target pattern
+    fn get_target_vertex_predicate(
+        &self, target_pattern_index: NodeIndex, approach_index: EdgeIndex,
+    ) -> Option<common_pb::Expression> {
+        let target_vertex_rank = self
+            .get_extend_weight(approach_index)?
+            .get_target_vertex_rank();
+        let target_pattern = self
+            .get_pattern_weight(target_pattern_index)?
+            .get_pattern();
+        let target_vertex = target_pattern.get_vertex_from_rank(target_vertex_rank)?;
+        target_pattern
+            .get_vertex_predicate(target_vertex.get_id())
+            .cloned()
+    }
+
     fn execcute_sub_tasks(
-        &self, sub_tasks: HashMap<NodeIndex, SubTask>, thread_num: usize, rate: f64, limit: Option<usize>,
+        &self, sub_tasks: HashMap<NodeIndex, SubTask>, config: &EstimateConfig,
     ) -> HashMap<NodeIndex, Arc<PatternCountInfo>> {
         let mut next_pattern_count_infos = HashMap::new();
         for (target_pattern_index, sub_task) in sub_tasks {
@@ -147,7 +951,14 @@ impl Catalogue {
                 .pattern_out_approaches_iter(target_pattern_index)
                 .next()
                 .is_none();
-            let sub_task_result = sub_task.execute(thread_num, rate, limit, is_end);
+            let sub_task_result = sub_task.execute(
+                config.thread_num,
+                config.rate,
+                config.limit,
+                is_end,
+                config.strategy,
+                get_pattern_seed(config.seed, target_pattern_index),
+            );
             let target_pattern = sub_task
                 .pattern_count_info
                 .pattern
@@ -155,11 +966,14 @@ impl Catalogue {
                 .unwrap();
             next_pattern_count_infos.insert(
                 target_pattern_index,
-                Arc::new(PatternCountInfo::new(
-                    target_pattern,
-                    sub_task_result.target_pattern_records,
-                    sub_task_result.target_pattern_count,
-                )),
+                Arc::new(
+                    PatternCountInfo::new(
+                        target_pattern,
+                        sub_task_result.target_pattern_records,
+                        sub_task_result.target_pattern_count,
+                    )
+                    .with_pattern_count_variance(sub_task_result.target_pattern_count_variance),
+                ),
             );
         }
         next_pattern_count_infos
@@ -173,17 +987,91 @@ impl Catalogue {
             .get_pattern_weight(pattern_index)
             .unwrap()
             .get_pattern();
-        let mut estimate_result = pattern_count as f64;
-        for edge in pattern.edges_iter() {
-            let src = edge.get_start_vertex().get_label();
-            let edge_label = edge.get_label();
-            let dst = edge.get_end_vertex().get_label();
-            let keys = (src as u8, edge_label as u8, dst as u8);
-            if sparsify_rate.contains_key(&keys) {
-                estimate_result /= sparsify_rate[&keys];
+        let estimate_result = pattern_count as f64 / get_sparsify_factor(pattern, sparsify_rate);
+        self.set_pattern_count_with_index(pattern_index, estimate_result.into())
+    }
+
+    fn set_pattern_count_variance_with_rate(
+        &mut self, pattern_index: NodeIndex, pattern_count_variance: f64,
+        sparsify_rate: &HashMap<(u8, u8, u8), f64>,
+    ) {
+        let pattern_weight = self
+            .get_pattern_weight_mut(pattern_index)
+            .unwrap();
+        // The count is scaled by the sparsify factor, so its variance is scaled by the square of it
+        let sparsify_factor = get_sparsify_factor(pattern_weight.get_pattern(), sparsify_rate);
+        pattern_weight.set_count_variance(pattern_count_variance / (sparsify_factor * sparsify_factor));
+    }
+
+    /// Infer the sparsify rate of every edge triple (src label, edge label, dst label) as the ratio of
+    /// the count of its one-edge pattern in the catalog to the number of its edges in the given graph
+    ///
+    /// The catalog is expected to be estimated on the sparsified graph without sparsify rates, and the
+    /// given graph is the one it is sparsified from.
+    pub fn infer_sparsify_rates(
+        &self, graph: &LargeGraphDB<DefaultId, InternalId>,
+    ) -> HashMap<(u8, u8, u8), f64> {
+        let edge_distribution = get_edge_distribution(graph);
+        let mut sparsify_rate = HashMap::new();
+        for pattern_index in self.pattern_indices_iter() {
+            let pattern_weight = self.get_pattern_weight(pattern_index).unwrap();
+            let pattern = pattern_weight.get_pattern();
+            if pattern.get_edges_num() != 1 {
+                continue;
+            }
+            let edge = pattern.edges_iter().next().unwrap();
+            if edge.is_undirected() {
+                continue;
+            }
+            let keys = get_edge_triple(edge);
+            if let Some(&edges_num) = edge_distribution.get(&keys) {
+                if edges_num > 0.0 {
+                    sparsify_rate.insert(keys, pattern_weight.get_count().into_inner() / edges_num);
+                }
             }
         }
-        self.set_pattern_count_with_index(pattern_index, estimate_result.into())
+        sparsify_rate
+    }
+
+    /// Scale the counts of all the patterns in the catalog by the sparsify rates of their edges,
+    /// e.g., by the rates inferred by `infer_sparsify_rates`
+    pub fn apply_sparsify_rates(&mut self, sparsify_rate: &HashMap<(u8, u8, u8), f64>) {
+        let pattern_indices: Vec<NodeIndex> = self.pattern_indices_iter().collect();
+        for pattern_index in pattern_indices {
+            let pattern_weight = self.get_pattern_weight(pattern_index).unwrap();
+            let sparsify_factor = get_sparsify_factor(pattern_weight.get_pattern(), sparsify_rate);
+            let pattern_count = pattern_weight.get_count().into_inner() / sparsify_factor;
+            self.set_pattern_count_with_index(pattern_index, pattern_count.into());
+        }
+    }
+}
+
+fn get_edge_triple(edge: &PatternEdge) -> (u8, u8, u8) {
+    let src = edge.get_start_vertex().get_label();
+    let edge_label = edge.get_label();
+    let dst = edge.get_end_vertex().get_label();
+    (src as u8, edge_label as u8, dst as u8)
+}
+
+/// The product of the sparsify rates of all the edges in the pattern
+fn get_sparsify_factor(pattern: &Pattern, sparsify_rate: &HashMap<(u8, u8, u8), f64>) -> f64 {
+    pattern
+        .edges_iter()
+        .filter_map(|edge| sparsify_rate.get(&get_edge_triple(edge)))
+        .product()
+}
+
+/// Q-error of an estimation, i.e., max(estimate / actual, actual / estimate).
+///
+/// An exact estimation of an empty result has Q-error 1, while any other estimation of an empty result,
+/// or an empty estimation of a nonempty result, has infinite Q-error
+pub fn q_error(estimate: f64, actual: f64) -> f64 {
+    if estimate == actual {
+        1.0
+    } else if estimate <= 0.0 || actual <= 0.0 {
+        f64::INFINITY
+    } else {
+        (estimate / actual).max(actual / estimate)
     }
 }
 
@@ -196,6 +1084,15 @@ fn update_pattern_counts_map(
     }
 }
 
+fn update_pattern_count_variances(
+    pattern_count_variances: &mut HashMap<NodeIndex, f64>,
+    pattern_count_infos: &HashMap<NodeIndex, Arc<PatternCountInfo>>,
+) {
+    for (&pattern_index, pattern_count_info) in pattern_count_infos.iter() {
+        pattern_count_variances.insert(pattern_index, pattern_count_info.pattern_count_variance);
+    }
+}
+
 impl TableLogue {
     pub fn estimate_graph(
         &mut self, graph: Arc<LargeGraphDB<DefaultId, InternalId>>, rate: f64, limit: Option<usize>,
@@ -229,7 +1126,8 @@ impl TableLogue {
                 .unwrap();
             let extend_step = Arc::new(row.get_extend_step().clone());
             let sub_task = SubTask::new(src_pattern_count_infos, &extend_step, &graph);
-            let sub_task_result = sub_task.execute(thread_num, rate, limit, false);
+            let sub_task_result =
+                sub_task.execute(thread_num, rate, limit, false, SamplingStrategy::Stride, None);
             let target_pattern = src_pattern.extend(&extend_step).unwrap();
             let target_pattern_code = target_pattern.encode_to();
             if !pattern_count_infos.contains_key(&target_pattern_code)
@@ -249,18 +1147,177 @@ impl TableLogue {
     }
 }
 
+/// The time cost, the peak number of records and the number of executed sub tasks of a level of
+/// patterns counted in an estimation
+#[derive(Debug, Clone)]
+pub struct LevelReport {
+    level: usize,
+    patterns_num: usize,
+    elapsed: Duration,
+    max_records_num: usize,
+    sub_tasks_num: usize,
+}
+
+impl LevelReport {
+    pub fn get_level(&self) -> usize {
+        self.level
+    }
+
+    pub fn get_patterns_num(&self) -> usize {
+        self.patterns_num
+    }
+
+    pub fn get_elapsed(&self) -> Duration {
+        self.elapsed
+    }
+
+    /// The largest number of records sampled for a pattern in the level
+    pub fn get_max_records_num(&self) -> usize {
+        self.max_records_num
+    }
+
+    /// The number of sub tasks executed to extend the records of the level, where the start patterns and
+    /// the patterns whose records are reused need none
+    pub fn get_sub_tasks_num(&self) -> usize {
+        self.sub_tasks_num
+    }
+}
+
+/// The instrumentation of `estimate_graph`, with a report for each level of patterns counted
+///
+/// When resumed from a checkpoint, the levels before the resumed one are not reported.
+#[derive(Debug, Clone, Default)]
+pub struct EstimationReport {
+    level_reports: Vec<LevelReport>,
+}
+
+impl EstimationReport {
+    pub fn get_level_reports(&self) -> &[LevelReport] {
+        &self.level_reports
+    }
+
+    pub fn get_total_elapsed(&self) -> Duration {
+        self.level_reports
+            .iter()
+            .map(|level_report| level_report.elapsed)
+            .sum()
+    }
+
+    pub fn get_total_sub_tasks_num(&self) -> usize {
+        self.level_reports
+            .iter()
+            .map(|level_report| level_report.sub_tasks_num)
+            .sum()
+    }
+
+    fn add_level(
+        &mut self, level: usize, elapsed: Duration, sub_tasks_num: usize,
+        pattern_count_infos: &HashMap<NodeIndex, Arc<PatternCountInfo>>,
+    ) {
+        let max_records_num = pattern_count_infos
+            .values()
+            .map(|pattern_count_info| pattern_count_info.pattern_records.len())
+            .max()
+            .unwrap_or(0);
+        self.level_reports.push(LevelReport {
+            level,
+            patterns_num: pattern_count_infos.len(),
+            elapsed,
+            max_records_num,
+            sub_tasks_num,
+        });
+    }
+}
+
+/// The progress of `estimate_graph_with_checkpoint` after a level of patterns is counted
+#[derive(Serialize, Deserialize)]
+struct EstimationCheckpoint {
+    level: usize,
+    /// The counts of all the counted patterns, keyed by their codes
+    pattern_counts: Vec<(Vec<u8>, usize)>,
+    /// The codes, counts and records of the patterns in the last level, where the records are keyed by
+    /// the vertex ranks, since the vertex ids of a pattern are not kept in its code
+    frontier: Vec<(Vec<u8>, usize, Vec<PatternRecord>)>,
+}
+
+impl EstimationCheckpoint {
+    fn save<P: AsRef<Path>>(&self, path: P) -> BincodeResult<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        serialize_into(&mut writer, self)
+    }
+
+    fn load<P: AsRef<Path>>(path: P) -> BincodeResult<Self> {
+        let mut reader = BufReader::new(File::open(path)?);
+        deserialize_from(&mut reader)
+    }
+
+    /// Restore the counts of the patterns, the count infos of the last level in the catalog, and the level
+    fn restore(
+        &self, catalog: &Catalogue,
+    ) -> BincodeResult<(HashMap<NodeIndex, usize>, HashMap<NodeIndex, Arc<PatternCountInfo>>, usize)> {
+        info!("resume estimation from level {}", self.level);
+        let get_pattern_index = |pattern_code: &Vec<u8>| {
+            catalog
+                .get_pattern_index(pattern_code)
+                .ok_or_else(|| {
+                    Box::new(bincode::ErrorKind::Custom(
+                        "the checkpoint has a pattern not in the catalog".to_string(),
+                    ))
+                })
+        };
+        let mut pattern_counts_map = HashMap::new();
+        for (pattern_code, pattern_count) in self.pattern_counts.iter() {
+            pattern_counts_map.insert(get_pattern_index(pattern_code)?, *pattern_count);
+        }
+        let mut pattern_count_infos = HashMap::new();
+        for (pattern_code, pattern_count, ranked_records) in self.frontier.iter() {
+            let pattern_index = get_pattern_index(pattern_code)?;
+            let pattern = Pattern::decode_from(pattern_code).unwrap();
+            let pattern_records = ranked_records
+                .iter()
+                .map(|ranked_record| {
+                    ranked_record
+                        .iter()
+                        .map(|(&v_rank, &graph_vertex_id)| {
+                            (
+                                pattern
+                                    .get_vertex_from_rank(v_rank)
+                                    .unwrap()
+                                    .get_id(),
+                                graph_vertex_id,
+                            )
+                        })
+                        .collect()
+                })
+                .collect();
+            pattern_count_infos.insert(
+                pattern_index,
+                Arc::new(PatternCountInfo::new(pattern, pattern_records, *pattern_count)),
+            );
+        }
+        Ok((pattern_counts_map, pattern_count_infos, self.level))
+    }
+}
+
 #[derive(Debug, Clone)]
-struct PatternCountInfo {
+pub(crate) struct PatternCountInfo {
     pattern: Pattern,
     pattern_records: Vec<PatternRecord>,
     pattern_count: usize,
+    /// The variance of the estimated pattern count, which is 0 for an exact count
+    pattern_count_variance: f64,
 }
 
 impl PatternCountInfo {
     fn new(
         pattern: Pattern, pattern_records: Vec<PatternRecord>, pattern_count: usize,
     ) -> PatternCountInfo {
-        PatternCountInfo { pattern, pattern_records, pattern_count }
+        PatternCountInfo { pattern, pattern_records, pattern_count, pattern_count_variance: 0.0 }
+    }
+
+    fn with_pattern_count_variance(mut self, pattern_count_variance: f64) -> PatternCountInfo {
+        self.pattern_count_variance = pattern_count_variance;
+        self
     }
 }
 
@@ -269,6 +1326,8 @@ struct SubTask {
     pattern_count_info: Arc<PatternCountInfo>,
     extend_step: Arc<ExtendStep>,
     graph: Arc<LargeGraphDB<DefaultId, InternalId>>,
+    /// The predicate that the extended target vertices must satisfy
+    target_vertex_predicate: Option<common_pb::Expression>,
 }
 
 impl SubTask {
@@ -280,9 +1339,17 @@ impl SubTask {
             pattern_count_info: Arc::clone(pattern_count_info),
             extend_step: Arc::clone(extend_step),
             graph: Arc::clone(graph),
+            target_vertex_predicate: None,
         }
     }
 
+    fn with_target_vertex_predicate(
+        mut self, target_vertex_predicate: Option<common_pb::Expression>,
+    ) -> SubTask {
+        self.target_vertex_predicate = target_vertex_predicate;
+        self
+    }
+
     fn get_pattern(&self) -> &Pattern {
         &self.pattern_count_info.pattern
     }
@@ -294,124 +1361,598 @@ impl SubTask {
     fn get_pattern_count(&self) -> usize {
         self.pattern_count_info.pattern_count
     }
+
+    fn get_pattern_count_variance(&self) -> f64 {
+        self.pattern_count_info.pattern_count_variance
+    }
 }
 
 impl SubTask {
-    fn execute(&self, thread_num: usize, rate: f64, limit: Option<usize>, is_end: bool) -> SubTaskResult {
+    /// Extend the records of the pattern by the extend step with the given threads
+    ///
+    /// If a limit is given and the target pattern can be extended further, the threads take the records
+    /// of the pattern one by one in a shuffled order, and stop once the limit of target records is
+    /// produced. The records expanded in the shuffled order until the limit is reached are a uniform
+    /// sample of all the records, so the target count is scaled by the fraction of the records expanded.
+    ///
+    /// The variance of the target count is estimated from the numbers of target records expanded from
+    /// each record, which is 0 if all the records of the pattern are expanded and its count is exact.
+    fn execute(
+        &self, thread_num: usize, rate: f64, limit: Option<usize>, is_end: bool,
+        strategy: SamplingStrategy, seed: Option<u64>,
+    ) -> SubTaskResult {
         debug!("execute subtask: {}", self.get_pattern());
-        let mut target_pattern_count = 0;
-        let mut target_pattern_records = Vec::new();
-        let (tx_record_count, rx_record_count) = mpsc::channel();
-        let (tx_records, rx_records) = mpsc::channel();
+        let expand_limit = if is_end { None } else { limit };
+        let mut records_order: Vec<usize> = (0..self.get_pattern_records().len()).collect();
+        if expand_limit.is_some() {
+            let mut rng = seed.map_or_else(StdRng::from_entropy, StdRng::seed_from_u64);
+            records_order.shuffle(&mut rng);
+        }
+        let records_cursor = Arc::new(RecordsCursor::new(records_order, expand_limit));
+        let (tx_expanded_records, rx_expanded_records) = mpsc::channel();
         let mut thread_handles = Vec::with_capacity(thread_num);
-        for thread_id in 0..thread_num {
+        for _ in 0..thread_num {
             let thread_sub_task = self.clone();
             let thread_handle = thread_sub_task.execute_internal(
-                thread_id,
-                thread_num,
-                tx_record_count.clone(),
-                tx_records.clone(),
+                tx_expanded_records.clone(),
                 is_end,
+                Arc::clone(&records_cursor),
             );
             thread_handles.push(thread_handle);
         }
         for thread_handle in thread_handles {
             thread_handle.join().unwrap();
         }
-        while let Ok(partial_count) = rx_record_count.try_recv() {
-            target_pattern_count += partial_count;
-        }
-        while let Ok(target_pattern_record) = rx_records.try_recv() {
-            target_pattern_records.push(target_pattern_record);
+        let mut expanded_records: Vec<ExpandedRecord> = rx_expanded_records
+            .try_iter()
+            .flatten()
+            .collect();
+        expanded_records.sort_by_key(|expanded_record| expanded_record.position);
+        // Keep the records expanded until the limit is reached in the shuffled order, and drop the ones
+        // expanded by the other threads meanwhile, so that the seeded sample is reproducible
+        if let Some(limit) = expand_limit {
+            let mut produced_records_num = 0;
+            if let Some(last_index) = expanded_records
+                .iter()
+                .position(|expanded_record| {
+                    produced_records_num += expanded_record.target_records_num;
+                    produced_records_num >= limit
+                })
+            {
+                expanded_records.truncate(last_index + 1);
+            }
         }
-        let target_pattern_count = if self.get_pattern_records().is_empty() {
+        let expanded_records_num = expanded_records.len();
+        let target_pattern_count: usize = expanded_records
+            .iter()
+            .map(|expanded_record| expanded_record.target_records_num)
+            .sum();
+        let target_pattern_count_square_sum: usize = expanded_records
+            .iter()
+            .map(|expanded_record| expanded_record.target_records_num.pow(2))
+            .sum();
+        let target_pattern_records: Vec<PatternRecord> = expanded_records
+            .into_iter()
+            .flat_map(|expanded_record| expanded_record.target_records)
+            .collect();
+        let target_pattern_count_variance = get_extended_count_variance(
+            self.get_pattern_count(),
+            self.get_pattern_count_variance(),
+            expanded_records_num,
+            target_pattern_count,
+            target_pattern_count_square_sum,
+        );
+        let target_pattern_count = if expanded_records_num == 0 {
             0
         } else {
-            (self.get_pattern_count() as f64
-                * (target_pattern_count as f64 / self.get_pattern_records().len() as f64))
+            (self.get_pattern_count() as f64 * (target_pattern_count as f64 / expanded_records_num as f64))
                 as usize
         };
-        SubTaskResult::new(sample_records(target_pattern_records, rate, limit), target_pattern_count)
+        SubTaskResult::new(
+            sample_records_by_strategy(target_pattern_records, rate, limit, strategy, seed),
+            target_pattern_count,
+        )
+        .with_target_pattern_count_variance(target_pattern_count_variance)
+        .with_expanded_records_num(expanded_records_num)
     }
 
     fn execute_internal(
-        self, thread_id: usize, thread_num: usize, tx_record_count: Sender<usize>,
-        tx_records: Sender<PatternRecord>, is_end: bool,
+        self, tx_expanded_records: Sender<Vec<ExpandedRecord>>, is_end: bool,
+        records_cursor: Arc<RecordsCursor>,
     ) -> JoinHandle<()> {
         thread::spawn(move || {
             let target_vertex_id = self.get_pattern().get_max_vertex_id() + 1;
-            let mut target_pattern_partial_count = 0;
-            for pattern_record in split_vector(self.get_pattern_records(), thread_num, thread_id) {
+            let target_vertex_predicate = compile_predicate(self.target_vertex_predicate.as_ref());
+            let extend_edges: Vec<DefiniteExtendEdge> = self
+                .extend_step
+                .iter()
+                .map(|extend_edge| {
+                    DefiniteExtendEdge::from_extend_edge(extend_edge, self.get_pattern()).unwrap()
+                })
+                .collect();
+            let edge_predicates: Vec<Option<PropertyPredicate>> = extend_edges
+                .iter()
+                .map(|extend_edge| compile_predicate(extend_edge.get_predicate()))
+                .collect();
+            let mut expanded_records = Vec::new();
+            while let Some((position, record_index)) = records_cursor.take_record() {
+                let pattern_record = &self.get_pattern_records()[record_index];
                 let mut intersect_vertices_set = BTreeSet::new();
-                for (i, extend_edge) in self.extend_step.iter().enumerate() {
+                for (i, (extend_edge, edge_predicate)) in extend_edges
+                    .iter()
+                    .zip(edge_predicates.iter())
+                    .enumerate()
+                {
                     let adj_vertices_set = get_adj_vertices_set(
                         &self.graph,
                         pattern_record,
-                        &DefiniteExtendEdge::from_extend_edge(extend_edge, self.get_pattern()).unwrap(),
+                        extend_edge,
+                        edge_predicate.as_ref(),
                         self.extend_step.get_target_vertex_label(),
                     );
                     intersect_vertices_set =
                         intersect_sets(intersect_vertices_set, adj_vertices_set, i == 0);
                 }
-                for target_pattern_record in intersect_vertices_set
-                    .iter()
-                    .map(|&adj_vertex_id| {
+                if let Some(predicate) = &target_vertex_predicate {
+                    intersect_vertices_set.retain(|&adj_vertex_id| {
+                        eval_vertex_predicate(predicate, &self.graph, adj_vertex_id)
+                    });
+                }
+                let mut target_records = Vec::new();
+                if !is_end {
+                    for &adj_vertex_id in intersect_vertices_set.iter() {
                         let mut target_pattern_record = pattern_record.clone();
                         target_pattern_record.insert(target_vertex_id, adj_vertex_id);
-                        target_pattern_record
-                    })
-                {
-                    if !is_end {
-                        tx_records.send(target_pattern_record).unwrap();
+                        target_records.push(target_pattern_record);
                     }
+                    records_cursor.add_produced_records(target_records.len());
                 }
-                target_pattern_partial_count += intersect_vertices_set.len();
+                expanded_records.push(ExpandedRecord {
+                    position,
+                    target_records_num: intersect_vertices_set.len(),
+                    target_records,
+                });
             }
-            tx_record_count
-                .send(target_pattern_partial_count)
+            tx_expanded_records
+                .send(expanded_records)
                 .unwrap();
         })
     }
 }
 
+/// The records of a pattern shared by the threads of a subtask, which are taken one by one in the
+/// given order until all are taken, or the limit of target records is produced
+struct RecordsCursor {
+    records_order: Vec<usize>,
+    next_position: AtomicUsize,
+    produced_records_num: AtomicUsize,
+    limit: Option<usize>,
+}
+
+impl RecordsCursor {
+    fn new(records_order: Vec<usize>, limit: Option<usize>) -> RecordsCursor {
+        RecordsCursor {
+            records_order,
+            next_position: AtomicUsize::new(0),
+            produced_records_num: AtomicUsize::new(0),
+            limit,
+        }
+    }
+
+    /// Take the next record, as its position in the order and its index in the records
+    fn take_record(&self) -> Option<(usize, usize)> {
+        if let Some(limit) = self.limit {
+            if self
+                .produced_records_num
+                .load(AtomicOrdering::SeqCst)
+                >= limit
+            {
+                return None;
+            }
+        }
+        let position = self
+            .next_position
+            .fetch_add(1, AtomicOrdering::SeqCst);
+        self.records_order
+            .get(position)
+            .map(|&record_index| (position, record_index))
+    }
+
+    fn add_produced_records(&self, records_num: usize) {
+        self.produced_records_num
+            .fetch_add(records_num, AtomicOrdering::SeqCst);
+    }
+}
+
+/// A record of a pattern expanded by a subtask, with the target records expanded from it
+struct ExpandedRecord {
+    position: usize,
+    target_records_num: usize,
+    target_records: Vec<PatternRecord>,
+}
+
 struct SubTaskResult {
     target_pattern_records: Vec<PatternRecord>,
     target_pattern_count: usize,
+    target_pattern_count_variance: f64,
+    expanded_records_num: usize,
 }
 
 impl SubTaskResult {
     fn new(target_pattern_records: Vec<PatternRecord>, target_pattern_count: usize) -> SubTaskResult {
-        SubTaskResult { target_pattern_records, target_pattern_count }
+        SubTaskResult {
+            target_pattern_records,
+            target_pattern_count,
+            target_pattern_count_variance: 0.0,
+            expanded_records_num: 0,
+        }
+    }
+
+    fn with_target_pattern_count_variance(mut self, target_pattern_count_variance: f64) -> SubTaskResult {
+        self.target_pattern_count_variance = target_pattern_count_variance;
+        self
+    }
+
+    fn with_expanded_records_num(mut self, expanded_records_num: usize) -> SubTaskResult {
+        self.expanded_records_num = expanded_records_num;
+        self
+    }
+}
+
+/// Estimate the variance of the count of a pattern extended from the sampled records of another one
+///
+/// The extended count is the source count times the mean number of target records extended from
+/// each expanded record. Its variance consists of
+/// - the sampling variance of the mean, with the finite population correction, so that it is 0 when
+///   all the source records are expanded, and
+/// - the variance of the source count, scaled by the square of the mean.
+fn get_extended_count_variance(
+    src_count: usize, src_count_variance: f64, expanded_records_num: usize, target_count_sum: usize,
+    target_count_square_sum: usize,
+) -> f64 {
+    if expanded_records_num == 0 {
+        return 0.0;
     }
+    let n = expanded_records_num as f64;
+    let mean = target_count_sum as f64 / n;
+    let sample_variance = if expanded_records_num > 1 {
+        ((target_count_square_sum as f64 - n * mean * mean) / (n - 1.0)).max(0.0)
+    } else {
+        0.0
+    };
+    let src_count = src_count as f64;
+    let finite_population_correction = if src_count > n { 1.0 - n / src_count } else { 0.0 };
+    src_count * src_count * sample_variance / n * finite_population_correction
+        + mean * mean * src_count_variance
 }
 
 pub fn get_src_records(
     graph: &LargeGraphDB<DefaultId, InternalId>, extend_steps: Vec<DefiniteExtendStep>,
     limit: Option<usize>,
 ) -> Vec<PatternRecord> {
+    get_src_records_iter(graph, extend_steps, limit).collect()
+}
+
+/// Count the matches of the pattern in the graph exactly, without any sampling
+///
+/// The pattern is matched along the extend steps picked by a catalogue built from the pattern itself,
+/// and the records are counted as they are generated, but it is still only feasible for small graphs
+pub fn count_pattern_exact(graph: &LargeGraphDB<DefaultId, InternalId>, pattern: &Pattern) -> usize {
+    let mut catalog = Catalogue::build_from_pattern(pattern, PatMatPlanSpace::ExtendWithIntersection);
+    let (extend_steps, _) = get_definite_extend_steps(pattern.clone(), &mut catalog);
+    get_src_records_iter(graph, extend_steps, None).count()
+}
+
+/// Estimate the count of a triangle pattern by sampling its seed edges with the given rate
+///
+/// The seed edges matching the first edge of the triangle are sampled by stride as they are scanned,
+/// and each sampled seed edge is closed by intersecting the adjacent vertices of its two ends, so
+/// neither the seed edges nor the two-hop paths are materialized. The labels, directions and
+/// predicates of the edges are respected as in `count_pattern_exact`.
+///
+/// Return None if the pattern is not a triangle, i.e., it does not have exactly 3 vertices and 3 edges
+/// forming a cycle
+pub fn count_triangles(
+    graph: &LargeGraphDB<DefaultId, InternalId>, pattern: &Pattern, rate: f64,
+) -> Option<f64> {
+    if pattern.get_vertices_num() != 3 || !pattern.is_single_cycle() {
+        return None;
+    }
+    let seed_edge = pattern.edges_iter().next()?;
+    let seed_src_vertex = seed_edge.get_start_vertex();
+    let seed_dst_vertex = seed_edge.get_end_vertex();
+    let closing_vertex = *pattern.vertices_iter().find(|vertex| {
+        vertex.get_id() != seed_src_vertex.get_id() && vertex.get_id() != seed_dst_vertex.get_id()
+    })?;
+    let src_extend_step = DefiniteExtendStep::new(seed_src_vertex, vec![]).with_target_vertex_predicate(
+        pattern
+            .get_vertex_predicate(seed_src_vertex.get_id())
+            .cloned(),
+    );
+    // Only the seed edge is kept to extend the dst vertex, the other one closes the triangle
+    let dst_extend_step =
+        DefiniteExtendStep::from_target_pattern(pattern, seed_dst_vertex.get_id()).map(|extend_step| {
+            DefiniteExtendStep::new(
+                seed_dst_vertex,
+                extend_step
+                    .iter()
+                    .filter(|extend_edge| extend_edge.get_edge_id() == seed_edge.get_id())
+                    .cloned()
+                    .collect(),
+            )
+            .with_target_vertex_predicate(
+                extend_step
+                    .get_target_vertex_predicate()
+                    .cloned(),
+            )
+        })?;
+    let closing_extend_step = DefiniteExtendStep::from_target_pattern(pattern, closing_vertex.get_id())?;
+    let closing_vertex_predicate = compile_predicate(closing_extend_step.get_target_vertex_predicate());
+    let closing_edge_predicates = compile_edge_predicates(&closing_extend_step);
+    let count_closed = |seed_record: &PatternRecord| {
+        let mut intersect_vertices = BTreeSet::new();
+        for (i, (extend_edge, edge_predicate)) in closing_extend_step
+            .iter()
+            .zip(closing_edge_predicates.iter())
+            .enumerate()
+        {
+            let adjacent_vertices = get_adj_vertices_set(
+                graph,
+                seed_record,
+                extend_edge,
+                edge_predicate.as_ref(),
+                closing_vertex.get_label(),
+            );
+            intersect_vertices = intersect_sets(intersect_vertices, adjacent_vertices, i == 0);
+        }
+        match &closing_vertex_predicate {
+            Some(predicate) => intersect_vertices
+                .into_iter()
+                .filter(|&adj_vertex_id| eval_vertex_predicate(predicate, graph, adj_vertex_id))
+                .count(),
+            None => intersect_vertices.len(),
+        }
+    };
+    // The seeds are sampled by stride as they stream in, so that they are never collected at once
+    let stride = (1.0 / rate).floor().max(1.0) as usize;
+    let mut seed_records_num = 0;
+    let mut sampled_seed_records_num = 0;
+    let mut closed_count = 0;
+    for seed_record in get_src_records_iter(graph, vec![src_extend_step, dst_extend_step], None) {
+        if seed_records_num % stride == 0 {
+            sampled_seed_records_num += 1;
+            closed_count += count_closed(&seed_record);
+        }
+        seed_records_num += 1;
+    }
+    if sampled_seed_records_num == 0 {
+        return Some(0.0);
+    }
+    Some(closed_count as f64 * seed_records_num as f64 / sampled_seed_records_num as f64)
+}
+
+/// The statistics of a graph to bound the counts of patterns without sampling
+///
+/// Like `ExpStore::collect_degree_stats`, but the maximum degrees are kept instead of the average ones
+#[derive(Debug, Clone, Default)]
+pub struct GraphDegreeStats {
+    /// Key: vertex label, Value: the number of vertices with the label
+    vertex_counts: HashMap<PatternLabelId, usize>,
+    /// Key: (vertex label, edge label, direction), Value: the maximum number of edges with the label and
+    /// direction adjacent to a vertex with the label
+    max_degrees: HashMap<(PatternLabelId, PatternLabelId, PatternDirection), usize>,
+}
+
+impl GraphDegreeStats {
+    /// Collect the statistics by a single scan of the vertices and their adjacent edges
+    pub fn from_graph(graph: &LargeGraphDB<DefaultId, InternalId>) -> Self {
+        let mut graph_stats = GraphDegreeStats::default();
+        for vertex in graph.get_all_vertices(None) {
+            let vertex_label = vertex.get_label()[0] as PatternLabelId;
+            *graph_stats
+                .vertex_counts
+                .entry(vertex_label)
+                .or_default() += 1;
+            let mut degrees: HashMap<(PatternLabelId, PatternDirection), usize> = HashMap::new();
+            for edge in graph.get_out_edges(vertex.get_id(), None) {
+                *degrees
+                    .entry((edge.get_label() as PatternLabelId, PatternDirection::Out))
+                    .or_default() += 1;
+            }
+            for edge in graph.get_in_edges(vertex.get_id(), None) {
+                *degrees
+                    .entry((edge.get_label() as PatternLabelId, PatternDirection::In))
+                    .or_default() += 1;
+            }
+            for ((edge_label, direction), degree) in degrees {
+                let max_degree = graph_stats
+                    .max_degrees
+                    .entry((vertex_label, edge_label, direction))
+                    .or_default();
+                *max_degree = (*max_degree).max(degree);
+            }
+        }
+        graph_stats
+    }
+
+    pub fn get_vertex_count(&self, vertex_label: PatternLabelId) -> usize {
+        self.vertex_counts
+            .get(&vertex_label)
+            .cloned()
+            .unwrap_or(0)
+    }
+
+    /// Get the maximum degree of the vertices with the label, along the edge label and direction,
+    /// which is 0 if there are no such edges
+    pub fn get_max_degree(
+        &self, vertex_label: PatternLabelId, edge_label: PatternLabelId, direction: PatternDirection,
+    ) -> usize {
+        self.max_degrees
+            .get(&(vertex_label, edge_label, direction))
+            .cloned()
+            .unwrap_or(0)
+    }
+}
+
+/// The maximum number of vertices of a pattern to be bounded by `estimate_pattern_count_bound`, whose
+/// dynamic programming goes over all the `2^n` subsets of vertices
+pub const MAX_COUNT_BOUND_VERTICES_NUM: usize = 20;
+
+/// Bound the count of the pattern from above by the degree statistics of the graph, without sampling
+///
+/// The vertices of the pattern are matched one after another. The first vertex has as many candidates
+/// as the vertices with its label, and each later vertex has at most as many candidates as the smallest
+/// maximum degree of its edges to the matched vertices. The bound is the minimum of the products
+/// over all the matching orders, which is found by a dynamic programming over the subsets of vertices
+/// (and thus only feasible for small patterns). Return error if the pattern has more than
+/// `MAX_COUNT_BOUND_VERTICES_NUM` vertices.
+///
+/// Vertex and edge predicates are ignored, and so are path edges, which still keeps the bound valid.
+pub fn estimate_pattern_count_bound(pattern: &Pattern, graph_stats: &GraphDegreeStats) -> IrResult<f64> {
+    let vertex_ids: Vec<PatternId> = pattern
+        .vertices_iter()
+        .map(|vertex| vertex.get_id())
+        .collect();
+    if vertex_ids.is_empty() {
+        return Ok(0.0);
+    } else if vertex_ids.len() > MAX_COUNT_BOUND_VERTICES_NUM {
+        return Err(IrError::Unsupported(format!(
+            "count bound of pattern with {} vertices, which is more than {}",
+            vertex_ids.len(),
+            MAX_COUNT_BOUND_VERTICES_NUM
+        )));
+    }
+    let vertex_indices: HashMap<PatternId, usize> = vertex_ids
+        .iter()
+        .enumerate()
+        .map(|(index, &vertex_id)| (vertex_id, index))
+        .collect();
+    // bounds[matched] is the bound of the count of the subpattern induced by the matched vertices
+    let mut bounds = vec![f64::INFINITY; 1 << vertex_ids.len()];
+    bounds[0] = 1.0;
+    for matched in 1..bounds.len() {
+        for (index, &vertex_id) in vertex_ids.iter().enumerate() {
+            if matched & (1 << index) == 0 {
+                continue;
+            }
+            let previous_matched = matched & !(1 << index);
+            let candidates_num =
+                get_candidates_num_bound(pattern, graph_stats, vertex_id, |adj_vertex_id| {
+                    previous_matched & (1 << vertex_indices[&adj_vertex_id]) != 0
+                });
+            bounds[matched] = bounds[matched].min(bounds[previous_matched] * candidates_num);
+        }
+    }
+    Ok(bounds[bounds.len() - 1])
+}
+
+/// Bound the number of candidates of the vertex, given the vertices which have been matched
+fn get_candidates_num_bound<F: Fn(PatternId) -> bool>(
+    pattern: &Pattern, graph_stats: &GraphDegreeStats, vertex_id: PatternId, is_matched: F,
+) -> f64 {
+    let vertex_label = pattern
+        .get_vertex(vertex_id)
+        .unwrap()
+        .get_label();
+    let mut candidates_num = graph_stats.get_vertex_count(vertex_label);
+    for adjacency in pattern.adjacencies_iter(vertex_id) {
+        let adj_vertex = adjacency.get_adj_vertex();
+        let edge = pattern
+            .get_edge(adjacency.get_edge_id())
+            .unwrap();
+        if adj_vertex.get_id() == vertex_id || edge.is_path() || !is_matched(adj_vertex.get_id()) {
+            continue;
+        }
+        // The degree is seen from the matched vertex, so the direction is reversed
+        let degree = if adjacency.is_bidirectional() {
+            graph_stats.get_max_degree(adj_vertex.get_label(), edge.get_label(), PatternDirection::Out)
+                + graph_stats.get_max_degree(adj_vertex.get_label(), edge.get_label(), PatternDirection::In)
+        } else {
+            graph_stats.get_max_degree(
+                adj_vertex.get_label(),
+                edge.get_label(),
+                adjacency.get_direction().reverse(),
+            )
+        };
+        candidates_num = candidates_num.min(degree);
+    }
+    candidates_num as f64
+}
+
+/// Count the vertices of a single-vertex pattern (given by its extend steps) by the label index of the
+/// graph, which is exact and needs no scan of the vertices
+///
+/// Return None if the pattern is not a single vertex, or its vertex has a predicate to be evaluated
+fn count_start_vertices_exact(
+    graph: &LargeGraphDB<DefaultId, InternalId>, extend_steps: &[DefiniteExtendStep],
+) -> Option<usize> {
+    if let [extend_step] = extend_steps {
+        if extend_step
+            .get_target_vertex_predicate()
+            .is_none()
+        {
+            let vertex_label = extend_step.get_target_vertex().get_label();
+            return Some(graph.count_all_vertices(Some(&vec![vertex_label as LabelId])));
+        }
+    }
+    None
+}
+
+fn get_src_records_iter(
+    graph: &LargeGraphDB<DefaultId, InternalId>, extend_steps: Vec<DefiniteExtendStep>,
+    limit: Option<usize>,
+) -> DynIter<PatternRecord> {
     let mut extend_steps = extend_steps.into_iter();
     let first_extend_step = extend_steps.next().unwrap();
     let src_vertex = first_extend_step.get_target_vertex();
     let src_vertex_label = src_vertex.get_label();
     let src_pattern_vertex_id = src_vertex.get_id();
+    let src_vertex_predicate = compile_predicate(first_extend_step.get_target_vertex_predicate());
     let mut pattern_records: DynIter<PatternRecord> = Box::new(
         graph
             .get_all_vertices(Some(&vec![src_vertex_label as LabelId]))
+            .filter(move |graph_vertex| {
+                src_vertex_predicate
+                    .as_ref()
+                    .map(|predicate| {
+                        predicate.eval(|key| {
+                            graph_vertex
+                                .get_property(key)
+                                .and_then(|value| value.try_to_owned())
+                        })
+                    })
+                    .unwrap_or(true)
+            })
             .map(|graph_vertex| PatternRecord::from_iter([(src_pattern_vertex_id, graph_vertex.get_id())])),
     );
     for extend_step in extend_steps {
         if let Some(upper_bound) = limit {
             pattern_records = Box::new(pattern_records.take(upper_bound));
         }
+        let target_vertex_predicate = compile_predicate(extend_step.get_target_vertex_predicate());
+        let edge_predicates = compile_edge_predicates(&extend_step);
         pattern_records = Box::new(pattern_records.flat_map(move |pattern_record| {
             let target_vertex = extend_step.get_target_vertex();
             let target_vertex_label = target_vertex.get_label();
             let mut intersect_vertices = BTreeSet::new();
-            for (i, extend_edge) in extend_step.iter().enumerate() {
-                let adjacent_vertices =
-                    get_adj_vertices_set(graph, &pattern_record, extend_edge, target_vertex_label);
+            for (i, (extend_edge, edge_predicate)) in extend_step
+                .iter()
+                .zip(edge_predicates.iter())
+                .enumerate()
+            {
+                let adjacent_vertices = get_adj_vertices_set(
+                    graph,
+                    &pattern_record,
+                    extend_edge,
+                    edge_predicate.as_ref(),
+                    target_vertex_label,
+                );
                 intersect_vertices = intersect_sets(intersect_vertices, adjacent_vertices, i == 0);
             }
+            if let Some(predicate) = &target_vertex_predicate {
+                intersect_vertices
+                    .retain(|&adj_vertex_id| eval_vertex_predicate(predicate, graph, adj_vertex_id));
+            }
             let target_pattern_vertex_id = target_vertex.get_id();
             intersect_vertices
                 .into_iter()
@@ -422,26 +1963,285 @@ pub fn get_src_records(
                 })
         }));
     }
-    pattern_records.collect()
+    pattern_records
 }
 
 fn get_adj_vertices_set(
     graph: &LargeGraphDB<DefaultId, InternalId>, pattern_record: &PatternRecord,
-    extend_edge: &DefiniteExtendEdge, target_vertex_label: PatternLabelId,
+    extend_edge: &DefiniteExtendEdge, edge_predicate: Option<&PropertyPredicate>,
+    target_vertex_label: PatternLabelId,
 ) -> BTreeSet<DefaultId> {
     let src_pattern_vertex_id = extend_edge.get_src_vertex().get_id();
     let src_graph_vertex_id = *pattern_record
         .get(&src_pattern_vertex_id)
         .unwrap();
     let edge_label = extend_edge.get_edge_label();
-    let direction = extend_edge.get_direction();
-    graph
-        .get_adj_vertices(src_graph_vertex_id, Some(&vec![edge_label as LabelId]), direction.into())
-        .filter(|graph_vertex| graph_vertex.get_label()[0] == (target_vertex_label as LabelId))
-        .map(|graph_vertex| graph_vertex.get_id())
+    let direction =
+        if extend_edge.is_undirected() { Direction::Both } else { extend_edge.get_direction().into() };
+    if let Some(predicate) = edge_predicate {
+        // The predicate is evaluated on the properties of the adjacent edges
+        graph
+            .get_adj_edges(src_graph_vertex_id, Some(&vec![edge_label as LabelId]), direction)
+            .filter(|graph_edge| eval_edge_predicate(predicate, graph_edge))
+            .map(|graph_edge| graph_edge.get_other_id())
+            .filter(|&adj_vertex_id| {
+                graph
+                    .get_vertex(adj_vertex_id)
+                    .map(|graph_vertex| graph_vertex.get_label()[0] == (target_vertex_label as LabelId))
+                    .unwrap_or(false)
+            })
+            .collect()
+    } else {
+        graph
+            .get_adj_vertices(src_graph_vertex_id, Some(&vec![edge_label as LabelId]), direction)
+            .filter(|graph_vertex| graph_vertex.get_label()[0] == (target_vertex_label as LabelId))
+            .map(|graph_vertex| graph_vertex.get_id())
+            .collect()
+    }
+}
+
+/// A predicate on the properties of a graph vertex or edge, compiled once from its expression and
+/// evaluated on every vertex or edge it filters
+///
+/// The predicates of pattern vertices and edges only refer to the properties of the element itself,
+/// e.g., `@.age > 30`, so only such properties, constants and logical operators are supported.
+#[derive(Debug, Clone)]
+pub struct PropertyPredicate {
+    /// The operands and operators of the expression in suffix order
+    suffix_oprs: Vec<PredicateOpr>,
+}
+
+#[derive(Debug, Clone)]
+enum PredicateOpr {
+    Const(Object),
+    Property(String),
+    Logical(common_pb::Logical),
+}
+
+impl TryFrom<&common_pb::Expression> for PropertyPredicate {
+    type Error = IrError;
+
+    fn try_from(expr: &common_pb::Expression) -> IrResult<Self> {
+        let mut suffix_oprs = vec![];
+        // The number of values on the stack during evaluation, to make sure no operand is missing
+        let mut stack_len = 0;
+        for opr in to_suffix_expr(expr.operators.clone())? {
+            let (predicate_opr, operands_num) = match &opr.item {
+                Some(common_pb::expr_opr::Item::Const(value)) => {
+                    (PredicateOpr::Const(Object::try_from(value.clone())?), 0)
+                }
+                Some(common_pb::expr_opr::Item::Var(var)) => match get_property_key(var) {
+                    Some(key) => (PredicateOpr::Property(key), 0),
+                    None => return Err(IrError::Unsupported(format!("variable {:?} in predicate", var))),
+                },
+                Some(common_pb::expr_opr::Item::Logical(logical)) => {
+                    match common_pb::Logical::from_i32(*logical) {
+                        Some(common_pb::Logical::Not) => {
+                            (PredicateOpr::Logical(common_pb::Logical::Not), 1)
+                        }
+                        Some(logical) => (PredicateOpr::Logical(logical), 2),
+                        None => {
+                            return Err(IrError::Unsupported(format!("logical {:?} in predicate", logical)))
+                        }
+                    }
+                }
+                _ => return Err(IrError::Unsupported(format!("operator {:?} in predicate", opr))),
+            };
+            if stack_len < operands_num {
+                return Err(IrError::Unsupported(format!("missing operands of {:?} in predicate", opr)));
+            }
+            stack_len = stack_len - operands_num + 1;
+            suffix_oprs.push(predicate_opr);
+        }
+        if stack_len != 1 {
+            return Err(IrError::Unsupported(format!("predicate {:?} not evaluated to one value", expr)));
+        }
+        Ok(PropertyPredicate { suffix_oprs })
+    }
+}
+
+impl PropertyPredicate {
+    /// Evaluate the predicate with the properties looked up by their keys
+    ///
+    /// Return false if the predicate fails to be evaluated, e.g., the property is missing
+    pub fn eval<F: Fn(&str) -> Option<Object>>(&self, get_property: F) -> bool {
+        let mut stack: Vec<Object> = Vec::with_capacity(self.suffix_oprs.len());
+        for opr in self.suffix_oprs.iter() {
+            let value = match opr {
+                PredicateOpr::Const(value) => value.clone(),
+                PredicateOpr::Property(key) => match get_property(key) {
+                    Some(value) => value,
+                    None => return false,
+                },
+                // The operands are made sure to be on the stack when the predicate is compiled
+                PredicateOpr::Logical(common_pb::Logical::Not) => match stack.pop().unwrap().as_bool() {
+                    Ok(value) => (!value).into(),
+                    Err(_) => return false,
+                },
+                PredicateOpr::Logical(logical) => {
+                    let right = stack.pop().unwrap();
+                    let left = stack.pop().unwrap();
+                    match apply_logical(*logical, &left, &right) {
+                        Ok(value) => value.into(),
+                        Err(_) => return false,
+                    }
+                }
+            };
+            stack.push(value);
+        }
+        stack
+            .pop()
+            .and_then(|value| value.as_bool().ok())
+            .unwrap_or(false)
+    }
+}
+
+/// Get the key of the property of the element itself that the variable refers to, e.g., `@.age`
+fn get_property_key(var: &common_pb::Variable) -> Option<String> {
+    if var.tag.is_some() {
+        return None;
+    }
+    match var
+        .property
+        .as_ref()
+        .and_then(|property| property.item.as_ref())
+    {
+        Some(common_pb::property::Item::Key(key)) => match NameOrId::try_from(key.clone()) {
+            Ok(NameOrId::Str(key)) => Some(key),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Apply a binary logical operator to its operands, in the same way as the graph proxy evaluator
+fn apply_logical(logical: common_pb::Logical, left: &Object, right: &Object) -> Result<bool, CastError> {
+    match logical {
+        common_pb::Logical::Eq => Ok(left == right),
+        common_pb::Logical::Ne => Ok(left != right),
+        common_pb::Logical::Lt => Ok(left < right),
+        common_pb::Logical::Le => Ok(left <= right),
+        common_pb::Logical::Gt => Ok(left > right),
+        common_pb::Logical::Ge => Ok(left >= right),
+        common_pb::Logical::And => Ok(left.as_bool()? && right.as_bool()?),
+        common_pb::Logical::Or => Ok(left.as_bool()? || right.as_bool()?),
+        common_pb::Logical::Within => Ok(right.contains(left)),
+        common_pb::Logical::Without => Ok(!right.contains(left)),
+        common_pb::Logical::Startswith => Ok(left
+            .as_str()?
+            .starts_with(right.as_str()?.as_ref())),
+        common_pb::Logical::Endswith => Ok(left
+            .as_str()?
+            .ends_with(right.as_str()?.as_ref())),
+        // Not takes a single operand, which is applied before
+        common_pb::Logical::Not => unreachable!(),
+    }
+}
+
+/// Compile the predicate of a vertex or edge if any
+///
+/// The predicates out of the compiled subset, e.g., the ones referring to tags or with arithmetics, are
+/// skipped with a warning, so the vertex or edge is not filtered when sampling, as if it had no predicate
+fn compile_predicate(predicate: Option<&common_pb::Expression>) -> Option<PropertyPredicate> {
+    predicate.and_then(|predicate| match PropertyPredicate::try_from(predicate) {
+        Ok(property_predicate) => Some(property_predicate),
+        Err(err) => {
+            warn!("skip the predicate which cannot be evaluated when sampling: {}", err);
+            None
+        }
+    })
+}
+
+/// Compile the predicates of the edges of an extend step, once for all the records it extends
+fn compile_edge_predicates(extend_step: &DefiniteExtendStep) -> Vec<Option<PropertyPredicate>> {
+    extend_step
+        .iter()
+        .map(|extend_edge| compile_predicate(extend_edge.get_predicate()))
         .collect()
 }
 
+/// Evaluate the predicate against the properties of the graph vertex with the given id
+///
+/// The vertex is filtered out if it is not found in the graph
+fn eval_vertex_predicate(
+    predicate: &PropertyPredicate, graph: &LargeGraphDB<DefaultId, InternalId>, graph_vertex_id: DefaultId,
+) -> bool {
+    graph
+        .get_vertex(graph_vertex_id)
+        .map(|graph_vertex| {
+            predicate.eval(|key| {
+                graph_vertex
+                    .get_property(key)
+                    .and_then(|value| value.try_to_owned())
+            })
+        })
+        .unwrap_or(false)
+}
+
+/// Evaluate the predicate against the properties of a graph edge
+///
+/// The edge is filtered out if the predicate fails to be evaluated, e.g., the property is missing
+fn eval_edge_predicate(
+    predicate: &PropertyPredicate, graph_edge: &LocalEdge<DefaultId, InternalId>,
+) -> bool {
+    predicate.eval(|key| {
+        graph_edge
+            .get_property(key)
+            .and_then(|value| value.try_to_owned())
+    })
+}
+
+/// Perform a random walk along the extend steps, and return the inverse of the probability it is picked
+///
+/// The edge predicates are compiled for each extend step in advance, as they are shared by the walks.
+/// Return 0 if the walk fails to reach the whole pattern
+fn random_walk(
+    graph: &LargeGraphDB<DefaultId, InternalId>, src_vertices: &[DefaultId],
+    extend_steps: &[DefiniteExtendStep], edge_predicates: &[Vec<Option<PropertyPredicate>>],
+    rng: &mut StdRng,
+) -> f64 {
+    if src_vertices.is_empty() {
+        return 0.0;
+    }
+    let mut extend_steps = extend_steps.iter();
+    let src_pattern_vertex_id = extend_steps
+        .next()
+        .unwrap()
+        .get_target_vertex()
+        .get_id();
+    let src_graph_vertex_id = src_vertices[rng.gen_range(0..src_vertices.len())];
+    let mut pattern_record = PatternRecord::from_iter([(src_pattern_vertex_id, src_graph_vertex_id)]);
+    let mut weight = src_vertices.len() as f64;
+    for (extend_step, edge_predicates) in extend_steps.zip(edge_predicates.iter().skip(1)) {
+        let target_vertex = extend_step.get_target_vertex();
+        let mut intersect_vertices = BTreeSet::new();
+        for (i, (extend_edge, edge_predicate)) in extend_step
+            .iter()
+            .zip(edge_predicates.iter())
+            .enumerate()
+        {
+            let adjacent_vertices = get_adj_vertices_set(
+                graph,
+                &pattern_record,
+                extend_edge,
+                edge_predicate.as_ref(),
+                target_vertex.get_label(),
+            );
+            intersect_vertices = intersect_sets(intersect_vertices, adjacent_vertices, i == 0);
+        }
+        if intersect_vertices.is_empty() {
+            return 0.0;
+        }
+        weight *= intersect_vertices.len() as f64;
+        let picked_vertex_id = *intersect_vertices
+            .iter()
+            .nth(rng.gen_range(0..intersect_vertices.len()))
+            .unwrap();
+        pattern_record.insert(target_vertex.get_id(), picked_vertex_id);
+    }
+    weight
+}
+
 fn intersect_sets<T: Clone + Ord>(set1: BTreeSet<T>, set2: BTreeSet<T>, is_start: bool) -> BTreeSet<T> {
     if is_start {
         set2
@@ -450,20 +2250,50 @@ fn intersect_sets<T: Clone + Ord>(set1: BTreeSet<T>, set2: BTreeSet<T>, is_start
     }
 }
 
-fn sample_records(mut records: Vec<PatternRecord>, rate: f64, limit: Option<usize>) -> Vec<PatternRecord> {
+fn get_expected_sample_len(records_len: usize, rate: f64, limit: Option<usize>) -> usize {
     if let Some(lower_bound) = limit {
-        if records.len() <= lower_bound {
-            return records;
+        if records_len <= lower_bound {
+            return records_len;
         }
     }
-    let expected_len = if let Some(upper_bound) = limit {
-        std::cmp::min(((records.len() as f64) * rate).floor() as usize, upper_bound)
+    if let Some(upper_bound) = limit {
+        std::cmp::min(((records_len as f64) * rate).floor() as usize, upper_bound)
     } else {
-        ((records.len() as f64) * rate).floor() as usize
-    };
+        ((records_len as f64) * rate).floor() as usize
+    }
+}
+
+/// Raise the sample rate of a label so that at least min_seeds records are expected to be kept
+fn get_stratified_sample_rate(records_len: usize, rate: f64, min_seeds: usize) -> f64 {
+    if records_len == 0 || (records_len as f64) * rate >= min_seeds as f64 {
+        rate
+    } else if min_seeds >= records_len {
+        1.0
+    } else {
+        // the extra half record keeps the floor of the expected length from dropping a seed
+        (min_seeds as f64 + 0.5) / records_len as f64
+    }
+}
+
+fn sample_records(records: Vec<PatternRecord>, rate: f64, limit: Option<usize>) -> Vec<PatternRecord> {
+    sample_records_by_stride(records, rate, limit, None)
+}
+
+/// Stride sampling
+///
+/// The records are picked with a fixed stride, starting from the first record, or from a random offset
+/// within the first stride if an RNG is given
+fn sample_records_by_stride(
+    mut records: Vec<PatternRecord>, rate: f64, limit: Option<usize>, rng: Option<&mut StdRng>,
+) -> Vec<PatternRecord> {
+    let expected_len = get_expected_sample_len(records.len(), rate, limit);
+    if expected_len == records.len() {
+        return records;
+    }
     let step = (records.len() as f64 / expected_len as f64).floor() as usize;
     if step > 1 {
-        let picked_indices = (0..(records.len() / step)).map(|i| i * step);
+        let offset = rng.map_or(0, |rng| rng.gen_range(0..=(records.len() - expected_len * step)));
+        let picked_indices = (0..expected_len).map(|i| offset + i * step);
         for (i, pi) in picked_indices.enumerate() {
             records.swap(i, pi);
         }
@@ -472,14 +2302,84 @@ fn sample_records(mut records: Vec<PatternRecord>, rate: f64, limit: Option<usiz
     records
 }
 
-fn split_vector<T>(vector: &[T], thread_num: usize, thread_id: usize) -> &[T] {
-    let start_index = (vector.len() / thread_num) * thread_id;
-    let end_index = if thread_id == thread_num - 1 {
-        vector.len()
+/// Reservoir sampling (Algorithm R)
+///
+/// Exactly the expected number of records are kept, each of which is kept with the same probability
+fn sample_records_by_reservoir(
+    records: Vec<PatternRecord>, rate: f64, limit: Option<usize>, rng: &mut StdRng,
+) -> Vec<PatternRecord> {
+    let expected_len = get_expected_sample_len(records.len(), rate, limit);
+    if expected_len == records.len() {
+        return records;
+    }
+    let mut reservoir = Vec::with_capacity(expected_len);
+    for (i, record) in records.into_iter().enumerate() {
+        if i < expected_len {
+            reservoir.push(record);
+        } else {
+            let picked_index = rng.gen_range(0..=i);
+            if picked_index < expected_len {
+                reservoir[picked_index] = record;
+            }
+        }
+    }
+    reservoir
+}
+
+/// Bernoulli sampling
+///
+/// Each record is kept independently, with the probability that the expected number of records are kept
+fn sample_records_by_bernoulli(
+    records: Vec<PatternRecord>, rate: f64, limit: Option<usize>, rng: &mut StdRng,
+) -> Vec<PatternRecord> {
+    let expected_len = get_expected_sample_len(records.len(), rate, limit);
+    if expected_len == records.len() {
+        return records;
+    }
+    let probability = expected_len as f64 / records.len() as f64;
+    records
+        .into_iter()
+        .filter(|_| rng.gen_bool(probability))
+        .collect()
+}
+
+/// Sample records by the given strategy
+fn sample_records_by_strategy(
+    mut records: Vec<PatternRecord>, rate: f64, limit: Option<usize>, strategy: SamplingStrategy,
+    seed: Option<u64>,
+) -> Vec<PatternRecord> {
+    match strategy {
+        SamplingStrategy::Stride if seed.is_some() => {
+            let mut rng = get_sampling_rng(&mut records, seed);
+            sample_records_by_stride(records, rate, limit, Some(&mut rng))
+        }
+        SamplingStrategy::Stride => sample_records(records, rate, limit),
+        SamplingStrategy::Reservoir => {
+            let mut rng = get_sampling_rng(&mut records, seed);
+            sample_records_by_reservoir(records, rate, limit, &mut rng)
+        }
+        SamplingStrategy::Bernoulli => {
+            let mut rng = get_sampling_rng(&mut records, seed);
+            sample_records_by_bernoulli(records, rate, limit, &mut rng)
+        }
+    }
+}
+
+/// Get the RNG for random sampling, which is seeded by the given seed, or from entropy if no seed is given
+///
+/// With a seed, records are sorted first, since the order they are collected from threads varies
+fn get_sampling_rng(records: &mut [PatternRecord], seed: Option<u64>) -> StdRng {
+    if let Some(seed) = seed {
+        records.sort();
+        StdRng::seed_from_u64(seed)
     } else {
-        (vector.len() / thread_num) * (thread_id + 1)
-    };
-    &vector[start_index..end_index]
+        StdRng::from_entropy()
+    }
+}
+
+/// Derive a seed for each pattern, so that sampling does not depend on the order patterns are visited
+fn get_pattern_seed(seed: Option<u64>, pattern_index: NodeIndex) -> Option<u64> {
+    seed.map(|seed| seed ^ (pattern_index.index() as u64).wrapping_mul(0x9e3779b97f4a7c15))
 }
 
 pub fn load_sample_graph(graph_path: &str) -> LargeGraphDB<DefaultId, InternalId> {
@@ -503,6 +2403,318 @@ pub fn load_pattern_meta(schema_path: &str) -> PatternMeta {
     PatternMeta::from(schema)
 }
 
+#[cfg(test)]
+mod test {
+    use graph_store::config::JsonConf;
+    use graph_store::ldbc::LDBCVertexParser;
+    use graph_store::prelude::{GlobalStoreUpdate, LDBCGraphSchema, MutableGraphDB, INVALID_LABEL_ID};
+    use ir_common::expr_parse::str_to_expr_pb;
+
+    use super::*;
+    use crate::catalogue::extend_step::ExtendEdge;
+
+    /// Every person knows all the other persons
+    fn build_knows_clique(persons_num: usize) -> LargeGraphDB<DefaultId, InternalId> {
+        let knows: Vec<(usize, usize)> = (0..persons_num)
+            .flat_map(|src_person| (0..persons_num).map(move |dst_person| (src_person, dst_person)))
+            .filter(|(src_person, dst_person)| src_person != dst_person)
+            .collect();
+        build_knows_graph(persons_num, &knows)
+    }
+
+    /// The persons know each other as the given (src person, dst person) pairs
+    fn build_knows_graph(
+        persons_num: usize, knows: &[(usize, usize)],
+    ) -> LargeGraphDB<DefaultId, InternalId> {
+        let mut mut_graph: MutableGraphDB<DefaultId, InternalId> = GraphDBConfig::default().new();
+        let persons: Vec<DefaultId> = (0..persons_num)
+            .map(|person_id| LDBCVertexParser::to_global_id(person_id, 0))
+            .collect();
+        for &person in persons.iter() {
+            mut_graph.add_vertex(person, [0, INVALID_LABEL_ID]);
+        }
+        for &(src_person, dst_person) in knows {
+            mut_graph.add_edge(persons[src_person], persons[dst_person], 0);
+        }
+        let schema = r#"
+        {
+          "vertex_type_map": {
+            "person": 0
+          },
+          "edge_type_map": {
+            "knows": 0
+          },
+          "vertex_prop": {},
+          "edge_prop": {}
+        }
+        "#;
+        let schema = LDBCGraphSchema::from_json(schema.to_string()).expect("Parse schema error!");
+        mut_graph.into_graph(schema)
+    }
+
+    #[test]
+    fn test_sub_task_samples_records_under_limit() {
+        let persons_num = 300;
+        let graph = Arc::new(build_knows_clique(persons_num));
+        let pattern = Pattern::from(PatternVertex::new(0, 0));
+        let pattern_records = graph
+            .get_all_vertices(Some(&vec![0]))
+            .map(|graph_vertex| PatternRecord::from_iter([(0, graph_vertex.get_id())]))
+            .collect();
+        let pattern_count_info = Arc::new(PatternCountInfo::new(pattern, pattern_records, persons_num));
+        let extend_step = Arc::new(ExtendStep::new(0, vec![ExtendEdge::new(0, 0, PatternDirection::Out)]));
+        let sub_task = SubTask::new(&pattern_count_info, &extend_step, &graph);
+        let knows_count = persons_num * (persons_num - 1);
+
+        let result = sub_task.execute(4, 1.0, None, false, SamplingStrategy::Stride, None);
+        assert_eq!(result.target_pattern_records.len(), knows_count);
+        assert_eq!(result.target_pattern_count, knows_count);
+        assert_eq!(result.expanded_records_num, persons_num);
+
+        let result = sub_task.execute(4, 1.0, Some(10), false, SamplingStrategy::Stride, Some(2022));
+        assert_eq!(result.target_pattern_records.len(), 10);
+        assert_eq!(
+            result
+                .target_pattern_records
+                .iter()
+                .collect::<BTreeSet<_>>()
+                .len(),
+            10
+        );
+        // the threads stop expanding once the limit is reached, and the count is scaled by the fraction
+        // of the persons expanded, which is still exact in a clique
+        assert!(result.expanded_records_num < persons_num);
+        assert_eq!(result.target_pattern_count, knows_count);
+        // the sample is reproducible with the same seed
+        let same_seed_result =
+            sub_task.execute(4, 1.0, Some(10), false, SamplingStrategy::Stride, Some(2022));
+        assert_eq!(same_seed_result.target_pattern_records, result.target_pattern_records);
+    }
+
+    #[test]
+    fn test_sub_task_extends_undirected_edge() {
+        // person 0 and person 1 know each other, and person 0 knows person 2
+        let graph = Arc::new(build_knows_graph(3, &[(0, 1), (1, 0), (0, 2)]));
+        let pattern = Pattern::from(PatternVertex::new(0, 0));
+        let pattern_records: Vec<PatternRecord> = graph
+            .get_all_vertices(Some(&vec![0]))
+            .map(|graph_vertex| PatternRecord::from_iter([(0, graph_vertex.get_id())]))
+            .collect();
+        let pattern_count_info = Arc::new(PatternCountInfo::new(pattern.clone(), pattern_records, 3));
+        let extend_count = |extend_edge: ExtendEdge| {
+            let extend_step = Arc::new(ExtendStep::new(0, vec![extend_edge]));
+            let sub_task = SubTask::new(&pattern_count_info, &extend_step, &graph);
+            sub_task
+                .execute(1, 1.0, None, false, SamplingStrategy::Stride, None)
+                .target_pattern_count
+        };
+        assert_eq!(extend_count(ExtendEdge::new(0, 0, PatternDirection::Out)), 3);
+        assert_eq!(extend_count(ExtendEdge::new(0, 0, PatternDirection::In)), 3);
+        // 3 out-neighbors and 3 in-neighbors, where the 2 between person 0 and person 1 are counted once
+        let undirected_extend_edge = ExtendEdge::new(0, 0, PatternDirection::In).with_undirected(true);
+        assert_eq!(extend_count(undirected_extend_edge), 4);
+        // the extended edge is undirected, and the code ignores its orientation
+        let extend_step = ExtendStep::new(0, vec![undirected_extend_edge]);
+        let extended_pattern = pattern.extend(&extend_step).unwrap();
+        assert!(extended_pattern
+            .edges_iter()
+            .all(|edge| edge.is_undirected()));
+        let reversed_pattern = Pattern::try_from(vec![PatternEdge::new(
+            0,
+            0,
+            PatternVertex::new(1, 0),
+            PatternVertex::new(0, 0),
+        )
+        .with_undirected(true)])
+        .unwrap();
+        assert_eq!(extended_pattern.encode_to(), reversed_pattern.encode_to());
+        assert_eq!(
+            ExtendStep::decode_from(&extend_step.encode_to())
+                .unwrap()
+                .encode_to(),
+            extend_step.encode_to()
+        );
+    }
+
+    #[test]
+    fn test_count_triangles_same_as_exact_count() {
+        // a directed cycle 0 -> 1 -> 2 -> 0, and a transitive triangle 3 -> 4 -> 5 <- 3
+        let graph = build_knows_graph(6, &[(0, 1), (1, 2), (2, 0), (3, 4), (4, 5), (3, 5)]);
+        let knows_triangle = |is_undirected: bool| {
+            Pattern::try_from(vec![
+                PatternEdge::new(0, 0, PatternVertex::new(0, 0), PatternVertex::new(1, 0))
+                    .with_undirected(is_undirected),
+                PatternEdge::new(1, 0, PatternVertex::new(1, 0), PatternVertex::new(2, 0))
+                    .with_undirected(is_undirected),
+                PatternEdge::new(2, 0, PatternVertex::new(2, 0), PatternVertex::new(0, 0))
+                    .with_undirected(is_undirected),
+            ])
+            .unwrap()
+        };
+        let directed_triangle = knows_triangle(false);
+        let undirected_triangle = knows_triangle(true);
+        assert_eq!(count_triangles(&graph, &directed_triangle, 1.0), Some(3.0));
+        assert_eq!(count_pattern_exact(&graph, &directed_triangle), 3);
+        assert_eq!(count_triangles(&graph, &undirected_triangle, 1.0), Some(12.0));
+        assert_eq!(count_pattern_exact(&graph, &undirected_triangle), 12);
+        assert_eq!(count_triangles(&graph, &Pattern::from(PatternVertex::new(0, 0)), 1.0), None);
+
+        let persons_num = 30;
+        let graph = build_knows_clique(persons_num);
+        let exact_count = count_pattern_exact(&graph, &directed_triangle) as f64;
+        assert_eq!(exact_count, (persons_num * (persons_num - 1) * (persons_num - 2)) as f64);
+        let estimated_count = count_triangles(&graph, &directed_triangle, 0.1).unwrap();
+        assert!(q_error(estimated_count, exact_count) < 1.1);
+    }
+
+    #[test]
+    fn test_estimate_graph_keeps_sampled_records_only_if_set() {
+        let graph = Arc::new(build_knows_graph(3, &[(0, 1), (1, 2), (2, 0)]));
+        let knows_path = |edges_num: usize| {
+            Pattern::try_from(
+                (0..edges_num)
+                    .map(|i| {
+                        PatternEdge::new(
+                            i as PatternId,
+                            0,
+                            PatternVertex::new(i as PatternId, 0),
+                            PatternVertex::new(i as PatternId + 1, 0),
+                        )
+                    })
+                    .collect::<Vec<_>>(),
+            )
+            .unwrap()
+        };
+        let mut catalog =
+            Catalogue::build_from_pattern(&knows_path(1), PatMatPlanSpace::ExtendWithIntersection);
+        catalog
+            .estimate_graph(Arc::clone(&graph), &EstimateConfig::default().with_keep_sampled_records(true));
+        assert!(!catalog.sampled_records.is_empty());
+        catalog.update_catalog_by_pattern_and_estimate(
+            &knows_path(2),
+            Arc::clone(&graph),
+            &EstimateConfig::default().with_keep_sampled_records(true),
+        );
+        assert!(!catalog.sampled_records.is_empty());
+        catalog.update_catalog_by_pattern_and_estimate(
+            &knows_path(3),
+            Arc::clone(&graph),
+            &EstimateConfig::default(),
+        );
+        assert!(catalog.sampled_records.is_empty());
+        catalog.estimate_graph(graph, &EstimateConfig::default());
+        assert!(catalog.sampled_records.is_empty());
+    }
+
+    #[test]
+    fn test_estimate_graph_reuses_cached_records() {
+        let graph = Arc::new(build_knows_graph(4, &[(0, 1), (1, 2), (2, 3), (3, 0), (0, 2)]));
+        let knows_path = |edges_num: usize| {
+            Pattern::try_from(
+                (0..edges_num)
+                    .map(|i| {
+                        PatternEdge::new(
+                            i as PatternId,
+                            0,
+                            PatternVertex::new(i as PatternId, 0),
+                            PatternVertex::new(i as PatternId + 1, 0),
+                        )
+                    })
+                    .collect::<Vec<_>>(),
+            )
+            .unwrap()
+        };
+        let estimate_updated_catalog = |reuse_records: bool| {
+            let config = EstimateConfig::default().with_reuse_records(reuse_records);
+            let mut catalog =
+                Catalogue::build_from_pattern(&knows_path(2), PatMatPlanSpace::ExtendWithIntersection);
+            catalog.estimate_graph(Arc::clone(&graph), &config);
+            catalog.update_catalog_by_pattern(&knows_path(3));
+            let report = catalog.estimate_graph(Arc::clone(&graph), &config);
+            (catalog, report)
+        };
+        let (catalog, report) = estimate_updated_catalog(false);
+        let (reused_catalog, reused_report) = estimate_updated_catalog(true);
+        // only the patterns added by the update are extended again
+        assert!(reused_report.get_total_sub_tasks_num() < report.get_total_sub_tasks_num());
+        assert!(!reused_catalog.cached_records.is_empty());
+        assert!(catalog.cached_records.is_empty());
+        for pattern_index in catalog.pattern_indices_iter() {
+            let pattern_code = catalog
+                .get_pattern_weight(pattern_index)
+                .unwrap()
+                .get_pattern()
+                .encode_to();
+            let reused_pattern_index = reused_catalog
+                .get_pattern_index(&pattern_code)
+                .unwrap();
+            assert_eq!(
+                catalog
+                    .get_pattern_weight(pattern_index)
+                    .unwrap()
+                    .get_count(),
+                reused_catalog
+                    .get_pattern_weight(reused_pattern_index)
+                    .unwrap()
+                    .get_count()
+            );
+        }
+    }
+
+    #[test]
+    fn test_sample_records_by_seeded_stride() {
+        let records: Vec<PatternRecord> = (0..100)
+            .map(|id| PatternRecord::from_iter([(0, id)]))
+            .collect();
+        let sample = |seed: Option<u64>| {
+            sample_records_by_strategy(records.clone(), 0.1, None, SamplingStrategy::Stride, seed)
+        };
+        assert_eq!(sample(None), sample_records(records.clone(), 0.1, None));
+        for seed in 0..10 {
+            let sampled_records = sample(Some(seed));
+            assert_eq!(sampled_records.len(), 10);
+            // every picked record is one stride after the previous one
+            for (record, next_record) in sampled_records
+                .iter()
+                .zip(sampled_records.iter().skip(1))
+            {
+                assert_eq!(next_record[&0] - record[&0], 10);
+            }
+            assert_eq!(sample(Some(seed)), sampled_records);
+        }
+    }
+
+    #[test]
+    fn test_sampling_strategy_from_str() {
+        assert_eq!("stride".parse::<SamplingStrategy>().unwrap(), SamplingStrategy::Stride);
+        assert_eq!("reservoir".parse::<SamplingStrategy>().unwrap(), SamplingStrategy::Reservoir);
+        assert_eq!("bernoulli".parse::<SamplingStrategy>().unwrap(), SamplingStrategy::Bernoulli);
+        assert!("random".parse::<SamplingStrategy>().is_err());
+    }
+
+    #[test]
+    fn test_eval_predicate() {
+        let eval = |expr: &str, properties: &[(&str, Object)]| {
+            let predicate =
+                PropertyPredicate::try_from(&str_to_expr_pb(expr.to_string()).unwrap()).unwrap();
+            let properties: HashMap<&str, Object> = properties.iter().cloned().collect();
+            predicate.eval(|key| properties.get(key).cloned())
+        };
+        assert!(eval("@.age > 30", &[("age", 32.into())]));
+        assert!(!eval("@.age > 30", &[("age", 29.into())]));
+        assert!(eval("@.age > 30 && @.name == \"marko\"", &[("age", 32.into()), ("name", "marko".into())]));
+        assert!(eval("!(@.age > 30) || @.age == 32", &[("age", 32.into())]));
+        assert!(eval("@.age within [29, 32]", &[("age", 32.into())]));
+        // a missing property fails the predicate
+        assert!(!eval("@.age > 30", &[]));
+        // only the properties of the element itself can be evaluated
+        assert!(PropertyPredicate::try_from(&str_to_expr_pb("@a.age > 30".to_string()).unwrap()).is_err());
+        assert!(
+            PropertyPredicate::try_from(&str_to_expr_pb("@.age + 1 > 30".to_string()).unwrap()).is_err()
+        );
+    }
+}
+
 // #[cfg(test)]
 // mod tests {
 //     use std::convert::TryFrom;
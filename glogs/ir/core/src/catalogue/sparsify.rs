@@ -22,6 +22,7 @@ use graph_store::prelude::{
 use rand::{thread_rng, Rng};
 use serde_json::{Map, Value};
 use std::fs::{self, File};
+use std::io;
 use std::{collections::HashMap, path::Path};
 
 // pub fn generate_sparsify_rate(rate: f64, edge_distribution: HashMap<(u8,u8,u8), f64>) -> HashMap<(u8,u8,u8),f64> {
@@ -57,7 +58,7 @@ pub fn read_sparsify_config(path: &str) -> HashMap<(u8, u8, u8), f64> {
     sparsify_rate
 }
 
-pub fn get_edge_distribution(src_graph: LargeGraphDB) -> HashMap<(u8, u8, u8), f64> {
+pub fn get_edge_distribution(src_graph: &LargeGraphDB) -> HashMap<(u8, u8, u8), f64> {
     let mut edge_distribution = HashMap::new();
     for j in src_graph.get_all_edges(None) {
         let src_label = src_graph
@@ -86,6 +87,100 @@ pub fn get_edge_distribution(src_graph: LargeGraphDB) -> HashMap<(u8, u8, u8), f
     edge_distribution
 }
 
+/// The counts of the vertices of each label, and of the edges of each (src label, edge label, dst label)
+/// keyed as the sparsify rates, which bound the counts of patterns without sampling
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct GraphStats {
+    vertex_counts: HashMap<u8, f64>,
+    edge_counts: HashMap<(u8, u8, u8), f64>,
+}
+
+impl GraphStats {
+    pub fn from_graph(graph: &LargeGraphDB) -> Self {
+        let mut vertex_counts = HashMap::new();
+        for vertex in graph.get_all_vertices(None) {
+            *vertex_counts
+                .entry(vertex.get_label()[0])
+                .or_insert(0.0) += 1.0;
+        }
+        GraphStats { vertex_counts, edge_counts: get_edge_distribution(graph) }
+    }
+
+    pub fn get_vertex_count(&self, vertex_label: u8) -> f64 {
+        self.vertex_counts
+            .get(&vertex_label)
+            .cloned()
+            .unwrap_or(0.0)
+    }
+
+    pub fn get_edge_count(&self, src_label: u8, edge_label: u8, dst_label: u8) -> f64 {
+        self.edge_counts
+            .get(&(src_label, edge_label, dst_label))
+            .cloned()
+            .unwrap_or(0.0)
+    }
+
+    /// Export the stats as JSON like `{"vertices": {"0": 4}, "edges": {"0_0_0": 2}}`, where the edges
+    /// are keyed in the same way as the sparsify rates
+    pub fn export<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let vertices: Map<String, Value> = self
+            .vertex_counts
+            .iter()
+            .map(|(label, count)| (label.to_string(), Value::from(*count)))
+            .collect();
+        let edges: Map<String, Value> = self
+            .edge_counts
+            .iter()
+            .map(|((src_label, edge_label, dst_label), count)| {
+                (format!("{}_{}_{}", src_label, edge_label, dst_label), Value::from(*count))
+            })
+            .collect();
+        let mut stats = Map::new();
+        stats.insert("vertices".to_string(), Value::Object(vertices));
+        stats.insert("edges".to_string(), Value::Object(edges));
+        serde_json::to_writer(File::create(path)?, &stats)?;
+        Ok(())
+    }
+
+    pub fn import<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let invalid_data = |message: String| io::Error::new(io::ErrorKind::InvalidData, message);
+        let stats: Value = serde_json::from_str(&fs::read_to_string(path)?)?;
+        let get_counts = |key: &str| {
+            stats
+                .get(key)
+                .and_then(Value::as_object)
+                .ok_or_else(|| invalid_data(format!("{} not found in graph stats", key)))
+        };
+        let get_count = |key: &String, count: &Value| {
+            count
+                .as_f64()
+                .ok_or_else(|| invalid_data(format!("invalid count of {} in graph stats", key)))
+        };
+        let mut graph_stats = GraphStats::default();
+        for (key, count) in get_counts("vertices")? {
+            let label = key
+                .parse::<u8>()
+                .map_err(|_| invalid_data(format!("invalid vertex label {} in graph stats", key)))?;
+            graph_stats
+                .vertex_counts
+                .insert(label, get_count(key, count)?);
+        }
+        for (key, count) in get_counts("edges")? {
+            let labels = key
+                .split('_')
+                .map(|label| label.parse::<u8>())
+                .collect::<Result<Vec<u8>, _>>()
+                .ok()
+                .filter(|labels| labels.len() == 3)
+                .ok_or_else(|| invalid_data(format!("invalid edge key {} in graph stats", key)))?;
+            graph_stats
+                .edge_counts
+                .insert((labels[0], labels[1], labels[2]), get_count(key, count)?);
+        }
+        Ok(graph_stats)
+    }
+}
+
 pub fn create_sparsified_graph<P: AsRef<Path>>(
     src_graph: LargeGraphDB, sparsify_rate: HashMap<(u8, u8, u8), f64>, path: P,
 ) {
@@ -33,6 +33,7 @@ pub enum IrError {
     ParsePbError(ParsePbError),
     ParseExprError(ExprError),
     InvalidPattern(String),
+    InvalidPlan(String),
 
     // Physical Errors
     PbEncodeError(EncodeError),
@@ -64,6 +65,7 @@ impl fmt::Display for IrError {
             IrError::ParsePbError(err) => write!(f, "parse pb error: {:?}", err),
             IrError::ParseExprError(err) => write!(f, "parse expression error: {:?}", err),
             IrError::InvalidPattern(s) => write!(f, "invalid pattern: {:?}", s),
+            IrError::InvalidPlan(s) => write!(f, "invalid plan: {:?}", s),
             IrError::PbEncodeError(err) => write!(f, "encoding protobuf error: {:?}", err),
             IrError::MissingData(s) => write!(f, "missing required data: {:?}", s),
             IrError::InvalidRange(lo, up) => {
@@ -27,6 +27,7 @@ use ir_common::generated::common as common_pb;
 use ir_common::NameOrId;
 
 use crate::catalogue::pattern::Pattern;
+use crate::catalogue::plan::PlanConfig;
 use crate::error::{IrError, IrResult};
 use crate::plan::meta::{PlanMeta, CATALOGUE, PATTERN_META};
 
@@ -1177,12 +1178,16 @@ impl MatchingStrategy for ExtendStrategy {
             let mut catalog_guard = CATALOGUE.write()?;
             if let Some(catalog) = catalog_guard.as_mut() {
                 println!("Generate Extend Plan with Catalogue");
-                self.pattern
-                    .generate_optimized_match_plan(catalog, &pattern_meta, true)
+                self.pattern.generate_optimized_match_plan(
+                    catalog,
+                    &pattern_meta,
+                    true,
+                    &PlanConfig::default(),
+                )
             } else {
                 println!("Generate Extend Plan without Catalogue");
                 self.pattern
-                    .generate_simple_extend_match_plan(&pattern_meta, true)
+                    .generate_simple_extend_match_plan(&pattern_meta, true, &PlanConfig::default())
             }
         } else {
             Err(IrError::MissingData("PATTERN_META".to_string()))
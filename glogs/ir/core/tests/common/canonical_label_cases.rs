@@ -1741,4 +1741,35 @@ pub fn build_pattern_rank_ranking_case21() -> Pattern {
     let edge6 = PatternEdge::new(6, 13, vertex4, vertex5);
     let edges = vec![edge0, edge2, edge4, edge6];
     Pattern::try_from(edges).unwrap()
+}
+
+/// All the patterns of the rank ranking cases, built with their random vertex ids
+pub fn get_canonical_label_case_patterns() -> Vec<Pattern> {
+    vec![
+        build_pattern_rank_ranking_case1().0,
+        build_pattern_rank_ranking_case2().0,
+        build_pattern_rank_ranking_case3().0,
+        build_pattern_rank_ranking_case4().0,
+        build_pattern_rank_ranking_case5().0,
+        build_pattern_rank_ranking_case6().0,
+        build_pattern_rank_ranking_case7().0,
+        build_pattern_rank_ranking_case8().0,
+        build_pattern_rank_ranking_case9().0,
+        build_pattern_rank_ranking_case10().0,
+        build_pattern_rank_ranking_case11().0,
+        build_pattern_rank_ranking_case12().0,
+        build_pattern_rank_ranking_case13().0,
+        build_pattern_rank_ranking_case14().0,
+        build_pattern_rank_ranking_case15().0,
+        build_pattern_rank_ranking_case16().0,
+        build_pattern_rank_ranking_case17().0,
+        build_pattern_rank_ranking_case17_even_num_chain().0,
+        build_pattern_rank_ranking_case17_long_chain().0,
+        build_pattern_rank_ranking_case17_special_id_situation_1().0,
+        build_pattern_rank_ranking_case17_special_id_situation_2().0,
+        build_pattern_rank_ranking_case18().0,
+        build_pattern_rank_ranking_case19().0,
+        build_pattern_rank_ranking_case20().0,
+        build_pattern_rank_ranking_case21(),
+    ]
 }
\ No newline at end of file
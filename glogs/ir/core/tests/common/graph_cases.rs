@@ -0,0 +1,132 @@
+//
+//! Copyright 2020 Alibaba Group Holding Limited.
+//!
+//! Licensed under the Apache License, Version 2.0 (the "License");
+//! you may not use this file except in compliance with the License.
+//! You may obtain a copy of the License at
+//!
+//! http://www.apache.org/licenses/LICENSE-2.0
+//!
+//! Unless required by applicable law or agreed to in writing, software
+//! distributed under the License is distributed on an "AS IS" BASIS,
+//! WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//! See the License for the specific language governing permissions and
+//! limitations under the License.
+
+use dyn_type::Object;
+use graph_store::config::JsonConf;
+use graph_store::ldbc::LDBCVertexParser;
+use graph_store::prelude::{
+    DefaultId, GlobalStoreUpdate, GraphDBConfig, InternalId, LDBCGraphSchema, LargeGraphDB, MutableGraphDB,
+    INVALID_LABEL_ID,
+};
+use graph_store::table::Row;
+
+/// The topology of the modern graph (without properties)
+///
+/// Vertex Label Map:
+/// ```text
+///     person: 0, software: 1
+/// ```
+/// Edge Label Map:
+/// ```text
+///     knows: 0, created: 1
+/// ```
+pub fn build_modern_graph() -> LargeGraphDB<DefaultId, InternalId> {
+    let mut mut_graph: MutableGraphDB<DefaultId, InternalId> = GraphDBConfig::default().new();
+    let v1: DefaultId = LDBCVertexParser::to_global_id(1, 0);
+    let v2: DefaultId = LDBCVertexParser::to_global_id(2, 0);
+    let v3: DefaultId = LDBCVertexParser::to_global_id(3, 1);
+    let v4: DefaultId = LDBCVertexParser::to_global_id(4, 0);
+    let v5: DefaultId = LDBCVertexParser::to_global_id(5, 1);
+    let v6: DefaultId = LDBCVertexParser::to_global_id(6, 0);
+    mut_graph.add_vertex(v1, [0, INVALID_LABEL_ID]);
+    mut_graph.add_vertex(v2, [0, INVALID_LABEL_ID]);
+    mut_graph.add_vertex(v3, [1, INVALID_LABEL_ID]);
+    mut_graph.add_vertex(v4, [0, INVALID_LABEL_ID]);
+    mut_graph.add_vertex(v5, [1, INVALID_LABEL_ID]);
+    mut_graph.add_vertex(v6, [0, INVALID_LABEL_ID]);
+    mut_graph.add_edge(v1, v2, 0);
+    mut_graph.add_edge(v1, v3, 1);
+    mut_graph.add_edge(v1, v4, 0);
+    mut_graph.add_edge(v4, v3, 1);
+    mut_graph.add_edge(v4, v5, 1);
+    mut_graph.add_edge(v6, v3, 1);
+    let modern_graph_schema = r#"
+    {
+      "vertex_type_map": {
+        "person": 0,
+        "software": 1
+      },
+      "edge_type_map": {
+        "knows": 0,
+        "created": 1
+      },
+      "vertex_prop": {},
+      "edge_prop": {}
+    }
+    "#;
+    let schema = LDBCGraphSchema::from_json(modern_graph_schema.to_string()).expect("Parse schema error!");
+    mut_graph.into_graph(schema)
+}
+
+/// The modern graph with the `age` property on persons and the `weight` property on edges
+///
+/// Person ages:
+/// ```text
+///     v1: 29, v2: 27, v4: 32, v6: 35
+/// ```
+/// Edge weights:
+/// ```text
+///     v1-knows->v2: 0.5, v1-knows->v4: 1.0,
+///     v1-created->v3: 0.4, v4-created->v3: 0.4, v4-created->v5: 1.0, v6-created->v3: 0.2
+/// ```
+pub fn build_modern_graph_with_properties() -> LargeGraphDB<DefaultId, InternalId> {
+    let mut mut_graph: MutableGraphDB<DefaultId, InternalId> = GraphDBConfig::default().new();
+    let v1: DefaultId = LDBCVertexParser::to_global_id(1, 0);
+    let v2: DefaultId = LDBCVertexParser::to_global_id(2, 0);
+    let v3: DefaultId = LDBCVertexParser::to_global_id(3, 1);
+    let v4: DefaultId = LDBCVertexParser::to_global_id(4, 0);
+    let v5: DefaultId = LDBCVertexParser::to_global_id(5, 1);
+    let v6: DefaultId = LDBCVertexParser::to_global_id(6, 0);
+    for (person, age) in [(v1, 29), (v2, 27), (v4, 32), (v6, 35)] {
+        mut_graph
+            .add_vertex_with_properties(person, [0, INVALID_LABEL_ID], Row::from(vec![Object::from(age)]))
+            .unwrap();
+    }
+    mut_graph.add_vertex(v3, [1, INVALID_LABEL_ID]);
+    mut_graph.add_vertex(v5, [1, INVALID_LABEL_ID]);
+    for (src, dst, label, weight) in [
+        (v1, v2, 0, 0.5),
+        (v1, v3, 1, 0.4),
+        (v1, v4, 0, 1.0),
+        (v4, v3, 1, 0.4),
+        (v4, v5, 1, 1.0),
+        (v6, v3, 1, 0.2),
+    ] {
+        mut_graph
+            .add_edge_with_properties(src, dst, label, Row::from(vec![Object::from(weight)]))
+            .unwrap();
+    }
+    let modern_graph_schema = r#"
+    {
+      "vertex_type_map": {
+        "person": 0,
+        "software": 1
+      },
+      "edge_type_map": {
+        "knows": 0,
+        "created": 1
+      },
+      "vertex_prop": {
+        "person": [["age", "Integer"]]
+      },
+      "edge_prop": {
+        "knows": [["weight", "Double"]],
+        "created": [["weight", "Double"]]
+      }
+    }
+    "#;
+    let schema = LDBCGraphSchema::from_json(modern_graph_schema.to_string()).expect("Parse schema error!");
+    mut_graph.into_graph(schema)
+}
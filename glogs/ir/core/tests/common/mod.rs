@@ -32,3 +32,7 @@ pub mod join_step_cases;
 #[cfg(test)]
 #[allow(dead_code)]
 pub mod canonical_label_cases;
+
+#[cfg(test)]
+#[allow(dead_code)]
+pub mod graph_cases;
@@ -342,6 +342,50 @@ pub fn build_pattern_case9() -> Pattern {
     Pattern::try_from(pattern_edges).unwrap()
 }
 
+/// The pattern looks like:
+/// ```text
+///     A -> B -> C -> D
+/// ```
+/// Vertex Label Map:
+/// ```text
+///     A: 1, B: 2, C: 3, D: 4
+/// ```
+/// Edge Label Map:
+/// ```text
+///     A->B: 1, B->C: 2, C->D: 3
+/// ```
+pub fn build_pattern_case10() -> Pattern {
+    let edge_1 = new_pattern_edge(0, 1, 0, 1, 1, 2);
+    let edge_2 = new_pattern_edge(1, 2, 1, 2, 2, 3);
+    let edge_3 = new_pattern_edge(2, 3, 2, 3, 3, 4);
+    let pattern_edges = vec![edge_1, edge_2, edge_3];
+    Pattern::try_from(pattern_edges).unwrap()
+}
+
+/// The pattern looks like:
+/// ```text
+///      A(0) -- B(1)
+///        \     /
+///         C(2)
+/// ```
+/// where every edge is undirected with label 1
+///
+/// The orientations of the edges are given by the orientations vector,
+/// true means the edge is stored as (smaller id -> larger id)
+pub fn build_undirected_pattern_case1(orientations: [bool; 3]) -> Pattern {
+    let endpoints = [(0, 1), (1, 2), (0, 2)];
+    let pattern_edges: Vec<PatternEdge> = endpoints
+        .iter()
+        .zip(orientations.iter())
+        .enumerate()
+        .map(|(i, (&(v1_id, v2_id), &is_forward))| {
+            let (start_v_id, end_v_id) = if is_forward { (v1_id, v2_id) } else { (v2_id, v1_id) };
+            new_pattern_edge(i, 1, start_v_id, end_v_id, 1, 1).with_undirected(true)
+        })
+        .collect();
+    Pattern::try_from(pattern_edges).unwrap()
+}
+
 /// Pattern from modern schema file
 ///
 /// Person only Pattern
@@ -388,6 +432,119 @@ pub fn build_modern_pattern_case5() -> Pattern {
     Pattern::try_from(vec![pattern_edge1, pattern_edge2, pattern_edge3]).unwrap()
 }
 
+/// The pattern looks like:
+///```text
+///           Software
+///   create/
+///  Person -> knows -> Person
+/// ```
+pub fn build_modern_pattern_case6() -> Pattern {
+    let pattern_edge1 = new_pattern_edge(0, 0, 0, 1, 0, 0);
+    let pattern_edge2 = new_pattern_edge(1, 1, 0, 2, 0, 1);
+    Pattern::try_from(vec![pattern_edge1, pattern_edge2]).unwrap()
+}
+
+/// The pattern looks like:
+///```text
+///                     Software
+///                   /create
+///  Person -> knows -> Person
+/// ```
+pub fn build_modern_pattern_case7() -> Pattern {
+    let pattern_edge1 = new_pattern_edge(0, 0, 0, 1, 0, 0);
+    let pattern_edge2 = new_pattern_edge(1, 1, 1, 2, 0, 1);
+    Pattern::try_from(vec![pattern_edge1, pattern_edge2]).unwrap()
+}
+
+/// The pattern looks like:
+///```text
+///           Software
+///   create/         \create
+///  Person             Person
+/// ```
+pub fn build_modern_pattern_case8() -> Pattern {
+    let pattern_edge1 = new_pattern_edge(0, 1, 0, 2, 0, 1);
+    let pattern_edge2 = new_pattern_edge(1, 1, 1, 2, 0, 1);
+    Pattern::try_from(vec![pattern_edge1, pattern_edge2]).unwrap()
+}
+
+/// The pattern looks like:
+///```text
+///           Software
+///   create/         \create
+///  Person -> knows -> Person -> knows -> Person
+/// ```
+pub fn build_modern_pattern_case9() -> Pattern {
+    let pattern_edge1 = new_pattern_edge(0, 0, 0, 1, 0, 0);
+    let pattern_edge2 = new_pattern_edge(1, 1, 0, 2, 0, 1);
+    let pattern_edge3 = new_pattern_edge(2, 1, 1, 2, 0, 1);
+    let pattern_edge4 = new_pattern_edge(3, 0, 1, 3, 0, 0);
+    Pattern::try_from(vec![pattern_edge1, pattern_edge2, pattern_edge3, pattern_edge4]).unwrap()
+}
+
+/// The pattern looks like:
+///```text
+///  Person -> knows -> Person
+///    ^                  |
+///  knows              knows
+///    |                  v
+///  Person <- knows <- Person
+/// ```
+pub fn build_modern_pattern_case10() -> Pattern {
+    let pattern_edge1 = new_pattern_edge(0, 0, 0, 1, 0, 0);
+    let pattern_edge2 = new_pattern_edge(1, 0, 1, 2, 0, 0);
+    let pattern_edge3 = new_pattern_edge(2, 0, 2, 3, 0, 0);
+    let pattern_edge4 = new_pattern_edge(3, 0, 3, 0, 0, 0);
+    Pattern::try_from(vec![pattern_edge1, pattern_edge2, pattern_edge3, pattern_edge4]).unwrap()
+}
+
+/// The pattern looks like:
+///```text
+///  Person -> knows -> Person -> knows -> Person -> knows -> Person -> knows -> Person -> knows -> Person
+/// ```
+pub fn build_modern_pattern_case11() -> Pattern {
+    let pattern_edges: Vec<PatternEdge> = (0..5)
+        .map(|i| new_pattern_edge(i, 0, i, i + 1, 0, 0))
+        .collect();
+    Pattern::try_from(pattern_edges).unwrap()
+}
+
+/// The pattern looks like:
+///```text
+///  Person -> knows*1..2 -> Person
+/// ```
+pub fn build_modern_pattern_case12() -> Pattern {
+    let pattern_edge = new_pattern_edge(0, 0, 0, 1, 0, 0).with_hop_range(Some((1, 2)));
+    Pattern::try_from(vec![pattern_edge]).unwrap()
+}
+
+/// The pattern on the sample graph looks like:
+///```text
+///  Player -> lovedby -> Fan
+///    |                   |
+///  lovedby              buy
+///    v                   v
+///   Fan  ->   buy  ->  Ticket
+/// ```
+pub fn build_sample_pattern_case1() -> Pattern {
+    let pattern_edge1 = new_pattern_edge(0, 1, 0, 1, 1, 2);
+    let pattern_edge2 = new_pattern_edge(1, 1, 0, 3, 1, 2);
+    let pattern_edge3 = new_pattern_edge(2, 2, 1, 2, 2, 3);
+    let pattern_edge4 = new_pattern_edge(3, 2, 3, 2, 2, 3);
+    Pattern::try_from(vec![pattern_edge1, pattern_edge2, pattern_edge3, pattern_edge4]).unwrap()
+}
+
+/// The pattern on the sample graph looks like:
+///```text
+///  Player -> lovedby -> Fan -> buy -> Ticket
+/// ```
+/// where the ticket vertex has the smallest id
+pub fn build_sample_pattern_case2() -> Pattern {
+    let pattern_edge1 = new_pattern_edge(0, 1, 2, 1, 1, 2);
+    let pattern_edge2 = new_pattern_edge(1, 2, 1, 0, 2, 3);
+    Pattern::try_from(vec![pattern_edge1, pattern_edge2]).unwrap()
+}
+
 /// Pattern from ldbc schema file
 /// ```text
 ///     Person -> knows -> Person
@@ -829,6 +986,103 @@ pub fn build_ldbc_pattern_from_pb_case6() -> Result<Pattern, IrError> {
     Pattern::from_pb_pattern(&pattern, &ldbc_pattern_mata, &mut PlanMeta::default())
 }
 
+/// Fuzzy pattern from ldbc schema file and build from pb::Pattern message
+/// ```text
+///           Message
+///     likes/       \hasCreator
+///     Person -> knows -> Person
+/// ```
+/// Message is either Comment or Post, which results in two patterns
+pub fn build_ldbc_fuzzy_pattern_from_pb_case1() -> Result<Vec<Pattern>, IrError> {
+    let ldbc_pattern_mata = get_ldbc_pattern_meta();
+    // define pb pattern message
+    let expand_opr1 = pb::EdgeExpand {
+        v_tag: None,
+        direction: 0,                                              // out
+        params: Some(query_params(vec![12.into()], vec![], None)), // KNOWS
+        expand_opt: 0,
+        alias: None,
+    };
+    let expand_opr2 = pb::EdgeExpand {
+        v_tag: None,
+        direction: 0,                                              // out
+        params: Some(query_params(vec![13.into()], vec![], None)), // LIKES
+        expand_opt: 0,
+        alias: None,
+    };
+    let expand_opr3 = pb::EdgeExpand {
+        v_tag: None,
+        direction: 0,                                             // out
+        params: Some(query_params(vec![0.into()], vec![], None)), // HASCREATOR
+        expand_opt: 0,
+        alias: None,
+    };
+    let pattern = pb::Pattern {
+        sentences: vec![
+            pb::pattern::Sentence {
+                start: Some(TAG_A.into()),
+                binders: vec![pb::pattern::Binder {
+                    item: Some(pb::pattern::binder::Item::Edge(expand_opr1)),
+                }],
+                end: Some(TAG_C.into()),
+                join_kind: 0,
+            },
+            pb::pattern::Sentence {
+                start: Some(TAG_A.into()),
+                binders: vec![pb::pattern::Binder {
+                    item: Some(pb::pattern::binder::Item::Edge(expand_opr2)),
+                }],
+                end: Some(TAG_B.into()),
+                join_kind: 0,
+            },
+            pb::pattern::Sentence {
+                start: Some(TAG_B.into()),
+                binders: vec![pb::pattern::Binder {
+                    item: Some(pb::pattern::binder::Item::Edge(expand_opr3)),
+                }],
+                end: Some(TAG_C.into()),
+                join_kind: 0,
+            },
+        ],
+    };
+    Pattern::from_pb_fuzzy_pattern(&pattern, &ldbc_pattern_mata, &mut PlanMeta::default())
+}
+
+/// Fuzzy pattern from ldbc schema file and build from pb::Pattern message
+/// ```text
+///     Person -> studyAt|workAt -> Organisation
+/// ```
+/// The edge expand has two labels, which results in two patterns
+pub fn build_ldbc_fuzzy_pattern_from_pb_case2(
+    edge_labels: Vec<PatternLabelId>,
+) -> Result<Vec<Pattern>, IrError> {
+    let ldbc_pattern_mata = get_ldbc_pattern_meta();
+    // define pb pattern message
+    let expand_opr = pb::EdgeExpand {
+        v_tag: None,
+        direction: 0, // out
+        params: Some(query_params(
+            edge_labels
+                .into_iter()
+                .map(|edge_label| edge_label.into())
+                .collect(),
+            vec![],
+            None,
+        )),
+        expand_opt: 0,
+        alias: None,
+    };
+    let pattern = pb::Pattern {
+        sentences: vec![pb::pattern::Sentence {
+            start: Some(TAG_A.into()),
+            binders: vec![pb::pattern::Binder { item: Some(pb::pattern::binder::Item::Edge(expand_opr)) }],
+            end: Some(TAG_B.into()),
+            join_kind: 0,
+        }],
+    };
+    Pattern::from_pb_fuzzy_pattern(&pattern, &ldbc_pattern_mata, &mut PlanMeta::default())
+}
+
 pub fn build_ldbc_bi3() -> Result<Pattern, IrError> {
     let ldbc_pattern_mata = get_ldbc_pattern_meta();
     // define pb pattern message
@@ -49,3 +49,23 @@ pub fn get_ldbc_pattern_meta() -> PatternMeta {
     let ldbc_schema = read_ldbc_graph_schema();
     PatternMeta::from(ldbc_schema)
 }
+
+pub fn read_sample_graph_schema() -> Schema {
+    let sample_schema_file = match File::open("resource/test_graph/graph_schema/schema.json") {
+        Ok(file) => file,
+        Err(_) => match File::open("core/resource/test_graph/graph_schema/schema.json") {
+            Ok(file) => file,
+            Err(_) => File::open("../core/resource/test_graph/graph_schema/schema.json").unwrap(),
+        },
+    };
+    Schema::from_json(sample_schema_file).unwrap()
+}
+
+/// The pattern meta of the sample graph, which has 10000 coaches, players and fans, but only 100 tickets
+pub fn get_sample_pattern_meta() -> PatternMeta {
+    let mut sample_pattern_meta = PatternMeta::from(read_sample_graph_schema());
+    for (vertex_label, vertex_count) in [(0, 10000), (1, 10000), (2, 10000), (3, 100)] {
+        sample_pattern_meta.set_vertex_label_count(vertex_label, vertex_count);
+    }
+    sample_pattern_meta
+}
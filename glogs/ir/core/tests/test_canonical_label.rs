@@ -17,6 +17,17 @@ mod common;
 
 #[cfg(test)]
 mod tests {
+    use std::collections::BTreeSet;
+    use std::convert::TryFrom;
+
+    use ir_core::catalogue::canonical_label::GroupingAlgo;
+    use ir_core::catalogue::extend_step::{ExtendEdge, ExtendStep};
+    use ir_core::catalogue::pattern::{Pattern, PatternEdge, PatternVertex};
+    use ir_core::catalogue::PatternDirection;
+    use rand::rngs::StdRng;
+    use rand::seq::SliceRandom;
+    use rand::{Rng, SeedableRng};
+
     use crate::common::canonical_label_cases::*;
 
     #[test]
@@ -1521,4 +1532,103 @@ mod tests {
         assert_eq!(pattern.get_vertex_group(4).unwrap(), 0);
         assert_eq!(pattern.get_vertex_group(5).unwrap(), 0);
     }
+
+    #[test]
+    fn vertex_grouping_weisfeiler_lehman_same_as_pairwise() {
+        let patterns = get_canonical_label_case_patterns();
+        for pattern in patterns {
+            let pairwise_groups = pattern.get_vertex_groups_by(GroupingAlgo::Pairwise);
+            let weisfeiler_lehman_groups = pattern.get_vertex_groups_by(GroupingAlgo::WeisfeilerLehman);
+            assert_eq!(pairwise_groups, weisfeiler_lehman_groups);
+            for (v_id, v_group) in weisfeiler_lehman_groups {
+                assert_eq!(pattern.get_vertex_group(v_id), Some(v_group));
+            }
+        }
+    }
+
+    /// Build random small patterns vertex by vertex with `extend`, and compare them with the same
+    /// patterns labeled from scratch with their vertex ids shuffled
+    #[test]
+    fn canonical_labeling_after_extend_same_as_from_scratch() {
+        for seed in 0..500 {
+            let mut rng = StdRng::seed_from_u64(seed);
+            let vertices_num: usize = rng.gen_range(2..=6);
+            let vertex_labels: Vec<_> = (0..vertices_num)
+                .map(|_| rng.gen_range(0..2))
+                .collect();
+            let mut vertex_ids: Vec<usize> = (0..vertices_num).collect();
+            vertex_ids.shuffle(&mut rng);
+            let mut pattern = Pattern::from(PatternVertex::new(0, vertex_labels[0]));
+            let mut pattern_edges = vec![];
+            for target_v_id in 1..vertices_num {
+                let mut src_v_ids: Vec<usize> = (0..target_v_id).collect();
+                src_v_ids.shuffle(&mut rng);
+                src_v_ids.truncate(rng.gen_range(1..=target_v_id.min(3)));
+                let mut extend_edges = vec![];
+                for src_v_id in src_v_ids {
+                    let edge_label = rng.gen_range(0..2);
+                    let dir = if rng.gen_bool(0.5) { PatternDirection::Out } else { PatternDirection::In };
+                    let src_v_rank = pattern.get_vertex_rank(src_v_id).unwrap();
+                    extend_edges.push(ExtendEdge::new(src_v_rank, edge_label, dir));
+                    let src_vertex = PatternVertex::new(vertex_ids[src_v_id], vertex_labels[src_v_id]);
+                    let target_vertex =
+                        PatternVertex::new(vertex_ids[target_v_id], vertex_labels[target_v_id]);
+                    let (start_vertex, end_vertex) = match dir {
+                        PatternDirection::Out => (src_vertex, target_vertex),
+                        PatternDirection::In => (target_vertex, src_vertex),
+                    };
+                    pattern_edges.push(PatternEdge::new(
+                        pattern_edges.len(),
+                        edge_label,
+                        start_vertex,
+                        end_vertex,
+                    ));
+                }
+                let extend_step = ExtendStep::new(vertex_labels[target_v_id], extend_edges);
+                pattern = pattern.extend(&extend_step).unwrap();
+                let pattern_from_scratch = Pattern::try_from(pattern_edges.clone()).unwrap();
+                assert_eq!(pattern.encode_to(), pattern_from_scratch.encode_to());
+                for (v_id, v_group) in pattern.get_vertex_groups_by(GroupingAlgo::WeisfeilerLehman) {
+                    assert_eq!(pattern.get_vertex_group(v_id), Some(v_group));
+                    assert_eq!(pattern_from_scratch.get_vertex_group(vertex_ids[v_id]), Some(v_group));
+                }
+            }
+        }
+    }
+
+    /// The directed path 0 -> 1 -> 2 -> 3 tells all its vertices apart, while closing it to a cycle by
+    /// vertex 4 makes them all equivalent, which the groups seeded by the path must not prevent
+    #[test]
+    fn canonical_labeling_after_closing_cycle_same_as_from_scratch() {
+        let vertices: Vec<PatternVertex> = (0..5)
+            .map(|v_id| PatternVertex::new(v_id, 0))
+            .collect();
+        let path_edges: Vec<PatternEdge> = (0..3)
+            .map(|i| PatternEdge::new(i, 0, vertices[i], vertices[i + 1]))
+            .collect();
+        let path = Pattern::try_from(path_edges.clone()).unwrap();
+        assert_eq!(
+            (0..4)
+                .filter_map(|v_id| path.get_vertex_group(v_id))
+                .collect::<BTreeSet<_>>()
+                .len(),
+            4
+        );
+        let closing_edges = vec![
+            PatternEdge::new(3, 0, vertices[3], vertices[4]),
+            PatternEdge::new(4, 0, vertices[4], vertices[0]),
+        ];
+        let cycle = path
+            .extend_by_edges(closing_edges.iter())
+            .unwrap();
+        let cycle_from_scratch = Pattern::try_from(
+            path_edges
+                .into_iter()
+                .chain(closing_edges)
+                .collect::<Vec<_>>(),
+        )
+        .unwrap();
+        assert_eq!(cycle.encode_to(), cycle_from_scratch.encode_to());
+        assert!((0..5).all(|v_id| cycle.get_vertex_group(v_id) == Some(0)));
+    }
 }
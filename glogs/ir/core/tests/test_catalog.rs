@@ -17,9 +17,28 @@ mod common;
 
 #[cfg(test)]
 mod test {
+    use std::collections::BTreeMap;
+    use std::convert::TryFrom;
+    use std::path::Path;
+    use std::sync::Arc;
+
+    use graph_store::prelude::{GlobalStoreTrait, LabelId};
+    use ir_common::expr_parse::str_to_expr_pb;
     use ir_core::catalogue::catalog::Catalogue;
-    use ir_core::catalogue::catalog::PatMatPlanSpace;
+    use ir_core::catalogue::catalog::{IntersectOrder, MergeMode, PatMatPlanSpace};
+    use ir_core::catalogue::pattern::{Pattern, PatternEdge, PatternVertex};
+    use ir_core::catalogue::sample::{
+        count_pattern_exact, count_triangles, estimate_pattern_count_bound, q_error, EstimateConfig,
+        GraphDegreeStats, SamplingStrategy, MAX_COUNT_BOUND_VERTICES_NUM,
+    };
+    use ir_core::catalogue::sparsify::GraphStats;
+    use ir_core::catalogue::PatternDirection;
+    use ir_core::error::IrError;
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
 
+    use crate::common::canonical_label_cases::*;
+    use crate::common::graph_cases::*;
     use crate::common::pattern_cases::*;
     use crate::common::pattern_meta_cases::*;
 
@@ -31,6 +50,46 @@ mod test {
         assert_eq!(4, catalog.get_approaches_num());
     }
 
+    #[test]
+    fn test_catalog_entries_iter_by_label() {
+        let sample_pattern_meta = get_sample_pattern_meta();
+        let catalog = Catalogue::build_from_meta(&sample_pattern_meta, 2, 3);
+        assert_eq!(catalog.entries_iter().count(), 4);
+        let coach_label = sample_pattern_meta
+            .get_vertex_label_id("COACH")
+            .unwrap();
+        let coach_entries: Vec<_> = catalog
+            .entries_iter_by_label(coach_label)
+            .collect();
+        assert_eq!(coach_entries.len(), 1);
+        let coach_pattern = catalog
+            .get_pattern_weight(coach_entries[0])
+            .unwrap()
+            .get_pattern();
+        assert_eq!(coach_pattern.get_vertices_num(), 1);
+        assert_eq!(coach_pattern.get_max_vertex_label(), Some(coach_label));
+        assert_eq!(catalog.entries_iter_by_label(9).count(), 0);
+    }
+
+    #[test]
+    fn test_catalog_to_dot() {
+        let ldbc_graph_meta = get_ldbc_pattern_meta();
+        let catalog = Catalogue::build_from_meta(&ldbc_graph_meta, 2, 3);
+        let dot = catalog.to_dot();
+        assert!(dot.starts_with("digraph catalog {"));
+        let nodes_num = dot
+            .lines()
+            .filter(|line| line.contains(" [label=") && !line.contains(" -> "))
+            .count();
+        let edges_num = dot
+            .lines()
+            .filter(|line| line.contains(" -> "))
+            .count();
+        assert_eq!(nodes_num, catalog.get_patterns_num());
+        assert_eq!(edges_num, catalog.get_approaches_num());
+        assert!(dot.contains("extend\\nadjacency_count="));
+    }
+
     #[test]
     fn test_catalog_for_ldbc_graph() {
         let ldbc_graph_meta = get_ldbc_pattern_meta();
@@ -83,6 +142,61 @@ mod test {
         assert_eq!(9, catalog.get_approaches_num());
     }
 
+    #[test]
+    fn test_catalog_build_from_pattern_same_as_sequential() {
+        let sample_pattern = build_sample_pattern_case1();
+        assert_eq!(4, sample_pattern.get_vertices_num());
+        for plan_space in
+            [PatMatPlanSpace::ExtendWithIntersection, PatMatPlanSpace::BinaryJoin, PatMatPlanSpace::Hybrid]
+        {
+            let mut sequential_catalog = Catalogue::default();
+            sequential_catalog.set_plan_space(plan_space);
+            sequential_catalog.update_catalog_by_pattern(&sample_pattern);
+            let catalog = Catalogue::build_from_pattern(&sample_pattern, plan_space);
+            assert_eq!(catalog.get_patterns_num(), sequential_catalog.get_patterns_num());
+            assert_eq!(catalog.get_approaches_num(), sequential_catalog.get_approaches_num());
+        }
+    }
+
+    #[test]
+    fn test_catalog_prune_triangle() {
+        let triangle = build_ldbc_pattern_from_pb_case1().unwrap();
+        let mut catalog = Catalogue::build_from_pattern(&triangle, PatMatPlanSpace::ExtendWithIntersection);
+        assert!(catalog
+            .get_pattern_index(&triangle.encode_to())
+            .is_some());
+        catalog.prune(2, None);
+        assert!(catalog.get_patterns_num() > 0);
+        assert!(catalog.get_patterns_num() < 6);
+        assert!(catalog
+            .pattern_indices_iter()
+            .all(|pattern_index| {
+                let pattern = catalog
+                    .get_pattern_weight(pattern_index)
+                    .unwrap()
+                    .get_pattern();
+                pattern.get_vertices_num() <= 2
+                    && catalog.get_pattern_index(&pattern.encode_to()) == Some(pattern_index)
+            }));
+        assert!(catalog
+            .get_pattern_index(&triangle.encode_to())
+            .is_none());
+        assert!(catalog.entries_iter().all(|entry| catalog
+            .get_pattern_weight(entry)
+            .unwrap()
+            .get_pattern()
+            .get_vertices_num()
+            == 1));
+        // the remaining approaches only connect the remaining patterns
+        assert!(catalog.get_approaches_num() > 0);
+        assert!(catalog
+            .pattern_indices_iter()
+            .flat_map(|pattern_index| catalog.pattern_out_approaches_iter(pattern_index))
+            .all(|approach| catalog
+                .get_pattern_weight(approach.get_target_pattern_index())
+                .is_some()));
+    }
+
     #[test]
     fn test_catalog_for_ldbc_pattern_from_pb_case2() {
         let ldbc_pattern = build_ldbc_pattern_from_pb_case2().unwrap();
@@ -106,4 +220,881 @@ mod test {
         assert_eq!(15, catalog.get_patterns_num());
         assert_eq!(25, catalog.get_approaches_num());
     }
+
+    #[test]
+    fn test_catalog_update_pattern_weight_in_place() {
+        let ldbc_pattern = build_ldbc_pattern_from_pb_case1().unwrap();
+        let mut catalog =
+            Catalogue::build_from_pattern(&ldbc_pattern, PatMatPlanSpace::ExtendWithIntersection);
+        let pattern_index = catalog
+            .get_pattern_index(&ldbc_pattern.encode_to())
+            .unwrap();
+        let pattern_weight = catalog
+            .get_pattern_weight_mut(pattern_index)
+            .unwrap();
+        assert_eq!(0, pattern_weight.get_sample_size());
+        pattern_weight.set_count(100.0_f64.into());
+        pattern_weight.set_sample_size(10);
+        let pattern_weight = catalog
+            .get_pattern_weight(pattern_index)
+            .unwrap();
+        assert_eq!(100.0, pattern_weight.get_count().into_inner());
+        assert_eq!(10, pattern_weight.get_sample_size());
+        assert_eq!(ldbc_pattern.encode_to(), pattern_weight.get_pattern().encode_to());
+        assert_eq!(Some(pattern_index), catalog.get_pattern_index(&ldbc_pattern.encode_to()));
+    }
+
+    #[test]
+    fn test_catalog_merge_estimates() {
+        let ldbc_pattern = build_ldbc_pattern_from_pb_case1().unwrap();
+        let build_estimated_catalog = |count: f64| {
+            let mut catalog =
+                Catalogue::build_from_pattern(&ldbc_pattern, PatMatPlanSpace::ExtendWithIntersection);
+            let pattern_indices: Vec<_> = catalog.pattern_indices_iter().collect();
+            for pattern_index in pattern_indices {
+                catalog
+                    .get_pattern_weight_mut(pattern_index)
+                    .unwrap()
+                    .set_count(count.into());
+                let approaches: Vec<_> = catalog
+                    .pattern_out_approaches_iter(pattern_index)
+                    .collect();
+                for approach in approaches {
+                    if let Some(extend_weight) =
+                        catalog.get_extend_weight_mut(approach.get_approach_index())
+                    {
+                        extend_weight.set_adjacency_count(count.into());
+                    }
+                }
+            }
+            catalog
+        };
+        for (merge_mode, expected_count) in [(MergeMode::Sum, 300.0), (MergeMode::Mean, 150.0)] {
+            let mut catalog = build_estimated_catalog(100.0);
+            catalog.merge_estimates(&build_estimated_catalog(200.0), merge_mode);
+            assert_eq!(6, catalog.get_patterns_num());
+            assert_eq!(9, catalog.get_approaches_num());
+            let pattern_index = catalog
+                .get_pattern_index(&ldbc_pattern.encode_to())
+                .unwrap();
+            let pattern_weight = catalog
+                .get_pattern_weight(pattern_index)
+                .unwrap();
+            assert_eq!(expected_count, pattern_weight.get_count().into_inner());
+            for approach in catalog.pattern_in_approaches_iter(pattern_index) {
+                if let Some(extend_weight) = catalog.get_extend_weight(approach.get_approach_index()) {
+                    assert_eq!(expected_count, extend_weight.get_adjacency_count().into_inner());
+                }
+            }
+        }
+        // the patterns and approaches only in the other catalog are added as they are
+        let mut catalog = Catalogue::default();
+        catalog.merge_estimates(&build_estimated_catalog(200.0), MergeMode::Mean);
+        assert_eq!(6, catalog.get_patterns_num());
+        assert_eq!(9, catalog.get_approaches_num());
+        let pattern_index = catalog
+            .get_pattern_index(&ldbc_pattern.encode_to())
+            .unwrap();
+        assert_eq!(
+            200.0,
+            catalog
+                .get_pattern_weight(pattern_index)
+                .unwrap()
+                .get_count()
+                .into_inner()
+        );
+    }
+
+    #[test]
+    fn test_catalog_sensitivity_analysis_for_path() {
+        // the counts of the subpatterns of the path A -> B -> C -> D grow with their edges, except that
+        // B -> C -> D is slightly more than A -> B -> C, so the path is extended from A -> B -> C
+        let pattern = build_pattern_case10();
+        let is_bcd_path = |pattern: &Pattern| {
+            pattern.get_vertices_num() == 3
+                && pattern
+                    .vertices_iter()
+                    .all(|vertex| vertex.get_label() != 1)
+        };
+        let get_expected_count = |pattern: &Pattern, bcd_count: f64| {
+            if is_bcd_path(pattern) {
+                bcd_count
+            } else {
+                10.0_f64.powi(pattern.get_edges_num() as i32 + 1)
+            }
+        };
+        let build_catalog = |bcd_count: f64| {
+            let mut catalog =
+                Catalogue::build_from_pattern(&pattern, PatMatPlanSpace::ExtendWithIntersection);
+            let pattern_indices: Vec<_> = catalog.pattern_indices_iter().collect();
+            for &pattern_index in pattern_indices.iter() {
+                let pattern_weight = catalog
+                    .get_pattern_weight_mut(pattern_index)
+                    .unwrap();
+                let count = get_expected_count(pattern_weight.get_pattern(), bcd_count);
+                pattern_weight.set_count(count.into());
+            }
+            for &pattern_index in pattern_indices.iter() {
+                catalog.set_extend_count_infos(pattern_index);
+            }
+            catalog
+        };
+        let get_best_src_pattern_code = |catalog: &Catalogue| {
+            let pattern_index = catalog
+                .get_pattern_index(&pattern.encode_to())
+                .unwrap();
+            let best_approach = catalog
+                .get_pattern_weight(pattern_index)
+                .unwrap()
+                .get_best_approach()
+                .unwrap();
+            catalog
+                .get_pattern_weight(best_approach.get_src_pattern_index())
+                .unwrap()
+                .get_pattern()
+                .encode_to()
+        };
+        let bcd_count = 1050.0;
+        let mut catalog = build_catalog(bcd_count);
+        let sensitivity_scores = catalog.sensitivity_analysis(&pattern).unwrap();
+        assert_eq!(catalog.get_patterns_num(), sensitivity_scores.len());
+        assert!(sensitivity_scores
+            .values()
+            .all(|&score| (0.0..=1.0).contains(&score)));
+        assert!(sensitivity_scores.contains_key(&pattern.encode_to()));
+        // counts are restored after the analysis
+        for pattern_index in catalog.pattern_indices_iter() {
+            let pattern_weight = catalog
+                .get_pattern_weight(pattern_index)
+                .unwrap();
+            assert_eq!(
+                get_expected_count(pattern_weight.get_pattern(), bcd_count),
+                pattern_weight.get_count().into_inner()
+            );
+        }
+        // perturbing either of the 2-edge subpaths by 10% changes which one the path is extended from
+        let (bcd_paths, abc_paths): (Vec<Pattern>, Vec<Pattern>) = catalog
+            .pattern_indices_iter()
+            .map(|pattern_index| {
+                catalog
+                    .get_pattern_weight(pattern_index)
+                    .unwrap()
+                    .get_pattern()
+                    .clone()
+            })
+            .filter(|subpattern| subpattern.get_vertices_num() == 3)
+            .partition(is_bcd_path);
+        let bcd_code = bcd_paths[0].encode_to();
+        let abc_code = abc_paths[0].encode_to();
+        assert!(sensitivity_scores[&bcd_code] > 0.0);
+        assert!(sensitivity_scores[&abc_code] > 0.0);
+        catalog.set_best_approach_by_pattern(&pattern);
+        assert_eq!(get_best_src_pattern_code(&catalog), abc_code);
+        let mut perturbed_catalog = build_catalog(bcd_count * 0.9);
+        perturbed_catalog.set_best_approach_by_pattern(&pattern);
+        assert_eq!(get_best_src_pattern_code(&perturbed_catalog), bcd_code);
+        // the pattern must be in the catalogue
+        assert!(matches!(
+            catalog.sensitivity_analysis(&Pattern::from(PatternVertex::new(0, 10))),
+            Err(IrError::Unsupported(_))
+        ));
+    }
+
+    #[test]
+    fn test_q_error() {
+        assert_eq!(q_error(100.0, 10.0), 10.0);
+        assert_eq!(q_error(10.0, 100.0), 10.0);
+        assert_eq!(q_error(0.0, 0.0), 1.0);
+        assert_eq!(q_error(5.0, 0.0), f64::INFINITY);
+        assert_eq!(q_error(0.0, 5.0), f64::INFINITY);
+    }
+
+    #[test]
+    fn test_count_pattern_exact_on_modern_graph() {
+        let modern_graph = build_modern_graph();
+        assert_eq!(count_pattern_exact(&modern_graph, &build_modern_pattern_case1()), 4);
+        assert_eq!(count_pattern_exact(&modern_graph, &build_modern_pattern_case3()), 2);
+        assert_eq!(count_pattern_exact(&modern_graph, &build_modern_pattern_case4()), 4);
+        // only marko and josh, who knows each other, created lop together
+        assert_eq!(count_pattern_exact(&modern_graph, &build_modern_pattern_case5()), 1);
+    }
+
+    #[test]
+    fn test_estimate_pattern_count_bound_on_modern_graph() {
+        let modern_graph = build_modern_graph();
+        let graph_stats = GraphDegreeStats::from_graph(&modern_graph);
+        // marko knows 2 persons, and lop is created by 3 persons
+        assert_eq!(graph_stats.get_vertex_count(0), 4);
+        assert_eq!(graph_stats.get_max_degree(0, 0, PatternDirection::Out), 2);
+        assert_eq!(graph_stats.get_max_degree(1, 1, PatternDirection::In), 3);
+        assert_eq!(graph_stats.get_max_degree(1, 1, PatternDirection::Out), 0);
+        for pattern in [
+            build_modern_pattern_case1(),
+            build_modern_pattern_case3(),
+            build_modern_pattern_case4(),
+            build_modern_pattern_case5(),
+        ] {
+            let bound = estimate_pattern_count_bound(&pattern, &graph_stats).unwrap();
+            assert!(bound >= count_pattern_exact(&modern_graph, &pattern) as f64);
+        }
+        // the triangle is bounded by matching the 2 softwares, the at most 3 persons creating each of them,
+        // and then the at most 1 person knowing each creator
+        let triangle = build_modern_pattern_case5();
+        assert_eq!(estimate_pattern_count_bound(&triangle, &graph_stats).unwrap(), 6.0);
+        // the subsets of vertices are too many to bound a long path
+        let long_path = Pattern::try_from(
+            (0..MAX_COUNT_BOUND_VERTICES_NUM)
+                .map(|i| PatternEdge::new(i, 0, PatternVertex::new(i, 0), PatternVertex::new(i + 1, 0)))
+                .collect::<Vec<_>>(),
+        )
+        .unwrap();
+        assert!(matches!(
+            estimate_pattern_count_bound(&long_path, &graph_stats),
+            Err(IrError::Unsupported(_))
+        ));
+    }
+
+    #[test]
+    fn test_count_pattern_exact_with_edge_predicate_on_modern_graph() {
+        let modern_graph = build_modern_graph_with_properties();
+        let edge_predicate = str_to_expr_pb("@.weight > 0.5".to_string()).unwrap();
+        for (mut pattern, count, filtered_count) in
+            [(build_modern_pattern_case3(), 2, 1), (build_modern_pattern_case4(), 4, 1)]
+        {
+            assert_eq!(count_pattern_exact(&modern_graph, &pattern), count);
+            let edge_id = pattern.edges_iter().next().unwrap().get_id();
+            pattern.set_edge_predicate(edge_id, edge_predicate.clone());
+            assert_eq!(count_pattern_exact(&modern_graph, &pattern), filtered_count);
+        }
+    }
+
+    #[test]
+    fn test_count_pattern_exact_with_vertex_predicate_on_modern_graph() {
+        let modern_graph = build_modern_graph_with_properties();
+        let vertex_predicate = str_to_expr_pb("@.age > 30".to_string()).unwrap();
+        // filter the single person vertex
+        let mut pattern = build_modern_pattern_case1();
+        assert_eq!(count_pattern_exact(&modern_graph, &pattern), 4);
+        pattern.set_vertex_predicate(0, vertex_predicate.clone());
+        assert_eq!(count_pattern_exact(&modern_graph, &pattern), 2);
+        // filter the person known by another one, where only v4 is older than 30
+        let mut pattern = build_modern_pattern_case3();
+        assert_eq!(count_pattern_exact(&modern_graph, &pattern), 2);
+        pattern.set_vertex_predicate(1, vertex_predicate);
+        assert_eq!(count_pattern_exact(&modern_graph, &pattern), 1);
+    }
+
+    #[test]
+    fn test_count_pattern_with_cross_vertex_predicate_on_modern_graph() {
+        let modern_graph = Arc::new(build_modern_graph_with_properties());
+        // the filter comparing the properties of two vertices is not evaluated when sampling
+        let cross_vertex_predicate = str_to_expr_pb("@a.age > @b.age".to_string()).unwrap();
+        let pattern = build_modern_pattern_case3();
+        let mut filtered_pattern = pattern.clone();
+        filtered_pattern.set_vertex_predicate(1, cross_vertex_predicate.clone());
+        assert_eq!(
+            count_pattern_exact(&modern_graph, &filtered_pattern),
+            count_pattern_exact(&modern_graph, &pattern)
+        );
+        let triangle = build_modern_pattern_case5();
+        let mut filtered_triangle = triangle.clone();
+        let vertex_ids: Vec<_> = triangle
+            .vertices_iter()
+            .map(|vertex| vertex.get_id())
+            .collect();
+        for vertex_id in vertex_ids {
+            filtered_triangle.set_vertex_predicate(vertex_id, cross_vertex_predicate.clone());
+        }
+        assert_eq!(
+            count_triangles(&modern_graph, &filtered_triangle, 1.0),
+            count_triangles(&modern_graph, &triangle, 1.0)
+        );
+        // the sub tasks extending the records skip the filter as well
+        let mut catalog =
+            Catalogue::build_from_pattern(&filtered_triangle, PatMatPlanSpace::ExtendWithIntersection);
+        catalog.estimate_graph(modern_graph, &EstimateConfig::default());
+        assert!(catalog.estimate_pattern_count(&filtered_triangle) > 0.0.into());
+    }
+
+    #[test]
+    fn test_catalog_estimate_graph_skips_unsupported_predicates() {
+        let modern_graph = Arc::new(build_modern_graph_with_properties());
+        let pattern = build_modern_pattern_case3();
+        let mut catalog = Catalogue::build_from_pattern(&pattern, PatMatPlanSpace::ExtendWithIntersection);
+        catalog.estimate_graph(Arc::clone(&modern_graph), &EstimateConfig::default());
+        let pattern_count = catalog.estimate_pattern_count(&pattern);
+        // the predicates referring to tags or with arithmetics are not evaluated by the sampler
+        let mut filtered_pattern = pattern.clone();
+        let edge_id = filtered_pattern
+            .edges_iter()
+            .next()
+            .unwrap()
+            .get_id();
+        filtered_pattern
+            .set_edge_predicate(edge_id, str_to_expr_pb("@.weight + 1 > 1.5".to_string()).unwrap());
+        filtered_pattern.set_vertex_predicate(1, str_to_expr_pb("@a.age > 30".to_string()).unwrap());
+        let mut filtered_catalog =
+            Catalogue::build_from_pattern(&filtered_pattern, PatMatPlanSpace::ExtendWithIntersection);
+        filtered_catalog.estimate_graph(modern_graph, &EstimateConfig::default());
+        assert_eq!(filtered_catalog.estimate_pattern_count(&filtered_pattern), pattern_count);
+    }
+
+    #[test]
+    fn test_catalog_infer_sparsify_rates_on_modern_graph() {
+        let modern_graph = Arc::new(build_modern_graph());
+        let modern_pattern = build_modern_pattern_case5();
+        let mut catalog =
+            Catalogue::build_from_pattern(&modern_pattern, PatMatPlanSpace::ExtendWithIntersection);
+        catalog.estimate_graph(Arc::clone(&modern_graph), &EstimateConfig::default());
+        let sparsify_rate = catalog.infer_sparsify_rates(&modern_graph);
+        // person -knows-> person, and person -created-> software
+        assert_eq!(sparsify_rate.len(), 2);
+        assert!(sparsify_rate.contains_key(&(0, 0, 0)));
+        assert!(sparsify_rate.contains_key(&(0, 1, 1)));
+        assert!(sparsify_rate
+            .values()
+            .all(|&rate| (rate - 1.0).abs() < 1e-6));
+        let pattern_index = catalog
+            .get_pattern_index(&modern_pattern.encode_to())
+            .unwrap();
+        let pattern_count = catalog
+            .get_pattern_weight(pattern_index)
+            .unwrap()
+            .get_count();
+        catalog.apply_sparsify_rates(&sparsify_rate);
+        let rescaled_pattern_count = catalog
+            .get_pattern_weight(pattern_index)
+            .unwrap()
+            .get_count();
+        assert!((rescaled_pattern_count.into_inner() - pattern_count.into_inner()).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_catalog_cross_validate_on_modern_graph() {
+        let modern_graph = Arc::new(build_modern_graph());
+        let modern_pattern = build_modern_pattern_case4();
+        let mut catalog =
+            Catalogue::build_from_pattern(&modern_pattern, PatMatPlanSpace::ExtendWithIntersection);
+        let q_errors = catalog.cross_validate(modern_graph, 2, 1.0);
+        assert_eq!(catalog.get_patterns_num(), q_errors.len());
+        assert!(q_errors.values().all(|&q_error| q_error >= 1.0));
+        // 4 persons split evenly, so the single person pattern is estimated exactly
+        let person_pattern = build_modern_pattern_case1();
+        assert_eq!(q_errors[&person_pattern.encode_to()], 1.0);
+    }
+
+    #[test]
+    fn test_catalog_estimate_graph_with_seed_is_reproducible() {
+        let modern_graph = Arc::new(build_modern_graph());
+        let modern_pattern = build_modern_pattern_case4();
+        let estimate_pattern_counts = |seed: Option<u64>| {
+            let mut catalog =
+                Catalogue::build_from_pattern(&modern_pattern, PatMatPlanSpace::ExtendWithIntersection);
+            // with the default sampling strategy
+            catalog.estimate_graph(
+                Arc::clone(&modern_graph),
+                &EstimateConfig::default()
+                    .with_rate(0.5)
+                    .with_thread_num(2)
+                    .with_seed(seed),
+            );
+            catalog
+                .pattern_indices_iter()
+                .map(|pattern_index| {
+                    let pattern_weight = catalog
+                        .get_pattern_weight(pattern_index)
+                        .unwrap();
+                    (pattern_weight.get_pattern().encode_to(), pattern_weight.get_count())
+                })
+                .collect::<BTreeMap<Vec<u8>, _>>()
+        };
+        let pattern_counts = estimate_pattern_counts(Some(12345));
+        assert!(!pattern_counts.is_empty());
+        for _ in 0..3 {
+            assert_eq!(estimate_pattern_counts(Some(12345)), pattern_counts);
+        }
+    }
+
+    #[test]
+    fn test_catalog_estimate_graph_counts_single_vertices_exactly() {
+        let modern_graph = Arc::new(build_modern_graph());
+        let modern_pattern = build_modern_pattern_case4();
+        let mut catalog =
+            Catalogue::build_from_pattern(&modern_pattern, PatMatPlanSpace::ExtendWithIntersection);
+        catalog.estimate_graph(
+            Arc::clone(&modern_graph),
+            &EstimateConfig::default()
+                .with_rate(0.5)
+                .with_limit(Some(1))
+                .with_thread_num(2)
+                .with_seed(Some(12345))
+                .with_strategy(SamplingStrategy::Reservoir),
+        );
+        assert!(catalog.entries_iter().next().is_some());
+        for entry in catalog.entries_iter() {
+            let pattern_weight = catalog.get_pattern_weight(entry).unwrap();
+            let vertex_label = pattern_weight
+                .get_pattern()
+                .vertices_iter()
+                .next()
+                .unwrap()
+                .get_label();
+            let vertex_count = modern_graph.count_all_vertices(Some(&vec![vertex_label as LabelId]));
+            assert_eq!(pattern_weight.get_count().into_inner(), vertex_count as f64);
+        }
+    }
+
+    #[test]
+    fn test_catalog_estimate_graph_partitioned() {
+        let modern_graph = Arc::new(build_modern_graph());
+        let modern_pattern = build_modern_pattern_case4();
+        let mut catalog =
+            Catalogue::build_from_pattern(&modern_pattern, PatMatPlanSpace::ExtendWithIntersection);
+        let mut partitioned_catalog = catalog.clone();
+        catalog.estimate_graph(Arc::clone(&modern_graph), &EstimateConfig::default().with_thread_num(2));
+        // two disjoint copies of the modern graph
+        partitioned_catalog.estimate_graph_partitioned(
+            vec![Arc::clone(&modern_graph), Arc::clone(&modern_graph)],
+            &EstimateConfig::default().with_thread_num(2),
+        );
+        for pattern_index in catalog.pattern_indices_iter() {
+            let pattern_weight = catalog
+                .get_pattern_weight(pattern_index)
+                .unwrap();
+            let partitioned_pattern_weight = partitioned_catalog
+                .get_pattern_weight(pattern_index)
+                .unwrap();
+            assert!(pattern_weight.get_count().into_inner() > 0.0);
+            assert_eq!(
+                partitioned_pattern_weight
+                    .get_count()
+                    .into_inner(),
+                pattern_weight.get_count().into_inner() * 2.0
+            );
+        }
+        for entry in catalog.entries_iter() {
+            assert_eq!(
+                partitioned_catalog
+                    .get_pattern_weight(entry)
+                    .unwrap()
+                    .get_sample_size(),
+                catalog
+                    .get_pattern_weight(entry)
+                    .unwrap()
+                    .get_sample_size()
+                    * 2
+            );
+        }
+    }
+
+    #[test]
+    fn test_catalog_estimate_graph_variance_across_sampling_strategies() {
+        let modern_graph = Arc::new(build_modern_graph());
+        let modern_pattern = build_modern_pattern_case4();
+        let person_pattern = build_modern_pattern_case1();
+        // Return the estimated count of the pattern, and the sample size of the person pattern
+        let estimate = |rate: f64, strategy: SamplingStrategy, seed: u64| {
+            let mut catalog =
+                Catalogue::build_from_pattern(&modern_pattern, PatMatPlanSpace::ExtendWithIntersection);
+            catalog.estimate_graph(
+                Arc::clone(&modern_graph),
+                &EstimateConfig::default()
+                    .with_rate(rate)
+                    .with_seed(Some(seed))
+                    .with_strategy(strategy),
+            );
+            let get_pattern_weight = |pattern: &Pattern| {
+                let pattern_index = catalog
+                    .get_pattern_index(&pattern.encode_to())
+                    .unwrap();
+                catalog
+                    .get_pattern_weight(pattern_index)
+                    .unwrap()
+                    .clone()
+            };
+            (
+                get_pattern_weight(&modern_pattern)
+                    .get_count()
+                    .into_inner(),
+                get_pattern_weight(&person_pattern).get_sample_size() as f64,
+            )
+        };
+        let variance = |values: &[f64]| {
+            let mean = values.iter().sum::<f64>() / values.len() as f64;
+            values
+                .iter()
+                .map(|value| (value - mean).powi(2))
+                .sum::<f64>()
+                / values.len() as f64
+        };
+        let strategies =
+            [SamplingStrategy::Stride, SamplingStrategy::Reservoir, SamplingStrategy::Bernoulli];
+        // Without sampling, all strategies give the exact count
+        let (exact_count, _) = estimate(1.0, SamplingStrategy::Stride, 0);
+        for &strategy in strategies.iter() {
+            assert_eq!(estimate(1.0, strategy, 0).0, exact_count);
+        }
+        // With sampling, compare the variances over the runs seeded from a fixed seed
+        let mut rng = StdRng::seed_from_u64(2022);
+        let run_seeds: Vec<u64> = (0..20).map(|_| rng.gen()).collect();
+        let get_variances = |strategy: SamplingStrategy| {
+            let (counts, sample_sizes): (Vec<f64>, Vec<f64>) = run_seeds
+                .iter()
+                .map(|&seed| estimate(0.5, strategy, seed))
+                .unzip();
+            (variance(&counts), variance(&sample_sizes))
+        };
+        let variances: Vec<(f64, f64)> = strategies
+            .iter()
+            .map(|&strategy| get_variances(strategy))
+            .collect();
+        for (&strategy, &(count_variance, _)) in strategies.iter().zip(variances.iter()) {
+            assert!(count_variance.is_finite());
+            // The same fixed seed gives the same variance
+            assert_eq!(get_variances(strategy).0, count_variance);
+        }
+        // Stride and reservoir sampling keep exactly 2 of the 4 persons, while bernoulli sampling only
+        // keeps 2 persons in expectation, whose sample size varies as well
+        assert_eq!(variances[0].1, 0.0);
+        assert_eq!(variances[1].1, 0.0);
+        assert!(variances[2].1 > 0.0);
+    }
+
+    #[test]
+    fn test_catalog_estimate_graph_keeps_min_seeds_per_label() {
+        let modern_graph = Arc::new(build_modern_graph());
+        let modern_pattern = build_modern_pattern_case4();
+        // 4 persons and 2 softwares in the modern graph
+        let software_pattern = build_modern_pattern_case2();
+        let get_software_sample_size = |min_seeds_per_label: usize| {
+            let mut catalog =
+                Catalogue::build_from_pattern(&modern_pattern, PatMatPlanSpace::ExtendWithIntersection);
+            catalog.estimate_graph(
+                Arc::clone(&modern_graph),
+                &EstimateConfig::default()
+                    .with_rate(0.25)
+                    .with_min_seeds_per_label(min_seeds_per_label),
+            );
+            let pattern_index = catalog
+                .get_pattern_index(&software_pattern.encode_to())
+                .unwrap();
+            catalog
+                .get_pattern_weight(pattern_index)
+                .unwrap()
+                .get_sample_size()
+        };
+        // Without stratification, the rare label gets no seeds at all
+        assert_eq!(get_software_sample_size(0), 0);
+        assert!(get_software_sample_size(1) >= 1);
+        assert!(get_software_sample_size(2) >= 2);
+        // The number of seeds is bounded by the number of vertices
+        assert_eq!(get_software_sample_size(10), 2);
+    }
+
+    #[test]
+    fn test_catalog_estimate_graph_report_per_level() {
+        let modern_graph = Arc::new(build_modern_graph());
+        let modern_pattern = build_modern_pattern_case5();
+        let mut catalog =
+            Catalogue::build_from_pattern(&modern_pattern, PatMatPlanSpace::ExtendWithIntersection);
+        let report = catalog.estimate_graph(modern_graph, &EstimateConfig::default());
+        // The triangle is counted level by level from the single vertices
+        let level_reports = report.get_level_reports();
+        let levels: Vec<usize> = level_reports
+            .iter()
+            .map(|level_report| level_report.get_level())
+            .collect();
+        assert_eq!(levels, vec![0, 1, 2]);
+        let patterns_num: usize = level_reports
+            .iter()
+            .map(|level_report| level_report.get_patterns_num())
+            .sum();
+        assert_eq!(patterns_num, catalog.get_patterns_num());
+        // All the 4 persons are sampled as the start records
+        assert_eq!(level_reports[0].get_max_records_num(), 4);
+        assert!(report.get_total_elapsed() >= level_reports[0].get_elapsed());
+    }
+
+    #[test]
+    fn test_catalog_estimate_graph_random_walk_on_triangle() {
+        let modern_graph = Arc::new(build_modern_graph());
+        let modern_pattern = build_modern_pattern_case5();
+        let get_pattern_counts = |catalog: &Catalogue| {
+            catalog
+                .pattern_indices_iter()
+                .map(|pattern_index| {
+                    let pattern_weight = catalog
+                        .get_pattern_weight(pattern_index)
+                        .unwrap();
+                    (pattern_weight.get_pattern().encode_to(), pattern_weight.get_count().into_inner())
+                })
+                .collect::<BTreeMap<Vec<u8>, f64>>()
+        };
+        let mut exact_catalog =
+            Catalogue::build_from_pattern(&modern_pattern, PatMatPlanSpace::ExtendWithIntersection);
+        exact_catalog.estimate_graph(Arc::clone(&modern_graph), &EstimateConfig::default());
+        let exact_counts = get_pattern_counts(&exact_catalog);
+        let mut random_walk_catalog =
+            Catalogue::build_from_pattern(&modern_pattern, PatMatPlanSpace::ExtendWithIntersection);
+        random_walk_catalog.estimate_graph_random_walk(Arc::clone(&modern_graph), 20000, Some(12345));
+        let random_walk_counts = get_pattern_counts(&random_walk_catalog);
+        assert_eq!(random_walk_counts.len(), exact_counts.len());
+        let triangle_count = exact_counts[&modern_pattern.encode_to()];
+        assert!(triangle_count > 0.0);
+        for (pattern_code, &exact_count) in exact_counts.iter() {
+            let random_walk_count = random_walk_counts[pattern_code];
+            assert!((random_walk_count - exact_count).abs() <= 0.2 * exact_count);
+        }
+    }
+
+    #[test]
+    fn test_catalog_count_ci_of_exact_estimation() {
+        let modern_graph = Arc::new(build_modern_graph());
+        let modern_pattern = build_modern_pattern_case5();
+        let mut catalog =
+            Catalogue::build_from_pattern(&modern_pattern, PatMatPlanSpace::ExtendWithIntersection);
+        catalog.estimate_graph(modern_graph, &EstimateConfig::default().with_thread_num(2));
+        for pattern_index in catalog.pattern_indices_iter() {
+            let pattern_weight = catalog
+                .get_pattern_weight(pattern_index)
+                .unwrap();
+            let count = pattern_weight.get_count().into_inner();
+            assert_eq!(Some(0.0), pattern_weight.get_count_variance());
+            assert_eq!(Some((count, count)), catalog.get_pattern_count_ci(pattern_index, 1.96));
+        }
+    }
+
+    #[test]
+    fn test_catalog_save_and_load() {
+        let modern_graph = Arc::new(build_modern_graph());
+        let modern_pattern = build_modern_pattern_case5();
+        let mut catalog =
+            Catalogue::build_from_pattern(&modern_pattern, PatMatPlanSpace::ExtendWithIntersection);
+        catalog.estimate_graph(modern_graph, &EstimateConfig::default());
+        catalog.set_best_approach_by_pattern(&modern_pattern);
+        catalog.set_intersect_order(IntersectOrder::Selectivity);
+        let catalog_path = std::env::temp_dir().join("test_catalog_save_and_load.bincode");
+        catalog.save(&catalog_path).unwrap();
+        let loaded_catalog = Catalogue::load(&catalog_path).unwrap();
+        std::fs::remove_file(&catalog_path).unwrap();
+        assert_eq!(loaded_catalog.get_patterns_num(), catalog.get_patterns_num());
+        assert_eq!(loaded_catalog.get_approaches_num(), catalog.get_approaches_num());
+        assert_eq!(loaded_catalog.get_intersect_order(), IntersectOrder::Selectivity);
+        for pattern_index in catalog.pattern_indices_iter() {
+            let pattern_weight = catalog
+                .get_pattern_weight(pattern_index)
+                .unwrap();
+            let loaded_pattern_weight = loaded_catalog
+                .get_pattern_weight(pattern_index)
+                .unwrap();
+            let pattern_code = pattern_weight.get_pattern().encode_to();
+            assert_eq!(loaded_pattern_weight.get_pattern().encode_to(), pattern_code);
+            assert_eq!(loaded_pattern_weight.get_count(), pattern_weight.get_count());
+            assert_eq!(loaded_pattern_weight.get_best_approach(), pattern_weight.get_best_approach());
+            assert_eq!(loaded_catalog.get_pattern_index(&pattern_code), Some(pattern_index));
+        }
+    }
+
+    #[test]
+    fn test_catalog_diff_estimates_against_itself() {
+        let modern_graph = Arc::new(build_modern_graph());
+        let modern_pattern = build_modern_pattern_case5();
+        let mut catalog =
+            Catalogue::build_from_pattern(&modern_pattern, PatMatPlanSpace::ExtendWithIntersection);
+        catalog.estimate_graph(modern_graph, &EstimateConfig::default());
+        let catalog_path = std::env::temp_dir().join("test_catalog_diff_estimates_against_itself.bincode");
+        catalog.export(&catalog_path).unwrap();
+        let imported_catalog = Catalogue::import(&catalog_path).unwrap();
+        std::fs::remove_file(&catalog_path).unwrap();
+        let diffs = catalog.diff(&imported_catalog, None);
+        assert_eq!(diffs.len(), catalog.get_patterns_num());
+        for diff in diffs.iter() {
+            assert_eq!(diff.get_count(), diff.get_other_count());
+            assert_eq!(diff.get_ratio(), 1.0);
+            assert_eq!(diff.get_divergence(), 1.0);
+            assert_eq!(diff.get_similarity(), 1.0);
+        }
+    }
+
+    #[test]
+    fn test_catalog_diff_matches_similar_patterns() {
+        let triangle = build_modern_pattern_case5();
+        let triangle_catalog =
+            Catalogue::build_from_pattern(&triangle, PatMatPlanSpace::ExtendWithIntersection);
+        let triangle_edge = triangle.edges_iter().next().unwrap().clone();
+        let edge_pattern = Pattern::try_from(vec![triangle_edge]).unwrap();
+        let edge_catalog =
+            Catalogue::build_from_pattern(&edge_pattern, PatMatPlanSpace::ExtendWithIntersection);
+        // without min_similarity, only patterns of the same code are matched
+        let exact_diffs = triangle_catalog.diff(&edge_catalog, None);
+        assert!(exact_diffs.len() < triangle_catalog.get_patterns_num());
+        assert!(exact_diffs
+            .iter()
+            .all(|diff| diff.get_pattern().encode_to() == diff.get_other_pattern().encode_to()
+                && diff.get_similarity() == 1.0));
+        // otherwise, every pattern is matched with its most similar pattern
+        let similar_diffs = triangle_catalog.diff(&edge_catalog, Some(0.0));
+        assert_eq!(similar_diffs.len(), triangle_catalog.get_patterns_num());
+        let triangle_diff = similar_diffs
+            .iter()
+            .find(|diff| diff.get_pattern().encode_to() == triangle.encode_to())
+            .unwrap();
+        assert!(triangle_diff.get_similarity() > 0.0 && triangle_diff.get_similarity() < 1.0);
+        assert_eq!(
+            triangle_diff.get_similarity(),
+            Pattern::hash_wl(&triangle, triangle_diff.get_other_pattern(), 3)
+        );
+        // a too high min_similarity falls back to exact matching
+        assert_eq!(
+            triangle_catalog
+                .diff(&edge_catalog, Some(1.0))
+                .len(),
+            exact_diffs.len()
+        );
+    }
+
+    #[test]
+    fn test_catalog_estimate_graph_resumes_from_checkpoint() {
+        let modern_graph = Arc::new(build_modern_graph());
+        let modern_pattern = build_modern_pattern_case9();
+        let checkpoint_dir = std::env::temp_dir().join("test_catalog_estimate_graph_checkpoint");
+        let _ = std::fs::remove_dir_all(&checkpoint_dir);
+        let get_pattern_counts = |catalog: &Catalogue| {
+            catalog
+                .pattern_indices_iter()
+                .map(|pattern_index| {
+                    let pattern_weight = catalog
+                        .get_pattern_weight(pattern_index)
+                        .unwrap();
+                    (pattern_weight.get_pattern().encode_to(), pattern_weight.get_count())
+                })
+                .collect::<BTreeMap<Vec<u8>, _>>()
+        };
+        let estimate_pattern_counts = |checkpoint_dir: Option<&Path>, resume_from: Option<&Path>| {
+            let mut catalog =
+                Catalogue::build_from_pattern(&modern_pattern, PatMatPlanSpace::ExtendWithIntersection);
+            catalog
+                .estimate_graph_with_checkpoint(
+                    Arc::clone(&modern_graph),
+                    &EstimateConfig::default().with_rate(0.5),
+                    checkpoint_dir,
+                    resume_from,
+                )
+                .unwrap();
+            get_pattern_counts(&catalog)
+        };
+        let pattern_counts = estimate_pattern_counts(Some(&checkpoint_dir), None);
+        // the start patterns, and the patterns of 2, 3 and 4 vertices
+        for level in 0..4 {
+            assert!(checkpoint_dir
+                .join(format!("level_{}.checkpoint", level))
+                .exists());
+        }
+        for level in 0..3 {
+            let checkpoint_path = checkpoint_dir.join(format!("level_{}.checkpoint", level));
+            assert_eq!(estimate_pattern_counts(None, Some(&checkpoint_path)), pattern_counts);
+        }
+        std::fs::remove_dir_all(&checkpoint_dir).unwrap();
+    }
+
+    #[test]
+    fn test_catalog_update_by_pattern_and_estimate_keeps_original_counts() {
+        let modern_graph = Arc::new(build_modern_graph());
+        let path_pattern = build_modern_pattern_case6();
+        let triangle_pattern = build_modern_pattern_case5();
+        let get_pattern_counts = |catalog: &Catalogue| {
+            catalog
+                .pattern_indices_iter()
+                .map(|pattern_index| {
+                    let pattern_weight = catalog
+                        .get_pattern_weight(pattern_index)
+                        .unwrap();
+                    (pattern_weight.get_pattern().encode_to(), pattern_weight.get_count())
+                })
+                .collect::<BTreeMap<Vec<u8>, _>>()
+        };
+        let mut catalog =
+            Catalogue::build_from_pattern(&path_pattern, PatMatPlanSpace::ExtendWithIntersection);
+        // keep the sampled records to be reused by the update
+        let config = EstimateConfig::default().with_keep_sampled_records(true);
+        catalog.estimate_graph(Arc::clone(&modern_graph), &config);
+        let original_counts = get_pattern_counts(&catalog);
+        let new_pattern_indices = catalog.update_catalog_by_pattern_and_estimate(
+            &triangle_pattern,
+            Arc::clone(&modern_graph),
+            &config,
+        );
+        let triangle_index = catalog
+            .get_pattern_index(&triangle_pattern.encode_to())
+            .unwrap();
+        assert!(new_pattern_indices.contains(&triangle_index));
+        let updated_counts = get_pattern_counts(&catalog);
+        assert_eq!(updated_counts.len(), original_counts.len() + new_pattern_indices.len());
+        for (pattern_code, count) in original_counts.iter() {
+            assert_eq!(updated_counts[pattern_code], *count);
+        }
+        // the new patterns are estimated as if the catalog were built from the triangle directly
+        let mut triangle_catalog =
+            Catalogue::build_from_pattern(&triangle_pattern, PatMatPlanSpace::ExtendWithIntersection);
+        triangle_catalog.estimate_graph(modern_graph, &EstimateConfig::default());
+        let triangle_counts = get_pattern_counts(&triangle_catalog);
+        assert!(triangle_counts[&triangle_pattern.encode_to()] > 0.0.into());
+        for pattern_index in new_pattern_indices {
+            let pattern_code = catalog
+                .get_pattern_weight(pattern_index)
+                .unwrap()
+                .get_pattern()
+                .encode_to();
+            assert_eq!(updated_counts[&pattern_code], triangle_counts[&pattern_code]);
+        }
+    }
+
+    #[test]
+    fn test_catalog_verify_no_code_collisions() {
+        let patterns = get_canonical_label_case_patterns();
+        // The fixtures are built with random vertex ids, so every build gives a relabeled pattern
+        for (pattern, relabeled_pattern) in patterns
+            .iter()
+            .zip(get_canonical_label_case_patterns())
+        {
+            assert!(pattern.is_structurally_isomorphic_to(&relabeled_pattern));
+        }
+        for pattern1 in patterns.iter() {
+            for pattern2 in patterns.iter() {
+                assert_eq!(
+                    pattern1.is_structurally_isomorphic_to(pattern2),
+                    pattern1.encode_to() == pattern2.encode_to()
+                );
+            }
+        }
+        for pattern in patterns.iter().take(12) {
+            let catalog = Catalogue::build_from_pattern(pattern, PatMatPlanSpace::Hybrid);
+            assert!(catalog.verify_no_code_collisions().is_ok());
+        }
+    }
+
+    #[test]
+    fn test_dump_modern_graph_stats() {
+        let modern_graph = build_modern_graph();
+        let graph_stats = GraphStats::from_graph(&modern_graph);
+        let stats_path = std::env::temp_dir().join("test_dump_modern_graph_stats.json");
+        graph_stats.export(&stats_path).unwrap();
+        let imported_graph_stats = GraphStats::import(&stats_path).unwrap();
+        std::fs::remove_file(&stats_path).unwrap();
+        assert_eq!(imported_graph_stats, graph_stats);
+        // vertex labels: person 0, software 1; edge labels: knows 0, created 1
+        for label in [0, 1] {
+            let vertex_count = modern_graph.count_all_vertices(Some(&vec![label]));
+            assert_eq!(imported_graph_stats.get_vertex_count(label), vertex_count as f64);
+            let edge_count = modern_graph.count_all_edges(Some(&vec![label]));
+            let dumped_edge_count: f64 = [(0, 0), (0, 1), (1, 0), (1, 1)]
+                .iter()
+                .map(|&(src_label, dst_label)| {
+                    imported_graph_stats.get_edge_count(src_label, label, dst_label)
+                })
+                .sum();
+            assert_eq!(dumped_edge_count, edge_count as f64);
+        }
+        assert_eq!(imported_graph_stats.get_edge_count(0, 1, 1), 4.0);
+    }
 }
@@ -17,6 +17,8 @@ mod common;
 
 #[cfg(test)]
 mod tests {
+    use std::convert::TryFrom;
+
     use ir_core::catalogue::pattern::*;
 
     use crate::common::pattern_cases::*;
@@ -355,4 +357,91 @@ mod tests {
         let pattern_code_from_decode = pattern_from_decode.encode_to();
         assert_eq!(pattern_code1, pattern_code_from_decode);
     }
+
+    #[test]
+    fn test_encode_undirected_of_reversed_triangle() {
+        // a cycle of three vertices with different labels, which is not isomorphic to its reverse
+        let v0 = PatternVertex::new(0, 0);
+        let v1 = PatternVertex::new(1, 1);
+        let v2 = PatternVertex::new(2, 2);
+        let triangle = Pattern::try_from(vec![
+            PatternEdge::new(0, 0, v0, v1),
+            PatternEdge::new(1, 0, v1, v2),
+            PatternEdge::new(2, 0, v2, v0),
+        ])
+        .unwrap();
+        let reversed_triangle = Pattern::try_from(vec![
+            PatternEdge::new(0, 0, v1, v0),
+            PatternEdge::new(1, 0, v2, v1),
+            PatternEdge::new(2, 0, v0, v2),
+        ])
+        .unwrap();
+        assert_ne!(triangle.encode_to(), reversed_triangle.encode_to());
+        assert_eq!(triangle.encode_undirected(), reversed_triangle.encode_undirected());
+        assert_ne!(triangle.encode_to(), triangle.encode_undirected());
+        // the code of a pattern with only undirected edges is kept
+        let undirected_triangle = Pattern::decode_from(&triangle.encode_undirected()).unwrap();
+        assert_eq!(undirected_triangle.encode_to(), undirected_triangle.encode_undirected());
+    }
+
+    #[test]
+    fn test_encode_decode_pattern_with_isolated_vertex() {
+        let v0 = PatternVertex::new(0, 0);
+        let v1 = PatternVertex::new(1, 1);
+        let v2 = PatternVertex::new(2, 0);
+        let edge = PatternEdge::new(0, 1, v0, v1);
+        let pattern = Pattern::from_parts(vec![v2], vec![edge.clone()]).unwrap();
+        let edge_pattern = Pattern::try_from(vec![edge]).unwrap();
+        // the isolated vertex is part of the code
+        assert_ne!(pattern.encode_to(), edge_pattern.encode_to());
+        let pattern_from_decode = Pattern::decode_from(&pattern.encode_to()).unwrap();
+        assert_eq!(pattern_from_decode.get_vertices_num(), 3);
+        assert_eq!(pattern_from_decode.get_edges_num(), 1);
+        assert_eq!(pattern_from_decode.encode_to(), pattern.encode_to());
+        // the isolated vertex is kept in the undirected code
+        assert_ne!(pattern.encode_undirected(), edge_pattern.encode_undirected());
+        let undirected_pattern = Pattern::decode_from(&pattern.encode_undirected()).unwrap();
+        assert_eq!(undirected_pattern.get_vertices_num(), 3);
+        assert_eq!(undirected_pattern.encode_to(), pattern.encode_undirected());
+        // two isolated vertices without any edge
+        let vertices_pattern = Pattern::from_parts(vec![v0, v2], vec![]).unwrap();
+        let pattern_from_decode = Pattern::decode_from(&vertices_pattern.encode_to()).unwrap();
+        assert_eq!(pattern_from_decode.get_vertices_num(), 2);
+        assert_eq!(pattern_from_decode.encode_to(), vertices_pattern.encode_to());
+    }
+
+    #[test]
+    fn test_encode_decode_path_pattern_with_hop_range() {
+        let single_edge_pattern = Pattern::try_from(vec![PatternEdge::new(
+            0,
+            0,
+            PatternVertex::new(0, 0),
+            PatternVertex::new(1, 0),
+        )])
+        .unwrap();
+        let path_pattern = build_modern_pattern_case12();
+        let longer_path_pattern = Pattern::try_from(vec![PatternEdge::new(
+            0,
+            0,
+            PatternVertex::new(0, 0),
+            PatternVertex::new(1, 0),
+        )
+        .with_hop_range(Some((1, 3)))])
+        .unwrap();
+        // the hop range is part of the code
+        assert_ne!(path_pattern.encode_to(), single_edge_pattern.encode_to());
+        assert_ne!(path_pattern.encode_to(), longer_path_pattern.encode_to());
+        let pattern_from_decode = Pattern::decode_from(&path_pattern.encode_to()).unwrap();
+        assert_eq!(
+            pattern_from_decode
+                .edges_iter()
+                .map(|edge| edge.get_hop_range())
+                .collect::<Vec<_>>(),
+            vec![Some((1, 2))]
+        );
+        assert_eq!(pattern_from_decode.encode_to(), path_pattern.encode_to());
+        // a truncated hop range is not decoded
+        let path_code = path_pattern.encode_to();
+        assert!(Pattern::decode_from(&path_code[..path_code.len() - 4]).is_none());
+    }
 }
@@ -17,6 +17,13 @@ mod common;
 
 #[cfg(test)]
 mod tests {
+    use std::convert::TryFrom;
+
+    use ir_core::catalogue::extend_step::{ExtendEdge, ExtendStep};
+    use ir_core::catalogue::pattern::{Pattern, PatternEdge, PatternVertex};
+    use ir_core::catalogue::PatternDirection;
+    use ir_core::error::IrError;
+
     use crate::common::{extend_step_cases::*, pattern_cases::*, pattern_meta_cases::*};
 
     /// Test whether pattern1 + extend_step = pattern2
@@ -39,6 +46,46 @@ mod tests {
         assert_eq!(pattern_after_de_extend_code, pattern1_code);
     }
 
+    /// Test de_extend on a symmetric pattern, where any vertex of the square can be removed
+    #[test]
+    fn test_square_de_extend_matches_target_code() {
+        let vertices: Vec<PatternVertex> = (0..4)
+            .map(|v_id| PatternVertex::new(v_id, 0))
+            .collect();
+        let square = Pattern::try_from(
+            (0..4)
+                .map(|e_id| PatternEdge::new(e_id, 0, vertices[e_id], vertices[(e_id + 1) % 4]))
+                .collect::<Vec<PatternEdge>>(),
+        )
+        .unwrap();
+        let path = Pattern::try_from(vec![
+            PatternEdge::new(0, 0, vertices[0], vertices[1]),
+            PatternEdge::new(1, 0, vertices[1], vertices[2]),
+        ])
+        .unwrap();
+        let path_code = path.encode_to();
+        // the new vertex closes the path to a square
+        let extend_step = ExtendStep::new(
+            0,
+            vec![
+                ExtendEdge::new(path.get_vertex_rank(2).unwrap(), 0, PatternDirection::Out),
+                ExtendEdge::new(path.get_vertex_rank(0).unwrap(), 0, PatternDirection::In),
+            ],
+        );
+        assert_eq!(path.extend(&extend_step).unwrap().encode_to(), square.encode_to());
+        let de_extended_path = square
+            .de_extend(&extend_step, &path_code)
+            .unwrap();
+        assert_eq!(de_extended_path.encode_to(), path_code);
+        // a target pattern which can not be reached by the extend step
+        let edge_code = Pattern::try_from(vec![PatternEdge::new(0, 0, vertices[0], vertices[1])])
+            .unwrap()
+            .encode_to();
+        assert!(square
+            .de_extend(&extend_step, &edge_code)
+            .is_none());
+    }
+
     #[test]
     fn test_pattern_case8_case9_extend_de_extend() {
         let pattern1 = build_pattern_case8();
@@ -211,4 +258,83 @@ mod tests {
         let all_extend_steps = person_knows_person.get_extend_steps(&ldbc_pattern_meta, 10);
         assert_eq!(all_extend_steps.len(), 46);
     }
+
+    #[test]
+    fn test_get_symmetric_extend_steps_of_star() {
+        let modern_pattern_meta = get_modern_pattern_meta();
+        // a person knows three other persons
+        let person_star = Pattern::try_from(
+            (1..4)
+                .map(|i| PatternEdge::new(i - 1, 0, PatternVertex::new(0, 0), PatternVertex::new(i, 0)))
+                .collect::<Vec<PatternEdge>>(),
+        )
+        .unwrap();
+        let extend_steps = person_star.get_extend_steps(&modern_pattern_meta, 10);
+        let symmetric_extend_steps =
+            person_star.get_extend_steps_with_symmetric(&modern_pattern_meta, 10, true);
+        assert!(symmetric_extend_steps.len() > extend_steps.len());
+        assert_eq!(
+            person_star
+                .get_extend_steps_with_symmetric(&modern_pattern_meta, 10, false)
+                .len(),
+            extend_steps.len()
+        );
+        // no more person can be extended, so only the software can be the target vertex in both modes
+        let extend_steps = person_star.get_extend_steps(&modern_pattern_meta, 4);
+        let symmetric_extend_steps =
+            person_star.get_extend_steps_with_symmetric(&modern_pattern_meta, 4, true);
+        assert_eq!(symmetric_extend_steps.len(), extend_steps.len());
+        assert!(symmetric_extend_steps
+            .iter()
+            .all(|extend_step| extend_step.get_target_vertex_label() == 1));
+    }
+
+    #[test]
+    fn test_try_extend() {
+        let modern_pattern_meta = get_modern_pattern_meta();
+        let person_only_pattern = build_modern_pattern_case1();
+        let knows_extend_step = ExtendStep::new(0, vec![ExtendEdge::new(0, 0, PatternDirection::Out)]);
+        let person_knows_person = person_only_pattern
+            .try_extend_with_meta(&knows_extend_step, &modern_pattern_meta)
+            .unwrap();
+        assert_eq!(person_knows_person.encode_to(), build_modern_pattern_case3().encode_to());
+        // there is no vertex of rank 5 in the pattern
+        let missing_rank_extend_step =
+            ExtendStep::new(0, vec![ExtendEdge::new(5, 0, PatternDirection::Out)]);
+        assert!(person_only_pattern
+            .extend(&missing_rank_extend_step)
+            .is_none());
+        match person_only_pattern.try_extend(&missing_rank_extend_step) {
+            Err(IrError::InvalidPattern(message)) => assert!(message.contains("rank 5")),
+            _ => panic!("extending from a missing rank should fail"),
+        }
+        // a person can know a software in no way
+        let knows_software_extend_step =
+            ExtendStep::new(1, vec![ExtendEdge::new(0, 0, PatternDirection::Out)]);
+        assert!(person_only_pattern
+            .try_extend(&knows_software_extend_step)
+            .is_ok());
+        assert!(person_only_pattern
+            .try_extend_with_meta(&knows_software_extend_step, &modern_pattern_meta)
+            .is_err());
+    }
+
+    #[test]
+    fn test_extend_operators_for_vertex() {
+        // triangle: person(0) -knows-> person(1), person(0) -create-> software(2),
+        // and person(1) -create-> software(2)
+        let triangle = build_modern_pattern_case5();
+        // person(1) is extended by knows from person(0), and by create from software(2) reversely
+        let mut edge_expands = triangle.extend_operators_for_vertex(1);
+        assert_eq!(edge_expands.len(), 2);
+        edge_expands.sort_by_key(|edge_expand| edge_expand.direction);
+        let expected = vec![(0, PatternDirection::Out, 0), (2, PatternDirection::In, 1)];
+        for (edge_expand, (src_vertex_id, direction, edge_label)) in edge_expands.iter().zip(expected) {
+            assert_eq!(edge_expand.v_tag, Some(src_vertex_id.into()));
+            assert_eq!(edge_expand.direction, direction as i32);
+            assert_eq!(edge_expand.params.as_ref().unwrap().tables, vec![edge_label.into()]);
+            assert_eq!(edge_expand.alias, Some(1.into()));
+        }
+        assert!(triangle.extend_operators_for_vertex(3).is_empty());
+    }
 }
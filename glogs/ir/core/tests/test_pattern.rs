@@ -17,13 +17,23 @@ mod common;
 
 #[cfg(test)]
 mod tests {
-    use std::convert::TryFrom;
+    use std::collections::hash_map::DefaultHasher;
+    use std::collections::{HashMap, HashSet};
+    use std::convert::{TryFrom, TryInto};
+    use std::hash::{Hash, Hasher};
+    use std::time::Duration;
 
-    use ir_core::catalogue::pattern::{Pattern, PatternEdge, PatternVertex};
-    use ir_core::catalogue::{PatternDirection, PatternId};
+    use ir_common::expr_parse::str_to_expr_pb;
+    use ir_core::catalogue::pattern::{
+        generate_patterns, Pattern, PatternCountRecord, PatternEdge, PatternVertex, PatternWithCount,
+    };
+    use ir_core::catalogue::plan::SubpatternsBudget;
+    use ir_core::catalogue::{PatternDirection, PatternId, PatternLabelId};
+    use ir_core::error::IrError;
     use ir_core::plan::meta::TagId;
 
     use crate::common::pattern_cases::*;
+    use crate::common::pattern_meta_cases::*;
 
     const TAG_A: TagId = 0;
     const TAG_B: TagId = 1;
@@ -604,4 +614,991 @@ mod tests {
             panic!("Build pattern from pb message failed: {:?}", error)
         }
     }
+
+    #[test]
+    fn test_subpatterns_of_size_for_path() {
+        let pattern = build_pattern_case10();
+        let vertex_subpatterns = pattern.subpatterns_of_size(1);
+        assert_eq!(vertex_subpatterns.len(), 4);
+        let edge_subpatterns = pattern.subpatterns_of_size(2);
+        assert_eq!(edge_subpatterns.len(), 3);
+        for edge in pattern.edges_iter() {
+            let expected_subpattern = Pattern::try_from(vec![edge.clone()]).unwrap();
+            assert!(edge_subpatterns
+                .iter()
+                .any(|subpattern| subpattern.encode_to() == expected_subpattern.encode_to()));
+        }
+        let path_subpatterns = pattern.subpatterns_of_size(3);
+        assert_eq!(path_subpatterns.len(), 2);
+        assert!(path_subpatterns
+            .iter()
+            .all(|subpattern| subpattern.get_edges_num() == 2));
+        let whole_patterns = pattern.subpatterns_of_size(4);
+        assert_eq!(whole_patterns.len(), 1);
+        assert_eq!(whole_patterns[0].encode_to(), pattern.encode_to());
+        assert!(pattern.subpatterns_of_size(0).is_empty());
+        assert!(pattern.subpatterns_of_size(5).is_empty());
+    }
+
+    #[test]
+    fn test_subpatterns_of_size_with_triangles() {
+        let pattern = build_pattern_case9();
+        let subpatterns = pattern.subpatterns_of_size(3);
+        // 2 triangles and 2 induced paths, plus the paths left after removing an edge from a triangle,
+        // one of which is the same as an induced path
+        assert_eq!(subpatterns.len(), 9);
+        let triangles_num = subpatterns
+            .iter()
+            .filter(|subpattern| subpattern.get_edges_num() == 3)
+            .count();
+        assert_eq!(triangles_num, 2);
+        assert!(subpatterns
+            .iter()
+            .all(|subpattern| subpattern.get_vertices_num() == 3 && subpattern.is_connected()));
+    }
+
+    #[test]
+    fn test_generate_subpatterns_iter_same_as_eager() {
+        for pattern in [build_pattern_case9(), build_pattern_case10(), build_modern_pattern_case9()] {
+            let streamed_codes: Vec<Vec<u8>> = pattern
+                .generate_subpatterns_iter()
+                .map(|subpattern| subpattern.encode_to())
+                .collect();
+            let streamed_code_set: HashSet<Vec<u8>> = streamed_codes.iter().cloned().collect();
+            // every subpattern is yielded only once
+            assert_eq!(streamed_code_set.len(), streamed_codes.len());
+            let eager_code_set: HashSet<Vec<u8>> = pattern
+                .generate_subpatterns()
+                .iter()
+                .map(|subpattern| subpattern.encode_to())
+                .collect();
+            assert_eq!(streamed_code_set, eager_code_set);
+            assert!(streamed_code_set.contains(&pattern.encode_to()));
+            for size in 1..=pattern.get_vertices_num() {
+                for subpattern in pattern.subpatterns_of_size(size) {
+                    assert!(streamed_code_set.contains(&subpattern.encode_to()));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_generate_subpatterns_with_budget() {
+        let pattern = build_pattern_case9();
+        let all_subpatterns = pattern.generate_subpatterns();
+        assert!(all_subpatterns.len() > 5);
+        let (subpatterns, truncated) =
+            pattern.generate_subpatterns_with_budget(&SubpatternsBudget::default().with_max_patterns(5));
+        assert!(subpatterns.len() <= 5);
+        assert!(truncated);
+        let (subpatterns, truncated) =
+            pattern.generate_subpatterns_with_budget(&SubpatternsBudget::default().with_max_depth(1));
+        assert!(truncated);
+        assert!(subpatterns
+            .iter()
+            .all(|subpattern| subpattern.get_vertices_num() + 1 >= pattern.get_vertices_num()));
+        let (subpatterns, truncated) =
+            pattern.generate_subpatterns_with_budget(&SubpatternsBudget::default());
+        assert!(!truncated);
+        assert_eq!(subpatterns.len(), all_subpatterns.len());
+    }
+
+    #[test]
+    fn test_undirected_neighbors_iter_on_bidirectional_edge() {
+        let pattern = build_pattern_case1();
+        assert_eq!(pattern.adjacencies_iter(0).count(), 2);
+        let neighbors: Vec<PatternVertex> = pattern.undirected_neighbors_iter(0).collect();
+        assert_eq!(neighbors, vec![PatternVertex::new(1, 0)]);
+        let neighbors: Vec<PatternVertex> = pattern.undirected_neighbors_iter(1).collect();
+        assert_eq!(neighbors, vec![PatternVertex::new(0, 0)]);
+        let pattern = build_pattern_case2();
+        assert_eq!(pattern.adjacencies_iter(0).count(), 3);
+        let neighbor_ids: Vec<PatternId> = pattern
+            .undirected_neighbors_iter(0)
+            .map(|vertex| vertex.get_id())
+            .collect();
+        assert_eq!(neighbor_ids, vec![1, 2]);
+        assert_eq!(pattern.undirected_neighbors_iter(3).count(), 0);
+    }
+
+    #[test]
+    fn test_hash_wl_similarity() {
+        let vertex_0 = PatternVertex::new(0, 0);
+        let vertex_1 = PatternVertex::new(1, 0);
+        let vertex_2 = PatternVertex::new(2, 0);
+        let triangle = Pattern::try_from(vec![
+            PatternEdge::new(0, 0, vertex_0, vertex_1),
+            PatternEdge::new(1, 0, vertex_1, vertex_2),
+            PatternEdge::new(2, 0, vertex_2, vertex_0),
+        ])
+        .unwrap();
+        let path = Pattern::try_from(vec![
+            PatternEdge::new(0, 0, vertex_0, vertex_1),
+            PatternEdge::new(1, 0, vertex_1, vertex_2),
+        ])
+        .unwrap();
+        assert_eq!(Pattern::hash_wl(&triangle, &triangle, 6), 1.0);
+        assert_eq!(Pattern::hash_wl(&path, &path, 6), 1.0);
+        let similarity = Pattern::hash_wl(&triangle, &path, 6);
+        assert!((similarity - 0.3).abs() < 0.05);
+        assert_eq!(similarity, Pattern::hash_wl(&path, &triangle, 6));
+        // patterns without any common vertex label
+        let pattern_case6 = build_pattern_case6();
+        assert_eq!(Pattern::hash_wl(&triangle, &pattern_case6, 6), 0.0);
+    }
+
+    #[test]
+    fn test_extract_cycles() {
+        let triangle = build_modern_pattern_case5();
+        assert_eq!(triangle.extract_cycles(), vec![vec![0, 1, 2]]);
+        assert!(triangle.is_single_cycle());
+        let path = build_pattern_case10();
+        assert!(path.extract_cycles().is_empty());
+        assert!(!path.is_single_cycle());
+        // multiple edges between two vertices are not a cycle
+        let pattern_case1 = build_pattern_case1();
+        assert!(pattern_case1.extract_cycles().is_empty());
+        assert!(!pattern_case1.is_single_cycle());
+        let square = build_pattern_case3();
+        assert_eq!(square.extract_cycles(), vec![vec![0, 1, 3, 2]]);
+        assert!(square.is_single_cycle());
+    }
+
+    #[test]
+    fn test_extract_cycles_from_4_clique() {
+        let vertices: Vec<PatternVertex> = (0..4)
+            .map(|v_id| PatternVertex::new(v_id, 0))
+            .collect();
+        let mut edges = vec![];
+        for i in 0..4 {
+            for j in (i + 1)..4 {
+                edges.push(PatternEdge::new(edges.len(), 0, vertices[i], vertices[j]));
+            }
+        }
+        let clique = Pattern::try_from(edges).unwrap();
+        let cycles = clique.extract_cycles();
+        assert_eq!(cycles.len(), 7);
+        assert_eq!(cycles.iter().filter(|cycle| cycle.len() == 3).count(), 4);
+        assert_eq!(cycles.iter().filter(|cycle| cycle.len() == 4).count(), 3);
+        assert!(!clique.is_single_cycle());
+    }
+
+    #[test]
+    fn test_ldbc_fuzzy_pattern_from_pb_case1() {
+        let patterns = build_ldbc_fuzzy_pattern_from_pb_case1().unwrap();
+        assert_eq!(patterns.len(), 2);
+        let mut message_labels = vec![];
+        for pattern in patterns.iter() {
+            assert_eq!(pattern.get_vertices_num(), 3);
+            assert_eq!(pattern.get_edges_num(), 3);
+            assert_eq!(pattern.get_vertex(TAG_A as PatternId).unwrap().get_label(), 1);
+            assert_eq!(pattern.get_vertex(TAG_C as PatternId).unwrap().get_label(), 1);
+            message_labels.push(
+                pattern
+                    .get_vertex(TAG_B as PatternId)
+                    .unwrap()
+                    .get_label(),
+            );
+        }
+        message_labels.sort();
+        // Comment: 2, Post: 3
+        assert_eq!(message_labels, vec![2, 3]);
+        assert_ne!(patterns[0].encode_to(), patterns[1].encode_to());
+    }
+
+    #[test]
+    fn test_ldbc_fuzzy_pattern_from_pb_case2() {
+        // STUDYAT: 15, WORKAT: 16
+        let patterns = build_ldbc_fuzzy_pattern_from_pb_case2(vec![15, 16]).unwrap();
+        assert_eq!(patterns.len(), 2);
+        let mut edge_labels: Vec<_> = patterns
+            .iter()
+            .map(|pattern| pattern.get_edge(0).unwrap().get_label())
+            .collect();
+        edge_labels.sort();
+        assert_eq!(edge_labels, vec![15, 16]);
+        for pattern in patterns.iter() {
+            assert_eq!(pattern.get_vertex(TAG_A as PatternId).unwrap().get_label(), 1);
+            assert_eq!(pattern.get_vertex(TAG_B as PatternId).unwrap().get_label(), 5);
+        }
+        // edge label not in the schema
+        assert!(build_ldbc_fuzzy_pattern_from_pb_case2(vec![15, 100]).is_err());
+    }
+
+    #[test]
+    fn test_undirected_triangle_encode_stability() {
+        let pattern = build_undirected_pattern_case1([true, true, true]);
+        let code = pattern.encode_to();
+        for orientations in [
+            [false, true, true],
+            [true, false, true],
+            [true, true, false],
+            [false, false, true],
+            [false, false, false],
+        ] {
+            assert_eq!(build_undirected_pattern_case1(orientations).encode_to(), code);
+        }
+        for adjacency in pattern.adjacencies_iter(0) {
+            assert!(adjacency.is_bidirectional());
+        }
+        // a directed triangle is different from an undirected one
+        let directed_pattern = Pattern::try_from(
+            pattern
+                .edges_iter()
+                .map(|edge| edge.clone().with_undirected(false))
+                .collect::<Vec<PatternEdge>>(),
+        )
+        .unwrap();
+        assert_ne!(directed_pattern.encode_to(), code);
+        // the undirected flags survive encoding and decoding
+        let decoded_pattern = Pattern::decode_from(&code).unwrap();
+        assert!(decoded_pattern
+            .edges_iter()
+            .all(|edge| edge.is_undirected()));
+        assert_eq!(decoded_pattern.encode_to(), code);
+    }
+
+    #[test]
+    fn test_undirected_triangle_bidirectional_adjacencies() {
+        for orientations in [[true, true, true], [false, true, false]] {
+            let pattern = build_undirected_pattern_case1(orientations);
+            for vertex in pattern.vertices_iter() {
+                let v_id = vertex.get_id();
+                // undirected edges are neither outgoing nor incoming
+                assert_eq!(pattern.get_vertex_out_degree(v_id), 0);
+                assert_eq!(pattern.get_vertex_in_degree(v_id), 0);
+                assert_eq!(pattern.get_vertex_bidirectional_degree(v_id), 2);
+                assert_eq!(pattern.get_vertex_degree(v_id), 2);
+                assert_eq!(pattern.adjacencies_iter(v_id).count(), 2);
+            }
+            // removing an edge removes its adjacencies from both end vertices
+            let path = pattern.remove_edge(0).unwrap();
+            assert_eq!(path.get_edges_num(), 2);
+            let degrees: Vec<usize> = path
+                .vertices_iter()
+                .map(|vertex| path.get_vertex_bidirectional_degree(vertex.get_id()))
+                .collect();
+            assert_eq!(degrees.iter().sum::<usize>(), 4);
+        }
+    }
+
+    /// Count the (node statements, edge statements) of a DOT graph, and check it is well enclosed
+    fn count_dot_statements(dot: &str) -> (usize, usize) {
+        let lines: Vec<&str> = dot.lines().map(|line| line.trim()).collect();
+        assert_eq!(lines.first(), Some(&"digraph pattern {"));
+        assert_eq!(lines.last(), Some(&"}"));
+        let statements = &lines[1..lines.len() - 1];
+        assert!(statements
+            .iter()
+            .all(|statement| statement.ends_with("];") && statement.matches('"').count() % 2 == 0));
+        let edges_num = statements
+            .iter()
+            .filter(|statement| statement.contains(" -> "))
+            .count();
+        (statements.len() - edges_num, edges_num)
+    }
+
+    #[test]
+    fn test_single_vertex_pattern_to_dot() {
+        let pattern = Pattern::from(PatternVertex::new(0, 1));
+        let dot = pattern.to_dot();
+        assert_eq!(count_dot_statements(&dot), (1, 0));
+        assert!(dot.contains("v0 [label=\"0:1\\nrank=0, group=0\"];"));
+    }
+
+    #[test]
+    fn test_path_pattern_to_dot() {
+        let vertex_1 = PatternVertex::new(0, 1);
+        let vertex_2 = PatternVertex::new(1, 2);
+        let vertex_3 = PatternVertex::new(2, 3);
+        let pattern = Pattern::try_from(vec![
+            PatternEdge::new(0, 1, vertex_1, vertex_2),
+            PatternEdge::new(1, 2, vertex_3, vertex_2),
+        ])
+        .unwrap();
+        let dot = pattern.to_dot();
+        assert_eq!(count_dot_statements(&dot), (3, 2));
+        assert!(dot.contains("v0 -> v1 [label=\"0:1\"];"));
+        assert!(dot.contains("v2 -> v1 [label=\"1:2\"];"));
+    }
+
+    #[test]
+    fn test_isomorphic_triangles_equal_and_hash() {
+        // A(0) -> B(1) -> C(2), A(0) -> C(2)
+        let triangle_1 = Pattern::try_from(vec![
+            PatternEdge::new(0, 1, PatternVertex::new(0, 1), PatternVertex::new(1, 2)),
+            PatternEdge::new(1, 2, PatternVertex::new(1, 2), PatternVertex::new(2, 3)),
+            PatternEdge::new(2, 3, PatternVertex::new(0, 1), PatternVertex::new(2, 3)),
+        ])
+        .unwrap();
+        // The same triangle with different vertex/edge ids and edge order
+        let triangle_2 = Pattern::try_from(vec![
+            PatternEdge::new(3, 3, PatternVertex::new(5, 1), PatternVertex::new(3, 3)),
+            PatternEdge::new(7, 2, PatternVertex::new(4, 2), PatternVertex::new(3, 3)),
+            PatternEdge::new(4, 1, PatternVertex::new(5, 1), PatternVertex::new(4, 2)),
+        ])
+        .unwrap();
+        assert!(triangle_1.is_isomorphic_to(&triangle_2));
+        assert_eq!(triangle_1, triangle_2);
+        let hash = |pattern: &Pattern| {
+            let mut hasher = DefaultHasher::new();
+            pattern.hash(&mut hasher);
+            hasher.finish()
+        };
+        assert_eq!(hash(&triangle_1), hash(&triangle_2));
+        let pattern_set: HashSet<Pattern> = vec![triangle_1.clone(), triangle_2].into_iter().collect();
+        assert_eq!(pattern_set.len(), 1);
+        // Reversing an edge breaks the isomorphism
+        let triangle_3 = Pattern::try_from(vec![
+            PatternEdge::new(0, 1, PatternVertex::new(0, 1), PatternVertex::new(1, 2)),
+            PatternEdge::new(1, 2, PatternVertex::new(2, 3), PatternVertex::new(1, 2)),
+            PatternEdge::new(2, 3, PatternVertex::new(0, 1), PatternVertex::new(2, 3)),
+        ])
+        .unwrap();
+        assert!(!triangle_1.is_isomorphic_to(&triangle_3));
+        assert_ne!(triangle_1, triangle_3);
+    }
+
+    /// Build a directed path whose vertices all have label 1, with the given edge labels and directions
+    fn build_labeled_path(edges: &[(PatternLabelId, PatternDirection)]) -> Pattern {
+        let pattern_edges: Vec<PatternEdge> = edges
+            .iter()
+            .enumerate()
+            .map(|(i, &(edge_label, direction))| {
+                PatternEdge::new(i, edge_label, PatternVertex::new(i, 1), PatternVertex::new(i + 1, 1))
+                    .with_direction(direction)
+            })
+            .collect();
+        Pattern::try_from(pattern_edges).unwrap()
+    }
+
+    #[test]
+    fn test_contains_subpattern_in_4_cycle() {
+        // 0 -> 1 -> 2 -> 3 -> 0, all vertices and edges have label 1
+        let cycle = Pattern::try_from(
+            (0..4)
+                .map(|i| {
+                    PatternEdge::new(i, 1, PatternVertex::new(i, 1), PatternVertex::new((i + 1) % 4, 1))
+                })
+                .collect::<Vec<PatternEdge>>(),
+        )
+        .unwrap();
+        let path = build_labeled_path(&[(1, PatternDirection::Out), (1, PatternDirection::Out)]);
+        assert!(cycle.contains_subpattern(&path));
+        let vertex_mapping = cycle.get_subpattern_mapping(&path).unwrap();
+        assert_eq!(vertex_mapping.len(), 3);
+        for edge in path.edges_iter() {
+            let start_v_id = vertex_mapping[&edge.get_start_vertex().get_id()];
+            let end_v_id = vertex_mapping[&edge.get_end_vertex().get_id()];
+            assert!(cycle
+                .out_adjacencies_iter(start_v_id)
+                .any(|adj| adj.get_adj_vertex().get_id() == end_v_id));
+        }
+        // the cycle itself is a subpattern, but a longer path is not
+        assert!(cycle.contains_subpattern(&cycle));
+        assert!(!cycle.contains_subpattern(&build_labeled_path(&[(1, PatternDirection::Out); 4])));
+        // edge label mismatch
+        let path_with_other_label =
+            build_labeled_path(&[(1, PatternDirection::Out), (2, PatternDirection::Out)]);
+        assert!(!cycle.contains_subpattern(&path_with_other_label));
+        // edge direction mismatch
+        let path_with_reversed_edge =
+            build_labeled_path(&[(1, PatternDirection::Out), (1, PatternDirection::In)]);
+        assert!(!cycle.contains_subpattern(&path_with_reversed_edge));
+    }
+
+    #[test]
+    fn test_pattern_with_count_round_trip_keeps_tags_and_predicates() {
+        let mut pattern = build_pattern_case10();
+        let vertex_predicate = str_to_expr_pb("@.name == \"marko\"".to_string()).unwrap();
+        let edge_predicate = str_to_expr_pb("@.weight > 0.5".to_string()).unwrap();
+        pattern.set_vertex_predicate(1, vertex_predicate.clone());
+        pattern.set_vertex_tag(0, TAG_A);
+        pattern.set_edge_tag(TAG_B, 2);
+        pattern.set_edge_predicate(2, edge_predicate.clone());
+        let json = serde_json::to_string(&PatternWithCount::from(pattern.clone())).unwrap();
+        let pattern_with_count: PatternWithCount = serde_json::from_str(&json).unwrap();
+        let reloaded_pattern = Pattern::try_from(pattern_with_count).unwrap();
+        assert_eq!(reloaded_pattern.encode_to(), pattern.encode_to());
+        assert_eq!(reloaded_pattern.get_vertex_predicate(1), Some(&vertex_predicate));
+        assert_eq!(reloaded_pattern.get_vertex_predicate(0), None);
+        assert_eq!(reloaded_pattern.get_vertex_tag(0), Some(TAG_A));
+        assert_eq!(reloaded_pattern.get_edge_tag(2), Some(TAG_B));
+        assert_eq!(reloaded_pattern.get_edge_tag(0), None);
+        assert_eq!(reloaded_pattern.get_edge_predicate(2), Some(&edge_predicate));
+    }
+
+    #[test]
+    fn test_pattern_with_count_round_trip_keeps_undirected_and_path_edges() {
+        let round_trip = |pattern: &Pattern| {
+            let json = serde_json::to_string(&PatternWithCount::from(pattern.clone())).unwrap();
+            let pattern_with_count: PatternWithCount = serde_json::from_str(&json).unwrap();
+            Pattern::try_from(pattern_with_count).unwrap()
+        };
+        let undirected_triangle = build_undirected_pattern_case1([true, false, true]);
+        let reloaded_triangle = round_trip(&undirected_triangle);
+        assert_eq!(reloaded_triangle.encode_to(), undirected_triangle.encode_to());
+        for edge in undirected_triangle.edges_iter() {
+            let reloaded_edge = reloaded_triangle
+                .get_edge(edge.get_id())
+                .unwrap();
+            assert!(reloaded_edge.is_undirected());
+            assert_eq!(reloaded_edge.get_start_vertex(), edge.get_start_vertex());
+            assert_eq!(reloaded_edge.get_end_vertex(), edge.get_end_vertex());
+        }
+        let path = Pattern::try_from(vec![PatternEdge::new(
+            0,
+            1,
+            PatternVertex::new(0, 1),
+            PatternVertex::new(1, 1),
+        )
+        .with_hop_range(Some((1, 3)))])
+        .unwrap();
+        let reloaded_path = round_trip(&path);
+        assert_eq!(reloaded_path.get_edge(0).unwrap().get_hop_range(), Some((1, 3)));
+        assert!(!reloaded_path.get_edge(0).unwrap().is_undirected());
+    }
+
+    #[test]
+    fn test_pattern_count_record_json() {
+        let triangle = build_modern_pattern_case5();
+        let triangle_count: usize = 42;
+        let plain_output = format!("{}", triangle_count);
+        let record = PatternCountRecord::new(&triangle, triangle_count as f64, Duration::from_millis(5))
+            .with_q_error(2.0);
+        let json: serde_json::Value =
+            serde_json::from_str(&serde_json::to_string(&record).unwrap()).unwrap();
+        assert_eq!(json["count"].as_f64(), plain_output.parse::<f64>().ok());
+        assert_eq!(json["vertices"].as_u64(), Some(3));
+        assert_eq!(json["edges"].as_u64(), Some(3));
+        assert_eq!(json["elapsed_ms"].as_f64(), Some(5.0));
+        assert_eq!(json["q_error"].as_f64(), Some(2.0));
+        let pattern_code = base64::decode(json["pattern_code"].as_str().unwrap()).unwrap();
+        assert_eq!(pattern_code, triangle.encode_to());
+        // the pattern itself is still readable from the JSON
+        let reloaded_triangle: Pattern = serde_json::from_value::<PatternCountRecord>(json)
+            .unwrap()
+            .into_pattern()
+            .try_into()
+            .unwrap();
+        assert_eq!(reloaded_triangle.encode_to(), triangle.encode_to());
+    }
+
+    #[test]
+    fn test_canonical_labeling_of_disconnected_pattern() {
+        // two triangles with the same labels, whose vertices and edges are given in different orders
+        let build_two_triangles = |vertex_ids: [PatternId; 6], edge_ids: [PatternId; 6]| {
+            let vertices: Vec<PatternVertex> = vertex_ids
+                .iter()
+                .map(|&v_id| PatternVertex::new(v_id, 1))
+                .collect();
+            Pattern::try_from(vec![
+                PatternEdge::new(edge_ids[0], 11, vertices[0], vertices[1]),
+                PatternEdge::new(edge_ids[1], 11, vertices[1], vertices[2]),
+                PatternEdge::new(edge_ids[2], 12, vertices[0], vertices[2]),
+                PatternEdge::new(edge_ids[3], 11, vertices[3], vertices[4]),
+                PatternEdge::new(edge_ids[4], 11, vertices[4], vertices[5]),
+                PatternEdge::new(edge_ids[5], 12, vertices[3], vertices[5]),
+            ])
+            .unwrap()
+        };
+        let pattern1 = build_two_triangles([0, 1, 2, 3, 4, 5], [0, 1, 2, 3, 4, 5]);
+        let pattern2 = build_two_triangles([5, 3, 4, 1, 0, 2], [3, 5, 4, 0, 2, 1]);
+        assert!(!pattern1.is_connected());
+        assert_eq!(pattern1.get_connected_component_num(), 2);
+        assert_eq!(pattern1.get_connected_components().len(), 2);
+        // the ranks of the two components do not overlap
+        let vertex_ranks: HashSet<PatternId> = pattern1
+            .vertices_iter()
+            .map(|vertex| {
+                pattern1
+                    .get_vertex_rank(vertex.get_id())
+                    .unwrap()
+            })
+            .collect();
+        assert_eq!(vertex_ranks, (0..6).collect());
+        let edge_ranks: HashSet<PatternId> = pattern1
+            .edges_iter()
+            .map(|edge| pattern1.get_edge_rank(edge.get_id()).unwrap())
+            .collect();
+        assert_eq!(edge_ranks, (0..6).collect());
+        assert_eq!(pattern1.encode_to(), pattern2.encode_to());
+        let decoded_pattern = Pattern::decode_from(&pattern1.encode_to()).unwrap();
+        assert_eq!(decoded_pattern.get_vertices_num(), 6);
+        assert_eq!(decoded_pattern.get_connected_component_num(), 2);
+        assert_eq!(decoded_pattern.encode_to(), pattern1.encode_to());
+    }
+
+    #[test]
+    fn test_connected_components_ordered_by_code() {
+        // a person-knows-person edge and a person-created-software edge, given in different orders
+        let person_knows_person = |e_id: PatternId, v_id: PatternId| {
+            PatternEdge::new(e_id, 0, PatternVertex::new(v_id, 0), PatternVertex::new(v_id + 1, 0))
+        };
+        let person_created_software = |e_id: PatternId, v_id: PatternId| {
+            PatternEdge::new(e_id, 1, PatternVertex::new(v_id, 0), PatternVertex::new(v_id + 1, 1))
+        };
+        let pattern1 =
+            Pattern::try_from(vec![person_knows_person(0, 0), person_created_software(1, 2)]).unwrap();
+        let pattern2 =
+            Pattern::try_from(vec![person_created_software(0, 0), person_knows_person(1, 2)]).unwrap();
+        let codes1: Vec<Vec<u8>> = pattern1
+            .get_connected_components()
+            .iter()
+            .map(|component| component.encode_to())
+            .collect();
+        let codes2: Vec<Vec<u8>> = pattern2
+            .get_connected_components()
+            .iter()
+            .map(|component| component.encode_to())
+            .collect();
+        assert_eq!(codes1.len(), 2);
+        assert!(codes1[0] < codes1[1]);
+        assert_eq!(codes1, codes2);
+    }
+
+    #[test]
+    fn test_display_pattern_with_meta() {
+        // a triangle of a player, a fan and a ticket
+        let player = PatternVertex::new(0, 1);
+        let fan = PatternVertex::new(1, 2);
+        let ticket = PatternVertex::new(2, 3);
+        let triangle = Pattern::try_from(vec![
+            PatternEdge::new(0, 1, player, fan),
+            PatternEdge::new(1, 2, fan, ticket),
+            PatternEdge::new(2, 0, player, ticket),
+        ])
+        .unwrap();
+        let sample_pattern_meta = get_sample_pattern_meta();
+        assert_eq!(
+            triangle.display_with_meta(&sample_pattern_meta),
+            "(0:PLAYER)-[0:LOVEDBY]->(1:FAN), (1:FAN)-[1:BUY]->(2:TICKET), (0:PLAYER)-[2:GUIDE]->(2:TICKET)"
+        );
+        assert_eq!(format!("{}", triangle), "(0:1)-[0:1]->(1:2), (1:2)-[1:2]->(2:3), (0:1)-[2:0]->(2:3)");
+        assert_eq!(Pattern::from(ticket).display_with_meta(&sample_pattern_meta), "(2:TICKET)");
+    }
+
+    #[test]
+    fn test_edges_between_with_parallel_edges() {
+        let vertices: Vec<PatternVertex> = (0..3)
+            .map(|v_id| PatternVertex::new(v_id, 0))
+            .collect();
+        // two parallel edges of different directions between v0 and v1, and an edge from v1 to v2
+        let pattern = Pattern::try_from(vec![
+            PatternEdge::new(0, 0, vertices[0], vertices[1]),
+            PatternEdge::new(1, 1, vertices[1], vertices[0]),
+            PatternEdge::new(2, 0, vertices[1], vertices[2]),
+        ])
+        .unwrap();
+        let edge_ids_between = |v1: PatternId, v2: PatternId| -> Vec<PatternId> {
+            pattern
+                .edges_between(v1, v2)
+                .into_iter()
+                .map(|edge| edge.get_id())
+                .collect()
+        };
+        assert_eq!(edge_ids_between(0, 1), vec![0, 1]);
+        assert_eq!(edge_ids_between(1, 0), vec![0, 1]);
+        assert_eq!(edge_ids_between(2, 1), vec![2]);
+        assert!(edge_ids_between(0, 2).is_empty());
+        assert!(pattern.are_adjacent(0, 1));
+        assert!(pattern.are_adjacent(2, 1));
+        assert!(!pattern.are_adjacent(0, 2));
+        assert!(!pattern.are_adjacent(0, 3));
+    }
+
+    #[test]
+    fn test_canonical_labeling_of_parallel_edges() {
+        // v0 has three parallel edges to v1, two of which have the same label, and v1 has an edge to v2
+        let build_pattern = |edge_ids: [PatternId; 4]| {
+            let (v0, v1, v2) =
+                (PatternVertex::new(0, 0), PatternVertex::new(1, 0), PatternVertex::new(2, 1));
+            Pattern::try_from(vec![
+                PatternEdge::new(edge_ids[0], 0, v0, v1),
+                PatternEdge::new(edge_ids[1], 1, v0, v1),
+                PatternEdge::new(edge_ids[2], 0, v0, v1),
+                PatternEdge::new(edge_ids[3], 2, v1, v2),
+            ])
+            .unwrap()
+        };
+        let pattern1 = build_pattern([0, 1, 2, 3]);
+        let pattern2 = build_pattern([1, 0, 3, 2]);
+        let pattern3 = build_pattern([3, 2, 1, 0]);
+        for pattern in [&pattern1, &pattern2, &pattern3] {
+            let edge_ranks: HashSet<PatternId> = pattern
+                .edges_iter()
+                .map(|edge| pattern.get_edge_rank(edge.get_id()).unwrap())
+                .collect();
+            assert_eq!(edge_ranks, (0..4).collect());
+        }
+        assert_eq!(pattern1.encode_to(), pattern2.encode_to());
+        assert_eq!(pattern1.encode_to(), pattern3.encode_to());
+        let decoded_pattern = Pattern::decode_from(&pattern1.encode_to()).unwrap();
+        assert_eq!(decoded_pattern.get_edges_num(), 4);
+        assert_eq!(decoded_pattern.encode_to(), pattern1.encode_to());
+    }
+
+    #[test]
+    fn test_merge_patterns_on_shared_tag() {
+        let build_path = |v_labels: &[PatternLabelId]| {
+            let vertices: Vec<PatternVertex> = v_labels
+                .iter()
+                .enumerate()
+                .map(|(v_id, &v_label)| PatternVertex::new(v_id, v_label))
+                .collect();
+            let edges: Vec<PatternEdge> = (1..vertices.len())
+                .map(|e_id| PatternEdge::new(e_id - 1, 12, vertices[e_id - 1], vertices[e_id]))
+                .collect();
+            Pattern::try_from(edges).unwrap()
+        };
+        let mut path1 = build_path(&[1, 1, 1]);
+        path1.set_vertex_tag(0, TAG_A);
+        path1.set_vertex_tag(2, TAG_C);
+        let mut path2 = build_path(&[1, 1, 1]);
+        path2.set_vertex_tag(0, TAG_C);
+        path2.set_vertex_tag(2, TAG_D);
+        path2.set_edge_tag(TAG_B, 1);
+        let merged_path = path1.merge(&path2, &[TAG_C]).unwrap();
+        assert_eq!(merged_path.get_vertices_num(), 5);
+        assert_eq!(merged_path.get_edges_num(), 4);
+        assert_eq!(merged_path.encode_to(), build_path(&[1, 1, 1, 1, 1]).encode_to());
+        // the shared vertex keeps its id in the first pattern
+        assert_eq!(
+            merged_path
+                .get_vertex_from_tag(TAG_C)
+                .unwrap()
+                .get_id(),
+            2
+        );
+        assert_eq!(merged_path.get_vertex_degree(2), 2);
+        assert_eq!(
+            merged_path
+                .get_vertex_from_tag(TAG_A)
+                .unwrap()
+                .get_id(),
+            0
+        );
+        assert!(merged_path.get_vertex_from_tag(TAG_D).is_some());
+        let tagged_edge = merged_path.get_edge_from_tag(TAG_B).unwrap();
+        assert!(tagged_edge.get_id() > path1.get_max_edge_id());
+        assert_eq!(
+            tagged_edge.get_end_vertex().get_id(),
+            merged_path
+                .get_vertex_from_tag(TAG_D)
+                .unwrap()
+                .get_id()
+        );
+        // the shared tag is on vertices of different labels
+        let mut path3 = build_path(&[2, 1, 1]);
+        path3.set_vertex_tag(0, TAG_C);
+        assert!(path1.merge(&path3, &[TAG_C]).is_err());
+        // the shared tag is missing
+        assert!(path1.merge(&path2, &[TAG_B]).is_err());
+    }
+
+    #[test]
+    fn test_rank_to_id_maps_of_triangle() {
+        let triangle = build_modern_pattern_case5();
+        let (vertex_rank_id_map, edge_rank_id_map) = triangle.rank_to_id_maps();
+        assert_eq!(vertex_rank_id_map.len(), 3);
+        assert_eq!(edge_rank_id_map.len(), 3);
+        for (&vertex_rank, &vertex_id) in vertex_rank_id_map.iter() {
+            assert_eq!(triangle.get_vertex_rank(vertex_id), Some(vertex_rank));
+        }
+        for vertex in triangle.vertices_iter() {
+            let vertex_rank = triangle
+                .get_vertex_rank(vertex.get_id())
+                .unwrap();
+            assert_eq!(vertex_rank_id_map[&vertex_rank], vertex.get_id());
+        }
+        for (&edge_rank, &edge_id) in edge_rank_id_map.iter() {
+            assert_eq!(triangle.get_edge_rank(edge_id), Some(edge_rank));
+        }
+        for edge in triangle.edges_iter() {
+            let edge_rank = triangle.get_edge_rank(edge.get_id()).unwrap();
+            assert_eq!(edge_rank_id_map[&edge_rank], edge.get_id());
+        }
+    }
+
+    #[test]
+    fn test_remove_edge_keeps_tags_and_ranks_consistent() {
+        let vertices: Vec<PatternVertex> = (0..3)
+            .map(|v_id| PatternVertex::new(v_id, 1))
+            .collect();
+        let mut triangle = Pattern::try_from(vec![
+            PatternEdge::new(0, 12, vertices[0], vertices[1]),
+            PatternEdge::new(1, 12, vertices[0], vertices[2]),
+            PatternEdge::new(2, 12, vertices[1], vertices[2]),
+        ])
+        .unwrap();
+        triangle.set_vertex_tag(0, TAG_A);
+        triangle.set_vertex_tag(2, TAG_C);
+        triangle.set_edge_tag(TAG_A, 1);
+        triangle.set_edge_tag(TAG_B, 2);
+        let path = triangle.clone().remove_edge(1).unwrap();
+        assert_eq!(path.get_vertices_num(), 3);
+        assert_eq!(path.get_edges_num(), 2);
+        assert!(path.get_edge_from_tag(TAG_A).is_none());
+        assert_eq!(path.get_edge_from_tag(TAG_B).unwrap().get_id(), 2);
+        assert_eq!(
+            path.get_vertex_from_tag(TAG_C)
+                .unwrap()
+                .get_id(),
+            2
+        );
+        let vertex_ranks: HashSet<PatternId> = path
+            .vertices_iter()
+            .map(|vertex| path.get_vertex_rank(vertex.get_id()).unwrap())
+            .collect();
+        assert_eq!(vertex_ranks, (0..3).collect());
+        let edge_ranks: HashSet<PatternId> = path
+            .edges_iter()
+            .map(|edge| path.get_edge_rank(edge.get_id()).unwrap())
+            .collect();
+        assert_eq!(edge_ranks, (0..2).collect());
+        for rank in 0..3 {
+            assert!(path.get_vertex_from_rank(rank).is_some());
+        }
+        // removing another edge leaves the tagged vertex 2 isolated, so it is removed as well
+        let edge = path.remove_edge(2).unwrap();
+        assert_eq!(edge.get_vertices_num(), 2);
+        assert!(edge.get_edge_from_tag(TAG_B).is_none());
+        assert!(edge.get_vertex_from_tag(TAG_C).is_none());
+        assert_eq!(
+            edge.get_vertex_from_tag(TAG_A)
+                .unwrap()
+                .get_id(),
+            0
+        );
+        assert!(edge.get_vertex_from_rank(2).is_none());
+        assert_eq!(
+            edge.encode_to(),
+            Pattern::try_from(vec![PatternEdge::new(0, 12, vertices[0], vertices[1])])
+                .unwrap()
+                .encode_to()
+        );
+    }
+
+    #[test]
+    fn test_empty_pattern_min_max_ids() {
+        let pattern = Pattern::default();
+        assert_eq!(pattern.get_min_vertex_id(), 0);
+        assert_eq!(pattern.get_max_vertex_id(), 0);
+        assert_eq!(pattern.get_min_edge_id(), 0);
+        assert_eq!(pattern.get_max_edge_id(), 0);
+    }
+
+    #[test]
+    fn test_pattern_predicate_num_counts_edge_predicates() {
+        let mut pattern = build_pattern_case10();
+        assert_eq!(pattern.get_predicate_num(), 0);
+        let edge_predicate = str_to_expr_pb("@.weight > 0.5".to_string()).unwrap();
+        pattern.set_edge_predicate(2, edge_predicate);
+        assert_eq!(pattern.get_edges_predicate_num(), 1);
+        assert_eq!(pattern.get_vertices_predicate_num(), 0);
+        assert_eq!(pattern.get_predicate_num(), 1);
+    }
+
+    #[test]
+    fn test_pattern_from_edge_list() {
+        let edge_list = "0,1,0,1,1,2\n\n1\t2\t1\t2\t2\t3\n2, 3, 2, 3, 3, 4\n";
+        let pattern = Pattern::from_edge_list(edge_list.as_bytes()).unwrap();
+        assert_eq!(pattern.get_edges_num(), 3);
+        assert_eq!(pattern.get_vertices_num(), 4);
+        assert_eq!(pattern.encode_to(), build_pattern_case10().encode_to());
+        // wrong number of fields
+        assert!(Pattern::from_edge_list("0,1,0,1,1\n".as_bytes()).is_err());
+        // non-numeric label
+        assert!(Pattern::from_edge_list("0,knows,0,1,1,2\n".as_bytes()).is_err());
+    }
+
+    #[test]
+    fn test_generate_connected_patterns_on_modern_meta() {
+        let modern_pattern_meta = get_modern_pattern_meta();
+        let patterns = generate_patterns(&modern_pattern_meta, 3, 3, 3, true, false);
+        // 2 one-vertex patterns, 2 one-edge patterns, 5 patterns of three persons,
+        // 4 patterns of two persons and a software, and 1 pattern of a person and two softwares
+        assert_eq!(patterns.len(), 14);
+        assert!(patterns
+            .iter()
+            .all(|pattern| pattern.is_connected() && pattern.get_vertices_num() <= 3));
+        let pattern_codes: HashSet<Vec<u8>> = patterns
+            .iter()
+            .map(|pattern| pattern.encode_to())
+            .collect();
+        assert_eq!(pattern_codes.len(), patterns.len());
+        // the triangles are out of the edge limit
+        assert_eq!(generate_patterns(&modern_pattern_meta, 3, 2, 3, true, false).len(), 11);
+        // at most one vertex of each label in a pattern
+        assert_eq!(generate_patterns(&modern_pattern_meta, 3, 3, 1, true, false).len(), 3);
+    }
+
+    #[test]
+    fn test_generate_disconnected_patterns_on_modern_meta() {
+        let modern_pattern_meta = get_modern_pattern_meta();
+        let patterns = generate_patterns(&modern_pattern_meta, 3, 3, 3, false, false);
+        // 7 more patterns of a connected pattern with an isolated vertex
+        assert_eq!(patterns.len(), 21);
+        assert_eq!(
+            patterns
+                .iter()
+                .filter(|pattern| !pattern.is_connected())
+                .count(),
+            7
+        );
+    }
+
+    #[test]
+    fn test_generate_tree_patterns_on_modern_meta() {
+        let modern_pattern_meta = get_modern_pattern_meta();
+        let patterns = generate_patterns(&modern_pattern_meta, 3, 3, 3, true, true);
+        assert!(patterns
+            .iter()
+            .all(|pattern| pattern.is_connected()
+                && pattern.get_edges_num() + 1 == pattern.get_vertices_num()));
+        // the triangles are the only cyclic patterns among the connected ones
+        assert_eq!(patterns.len(), 11);
+        let pattern_codes: HashSet<Vec<u8>> = patterns
+            .iter()
+            .map(|pattern| pattern.encode_to())
+            .collect();
+        assert_eq!(pattern_codes.len(), patterns.len());
+        // trees are connected no matter whether the disconnected patterns are asked for
+        let patterns = generate_patterns(&modern_pattern_meta, 4, 4, 4, false, true);
+        assert!(patterns
+            .iter()
+            .all(|pattern| pattern.is_connected()
+                && pattern.get_edges_num() + 1 == pattern.get_vertices_num()));
+        assert!(patterns
+            .iter()
+            .any(|pattern| pattern.get_vertices_num() == 4));
+    }
+
+    #[test]
+    fn test_validate_pattern_against_meta() {
+        assert!(build_modern_pattern_case5()
+            .validate_against_meta(&get_modern_pattern_meta())
+            .is_ok());
+        let sample_pattern_meta = get_sample_pattern_meta();
+        assert!(build_sample_pattern_case1()
+            .validate_against_meta(&sample_pattern_meta)
+            .is_ok());
+        // a triangle of fans which are connected by an edge label not in the sample schema
+        let bogus_triangle = Pattern::try_from(vec![
+            PatternEdge::new(0, 9, PatternVertex::new(0, 2), PatternVertex::new(1, 2)),
+            PatternEdge::new(1, 9, PatternVertex::new(1, 2), PatternVertex::new(2, 2)),
+            PatternEdge::new(2, 9, PatternVertex::new(2, 2), PatternVertex::new(0, 2)),
+        ])
+        .unwrap();
+        assert!(bogus_triangle
+            .validate_against_meta(&sample_pattern_meta)
+            .is_err());
+        // the edge label is known, but a ticket cannot buy a fan
+        let reversed_buy = Pattern::try_from(vec![PatternEdge::new(
+            0,
+            2,
+            PatternVertex::new(0, 3),
+            PatternVertex::new(1, 2),
+        )])
+        .unwrap();
+        assert!(reversed_buy
+            .validate_against_meta(&sample_pattern_meta)
+            .is_err());
+    }
+
+    #[test]
+    fn test_relabel_triangle_pattern() {
+        // vertex labels: person 0, software 1; edge labels: knows 0, create 1
+        let triangle = build_modern_pattern_case5();
+        let identity_map: HashMap<PatternLabelId, PatternLabelId> = HashMap::from([(0, 0), (1, 1)]);
+        let same_triangle = triangle
+            .relabel(&identity_map, &identity_map)
+            .unwrap();
+        assert_eq!(triangle.encode_to(), same_triangle.encode_to());
+        let vertex_label_map: HashMap<PatternLabelId, PatternLabelId> = HashMap::from([(0, 1), (1, 0)]);
+        let edge_label_map: HashMap<PatternLabelId, PatternLabelId> = HashMap::from([(0, 10), (1, 11)]);
+        let relabeled_triangle = triangle
+            .relabel(&vertex_label_map, &edge_label_map)
+            .unwrap();
+        assert_eq!(relabeled_triangle.get_vertices_num(), triangle.get_vertices_num());
+        assert_eq!(relabeled_triangle.get_edges_num(), triangle.get_edges_num());
+        assert!(relabeled_triangle.is_connected());
+        for vertex in triangle.vertices_iter() {
+            assert_eq!(
+                relabeled_triangle
+                    .get_vertex(vertex.get_id())
+                    .unwrap()
+                    .get_label(),
+                vertex_label_map[&vertex.get_label()]
+            );
+        }
+        for edge in triangle.edges_iter() {
+            let relabeled_edge = relabeled_triangle
+                .get_edge(edge.get_id())
+                .unwrap();
+            assert_eq!(relabeled_edge.get_label(), edge_label_map[&edge.get_label()]);
+            assert_eq!(relabeled_edge.get_start_vertex().get_id(), edge.get_start_vertex().get_id());
+            assert_eq!(relabeled_edge.get_end_vertex().get_id(), edge.get_end_vertex().get_id());
+        }
+        assert_ne!(triangle.encode_to(), relabeled_triangle.encode_to());
+        // the edge label 1 (create) is missing in the map
+        let partial_edge_label_map: HashMap<PatternLabelId, PatternLabelId> = HashMap::from([(0, 10)]);
+        assert!(matches!(
+            triangle.relabel(&vertex_label_map, &partial_edge_label_map),
+            Err(IrError::InvalidPattern(_))
+        ));
+    }
+
+    #[test]
+    fn test_pattern_from_parts_with_isolated_vertex() {
+        let v0 = PatternVertex::new(0, 0);
+        let v1 = PatternVertex::new(1, 1);
+        let v2 = PatternVertex::new(2, 0);
+        let edge = PatternEdge::new(0, 1, v0, v1);
+        let pattern = Pattern::from_parts(vec![v2], vec![edge.clone()]).unwrap();
+        assert_eq!(pattern.get_vertices_num(), 3);
+        assert_eq!(pattern.get_edges_num(), 1);
+        assert!(!pattern.is_connected());
+        assert_eq!(pattern.get_vertex_degree(2), 0);
+        let components = pattern.get_connected_components();
+        assert_eq!(components.len(), 2);
+        assert!(components
+            .iter()
+            .any(|component| component.get_vertices_num() == 1 && component.get_edges_num() == 0));
+        assert!(components
+            .iter()
+            .any(|component| component.get_vertices_num() == 2 && component.get_edges_num() == 1));
+        // the edge only part is the same as building from edges
+        let edge_pattern = Pattern::try_from(vec![edge.clone()]).unwrap();
+        let edge_only_pattern = Pattern::from_parts(vec![v0], vec![edge.clone()]).unwrap();
+        assert_eq!(edge_pattern.encode_to(), edge_only_pattern.encode_to());
+        // conflicting vertex labels and empty patterns are rejected
+        assert!(matches!(
+            Pattern::from_parts(vec![PatternVertex::new(0, 1)], vec![edge]),
+            Err(IrError::InvalidPattern(_))
+        ));
+        assert!(matches!(Pattern::from_parts(vec![], vec![]), Err(IrError::InvalidPattern(_))));
+    }
+
+    #[test]
+    fn test_triangle_to_cypher() {
+        let modern_pattern_meta = get_modern_pattern_meta();
+        let mut triangle = build_modern_pattern_case5();
+        let cypher = triangle.to_cypher(&modern_pattern_meta);
+        assert!(cypher.starts_with("MATCH "));
+        assert!(cypher.ends_with(" RETURN count(*)"));
+        assert_eq!(cypher.matches("]->(").count(), 3);
+        assert!(cypher.contains("-[e0:knows]->"));
+        assert_eq!(cypher.matches(":created]->").count(), 2);
+        // every vertex is labeled only once
+        assert_eq!(cypher.matches("(v2:software)").count(), 1);
+        assert!(!cypher.contains("WHERE"));
+        triangle.set_vertex_predicate(0, str_to_expr_pb("@.name == \"marko\"".to_string()).unwrap());
+        triangle.set_edge_predicate(0, str_to_expr_pb("@.weight > 0.5".to_string()).unwrap());
+        // the predicate refers to another tag, which is not representable
+        triangle.set_vertex_predicate(1, str_to_expr_pb("@a.age > 30".to_string()).unwrap());
+        let cypher = triangle.to_cypher(&modern_pattern_meta);
+        assert!(cypher.starts_with("// the predicate of v1 is omitted\nMATCH "));
+        assert!(cypher.contains(" WHERE (v0.name = 'marko') AND (e0.weight > 0.5) RETURN count(*)"));
+    }
 }
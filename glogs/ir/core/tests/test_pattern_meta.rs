@@ -181,4 +181,44 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn test_associated_labels_same_as_iterators() {
+        let sample_pattern_meta = get_sample_pattern_meta();
+        for edge_label in sample_pattern_meta.edge_label_ids_iter() {
+            let vertex_labels: Vec<(PatternLabelId, PatternLabelId)> = sample_pattern_meta
+                .associated_vlabels_iter_by_elabel(edge_label)
+                .collect();
+            assert_eq!(sample_pattern_meta.associated_vlabels(edge_label), vertex_labels.as_slice());
+        }
+        for start_v_label in sample_pattern_meta.vertex_label_ids_iter() {
+            for end_v_label in sample_pattern_meta.vertex_label_ids_iter() {
+                let edge_labels: Vec<(PatternLabelId, PatternDirection)> = sample_pattern_meta
+                    .associated_elabels_iter_by_vlabel(start_v_label, end_v_label)
+                    .collect();
+                assert_eq!(
+                    sample_pattern_meta.associated_elabels(start_v_label, end_v_label),
+                    edge_labels.as_slice()
+                );
+            }
+        }
+        assert!(sample_pattern_meta
+            .associated_vlabels(9)
+            .is_empty());
+    }
+
+    #[test]
+    fn test_label_interners_of_sample_schema() {
+        let sample_pattern_meta = get_sample_pattern_meta();
+        let vertex_labels = sample_pattern_meta.get_vertex_label_interner();
+        let edge_labels = sample_pattern_meta.get_edge_label_interner();
+        assert_eq!(vertex_labels.len(), 4);
+        assert_eq!(edge_labels.len(), 3);
+        assert_eq!(vertex_labels.name_to_id("TICKET"), Some(3));
+        assert_eq!(vertex_labels.id_to_name(1), Some("PLAYER"));
+        // vertex and edge labels are interned separately
+        assert_eq!(edge_labels.id_to_name(1), Some("LOVEDBY"));
+        assert_eq!(edge_labels.name_to_id("PLAYER"), None);
+        assert_eq!(vertex_labels.display_label(9), "9");
+    }
 }
@@ -0,0 +1,921 @@
+//
+//! Copyright 2020 Alibaba Group Holding Limited.
+//!
+//! Licensed under the Apache License, Version 2.0 (the "License");
+//! you may not use this file except in compliance with the License.
+//! You may obtain a copy of the License at
+//!
+//! http://www.apache.org/licenses/LICENSE-2.0
+//!
+//! Unless required by applicable law or agreed to in writing, software
+//! distributed under the License is distributed on an "AS IS" BASIS,
+//! WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//! See the License for the specific language governing permissions and
+//! limitations under the License.
+
+mod common;
+
+#[cfg(test)]
+mod test {
+    use std::convert::TryFrom;
+
+    use ir_common::expr_parse::str_to_expr_pb;
+    use ir_common::generated::algebra as pb;
+    use ir_common::generated::common as common_pb;
+    use ir_core::catalogue::catalog::{Catalogue, IntersectOrder, PatMatPlanSpace};
+    use ir_core::catalogue::extend_step::DefiniteExtendStep;
+    use ir_core::catalogue::pattern::{Pattern, PatternEdge, PatternVertex};
+    use ir_core::catalogue::plan::{
+        get_definite_extend_steps, get_definite_extend_steps_in_order, get_definite_extend_steps_parallel,
+        get_definite_extend_steps_with_cache, validate_logical_plan, AggregateSpec, CostModel,
+        PatternCountCache, PlanConfig, PlanGenerator, PlanStepKind,
+    };
+    use ir_core::catalogue::PatternLabelId;
+    use ir_core::error::IrError;
+
+    use crate::common::pattern_cases::*;
+    use crate::common::pattern_meta_cases::*;
+
+    #[test]
+    fn test_stand_alone_match_plan_roots() {
+        let ldbc_pattern_meta = get_ldbc_pattern_meta();
+        let ldbc_pattern = build_ldbc_pattern_from_pb_case1().unwrap();
+        let plan = ldbc_pattern
+            .generate_simple_extend_match_plan(&ldbc_pattern_meta, false, &PlanConfig::default())
+            .unwrap();
+        assert_eq!(plan.roots, vec![0]);
+    }
+
+    #[test]
+    fn test_distributed_match_plan_roots() {
+        let ldbc_pattern_meta = get_ldbc_pattern_meta();
+        let ldbc_pattern = build_ldbc_pattern_from_pb_case1().unwrap();
+        let plan = ldbc_pattern
+            .generate_simple_extend_match_plan(&ldbc_pattern_meta, true, &PlanConfig::default())
+            .unwrap();
+        assert_eq!(plan.roots, vec![0]);
+    }
+
+    #[test]
+    fn test_match_plan_without_aggregate() {
+        let ldbc_pattern_meta = get_ldbc_pattern_meta();
+        let ldbc_pattern = build_ldbc_pattern_from_pb_case1().unwrap();
+        let count_plan = ldbc_pattern
+            .generate_simple_extend_match_plan(&ldbc_pattern_meta, false, &PlanConfig::default())
+            .unwrap();
+        let plan = ldbc_pattern
+            .generate_simple_extend_match_plan(
+                &ldbc_pattern_meta,
+                false,
+                &PlanConfig::default().with_aggregate(AggregateSpec::None),
+            )
+            .unwrap();
+        let nodes_num = plan.nodes.len();
+        assert_eq!(count_plan.nodes.len() - 1, nodes_num);
+        // the last match operator is followed by the sink directly
+        let last_match_node = &plan.nodes[nodes_num - 2];
+        assert_eq!(count_plan.nodes[nodes_num - 2], *last_match_node);
+        assert_eq!(last_match_node.children, vec![(nodes_num - 1) as i32]);
+        assert!(!matches!(
+            last_match_node
+                .opr
+                .as_ref()
+                .unwrap()
+                .opr
+                .as_ref()
+                .unwrap(),
+            pb::logical_plan::operator::Opr::GroupBy(_)
+        ));
+        match plan.nodes[nodes_num - 1]
+            .opr
+            .as_ref()
+            .unwrap()
+            .opr
+            .as_ref()
+            .unwrap()
+        {
+            pb::logical_plan::operator::Opr::Sink(sink) => {
+                assert_eq!(sink.tags.len(), ldbc_pattern.get_vertices_num())
+            }
+            _ => panic!("the last node should be a sink"),
+        }
+    }
+
+    #[test]
+    fn test_heuristic_match_plan_for_cycle() {
+        let ldbc_pattern_meta = get_ldbc_pattern_meta();
+        let ldbc_pattern = build_ldbc_pattern_from_pb_case1().unwrap();
+        assert!(ldbc_pattern.is_single_cycle());
+        let mut catalog =
+            Catalogue::build_from_pattern(&ldbc_pattern, PatMatPlanSpace::ExtendWithIntersection);
+        let plan = ldbc_pattern
+            .generate_heuristic_match_plan(&mut catalog, &ldbc_pattern_meta, false, &PlanConfig::default())
+            .unwrap();
+        assert_eq!(plan.roots, vec![0]);
+        let simple_plan = ldbc_pattern
+            .generate_simple_extend_match_plan(&ldbc_pattern_meta, false, &PlanConfig::default())
+            .unwrap();
+        assert_eq!(plan.nodes.len(), simple_plan.nodes.len());
+        assert!(validate_logical_plan(&plan).is_ok());
+        // every edge of the cycle is expanded exactly once, with its own label
+        let get_expand_labels = |plan: &pb::LogicalPlan| {
+            let mut expand_labels: Vec<PatternLabelId> = plan
+                .nodes
+                .iter()
+                .filter_map(|node| match node.opr.as_ref().unwrap().opr.as_ref().unwrap() {
+                    pb::logical_plan::operator::Opr::Edge(expand) => Some(expand),
+                    _ => None,
+                })
+                .flat_map(|expand| expand.params.as_ref().unwrap().tables.iter())
+                .map(|label| match label.item {
+                    Some(common_pb::name_or_id::Item::Id(label_id)) => label_id,
+                    _ => panic!("edge label should be an id"),
+                })
+                .collect();
+            expand_labels.sort();
+            expand_labels
+        };
+        let mut edge_labels: Vec<PatternLabelId> = ldbc_pattern
+            .edges_iter()
+            .map(|edge| edge.get_label())
+            .collect();
+        edge_labels.sort();
+        assert_eq!(get_expand_labels(&plan), edge_labels);
+        assert_eq!(get_expand_labels(&simple_plan), edge_labels);
+    }
+
+    #[test]
+    fn test_heuristic_match_plan_starts_from_rare_label() {
+        let sample_pattern_meta = get_sample_pattern_meta();
+        let sample_pattern = build_sample_pattern_case1();
+        assert!(sample_pattern.is_single_cycle());
+        let mut catalog =
+            Catalogue::build_from_pattern(&sample_pattern, PatMatPlanSpace::ExtendWithIntersection);
+        let plan = sample_pattern
+            .generate_heuristic_match_plan(
+                &mut catalog,
+                &sample_pattern_meta,
+                false,
+                &PlanConfig::default(),
+            )
+            .unwrap();
+        // the ticket vertex is the rarest one, and should be scanned first
+        match plan.nodes[0]
+            .opr
+            .as_ref()
+            .and_then(|opr| opr.opr.as_ref())
+        {
+            Some(pb::logical_plan::operator::Opr::Scan(scan)) => {
+                assert_eq!(scan.params.as_ref().unwrap().tables, vec![3.into()])
+            }
+            _ => panic!("the first operator of the plan should be a scan"),
+        }
+    }
+
+    #[test]
+    fn test_heuristic_match_plan_starts_from_rare_label_for_path() {
+        let sample_pattern_meta = get_sample_pattern_meta();
+        let sample_pattern = build_sample_pattern_case2();
+        assert!(!sample_pattern.is_single_cycle());
+        // all the costs are tied in an empty catalog, so the source is chosen by the pattern meta
+        let mut catalog = Catalogue::default();
+        let plan = sample_pattern
+            .generate_heuristic_match_plan(
+                &mut catalog,
+                &sample_pattern_meta,
+                false,
+                &PlanConfig::default(),
+            )
+            .unwrap();
+        match plan.nodes[0]
+            .opr
+            .as_ref()
+            .and_then(|opr| opr.opr.as_ref())
+        {
+            Some(pb::logical_plan::operator::Opr::Scan(scan)) => {
+                assert_eq!(scan.params.as_ref().unwrap().tables, vec![3.into()])
+            }
+            _ => panic!("the first operator of the plan should be a scan"),
+        }
+    }
+
+    #[test]
+    fn test_plan_generators_with_different_cost_models() {
+        let modern_pattern_meta = get_modern_pattern_meta();
+        let triangle = build_modern_pattern_case5();
+        let mut catalog = Catalogue::build_from_pattern(&triangle, PatMatPlanSpace::ExtendWithIntersection);
+        // knows edges are rarer than created edges, but the knows edge has a large adjacent path
+        let pattern_counts = vec![
+            (build_modern_pattern_case1(), 1.0),
+            (build_modern_pattern_case2(), 1.0),
+            (build_modern_pattern_case3(), 1.0),
+            (build_modern_pattern_case4(), 10.0),
+            (build_modern_pattern_case6(), 1.0),
+            (build_modern_pattern_case7(), 1000000.0),
+            (build_modern_pattern_case8(), 1.0),
+            (triangle.clone(), 1.0),
+        ];
+        for (pattern, count) in pattern_counts {
+            assert!(catalog.set_pattern_count(&pattern, count.into()));
+        }
+        let pattern_indices: Vec<_> = catalog.pattern_indices_iter().collect();
+        for pattern_index in pattern_indices {
+            catalog.set_extend_count_infos(pattern_index);
+        }
+        let triangle_index = catalog
+            .get_pattern_index(&triangle.encode_to())
+            .unwrap();
+        let knows_index = catalog
+            .get_pattern_index(&build_modern_pattern_case3().encode_to())
+            .unwrap();
+        let created_index = catalog
+            .get_pattern_index(&build_modern_pattern_case4().encode_to())
+            .unwrap();
+        let mut count_only_generator = PlanGenerator::new(&triangle, &catalog, &modern_pattern_meta, false)
+            .with_cost_model(CostModel::new(0.0, 0.0, 6.0, 3.0));
+        let mut adjacency_aware_generator =
+            PlanGenerator::new(&triangle, &catalog, &modern_pattern_meta, false)
+                .with_cost_model(CostModel::new(1.0, 0.0, 6.0, 3.0));
+        let count_only_approach = count_only_generator
+            .get_best_approach(triangle_index)
+            .unwrap()
+            .unwrap();
+        let adjacency_aware_approach = adjacency_aware_generator
+            .get_best_approach(triangle_index)
+            .unwrap()
+            .unwrap();
+        assert_eq!(count_only_approach.get_src_pattern_index(), knows_index);
+        assert_eq!(adjacency_aware_approach.get_src_pattern_index(), created_index);
+        // the chosen approaches are not written back to the shared catalog
+        assert!(catalog
+            .get_pattern_weight(triangle_index)
+            .unwrap()
+            .get_best_approach()
+            .is_none());
+    }
+
+    #[test]
+    fn test_plan_generators_with_distributed_cost_weights() {
+        let modern_pattern_meta = get_modern_pattern_meta();
+        let triangle = build_modern_pattern_case5();
+        let mut catalog = Catalogue::build_from_pattern(&triangle, PatMatPlanSpace::ExtendWithIntersection);
+        // the same counts as test_plan_generators_with_different_cost_models
+        let pattern_counts = vec![
+            (build_modern_pattern_case1(), 1.0),
+            (build_modern_pattern_case2(), 1.0),
+            (build_modern_pattern_case3(), 1.0),
+            (build_modern_pattern_case4(), 10.0),
+            (build_modern_pattern_case6(), 1.0),
+            (build_modern_pattern_case7(), 1000000.0),
+            (build_modern_pattern_case8(), 1.0),
+            (triangle.clone(), 1.0),
+        ];
+        for (pattern, count) in pattern_counts {
+            assert!(catalog.set_pattern_count(&pattern, count.into()));
+        }
+        let pattern_indices: Vec<_> = catalog.pattern_indices_iter().collect();
+        for pattern_index in pattern_indices {
+            catalog.set_extend_count_infos(pattern_index);
+        }
+        let triangle_index = catalog
+            .get_pattern_index(&triangle.encode_to())
+            .unwrap();
+        let knows_index = catalog
+            .get_pattern_index(&build_modern_pattern_case3().encode_to())
+            .unwrap();
+        let created_index = catalog
+            .get_pattern_index(&build_modern_pattern_case4().encode_to())
+            .unwrap();
+        // adjacency counts are only weighted in the distributed mode
+        let cost_model = CostModel::new(0.0, 0.0, 6.0, 3.0).with_distributed_weights(1.0, 0.0, 6.0, 3.0);
+        let mut stand_alone_generator =
+            PlanGenerator::new(&triangle, &catalog, &modern_pattern_meta, false)
+                .with_cost_model(cost_model);
+        let mut distributed_generator =
+            PlanGenerator::new(&triangle, &catalog, &modern_pattern_meta, true).with_cost_model(cost_model);
+        assert_eq!(
+            stand_alone_generator
+                .get_cost_model()
+                .get_alpha(),
+            0.0
+        );
+        assert_eq!(
+            distributed_generator
+                .get_cost_model()
+                .get_alpha(),
+            1.0
+        );
+        let stand_alone_approach = stand_alone_generator
+            .get_best_approach(triangle_index)
+            .unwrap()
+            .unwrap();
+        let distributed_approach = distributed_generator
+            .get_best_approach(triangle_index)
+            .unwrap()
+            .unwrap();
+        assert_eq!(stand_alone_approach.get_src_pattern_index(), knows_index);
+        assert_eq!(distributed_approach.get_src_pattern_index(), created_index);
+        // without distributed weights, both modes share the same weights
+        let shared_cost_model = CostModel::new(0.0, 0.0, 6.0, 3.0);
+        assert_eq!(shared_cost_model.for_mode(true), shared_cost_model.for_mode(false));
+    }
+
+    #[test]
+    fn test_plan_explanation_matches_logical_plan() {
+        let modern_pattern_meta = get_modern_pattern_meta();
+        let pattern = build_modern_pattern_case9();
+        assert_eq!(pattern.get_vertices_num(), 4);
+        for plan_space in [PatMatPlanSpace::ExtendWithIntersection, PatMatPlanSpace::Hybrid] {
+            let mut catalog = Catalogue::build_from_pattern(&pattern, plan_space);
+            let pattern_indices: Vec<_> = catalog.pattern_indices_iter().collect();
+            for &pattern_index in pattern_indices.iter() {
+                catalog.set_pattern_count_with_index(pattern_index, 1.0.into());
+            }
+            for pattern_index in pattern_indices {
+                catalog.set_extend_count_infos(pattern_index);
+            }
+            let mut plan_generator = PlanGenerator::new(&pattern, &catalog, &modern_pattern_meta, false);
+            let plan = plan_generator
+                .generate_pattern_match_plan()
+                .unwrap();
+            let explanation = plan_generator.explain();
+            assert_eq!(explanation.get_nodes_num(), plan.nodes.len());
+            assert_eq!(explanation.get_roots(), &plan.roots);
+            // only the scan, count and sink nodes are not generated from a catalogue step
+            let steps: Vec<_> = explanation
+                .get_nodes()
+                .iter()
+                .filter_map(|node| node.get_step())
+                .collect();
+            assert_eq!(steps.len(), plan.nodes.len() - 3);
+            assert!(steps
+                .iter()
+                .any(|step| step.get_kind() == PlanStepKind::Source));
+            assert!(steps
+                .iter()
+                .any(|step| step.get_target_pattern_code() == &pattern.encode_to()));
+            assert!(!explanation.to_string().is_empty());
+        }
+    }
+
+    #[test]
+    fn test_match_plan_with_limit() {
+        let ldbc_pattern_meta = get_ldbc_pattern_meta();
+        let ldbc_pattern = build_ldbc_pattern_from_pb_case1().unwrap();
+        let simple_plan = ldbc_pattern
+            .generate_simple_extend_match_plan(
+                &ldbc_pattern_meta,
+                false,
+                &PlanConfig::default().with_limit(Some(10)),
+            )
+            .unwrap();
+        let modern_pattern_meta = get_modern_pattern_meta();
+        let square = build_modern_pattern_case10();
+        let mut catalog = Catalogue::build_from_pattern(&square, PatMatPlanSpace::BinaryJoin);
+        let optimized_plan = square
+            .generate_optimized_match_plan(
+                &mut catalog,
+                &modern_pattern_meta,
+                false,
+                &PlanConfig::default().with_limit(Some(10)),
+            )
+            .unwrap();
+        for plan in [simple_plan, optimized_plan] {
+            let operators: Vec<_> = plan
+                .nodes
+                .iter()
+                .map(|node| node.opr.as_ref().unwrap().opr.as_ref().unwrap())
+                .collect();
+            let nodes_num = operators.len();
+            // the limit operator is followed by the count operator and the sink
+            match operators[nodes_num - 3] {
+                pb::logical_plan::operator::Opr::Limit(limit) => {
+                    assert_eq!(limit.range, Some(pb::Range { lower: 0, upper: 10 }))
+                }
+                _ => panic!("the limit operator should be before the count operator"),
+            }
+            assert!(matches!(operators[nodes_num - 2], pb::logical_plan::operator::Opr::GroupBy(_)));
+            assert!(matches!(operators[nodes_num - 1], pb::logical_plan::operator::Opr::Sink(_)));
+            assert_eq!(plan.nodes[nodes_num - 4].children, vec![(nodes_num - 3) as i32]);
+            assert_eq!(plan.nodes[nodes_num - 3].children, vec![(nodes_num - 2) as i32]);
+            assert_eq!(plan.nodes[nodes_num - 2].children, vec![(nodes_num - 1) as i32]);
+            assert!(plan.nodes[nodes_num - 1].children.is_empty());
+        }
+        // a limit out of the range of the operator is rejected instead of being truncated
+        assert!(ldbc_pattern
+            .generate_simple_extend_match_plan(
+                &ldbc_pattern_meta,
+                false,
+                &PlanConfig::default().with_limit(Some(i32::MAX as usize + 1)),
+            )
+            .is_err());
+    }
+
+    #[test]
+    fn test_binary_join_match_plan_for_square() {
+        let modern_pattern_meta = get_modern_pattern_meta();
+        let square = build_modern_pattern_case10();
+        let mut catalog = Catalogue::build_from_pattern(&square, PatMatPlanSpace::BinaryJoin);
+        let plan = square
+            .generate_optimized_match_plan(
+                &mut catalog,
+                &modern_pattern_meta,
+                false,
+                &PlanConfig::default(),
+            )
+            .unwrap();
+        let operators: Vec<_> = plan
+            .nodes
+            .iter()
+            .map(|node| node.opr.as_ref().unwrap().opr.as_ref().unwrap())
+            .collect();
+        assert!(operators
+            .iter()
+            .any(|opr| matches!(opr, pb::logical_plan::operator::Opr::Join(_))));
+        // the count operator followed by the sink
+        let nodes_num = operators.len();
+        assert!(matches!(operators[nodes_num - 2], pb::logical_plan::operator::Opr::GroupBy(_)));
+        assert!(matches!(operators[nodes_num - 1], pb::logical_plan::operator::Opr::Sink(_)));
+        // the nodes can be topologically sorted, i.e., there is no cycle in the plan
+        let mut in_degrees = vec![0; nodes_num];
+        for node in plan.nodes.iter() {
+            for &child in node.children.iter() {
+                assert!((child as usize) < nodes_num);
+                in_degrees[child as usize] += 1;
+            }
+        }
+        let mut queue: Vec<usize> = (0..nodes_num)
+            .filter(|&node_id| in_degrees[node_id] == 0)
+            .collect();
+        let mut sorted_nodes_num = 0;
+        while let Some(node_id) = queue.pop() {
+            sorted_nodes_num += 1;
+            for &child in plan.nodes[node_id].children.iter() {
+                in_degrees[child as usize] -= 1;
+                if in_degrees[child as usize] == 0 {
+                    queue.push(child as usize);
+                }
+            }
+        }
+        assert_eq!(sorted_nodes_num, nodes_num);
+    }
+
+    #[test]
+    fn test_hybrid_match_plan_chooses_bushy_join_when_cheaper() {
+        let modern_pattern_meta = get_modern_pattern_meta();
+        // Person -> knows -> Person -> ... -> Person with 9 vertices
+        let path_edges: Vec<PatternEdge> = (0..8)
+            .map(|i| PatternEdge::new(i, 0, PatternVertex::new(i, 0), PatternVertex::new(i + 1, 0)))
+            .collect();
+        let path = Pattern::try_from(path_edges).unwrap();
+        let generate_join_sizes = |expensive_vertices_nums: &[usize]| {
+            let mut catalog = Catalogue::build_from_pattern(&path, PatMatPlanSpace::Hybrid);
+            let pattern_indices: Vec<_> = catalog.pattern_indices_iter().collect();
+            for &pattern_index in pattern_indices.iter() {
+                let vertices_num = catalog
+                    .get_pattern_weight(pattern_index)
+                    .unwrap()
+                    .get_pattern()
+                    .get_vertices_num();
+                let count = if expensive_vertices_nums.contains(&vertices_num) { 1000000.0 } else { 1.0 };
+                catalog.set_pattern_count_with_index(pattern_index, count.into());
+            }
+            for pattern_index in pattern_indices {
+                catalog.set_extend_count_infos(pattern_index);
+            }
+            let mut plan_generator = PlanGenerator::new(&path, &catalog, &modern_pattern_meta, false)
+                .with_cost_model(CostModel::new(0.0, 0.0, 0.0, 0.0));
+            plan_generator
+                .generate_pattern_match_plan()
+                .unwrap();
+            // the vertices nums of the patterns joined by the join nodes
+            let mut join_sizes: Vec<usize> = plan_generator
+                .explain()
+                .get_nodes()
+                .iter()
+                .filter(|node| node.get_operator() == "Join")
+                .map(|node| {
+                    let join_step = node.get_step().unwrap();
+                    assert_eq!(join_step.get_kind(), PlanStepKind::Join);
+                    let pattern_index = catalog
+                        .get_pattern_index(join_step.get_target_pattern_code())
+                        .unwrap();
+                    catalog
+                        .get_pattern_weight(pattern_index)
+                        .unwrap()
+                        .get_pattern()
+                        .get_vertices_num()
+                })
+                .collect();
+            join_sizes.sort();
+            join_sizes
+        };
+        // with uniform counts, extending the path edge by edge is the cheapest
+        assert!(generate_join_sizes(&[]).is_empty());
+        // when only the paths with 1, 2, 3, 5 and 9 vertices are cheap, every left-deep plan passes
+        // through an expensive path, as the 5-vertex path is expensive to reach by extend steps.
+        // The bushy plan joins two 5-vertex paths, each of which is a join of two 3-vertex paths
+        assert_eq!(generate_join_sizes(&[4, 6, 7, 8]), vec![5, 5, 9]);
+    }
+
+    #[test]
+    fn test_parallel_definite_extend_steps_same_as_sequential() {
+        let modern_pattern_meta = get_modern_pattern_meta();
+        let mut catalog = Catalogue::build_from_meta(&modern_pattern_meta, 2, 3);
+        let pattern_indices: Vec<_> = catalog.pattern_indices_iter().collect();
+        for (i, &pattern_index) in pattern_indices.iter().enumerate() {
+            catalog.set_pattern_count_with_index(pattern_index, (10.0 * (i + 1) as f64).into());
+        }
+        for &pattern_index in pattern_indices.iter() {
+            catalog.set_extend_count_infos(pattern_index);
+        }
+        for pattern in [build_modern_pattern_case9(), build_modern_pattern_case11()] {
+            assert!(catalog
+                .get_pattern_index(&pattern.encode_to())
+                .is_none());
+            let mut sequential_catalog = catalog.clone();
+            let mut parallel_catalog = catalog.clone();
+            let (sequential_extend_steps, sequential_cost) =
+                get_definite_extend_steps(pattern.clone(), &mut sequential_catalog);
+            let (parallel_extend_steps, parallel_cost) =
+                get_definite_extend_steps_parallel(pattern.clone(), &mut parallel_catalog, None);
+            let get_extend_step_ids = |extend_steps: &[DefiniteExtendStep]| -> Vec<(_, Vec<_>)> {
+                extend_steps
+                    .iter()
+                    .map(|extend_step| {
+                        (
+                            extend_step.get_target_vertex().get_id(),
+                            extend_step
+                                .iter()
+                                .map(|extend_edge| extend_edge.get_edge_id())
+                                .collect(),
+                        )
+                    })
+                    .collect()
+            };
+            assert_eq!(sequential_extend_steps.len(), pattern.get_vertices_num());
+            assert_eq!(
+                get_extend_step_ids(&sequential_extend_steps),
+                get_extend_step_ids(&parallel_extend_steps)
+            );
+            assert_eq!(sequential_cost, parallel_cost);
+            // the best approaches chosen in catalog are set in the same way
+            for &pattern_index in pattern_indices.iter() {
+                assert_eq!(
+                    sequential_catalog
+                        .get_pattern_weight(pattern_index)
+                        .unwrap()
+                        .get_best_approach(),
+                    parallel_catalog
+                        .get_pattern_weight(pattern_index)
+                        .unwrap()
+                        .get_best_approach()
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_definite_extend_steps_estimate_repeated_sub_patterns_once() {
+        let modern_pattern_meta = get_modern_pattern_meta();
+        let mut catalog = Catalogue::build_from_meta(&modern_pattern_meta, 2, 3);
+        let pattern_indices: Vec<_> = catalog.pattern_indices_iter().collect();
+        for &pattern_index in pattern_indices.iter() {
+            catalog.set_pattern_count_with_index(pattern_index, 10.0.into());
+        }
+        // the sub-paths of a path are reached by removing vertices from both ends
+        let path = build_modern_pattern_case11();
+        let pattern_count_cache = PatternCountCache::default();
+        let (cached_extend_steps, cached_cost) =
+            get_definite_extend_steps_with_cache(path.clone(), &mut catalog.clone(), &pattern_count_cache);
+        let (extend_steps, cost) = get_definite_extend_steps(path, &mut catalog);
+        assert_eq!(cached_extend_steps.len(), extend_steps.len());
+        assert_eq!(cached_cost, cost);
+        assert!(pattern_count_cache.get_patterns_num() > 0);
+        assert!(pattern_count_cache.get_patterns_num() < pattern_count_cache.get_lookup_num());
+    }
+
+    #[test]
+    fn test_pattern_count_cache_inflates_path_edges_by_cost_model() {
+        let modern_pattern_meta = get_modern_pattern_meta();
+        let mut catalog = Catalogue::build_from_meta(&modern_pattern_meta, 2, 3);
+        let pattern_indices: Vec<_> = catalog.pattern_indices_iter().collect();
+        for &pattern_index in pattern_indices.iter() {
+            catalog.set_pattern_count_with_index(pattern_index, 10.0.into());
+        }
+        // a path of 1..2 hops is estimated as a single edge plus the inflated 2-hop one
+        let path = build_modern_pattern_case12();
+        let single_edge = path
+            .edges_iter()
+            .next()
+            .unwrap()
+            .clone()
+            .with_hop_range(None);
+        let single_edge_count = PatternCountCache::default()
+            .estimate_pattern_count(&catalog, &Pattern::try_from(vec![single_edge]).unwrap());
+        let pattern_count_cache =
+            PatternCountCache::new(&CostModel::default().with_path_hop_inflation(2.0));
+        assert_eq!(pattern_count_cache.estimate_pattern_count(&catalog, &path), single_edge_count * 3.0);
+        assert_eq!(
+            PatternCountCache::default().estimate_pattern_count(&catalog, &path),
+            single_edge_count * 11.0
+        );
+    }
+
+    #[test]
+    fn test_definite_extend_steps_in_manual_order() {
+        let build_counted_catalog = |pattern| {
+            let mut catalog =
+                Catalogue::build_from_pattern(pattern, PatMatPlanSpace::ExtendWithIntersection);
+            let pattern_indices: Vec<_> = catalog.pattern_indices_iter().collect();
+            for &pattern_index in pattern_indices.iter() {
+                catalog.set_pattern_count_with_index(pattern_index, 10.0.into());
+            }
+            for pattern_index in pattern_indices {
+                catalog.set_extend_count_infos(pattern_index);
+            }
+            catalog
+        };
+        let triangle = build_modern_pattern_case5();
+        let catalog = build_counted_catalog(&triangle);
+        let (extend_steps, _) =
+            get_definite_extend_steps_in_order(&triangle, &[2, 0, 1], &catalog).unwrap();
+        let target_vertex_ids: Vec<_> = extend_steps
+            .iter()
+            .map(|extend_step| extend_step.get_target_vertex().get_id())
+            .collect();
+        assert_eq!(target_vertex_ids, vec![2, 0, 1]);
+        assert_eq!(extend_steps[0].get_extend_edges_num(), 0);
+        assert_eq!(extend_steps[2].get_extend_edges_num(), 2);
+        for invalid_order in [vec![0, 1], vec![0, 1, 1], vec![0, 1, 3]] {
+            assert!(matches!(
+                get_definite_extend_steps_in_order(&triangle, &invalid_order, &catalog),
+                Err(IrError::InvalidPattern(_))
+            ));
+        }
+        // vertices 1 and 2 are only connected through vertex 0
+        let path = build_modern_pattern_case6();
+        let catalog = build_counted_catalog(&path);
+        assert!(get_definite_extend_steps_in_order(&path, &[1, 0, 2], &catalog).is_ok());
+        assert!(matches!(
+            get_definite_extend_steps_in_order(&path, &[1, 2, 0], &catalog),
+            Err(IrError::InvalidPattern(_))
+        ));
+    }
+
+    #[test]
+    fn test_definite_extend_steps_in_intersect_order() {
+        // a path 0 -> 1 -> 2, and vertex 3 is extended last from all of them by edge labels 0, 1, 2
+        let pattern = Pattern::try_from(vec![
+            PatternEdge::new(0, 0, PatternVertex::new(0, 0), PatternVertex::new(1, 0)),
+            PatternEdge::new(1, 0, PatternVertex::new(1, 0), PatternVertex::new(2, 0)),
+            PatternEdge::new(2, 0, PatternVertex::new(0, 0), PatternVertex::new(3, 0)),
+            PatternEdge::new(3, 1, PatternVertex::new(1, 0), PatternVertex::new(3, 0)),
+            PatternEdge::new(4, 2, PatternVertex::new(2, 0), PatternVertex::new(3, 0)),
+        ])
+        .unwrap();
+        let mut catalog = Catalogue::build_from_pattern(&pattern, PatMatPlanSpace::ExtendWithIntersection);
+        // every edge of label l multiplies the pattern count by l + 2
+        let pattern_indices: Vec<_> = catalog.pattern_indices_iter().collect();
+        for pattern_index in pattern_indices {
+            let count: f64 = catalog
+                .get_pattern_weight(pattern_index)
+                .unwrap()
+                .get_pattern()
+                .edges_iter()
+                .map(|edge| (edge.get_label() + 2) as f64)
+                .product();
+            catalog.set_pattern_count_with_index(pattern_index, count.into());
+        }
+        let mut intersect_counts = vec![];
+        for (intersect_order, expected_first_label) in
+            [(IntersectOrder::AscendingCount, 0), (IntersectOrder::DescendingCount, 2)]
+        {
+            catalog.set_intersect_order(intersect_order);
+            let (extend_steps, cost) =
+                get_definite_extend_steps_in_order(&pattern, &[0, 1, 2, 3], &catalog).unwrap();
+            let last_extend_step = extend_steps.last().unwrap();
+            assert_eq!(last_extend_step.get_target_vertex().get_id(), 3);
+            assert_eq!(
+                last_extend_step
+                    .iter()
+                    .next()
+                    .unwrap()
+                    .get_edge_label(),
+                expected_first_label
+            );
+            intersect_counts.push(cost.get_intersect_count().into_inner());
+        }
+        // the path of count 4 is intersected with edges of labels 0, 1 (8 + 24), or labels 2, 1 (16 + 48)
+        assert_eq!(intersect_counts, vec![32.0, 64.0]);
+    }
+
+    #[test]
+    fn test_plan_estimated_cardinalities_end_with_pattern_count() {
+        let modern_pattern_meta = get_modern_pattern_meta();
+        let pattern = build_modern_pattern_case9();
+        for plan_space in [PatMatPlanSpace::ExtendWithIntersection, PatMatPlanSpace::Hybrid] {
+            let mut catalog = Catalogue::build_from_pattern(&pattern, plan_space);
+            let pattern_indices: Vec<_> = catalog.pattern_indices_iter().collect();
+            for &pattern_index in pattern_indices.iter() {
+                let edges_num = catalog
+                    .get_pattern_weight(pattern_index)
+                    .unwrap()
+                    .get_pattern()
+                    .get_edges_num();
+                catalog.set_pattern_count_with_index(pattern_index, (10.0 + edges_num as f64).into());
+            }
+            for pattern_index in pattern_indices {
+                catalog.set_extend_count_infos(pattern_index);
+            }
+            let pattern_count = catalog
+                .estimate_pattern_count(&pattern)
+                .into_inner();
+            let mut plan_generator = PlanGenerator::new(&pattern, &catalog, &modern_pattern_meta, false);
+            let plan = plan_generator
+                .generate_pattern_match_plan()
+                .unwrap();
+            let estimated_cardinalities = plan_generator.get_estimated_cardinalities();
+            assert_eq!(estimated_cardinalities.len(), plan.nodes.len());
+            // the node before the count and sink nodes outputs the whole pattern
+            let nodes_num = plan.nodes.len();
+            assert_eq!(estimated_cardinalities[nodes_num - 3], Some(pattern_count));
+            assert!(estimated_cardinalities[nodes_num - 2].is_none());
+            assert!(estimated_cardinalities[nodes_num - 1].is_none());
+            // the vertex scanned first is estimated by its count
+            assert!(estimated_cardinalities.contains(&Some(10.0)));
+            let explanation = plan_generator.explain();
+            assert_eq!(
+                explanation.get_nodes()[nodes_num - 3].get_estimated_cardinality(),
+                Some(pattern_count)
+            );
+        }
+    }
+
+    #[test]
+    fn test_match_plan_with_path_edge() {
+        let modern_pattern_meta = get_modern_pattern_meta();
+        let path_pattern = build_modern_pattern_case12();
+        for is_distributed in [false, true] {
+            let plan = path_pattern
+                .generate_simple_extend_match_plan(
+                    &modern_pattern_meta,
+                    is_distributed,
+                    &PlanConfig::default(),
+                )
+                .unwrap();
+            let operators: Vec<&pb::logical_plan::operator::Opr> = plan
+                .nodes
+                .iter()
+                .map(|node| node.opr.as_ref().unwrap().opr.as_ref().unwrap())
+                .collect();
+            let path_expand_idx = operators
+                .iter()
+                .position(|opr| matches!(opr, pb::logical_plan::operator::Opr::Path(_)))
+                .expect("PathExpand operator not found");
+            if let pb::logical_plan::operator::Opr::Path(path_expand) = operators[path_expand_idx] {
+                // the hop range of PathExpand is [lower, upper)
+                assert_eq!(path_expand.hop_range, Some(pb::Range { lower: 1, upper: 3 }));
+            }
+            // the end vertex of the path is tagged by the following GetV
+            assert!(matches!(operators[path_expand_idx + 1], pb::logical_plan::operator::Opr::Vertex(_)));
+            assert!(!operators
+                .iter()
+                .any(|opr| matches!(opr, pb::logical_plan::operator::Opr::Edge(_))));
+        }
+    }
+
+    #[test]
+    fn test_validate_logical_plan_wiring() {
+        let modern_pattern_meta = get_modern_pattern_meta();
+        let plan = build_modern_pattern_case5()
+            .generate_simple_extend_match_plan(&modern_pattern_meta, true, &PlanConfig::default())
+            .unwrap();
+        assert!(validate_logical_plan(&plan).is_ok());
+        let nodes_num = plan.nodes.len() as i32;
+        let assert_invalid_plan =
+            |plan: &pb::LogicalPlan, expected_msg: String| match validate_logical_plan(plan) {
+                Err(IrError::InvalidPlan(msg)) => assert_eq!(msg, expected_msg),
+                result => panic!("unexpected validation result: {:?}", result),
+            };
+        // a root out of range
+        let mut miswired_plan = plan.clone();
+        miswired_plan.roots = vec![nodes_num];
+        assert_invalid_plan(&miswired_plan, format!("root {} is out of range", nodes_num));
+        // a child out of range
+        let mut miswired_plan = plan.clone();
+        miswired_plan.nodes[0].children = vec![nodes_num];
+        assert_invalid_plan(&miswired_plan, format!("child {} of node 0 is invalid", nodes_num));
+        // a node being its own child
+        let mut miswired_plan = plan.clone();
+        miswired_plan.nodes[1].children = vec![1];
+        assert_invalid_plan(&miswired_plan, "child 1 of node 1 is invalid".to_string());
+        // a node wired to nothing becomes another sink
+        let mut miswired_plan = plan.clone();
+        miswired_plan.nodes[0].children.clear();
+        assert_invalid_plan(&miswired_plan, "2 sinks in the plan, expected exactly one".to_string());
+        // an intersect node with a parent out of range
+        let mut miswired_plan = plan.clone();
+        let intersect_idx = miswired_plan
+            .nodes
+            .iter()
+            .position(|node| {
+                matches!(
+                    node.opr.as_ref().unwrap().opr.as_ref().unwrap(),
+                    pb::logical_plan::operator::Opr::Intersect(_)
+                )
+            })
+            .expect("Intersect operator not found");
+        let intersect_node = &mut miswired_plan.nodes[intersect_idx];
+        if let Some(pb::logical_plan::operator::Opr::Intersect(intersect)) = intersect_node
+            .opr
+            .as_mut()
+            .and_then(|opr| opr.opr.as_mut())
+        {
+            intersect.parents[0] = nodes_num;
+        }
+        assert_invalid_plan(
+            &miswired_plan,
+            format!("parent {} of intersect node {} is out of range", nodes_num, intersect_idx),
+        );
+    }
+
+    #[test]
+    fn test_match_plan_pushes_source_predicate_into_scan() {
+        let modern_pattern_meta = get_modern_pattern_meta();
+        let mut person = build_modern_pattern_case1();
+        let age_predicate = str_to_expr_pb("@.age > 30".to_string()).unwrap();
+        person.set_vertex_predicate(0, age_predicate.clone());
+        for is_distributed in [false, true] {
+            let plan = person
+                .generate_simple_extend_match_plan(
+                    &modern_pattern_meta,
+                    is_distributed,
+                    &PlanConfig::default(),
+                )
+                .unwrap();
+            match plan.nodes[0]
+                .opr
+                .as_ref()
+                .and_then(|opr| opr.opr.as_ref())
+            {
+                Some(pb::logical_plan::operator::Opr::Scan(scan)) => {
+                    assert_eq!(scan.params.as_ref().unwrap().predicate, Some(age_predicate.clone()))
+                }
+                _ => panic!("the first operator of the plan should be a scan"),
+            }
+            // the predicate is not evaluated by a separate select any more
+            assert!(!plan.nodes.iter().any(|node| matches!(
+                node.opr
+                    .as_ref()
+                    .and_then(|opr| opr.opr.as_ref()),
+                Some(pb::logical_plan::operator::Opr::Select(_))
+            )));
+            assert_eq!(plan.nodes[0].children, vec![1]);
+        }
+    }
+
+    #[test]
+    fn test_plan_generator_pushes_source_predicate_into_scan() {
+        let modern_pattern_meta = get_modern_pattern_meta();
+        let mut pattern = build_modern_pattern_case9();
+        let age_predicate = str_to_expr_pb("@.age > 30".to_string()).unwrap();
+        let vertex_ids: Vec<_> = pattern
+            .vertices_iter()
+            .map(|vertex| vertex.get_id())
+            .collect();
+        for vertex_id in vertex_ids {
+            pattern.set_vertex_predicate(vertex_id, age_predicate.clone());
+        }
+        let mut catalog = Catalogue::build_from_pattern(&pattern, PatMatPlanSpace::ExtendWithIntersection);
+        let pattern_indices: Vec<_> = catalog.pattern_indices_iter().collect();
+        for &pattern_index in pattern_indices.iter() {
+            catalog.set_pattern_count_with_index(pattern_index, 1.0.into());
+        }
+        for pattern_index in pattern_indices {
+            catalog.set_extend_count_infos(pattern_index);
+        }
+        let plan = PlanGenerator::new(&pattern, &catalog, &modern_pattern_meta, false)
+            .generate_pattern_match_plan()
+            .unwrap();
+        match plan.nodes[0]
+            .opr
+            .as_ref()
+            .and_then(|opr| opr.opr.as_ref())
+        {
+            Some(pb::logical_plan::operator::Opr::Scan(scan)) => {
+                assert_eq!(scan.params.as_ref().unwrap().predicate, Some(age_predicate))
+            }
+            _ => panic!("the first operator of the plan should be a scan"),
+        }
+        // the scan is followed by the as node of the source vertex
+        assert!(matches!(
+            plan.nodes[1]
+                .opr
+                .as_ref()
+                .and_then(|opr| opr.opr.as_ref()),
+            Some(pb::logical_plan::operator::Opr::As(_))
+        ));
+        assert_eq!(plan.nodes[0].children, vec![1]);
+    }
+}
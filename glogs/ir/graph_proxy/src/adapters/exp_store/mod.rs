@@ -17,4 +17,4 @@ mod partitioner;
 mod read_graph;
 
 pub use partitioner::SimplePartition;
-pub use read_graph::create_exp_store;
+pub use read_graph::{collect_degree_stats, create_exp_store, load_graph_from_csv, ExpStore};
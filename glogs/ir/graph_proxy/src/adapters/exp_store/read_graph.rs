@@ -14,25 +14,28 @@
 //! limitations under the License.
 
 use std::fmt;
+use std::fs::File;
+use std::io::BufReader;
 use std::path::Path;
 use std::sync::atomic::{AtomicPtr, Ordering};
 use std::sync::Arc;
 
 use ahash::{HashMap, HashMapExt};
+use csv::{ReaderBuilder, StringRecord};
 use dyn_type::{object, Object};
 use graph_store::common::LabelId as StoreLabelId;
 use graph_store::config::{JsonConf, DIR_GRAPH_SCHEMA, FILE_SCHEMA};
 use graph_store::ldbc::{LDBCVertexParser, LABEL_SHIFT_BITS};
+use graph_store::parser::{parse_properties, ColumnMeta, DataType};
 use graph_store::prelude::{
     DefaultId, EdgeId, GlobalStoreTrait, GlobalStoreUpdate, GraphDBConfig, InternalId, LDBCGraphSchema,
-    LargeGraphDB, LocalEdge, LocalVertex, MutableGraphDB, Row, INVALID_LABEL_ID,
+    LargeGraphDB, LocalEdge, LocalVertex, MutableGraphDB, Row, Schema, INVALID_LABEL_ID,
 };
-use ir_common::{KeyId, LabelId, NameOrId};
+use graph_store::schema::{END_ID_FIELD, ID_FIELD, START_ID_FIELD};
+use ir_common::{KeyId, LabelId, NameOrId, OneOrMany};
 use pegasus::configure_with_default;
 use pegasus_common::downcast::*;
 use pegasus_common::impl_as_any;
-use rand::prelude::StdRng;
-use rand::{Rng, SeedableRng};
 
 use crate::apis::graph::PKV;
 use crate::apis::{
@@ -40,24 +43,36 @@ use crate::apis::{
     Statement, Vertex, ID,
 };
 use crate::errors::{GraphProxyError, GraphProxyResult};
-use crate::{filter_limit, filter_sample_limit, limit_n, sample_limit};
+use crate::utils::is_hash_sampled;
+use crate::{filter_hash_sample_limit, filter_limit, hash_sample_limit, limit_n};
 
 const EXP_STORE_PK: KeyId = 0;
 
 lazy_static! {
     pub static ref DATA_PATH: String = configure_with_default!(String, "DATA_PATH", "".to_string());
     pub static ref PARTITION_ID: usize = configure_with_default!(usize, "PARTITION_ID", 0);
-    pub static ref GRAPH: LargeGraphDB<DefaultId, InternalId> = _init_graph();
+    pub static ref SAMPLE_SEED: u64 = configure_with_default!(u64, "SAMPLE_SEED", 0);
+    pub static ref GRAPH: Arc<LargeGraphDB<DefaultId, InternalId>> = Arc::new(_init_graph());
     static ref GRAPH_PROXY: Arc<ExpStore> = initialize();
 }
 
 pub struct ExpStore {
-    store: &'static LargeGraphDB<DefaultId, InternalId>,
+    store: Arc<LargeGraphDB<DefaultId, InternalId>>,
 }
 
 fn initialize() -> Arc<ExpStore> {
     lazy_static::initialize(&GRAPH);
-    Arc::new(ExpStore { store: &GRAPH })
+    Arc::new(ExpStore::from_graph(GRAPH.clone()))
+}
+
+/// Borrow the graph for the elements scanned from it, which are `'static` in the runtime.
+///
+/// The graph is kept alive by a clone of its `Arc` in every iterator and lazy details borrowing from it,
+/// so it outlives the borrowed elements.
+fn borrow_graph(
+    graph: &Arc<LargeGraphDB<DefaultId, InternalId>>,
+) -> &'static LargeGraphDB<DefaultId, InternalId> {
+    unsafe { &*Arc::as_ptr(graph) }
 }
 
 fn _init_graph() -> LargeGraphDB<DefaultId, InternalId> {
@@ -225,81 +240,298 @@ fn _init_modern_graph() -> LargeGraphDB<DefaultId, InternalId> {
     mut_graph.into_graph(schema)
 }
 
-impl ReadGraph for ExpStore {
-    fn scan_vertex(
-        &self, params: &QueryParams,
+/// Load a graph from raw csv files with the schema of the given path.
+///
+/// Each vertex file is given with its vertex type, e.g., "person", and each edge file is given
+/// with its full edge type, e.g., "person_knows_person". A file has a header line, followed by
+/// the records whose `|`-separated columns are in the order of the properties in the schema.
+pub fn load_graph_from_csv<S: AsRef<Path>, P: AsRef<Path>>(
+    schema_path: S, vertex_csvs: &[(&str, P)], edge_csvs: &[(&str, P)],
+) -> GraphProxyResult<LargeGraphDB<DefaultId, InternalId>> {
+    let schema = LDBCGraphSchema::from_json_file(schema_path)
+        .map_err(|e| GraphProxyError::query_store_error(&format!("read schema error: {}", e)))?;
+    let mut mut_graph: MutableGraphDB<DefaultId, InternalId> = GraphDBConfig::default().new();
+    for (vertex_type, path) in vertex_csvs {
+        _load_vertices_from_csv(&mut mut_graph, &schema, vertex_type, path)?;
+    }
+    for (edge_type, path) in edge_csvs {
+        _load_edges_from_csv(&mut mut_graph, &schema, edge_type, path)?;
+    }
+    Ok(mut_graph.into_graph(schema))
+}
+
+impl ExpStore {
+    /// Build the store of the given graph, which is shared with the elements scanned from the store.
+    pub fn from_graph(graph: Arc<LargeGraphDB<DefaultId, InternalId>>) -> ExpStore {
+        ExpStore { store: graph }
+    }
+
+    /// Build the store of the graph loaded from raw csv files, see `load_graph_from_csv()`.
+    pub fn from_csv<S: AsRef<Path>, P: AsRef<Path>>(
+        schema_path: S, vertex_csvs: &[(&str, P)], edge_csvs: &[(&str, P)],
+    ) -> GraphProxyResult<ExpStore> {
+        let graph = load_graph_from_csv(schema_path, vertex_csvs, edge_csvs)?;
+        Ok(ExpStore::from_graph(Arc::new(graph)))
+    }
+
+    /// Collect the average degrees of the graph of the store, see `collect_degree_stats()`.
+    pub fn collect_degree_stats(&self) -> HashMap<(LabelId, LabelId, Direction), f64> {
+        collect_degree_stats(&self.store)
+    }
+
+    /// Scan the vertices assigned to the given worker.
+    ///
+    /// DemoGraph contains a single graph partition on each server,
+    /// therefore, there's no need to use the specific partition id for query.
+    /// Besides, workers will scan the vertices in a parallel way.
+    /// The vertices are sampled by the hash of their ids seeded with `SAMPLE_SEED`,
+    /// so the same vertices are sampled regardless of the number of workers.
+    fn scan_vertex_of_worker(
+        &self, params: &QueryParams, worker_id: u32, workers_num: u32,
     ) -> GraphProxyResult<Box<dyn Iterator<Item = Vertex> + Send>> {
-        // DemoGraph contains a single graph partition on each server,
-        // therefore, there's no need to use the specific partition id for query.
-        // Besides, workers will scan the vertices in a parallel way
         let label_ids = encode_storage_label(&params.labels);
         let props = params.columns.clone();
+        let schema = self.store.get_graph_schema();
+        let seed = *SAMPLE_SEED;
+        let store = self.store.clone();
 
-        // get_current_worker_checked() in case pegasus not started, i.e., for ci tests.
-        let worker_id = pegasus::get_current_worker_checked()
-            .map(|worker| worker.index)
-            .unwrap_or(0);
-        let workers_num = pegasus::get_current_worker_checked()
-            .map(|worker| worker.local_peers)
-            .unwrap_or(1);
         let count = self
             .store
             .count_all_vertices(label_ids.as_ref());
-        let partial_count = count / workers_num as usize;
-        let take_count = if (worker_id + 1) % workers_num == 0 {
-            count - partial_count * (workers_num as usize - 1)
-        } else {
-            partial_count
-        };
+        let (skip_count, take_count) = get_worker_range(count, worker_id, workers_num);
 
-        let result = self
-            .store
+        let result = borrow_graph(&self.store)
             .get_all_vertices(label_ids.as_ref())
-            .skip((worker_id % workers_num) as usize * partial_count)
+            .skip(skip_count)
             .take(take_count)
-            .map(move |v| to_runtime_vertex(v, props.clone()));
-
-        Ok(filter_sample_limit!(result, params.filter, params.sample_ratio, params.limit))
-    }
+            .map(move |v| to_runtime_vertex(v, props.clone(), schema.clone(), store.clone()));
 
-    fn index_scan_vertex(
-        &self, _label: LabelId, _primary_key: &PKV, _params: &QueryParams,
-    ) -> GraphProxyResult<Option<Vertex>> {
-        Err(GraphProxyError::unsupported_error(
-            "Experiment storage does not support index_scan_vertex for now",
-        ))?
+        Ok(filter_hash_sample_limit!(result, params.filter, params.sample_ratio, seed, params.limit))
     }
 
-    fn scan_edge(&self, params: &QueryParams) -> GraphProxyResult<Box<dyn Iterator<Item = Edge> + Send>> {
-        // DemoGraph contains a single graph partition on each server,
-        // therefore, there's no need to use the specific partition id for query.
-        // Besides, workers will scan the edges in a parallel way
+    /// Scan the edges assigned to the given worker, analogous to `scan_vertex_of_worker()`.
+    ///
+    /// Different from the vertices, the edges are sampled before being converted into runtime edges,
+    /// so that the `LazyEdgeDetails` are only constructed for the retained edges.
+    fn scan_edge_of_worker(
+        &self, params: &QueryParams, worker_id: u32, workers_num: u32,
+    ) -> GraphProxyResult<Box<dyn Iterator<Item = Edge> + Send>> {
         let label_ids = encode_storage_label(&params.labels);
         let props = params.columns.clone();
+        let schema = self.store.get_graph_schema();
+        let seed = *SAMPLE_SEED;
+        let sample_ratio = params.sample_ratio;
+        let store = self.store.clone();
 
-        // get_current_worker_checked() in case pegasus not started, i.e., for ci tests.
-        let worker_id = pegasus::get_current_worker_checked()
-            .map(|worker| worker.index)
-            .unwrap_or(0);
-        let workers_num = pegasus::get_current_worker_checked()
-            .map(|worker| worker.local_peers)
-            .unwrap_or(1);
         let count = self.store.count_all_edges(label_ids.as_ref());
-        let partial_count = count / workers_num as usize;
-        let take_count = if (worker_id + 1) % workers_num == 0 {
-            count - partial_count * (workers_num as usize - 1)
-        } else {
-            partial_count
-        };
+        let (skip_count, take_count) = get_worker_range(count, worker_id, workers_num);
 
-        let result = self
-            .store
+        let result = borrow_graph(&self.store)
             .get_all_edges(label_ids.as_ref())
-            .skip((worker_id % workers_num) as usize * partial_count)
+            .skip(skip_count)
             .take(take_count)
-            .map(move |v| to_runtime_edge(v, props.clone()));
+            .filter(move |e| {
+                sample_ratio.map_or(true, |ratio| is_hash_sampled(encode_runtime_e_id(e), seed, ratio))
+            })
+            .map(move |e| to_runtime_edge(e, props.clone(), schema.clone(), store.clone()));
+
+        Ok(filter_limit!(result, params.filter, params.limit))
+    }
+}
+
+/// Collect the average degrees of vertices of each label, along each edge label and direction.
+///
+/// The key is (vertex label, edge label, direction), where only `Direction::Out` and `Direction::In`
+/// present. The degree of a vertex label with no such edges is omitted.
+pub fn collect_degree_stats(
+    graph: &LargeGraphDB<DefaultId, InternalId>,
+) -> HashMap<(LabelId, LabelId, Direction), f64> {
+    let mut vertices_count: HashMap<LabelId, usize> = HashMap::new();
+    let mut edges_count: HashMap<(LabelId, LabelId, Direction), usize> = HashMap::new();
+    for v in graph.get_all_vertices(None) {
+        let v_label = encode_runtime_v_label(&v);
+        *vertices_count.entry(v_label).or_default() += 1;
+        for e in graph.get_out_edges(v.get_id(), None) {
+            *edges_count
+                .entry((v_label, encode_runtime_e_label(&e), Direction::Out))
+                .or_default() += 1;
+        }
+        for e in graph.get_in_edges(v.get_id(), None) {
+            *edges_count
+                .entry((v_label, encode_runtime_e_label(&e), Direction::In))
+                .or_default() += 1;
+        }
+    }
+    edges_count
+        .into_iter()
+        .map(|(key, count)| (key, count as f64 / vertices_count[&key.0] as f64))
+        .collect()
+}
+
+/// Get the index of the current worker and the number of workers
+fn get_current_worker_info() -> (u32, u32) {
+    // get_current_worker_checked() in case pegasus not started, i.e., for ci tests.
+    let worker_id = pegasus::get_current_worker_checked()
+        .map(|worker| worker.index)
+        .unwrap_or(0);
+    let workers_num = pegasus::get_current_worker_checked()
+        .map(|worker| worker.local_peers)
+        .unwrap_or(1);
+    (worker_id, workers_num)
+}
+
+/// Get the numbers of elements to skip and to take for the given worker, when `count` elements
+/// are scanned by `workers_num` workers in parallel
+fn get_worker_range(count: usize, worker_id: u32, workers_num: u32) -> (usize, usize) {
+    let partial_count = count / workers_num as usize;
+    let take_count = if (worker_id + 1) % workers_num == 0 {
+        count - partial_count * (workers_num as usize - 1)
+    } else {
+        partial_count
+    };
+    ((worker_id % workers_num) as usize * partial_count, take_count)
+}
+
+fn _read_csv_records<P: AsRef<Path>>(path: P) -> GraphProxyResult<Vec<StringRecord>> {
+    let file = File::open(path.as_ref()).map_err(|e| {
+        GraphProxyError::query_store_error(&format!("open {:?} error: {}", path.as_ref(), e))
+    })?;
+    ReaderBuilder::new()
+        .delimiter(b'|')
+        .has_headers(true)
+        .from_reader(BufReader::new(file))
+        .records()
+        .collect::<Result<Vec<StringRecord>, csv::Error>>()
+        .map_err(|e| GraphProxyError::query_store_error(&format!("read {:?} error: {}", path.as_ref(), e)))
+}
+
+fn _get_columns(header: &[(String, DataType)]) -> Vec<ColumnMeta> {
+    header
+        .iter()
+        .map(|(name, data_type)| (name.clone(), *data_type, name == ID_FIELD).into())
+        .collect()
+}
+
+fn _get_column_index(header: &[(String, DataType)], field: &str) -> GraphProxyResult<usize> {
+    header
+        .iter()
+        .position(|(name, _)| name == field)
+        .ok_or_else(|| GraphProxyError::query_store_error(&format!("column {} not found in schema", field)))
+}
+
+fn _parse_ldbc_id(record: &StringRecord, index: usize) -> GraphProxyResult<usize> {
+    record
+        .get(index)
+        .and_then(|id| id.parse::<usize>().ok())
+        .ok_or_else(|| {
+            GraphProxyError::query_store_error(&format!("parse id error in record {:?}", record))
+        })
+}
+
+fn _load_vertices_from_csv<P: AsRef<Path>>(
+    mut_graph: &mut MutableGraphDB<DefaultId, InternalId>, schema: &LDBCGraphSchema, vertex_type: &str,
+    path: P,
+) -> GraphProxyResult<()> {
+    let label = schema
+        .get_vertex_label_id(vertex_type)
+        .ok_or_else(|| {
+            GraphProxyError::query_store_error(&format!("vertex type {} not found", vertex_type))
+        })?;
+    let header = schema.get_vertex_header(label).unwrap_or(&[]);
+    let columns = _get_columns(header);
+    let id_index = _get_column_index(header, ID_FIELD)?;
+    for record in _read_csv_records(path)? {
+        let global_id = LDBCVertexParser::to_global_id(_parse_ldbc_id(&record, id_index)?, label);
+        let properties = parse_properties(record.iter(), Some(&columns)).map_err(|e| {
+            GraphProxyError::query_store_error(&format!("parse record {:?} error: {:?}", record, e))
+        })?;
+        if !properties.is_empty() {
+            mut_graph
+                .add_vertex_with_properties(global_id, [label, INVALID_LABEL_ID], properties)
+                .map_err(|e| GraphProxyError::query_store_error(&format!("add vertex error: {:?}", e)))?;
+        } else {
+            mut_graph.add_vertex(global_id, [label, INVALID_LABEL_ID]);
+        }
+    }
+    Ok(())
+}
+
+fn _load_edges_from_csv<P: AsRef<Path>>(
+    mut_graph: &mut MutableGraphDB<DefaultId, InternalId>, schema: &LDBCGraphSchema, edge_type: &str,
+    path: P,
+) -> GraphProxyResult<()> {
+    let edge_label_tuple = schema
+        .get_edge_label_tuple(edge_type)
+        .ok_or_else(|| GraphProxyError::query_store_error(&format!("edge type {} not found", edge_type)))?;
+    let header = schema
+        .get_edge_header(edge_label_tuple.edge_label)
+        .unwrap_or(&[]);
+    let columns = _get_columns(header);
+    let src_id_index = _get_column_index(header, START_ID_FIELD)?;
+    let dst_id_index = _get_column_index(header, END_ID_FIELD)?;
+    for record in _read_csv_records(path)? {
+        let src_global_id = LDBCVertexParser::to_global_id(
+            _parse_ldbc_id(&record, src_id_index)?,
+            edge_label_tuple.src_vertex_label,
+        );
+        let dst_global_id = LDBCVertexParser::to_global_id(
+            _parse_ldbc_id(&record, dst_id_index)?,
+            edge_label_tuple.dst_vertex_label,
+        );
+        let properties = parse_properties(record.iter(), Some(&columns)).map_err(|e| {
+            GraphProxyError::query_store_error(&format!("parse record {:?} error: {:?}", record, e))
+        })?;
+        let is_added = if !properties.is_empty() {
+            mut_graph
+                .add_edge_with_properties(
+                    src_global_id,
+                    dst_global_id,
+                    edge_label_tuple.edge_label,
+                    properties,
+                )
+                .is_ok()
+        } else {
+            mut_graph.add_edge(src_global_id, dst_global_id, edge_label_tuple.edge_label)
+        };
+        if !is_added {
+            Err(GraphProxyError::query_store_error(&format!(
+                "add edge error, the end vertices of record {:?} are not found",
+                record
+            )))?
+        }
+    }
+    Ok(())
+}
+
+impl ReadGraph for ExpStore {
+    fn scan_vertex(
+        &self, params: &QueryParams,
+    ) -> GraphProxyResult<Box<dyn Iterator<Item = Vertex> + Send>> {
+        let (worker_id, workers_num) = get_current_worker_info();
+        self.scan_vertex_of_worker(params, worker_id, workers_num)
+    }
+
+    fn index_scan_vertex(
+        &self, label: LabelId, primary_key: &PKV, params: &QueryParams,
+    ) -> GraphProxyResult<Option<Vertex>> {
+        // The only primary key of a vertex is its outer id (see `get_primary_key()`),
+        // thus its global id can be reconstructed together with the label.
+        let outer_id = match primary_key {
+            OneOrMany::One(pkv) => pkv[0].1.as_usize().map_err(|e| {
+                GraphProxyError::query_store_error(&format!("invalid primary key {:?}: {}", pkv[0].1, e))
+            })?,
+            OneOrMany::Many(_) => Err(GraphProxyError::unsupported_error(
+                "Experiment storage does not support index_scan_vertex with multiple primary keys",
+            ))?,
+        };
+        let global_id: DefaultId = LDBCVertexParser::to_global_id(outer_id, label as StoreLabelId);
+        self.get_vertex(&[global_id as ID], params)
+            .map(|mut v_iter| v_iter.next())
+    }
 
-        Ok(filter_sample_limit!(result, params.filter, params.sample_ratio, params.limit))
+    fn scan_edge(&self, params: &QueryParams) -> GraphProxyResult<Box<dyn Iterator<Item = Edge> + Send>> {
+        let (worker_id, workers_num) = get_current_worker_info();
+        self.scan_edge_of_worker(params, worker_id, workers_num)
     }
 
     fn get_vertex(
@@ -307,8 +539,13 @@ impl ReadGraph for ExpStore {
     ) -> GraphProxyResult<Box<dyn Iterator<Item = Vertex> + Send>> {
         let mut result = Vec::with_capacity(ids.len());
         for id in ids {
-            if let Some(local_vertex) = self.store.get_vertex(*id as DefaultId) {
-                let v = to_runtime_vertex(local_vertex, params.columns.clone());
+            if let Some(local_vertex) = borrow_graph(&self.store).get_vertex(*id as DefaultId) {
+                let v = to_runtime_vertex(
+                    local_vertex,
+                    params.columns.clone(),
+                    self.store.get_graph_schema(),
+                    self.store.clone(),
+                );
                 result.push(v);
             }
         }
@@ -321,8 +558,13 @@ impl ReadGraph for ExpStore {
         let mut result = Vec::with_capacity(ids.len());
         for id in ids {
             let eid = encode_store_e_id(id);
-            if let Some(local_edge) = self.store.get_edge(eid) {
-                let e = to_runtime_edge(local_edge, params.columns.clone());
+            if let Some(local_edge) = borrow_graph(&self.store).get_edge(eid) {
+                let e = to_runtime_edge(
+                    local_edge,
+                    params.columns.clone(),
+                    self.store.get_graph_schema(),
+                    self.store.clone(),
+                );
                 result.push(e);
             }
         }
@@ -335,15 +577,21 @@ impl ReadGraph for ExpStore {
         let edge_label_ids = encode_storage_label(params.labels.as_ref());
         let filter = params.filter.clone();
         let limit = params.limit.clone();
-        let graph = self.store;
+        let store = self.store.clone();
 
         let stmt = from_fn(move |v: ID| {
+            let graph = borrow_graph(&store);
+            let store = store.clone();
             let iter = match direction {
                 Direction::Out => graph.get_out_vertices(v as DefaultId, edge_label_ids.as_ref()),
                 Direction::In => graph.get_in_vertices(v as DefaultId, edge_label_ids.as_ref()),
                 Direction::Both => graph.get_both_vertices(v as DefaultId, edge_label_ids.as_ref()),
             }
-            .map(move |v| to_empty_vertex(v));
+            .map(move |v| {
+                // the iterator keeps the graph it borrows from alive
+                let _ = &store;
+                to_empty_vertex(v)
+            });
             Ok(filter_limit!(iter, filter, limit))
         });
         Ok(stmt)
@@ -355,17 +603,21 @@ impl ReadGraph for ExpStore {
         let edge_label_ids = encode_storage_label(&params.labels);
         let filter = params.filter.clone();
         let limit = params.limit.clone();
-        let graph = self.store;
+        let store = self.store.clone();
         let props = params.columns.clone();
+        let schema = store.get_graph_schema();
 
         let stmt = from_fn(move |v: ID| {
+            let graph = borrow_graph(&store);
+            let store = store.clone();
             let props = props.clone();
+            let schema = schema.clone();
             let iter = match direction {
                 Direction::Out => graph.get_out_edges(v as DefaultId, edge_label_ids.as_ref()),
                 Direction::In => graph.get_in_edges(v as DefaultId, edge_label_ids.as_ref()),
                 Direction::Both => graph.get_both_edges(v as DefaultId, edge_label_ids.as_ref()),
             }
-            .map(move |e| to_runtime_edge(e, props.clone()));
+            .map(move |e| to_runtime_edge(e, props.clone(), schema.clone(), store.clone()));
             Ok(filter_limit!(iter, filter, limit))
         });
         Ok(stmt)
@@ -385,11 +637,14 @@ pub fn create_exp_store() {
 }
 
 #[inline]
-fn to_runtime_vertex(v: LocalVertex<'static, DefaultId>, prop_keys: Option<Vec<NameOrId>>) -> Vertex {
+fn to_runtime_vertex(
+    v: LocalVertex<'static, DefaultId>, prop_keys: Option<Vec<NameOrId>>, schema: Arc<LDBCGraphSchema>,
+    graph: Arc<LargeGraphDB<DefaultId, InternalId>>,
+) -> Vertex {
     // For vertices, we query properties via vid
     let id = v.get_id() as ID;
     let label = encode_runtime_v_label(&v);
-    let details = LazyVertexDetails::new(v, prop_keys);
+    let details = LazyVertexDetails::new(v, prop_keys, schema, graph);
     Vertex::new(id, Some(label), DynDetails::lazy(details))
 }
 
@@ -401,13 +656,16 @@ fn to_empty_vertex(v: LocalVertex<'static, DefaultId>) -> Vertex {
 }
 
 #[inline]
-fn to_runtime_edge(e: LocalEdge<'static, DefaultId, InternalId>, prop_keys: Option<Vec<NameOrId>>) -> Edge {
+fn to_runtime_edge(
+    e: LocalEdge<'static, DefaultId, InternalId>, prop_keys: Option<Vec<NameOrId>>,
+    schema: Arc<LDBCGraphSchema>, graph: Arc<LargeGraphDB<DefaultId, InternalId>>,
+) -> Edge {
     let id = encode_runtime_e_id(&e);
     let label = encode_runtime_e_label(&e);
     let src_id = e.get_src_id();
     let dst_id = e.get_dst_id();
     let from_src = e.is_from_start();
-    let details = LazyEdgeDetails::new(e, prop_keys);
+    let details = LazyEdgeDetails::new(e, prop_keys, schema, graph);
     let store_src_label: StoreLabelId = (src_id >> LABEL_SHIFT_BITS) as StoreLabelId;
     let store_dst_label: StoreLabelId = (dst_id >> LABEL_SHIFT_BITS) as StoreLabelId;
     let src_label = encode_runtime_label(store_src_label);
@@ -437,14 +695,21 @@ struct LazyVertexDetails {
     // and None indicates we do not need any property
     prop_keys: Option<Vec<NameOrId>>,
     inner: AtomicPtr<LocalVertex<'static, DefaultId>>,
+    // the schema to look up the property names of property ids
+    schema: Arc<LDBCGraphSchema>,
+    // the graph the inner vertex borrows from, which is kept alive until the inner vertex is dropped
+    graph: Arc<LargeGraphDB<DefaultId, InternalId>>,
 }
 
 impl_as_any!(LazyVertexDetails);
 
 impl LazyVertexDetails {
-    pub fn new(v: LocalVertex<'static, DefaultId>, prop_keys: Option<Vec<NameOrId>>) -> Self {
+    pub fn new(
+        v: LocalVertex<'static, DefaultId>, prop_keys: Option<Vec<NameOrId>>, schema: Arc<LDBCGraphSchema>,
+        graph: Arc<LargeGraphDB<DefaultId, InternalId>>,
+    ) -> Self {
         let ptr = Box::into_raw(Box::new(v));
-        LazyVertexDetails { prop_keys, inner: AtomicPtr::new(ptr) }
+        LazyVertexDetails { prop_keys, inner: AtomicPtr::new(ptr), schema, graph }
     }
 
     fn get_vertex_ptr(&self) -> Option<*mut LocalVertex<'static, DefaultId>> {
@@ -468,18 +733,22 @@ impl fmt::Debug for LazyVertexDetails {
 
 impl Details for LazyVertexDetails {
     fn get_property(&self, key: &NameOrId) -> Option<PropertyValue> {
-        if let NameOrId::Str(key) = key {
-            if let Some(ptr) = self.get_vertex_ptr() {
-                unsafe {
-                    (*ptr)
-                        .get_property(key)
-                        .map(|prop| PropertyValue::Borrowed(prop))
-                }
-            } else {
-                None
+        if let Some(ptr) = self.get_vertex_ptr() {
+            unsafe {
+                let key = match key {
+                    NameOrId::Str(key) => key.as_str(),
+                    // the property id is the index of the property in the schema of the vertex label
+                    NameOrId::Id(prop_id) => get_prop_name(
+                        self.schema
+                            .get_vertex_header((*ptr).get_label()[0]),
+                        *prop_id,
+                    )?,
+                };
+                (*ptr)
+                    .get_property(key)
+                    .map(|prop| PropertyValue::Borrowed(prop))
             }
         } else {
-            info!("Have not support getting property by prop_id in exp_store yet");
             None
         }
     }
@@ -549,14 +818,21 @@ struct LazyEdgeDetails {
     // and None indicates we do not need any property,
     prop_keys: Option<Vec<NameOrId>>,
     inner: AtomicPtr<LocalEdge<'static, DefaultId, InternalId>>,
+    // the schema to look up the property names of property ids
+    schema: Arc<LDBCGraphSchema>,
+    // the graph the inner edge borrows from, which is kept alive until the inner edge is dropped
+    graph: Arc<LargeGraphDB<DefaultId, InternalId>>,
 }
 
 impl_as_any!(LazyEdgeDetails);
 
 impl LazyEdgeDetails {
-    pub fn new(e: LocalEdge<'static, DefaultId, InternalId>, prop_keys: Option<Vec<NameOrId>>) -> Self {
+    pub fn new(
+        e: LocalEdge<'static, DefaultId, InternalId>, prop_keys: Option<Vec<NameOrId>>,
+        schema: Arc<LDBCGraphSchema>, graph: Arc<LargeGraphDB<DefaultId, InternalId>>,
+    ) -> Self {
         let ptr = Box::into_raw(Box::new(e));
-        LazyEdgeDetails { prop_keys, inner: AtomicPtr::new(ptr) }
+        LazyEdgeDetails { prop_keys, inner: AtomicPtr::new(ptr), schema, graph }
     }
 
     fn get_edge_ptr(&self) -> Option<*mut LocalEdge<'static, DefaultId, InternalId>> {
@@ -580,19 +856,21 @@ impl fmt::Debug for LazyEdgeDetails {
 
 impl Details for LazyEdgeDetails {
     fn get_property(&self, key: &NameOrId) -> Option<PropertyValue> {
-        if let NameOrId::Str(key) = key {
-            let ptr = self.get_edge_ptr();
-            if let Some(ptr) = ptr {
-                unsafe {
-                    (*ptr)
-                        .get_property(key)
-                        .map(|prop| PropertyValue::Borrowed(prop))
-                }
-            } else {
-                None
+        let ptr = self.get_edge_ptr();
+        if let Some(ptr) = ptr {
+            unsafe {
+                let key = match key {
+                    NameOrId::Str(key) => key.as_str(),
+                    // the property id is the index of the property in the schema of the edge label
+                    NameOrId::Id(prop_id) => {
+                        get_prop_name(self.schema.get_edge_header((*ptr).get_label()), *prop_id)?
+                    }
+                };
+                (*ptr)
+                    .get_property(key)
+                    .map(|prop| PropertyValue::Borrowed(prop))
             }
         } else {
-            info!("Have not support getting property by prop_id in experiments store yet");
             None
         }
     }
@@ -653,6 +931,17 @@ impl Drop for LazyEdgeDetails {
     }
 }
 
+/// Get the name of the property by its id, i.e., its index in the header of the schema
+#[inline]
+fn get_prop_name(header: Option<&[(String, DataType)]>, prop_id: KeyId) -> Option<&str> {
+    if prop_id < 0 {
+        return None;
+    }
+    header
+        .and_then(|header| header.get(prop_id as usize))
+        .map(|(name, _)| name.as_str())
+}
+
 /// Edge's ID is encoded by its internal index
 #[inline]
 fn encode_runtime_e_id(e: &LocalEdge<DefaultId, InternalId>) -> ID {
@@ -708,11 +997,16 @@ fn encode_storage_label(labels: &Vec<LabelId>) -> Option<Vec<StoreLabelId>> {
 
 #[cfg(test)]
 mod tests {
+    use std::fs::write;
+
+    use dyn_type::{object, Object};
     use graph_store::common::LabelId;
     use graph_store::ldbc::{LDBCVertexParser, LABEL_SHIFT_BITS};
-    use graph_store::prelude::{DefaultId, GlobalStoreTrait};
+    use graph_store::prelude::{DefaultId, GlobalStoreTrait, Schema};
+    use ir_common::{KeyId, NameOrId, OneOrMany};
 
-    use super::GRAPH;
+    use super::{load_graph_from_csv, ExpStore, EXP_STORE_PK, GRAPH};
+    use crate::apis::{Details, Direction, GraphElement, QueryParams, ReadGraph, ID};
 
     #[test]
     fn it_works() {
@@ -738,4 +1032,218 @@ mod tests {
         assert_eq!(v1_label, 0);
         assert_eq!(v2_label, 1);
     }
+
+    #[test]
+    fn load_graph_from_csv_test() {
+        let dir = tempfile::tempdir().unwrap();
+        let schema = r#"
+        {
+          "vertex_type_map": { "person": 0 },
+          "edge_type_map": { "knows": 0 },
+          "vertex_prop": { "person": [["id", "ID"], ["name", "String"], ["age", "Integer"]] },
+          "edge_prop": { "knows": [["start_id", "ID"], ["end_id", "ID"], ["weight", "Double"]] }
+        }
+        "#;
+        write(dir.path().join("schema.json"), schema).unwrap();
+        write(dir.path().join("person.csv"), "id|name|age\n1|marko|29\n2|vadas|27\n").unwrap();
+        write(dir.path().join("person_knows_person.csv"), "start_id|end_id|weight\n1|2|0.5\n").unwrap();
+
+        let graph = load_graph_from_csv(
+            dir.path().join("schema.json"),
+            &[("person", dir.path().join("person.csv"))],
+            &[("person_knows_person", dir.path().join("person_knows_person.csv"))],
+        )
+        .unwrap();
+        let v1: DefaultId = LDBCVertexParser::to_global_id(1, 0);
+        let v2: DefaultId = LDBCVertexParser::to_global_id(2, 0);
+
+        let mut vertices: Vec<(DefaultId, Option<Object>, Option<Object>)> = graph
+            .get_all_vertices(None)
+            .map(|v| {
+                let name = v
+                    .get_property("name")
+                    .and_then(|name| name.try_to_owned());
+                let age = v
+                    .get_property("age")
+                    .and_then(|age| age.try_to_owned());
+                (v.get_id(), name, age)
+            })
+            .collect();
+        vertices.sort_by_key(|(id, _, _)| *id);
+        assert_eq!(
+            vertices,
+            vec![
+                (v1, Some(object!("marko")), Some(object!(29))),
+                (v2, Some(object!("vadas")), Some(object!(27)))
+            ]
+        );
+
+        let edges: Vec<(DefaultId, DefaultId, Option<Object>)> = graph
+            .get_all_edges(None)
+            .map(|e| {
+                let weight = e
+                    .get_property("weight")
+                    .and_then(|weight| weight.try_to_owned());
+                (e.get_src_id(), e.get_dst_id(), weight)
+            })
+            .collect();
+        assert_eq!(edges, vec![(v1, v2, Some(object!(0.5)))]);
+    }
+
+    #[test]
+    fn exp_store_from_csv_test() {
+        let dir = tempfile::tempdir().unwrap();
+        let schema = r#"
+        {
+          "vertex_type_map": { "person": 0 },
+          "edge_type_map": { "knows": 0 },
+          "vertex_prop": { "person": [["id", "ID"], ["name", "String"]] },
+          "edge_prop": { "knows": [["start_id", "ID"], ["end_id", "ID"]] }
+        }
+        "#;
+        let schema_path = dir.path().join("schema.json");
+        write(&schema_path, schema).unwrap();
+        write(dir.path().join("person.csv"), "id|name\n1|marko\n2|vadas\n").unwrap();
+        write(dir.path().join("person_knows_person.csv"), "start_id|end_id\n1|2\n").unwrap();
+
+        let store = ExpStore::from_csv(
+            &schema_path,
+            &[("person", dir.path().join("person.csv"))],
+            &[("person_knows_person", dir.path().join("person_knows_person.csv"))],
+        )
+        .unwrap();
+        let v1: DefaultId = LDBCVertexParser::to_global_id(1, 0);
+        let v2: DefaultId = LDBCVertexParser::to_global_id(2, 0);
+        let vertices: Vec<_> = store
+            .scan_vertex(&QueryParams::default())
+            .unwrap()
+            .collect();
+        // the scanned elements still read the properties after the store is dropped
+        drop(store);
+        let mut names: Vec<(ID, Option<Object>)> = vertices
+            .iter()
+            .map(|v| {
+                let name = v
+                    .details()
+                    .unwrap()
+                    .get_property(&"name".into())
+                    .and_then(|name| name.try_to_owned());
+                (v.id(), name)
+            })
+            .collect();
+        names.sort_by_key(|(id, _)| *id);
+        assert_eq!(names, vec![(v1 as ID, Some(object!("marko"))), (v2 as ID, Some(object!("vadas")))]);
+    }
+
+    #[test]
+    fn index_scan_vertex_test() {
+        let store = super::initialize();
+        let v1: DefaultId = LDBCVertexParser::to_global_id(1, 0);
+        let pk = OneOrMany::One([(EXP_STORE_PK.into(), object!(1))]);
+        let index_scanned = store
+            .index_scan_vertex(0, &pk, &QueryParams::default())
+            .unwrap()
+            .unwrap();
+        let mut scanned = store
+            .scan_vertex(&QueryParams::default())
+            .unwrap()
+            .filter(|v| v.id() == v1 as ID);
+        let scanned = scanned.next().unwrap();
+        assert_eq!(index_scanned.id(), scanned.id());
+        assert_eq!(index_scanned.label(), scanned.label());
+        assert_eq!(index_scanned.id(), v1 as ID);
+
+        // the vertex is absent
+        let pk = OneOrMany::One([(EXP_STORE_PK.into(), object!(100))]);
+        assert!(store
+            .index_scan_vertex(0, &pk, &QueryParams::default())
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn get_property_by_id_test() {
+        let store = super::initialize();
+        let v1: DefaultId = LDBCVertexParser::to_global_id(1, 0);
+        let schema = GRAPH.get_graph_schema();
+        let (_, name_index) = schema.get_vertex_schema(0).unwrap()["name"];
+        let name_id = name_index as KeyId;
+        let vertex = store
+            .get_vertex(&[v1 as ID], &QueryParams::default())
+            .unwrap()
+            .next()
+            .unwrap();
+        let details = vertex.details().unwrap();
+        let name_by_id = details
+            .get_property(&NameOrId::Id(name_id))
+            .and_then(|name| name.try_to_owned());
+        let name_by_str = details
+            .get_property(&"name".into())
+            .and_then(|name| name.try_to_owned());
+        assert_eq!(name_by_id, Some(object!("marko")));
+        assert_eq!(name_by_id, name_by_str);
+    }
+
+    #[test]
+    fn hash_sample_scan_test() {
+        let store = super::initialize();
+        let params = QueryParams { sample_ratio: Some(0.5), ..Default::default() };
+        for scan_edge in [false, true] {
+            let scan = |worker_id, workers_num| -> Vec<ID> {
+                if scan_edge {
+                    store
+                        .scan_edge_of_worker(&params, worker_id, workers_num)
+                        .unwrap()
+                        .map(|e| e.id())
+                        .collect()
+                } else {
+                    store
+                        .scan_vertex_of_worker(&params, worker_id, workers_num)
+                        .unwrap()
+                        .map(|v| v.id())
+                        .collect()
+                }
+            };
+            let mut sampled_by_one: Vec<ID> = scan(0, 1);
+            let mut sampled_by_four: Vec<ID> = (0..4)
+                .flat_map(|worker_id| scan(worker_id, 4))
+                .collect();
+            sampled_by_one.sort();
+            sampled_by_four.sort();
+            assert_eq!(sampled_by_one, sampled_by_four);
+            // sampling again gives the same result
+            let mut sampled_again = scan(0, 1);
+            sampled_again.sort();
+            assert_eq!(sampled_by_one, sampled_again);
+        }
+    }
+
+    #[test]
+    fn sample_scan_edge_by_ratio_test() {
+        let store = super::initialize();
+        let all_edges = store
+            .scan_edge(&QueryParams::default())
+            .unwrap()
+            .count();
+        assert!(all_edges > 0);
+        let scan_with_ratio = |ratio| {
+            let params = QueryParams { sample_ratio: Some(ratio), ..Default::default() };
+            store.scan_edge(&params).unwrap().count()
+        };
+        assert_eq!(scan_with_ratio(0.0), 0);
+        assert_eq!(scan_with_ratio(1.0), all_edges);
+    }
+
+    #[test]
+    fn collect_degree_stats_test() {
+        let store = super::initialize();
+        let degree_stats = store.collect_degree_stats();
+        // 4 persons know 2 persons, and create 4 softwares
+        assert_eq!(degree_stats[&(0, 0, Direction::Out)], 0.5);
+        assert_eq!(degree_stats[&(0, 0, Direction::In)], 0.5);
+        assert_eq!(degree_stats[&(0, 1, Direction::Out)], 1.0);
+        // 2 softwares are created 4 times
+        assert_eq!(degree_stats[&(1, 1, Direction::In)], 2.0);
+        assert!(!degree_stats.contains_key(&(1, 1, Direction::Out)));
+    }
 }
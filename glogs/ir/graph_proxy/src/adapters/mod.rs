@@ -17,6 +17,8 @@ mod exp_store;
 mod gs_store;
 mod vineyard_store;
 
-pub use exp_store::{create_exp_store, SimplePartition};
+pub use exp_store::{
+    collect_degree_stats, create_exp_store, load_graph_from_csv, ExpStore, SimplePartition,
+};
 pub use gs_store::{create_gs_store, GrootMultiPartition, VineyardMultiPartition};
 pub use vineyard_store::VineyardGraphWriter;
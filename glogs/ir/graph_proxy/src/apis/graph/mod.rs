@@ -44,7 +44,7 @@ pub const ID_BITS: usize = std::mem::size_of::<ID>() * 8;
 /// Primary key in storage, including single column pk and multi column pks.
 pub type PKV = OneOrMany<(NameOrId, Object)>;
 
-#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
 pub enum Direction {
     Out = 0,
     In = 1,
@@ -18,8 +18,8 @@ extern crate log;
 #[macro_use]
 extern crate lazy_static;
 pub use adapters::{
-    create_exp_store, create_gs_store, GrootMultiPartition, SimplePartition, VineyardGraphWriter,
-    VineyardMultiPartition,
+    collect_degree_stats, create_exp_store, create_gs_store, load_graph_from_csv, ExpStore,
+    GrootMultiPartition, SimplePartition, VineyardGraphWriter, VineyardMultiPartition,
 };
 pub use errors::{GraphProxyError, GraphProxyResult};
 
@@ -69,6 +69,23 @@ macro_rules! sample_limit {
     };
 }
 
+/// Sample by the hash of the element's id with the given seed, instead of a random generator,
+/// which keeps the sampled elements the same no matter how the elements are scanned.
+#[macro_export]
+macro_rules! hash_sample_limit {
+    ($iter: expr, $s: expr, $seed: expr, $n: expr) => {
+        if let Some(ratio) = $s {
+            use crate::apis::GraphElement;
+            let seed = $seed;
+            let r = $iter.filter(move |v| crate::utils::is_hash_sampled(v.id(), seed, ratio));
+            limit_n!(r, $n)
+        } else {
+            let r = $iter;
+            limit_n!(r, $n)
+        }
+    };
+}
+
 #[macro_export]
 macro_rules! filter_sample_limit {
     ($iter: expr, $f: expr, $s: expr, $n: expr) => {
@@ -83,3 +100,18 @@ macro_rules! filter_sample_limit {
         }
     };
 }
+
+#[macro_export]
+macro_rules! filter_hash_sample_limit {
+    ($iter: expr, $f: expr, $s: expr, $seed: expr, $n: expr) => {
+        if let Some(ref f) = $f {
+            use crate::utils::expr::eval_pred::EvalPred;
+            let f = f.clone();
+            let r = $iter.filter(move |v| f.eval_bool(Some(v)).unwrap_or(false));
+            hash_sample_limit!(r, $s, $seed, $n)
+        } else {
+            let r = $iter;
+            hash_sample_limit!(r, $s, $seed, $n)
+        }
+    };
+}
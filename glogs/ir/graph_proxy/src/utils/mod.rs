@@ -13,4 +13,18 @@
 //! See the License for the specific language governing permissions and
 //! limitations under the License.
 
+use crate::apis::ID;
+
 pub mod expr;
+
+/// Decide whether an element is sampled by the hash of its id and the seed, such that
+/// the decision is reproducible, and independent of which worker the element is scanned by.
+pub fn is_hash_sampled(id: ID, seed: u64, ratio: f64) -> bool {
+    // the finalizer of SplitMix64 to spread the ids uniformly
+    let mut hash = (id as u64) ^ seed.wrapping_mul(0x9E37_79B9_7F4A_7C15);
+    hash = (hash ^ (hash >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    hash = (hash ^ (hash >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    hash ^= hash >> 31;
+    // take the highest 53 bits as a uniform float in [0, 1)
+    ((hash >> 11) as f64 / (1_u64 << 53) as f64) < ratio
+}
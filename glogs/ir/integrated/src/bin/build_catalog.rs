@@ -18,9 +18,10 @@ use std::sync::Arc;
 use std::time::Instant;
 
 use ir_core::catalogue::catalog::{Catalogue, PatMatPlanSpace};
-use ir_core::catalogue::sparsify::read_sparsify_config;
+use ir_core::catalogue::sample::{EstimateConfig, SamplingStrategy};
+use ir_core::catalogue::sparsify::{dump_edge_info, read_sparsify_config};
 use log::info;
-use runtime_integration::{read_pattern, read_pattern_meta, read_patterns, read_sample_graph};
+use runtime_integration::{read_graph, read_pattern, read_pattern_meta, read_patterns, read_sample_graph};
 use structopt::StructOpt;
 
 #[global_allocator]
@@ -36,14 +37,27 @@ pub struct Config {
     export_path: String,
     #[structopt(short = "s", long = "sparsify_rate_path")]
     sparsify_rate_path: Option<String>,
+    /// Infer the sparsify rates by comparing the sample graph with the graph of GRAPH_PATH instead,
+    /// and save the inferred rates to the path
+    #[structopt(long = "infer_sparsify_rate_path", conflicts_with = "sparsify_rate_path")]
+    infer_sparsify_rate_path: Option<String>,
     #[structopt(short = "t", long = "thread_num", default_value = "1")]
     thread_num: usize,
     #[structopt(short = "r", long = "sample_rate", default_value = "1.0")]
     sample_rate: f64,
     #[structopt(short = "l", long = "medium_results_limit")]
     limit: Option<usize>,
+    #[structopt(long = "min_seeds_per_label", default_value = "0")]
+    min_seeds_per_label: usize,
+    #[structopt(long = "seed")]
+    seed: Option<u64>,
+    #[structopt(long = "sampling_strategy", default_value = "stride")]
+    sampling_strategy: String,
     #[structopt(short = "s", long = "plan_space", default_value = "hybrid")]
     plan_space: String,
+    /// Print the time cost and the peak number of records of each level of patterns in the estimation
+    #[structopt(long = "report")]
+    report: bool,
 }
 
 fn print_config(config: &Config) {
@@ -52,10 +66,15 @@ fn print_config(config: &Config) {
     println!("  Catalog depth: {}", config.catalog_depth);
     println!("  export path: {}", config.export_path);
     println!("  sparsify_rate_path: {:?}", config.sparsify_rate_path);
+    println!("  infer_sparsify_rate_path: {:?}", config.infer_sparsify_rate_path);
     println!("  Num threads: {}", config.thread_num);
     println!("  Sample Rate: {}", config.sample_rate);
     println!("  Medium results limit: {:?}", config.limit);
+    println!("  Min seeds per label: {}", config.min_seeds_per_label);
+    println!("  Seed: {:?}", config.seed);
+    println!("  Sampling strategy: {}", config.sampling_strategy);
     println!("  plan space: {}", config.plan_space);
+    println!("  report: {}", config.report);
     println!();
 }
 
@@ -70,6 +89,7 @@ fn main() -> anyhow::Result<()> {
         "hybrid" => PatMatPlanSpace::Hybrid,
         _ => unreachable!(),
     };
+    let sampling_strategy: SamplingStrategy = config.sampling_strategy.parse()?;
     let catalog_build_start_time = Instant::now();
     let mut catalog = match config.catalog_mode.as_str() {
         "from_pattern" => {
@@ -106,13 +126,36 @@ fn main() -> anyhow::Result<()> {
         .sparsify_rate_path
         .map(|s| read_sparsify_config(&s))
         .unwrap_or_default();
-    catalog.estimate_graph(
+    let report = catalog.estimate_graph(
         sample_graph,
-        config.sample_rate,
-        sparsify_rate,
-        config.limit,
-        config.thread_num,
+        &EstimateConfig::default()
+            .with_rate(config.sample_rate)
+            .with_sparsify_rate(sparsify_rate)
+            .with_limit(config.limit)
+            .with_min_seeds_per_label(config.min_seeds_per_label)
+            .with_thread_num(config.thread_num)
+            .with_seed(config.seed)
+            .with_strategy(sampling_strategy),
     );
+    if config.report {
+        for level_report in report.get_level_reports() {
+            println!(
+                "level {}: {} patterns, time cost: {} ms, max records: {}",
+                level_report.get_level(),
+                level_report.get_patterns_num(),
+                level_report.get_elapsed().as_millis(),
+                level_report.get_max_records_num()
+            );
+        }
+        println!("estimation time cost: {} ms", report.get_total_elapsed().as_millis());
+    }
+    if let Some(infer_sparsify_rate_path) = &config.infer_sparsify_rate_path {
+        let graph = read_graph()?;
+        let sparsify_rate = catalog.infer_sparsify_rates(&graph);
+        info!("inferred sparsify rates: {:?}", sparsify_rate);
+        catalog.apply_sparsify_rates(&sparsify_rate);
+        dump_edge_info(sparsify_rate, infer_sparsify_rate_path);
+    }
     info!("building catalog time cost is: {:?} s", catalog_build_start_time.elapsed().as_secs_f64());
     catalog.export(config.export_path)?;
     Ok(())
@@ -19,10 +19,12 @@ use std::io::{BufRead, BufReader, BufWriter, Write};
 use std::path::PathBuf;
 
 use ir_core::catalogue::catalog::Catalogue;
-use ir_core::catalogue::pattern::{Pattern, PatternEdge, PatternVertex};
+use ir_core::catalogue::pattern::{Pattern, PatternEdge, PatternVertex, PatternWithCount};
 use ir_core::catalogue::pattern_meta::PatternMeta;
+use ir_core::catalogue::plan::SubpatternsBudget;
 use ir_core::catalogue::{PatternId, PatternLabelId};
 use itertools::Itertools;
+use log::{error, info, warn};
 use runtime_integration::read_pattern_meta;
 use structopt::StructOpt;
 
@@ -33,10 +35,26 @@ static ALLOC: snmalloc_rs::SnMalloc = snmalloc_rs::SnMalloc;
 pub struct Config {
     #[structopt(short = "c", long = "catalog")]
     catalog: PathBuf,
-    #[structopt(short = "i", long = "input")]
-    input: PathBuf,
+    #[structopt(short = "i", long = "input", required_unless = "pattern")]
+    input: Option<PathBuf>,
+    /// Take the subpatterns of the pattern as the decom patterns instead of reading them from the input
+    #[structopt(short = "p", long = "pattern", conflicts_with = "input")]
+    pattern: Option<PathBuf>,
+    /// The maximum number of vertices removed from the pattern to generate a subpattern
+    #[structopt(long = "max-depth")]
+    max_depth: Option<usize>,
+    /// The maximum number of subpatterns generated from the pattern
+    #[structopt(long = "max-patterns")]
+    max_patterns: Option<usize>,
     #[structopt(short = "o", long = "output")]
     output: PathBuf,
+    /// Also save the full catalogue by `Catalogue::save`, which can be reloaded by pattern_count with
+    /// --catalog and --saved instead of sampling the graph again
+    #[structopt(long = "catalog-output")]
+    catalog_output: Option<PathBuf>,
+    /// Verify that no non-isomorphic patterns share a canonical code in the catalog
+    #[structopt(long = "verify")]
+    verify: bool,
 }
 
 fn parse_pattern(meta: &PatternMeta, line: &str) -> Pattern {
@@ -73,18 +91,71 @@ fn parse_pattern(meta: &PatternMeta, line: &str) -> Pattern {
     edges.try_into().unwrap()
 }
 
+/// Format the pattern as a line of the decom file, which is parsed back by parse_pattern
+fn format_pattern(index: usize, pattern: &Pattern) -> String {
+    let edges = pattern
+        .edges_iter()
+        .map(|edge| format!("{}-{}", edge.get_start_vertex().get_id(), edge.get_end_vertex().get_id()))
+        .join(";");
+    let labels = pattern
+        .edges_iter()
+        .map(|edge| edge.get_label())
+        .join("->");
+    format!("{index},{edges},{labels}")
+}
+
+/// Generate the decom lines of the subpatterns of the pattern within the budget
+fn generate_decom_lines(pattern: &Pattern, budget: &SubpatternsBudget) -> Vec<String> {
+    let (subpatterns, truncated) = pattern.generate_subpatterns_with_budget(budget);
+    if truncated {
+        warn!("the subpatterns are truncated by the budget {:?}", budget);
+    }
+    info!("generate {} subpatterns", subpatterns.len());
+    // single vertex subpatterns have no edges to be written in the decom format
+    subpatterns
+        .iter()
+        .filter(|subpattern| subpattern.get_edges_num() > 0)
+        .enumerate()
+        .map(|(index, subpattern)| format_pattern(index, subpattern))
+        .collect()
+}
+
 fn main() -> anyhow::Result<()> {
     env_logger::init();
     let config = Config::from_args();
     let catalog = Catalogue::import(config.catalog)?;
+    if config.verify {
+        if let Err(collisions) = catalog.verify_no_code_collisions() {
+            for (pattern1, pattern2) in collisions.iter() {
+                error!("code collision: {} and {}", pattern1, pattern2);
+            }
+            anyhow::bail!("found {} code collisions in the catalog", collisions.len());
+        }
+        info!("no code collisions found in the catalog");
+    }
+    if let Some(catalog_output) = &config.catalog_output {
+        catalog.save(catalog_output)?;
+        info!("save the full catalog to {:?}", catalog_output);
+    }
     let pattern_meta = read_pattern_meta()?;
-    let decom = {
-        let file = File::open(config.input)?;
+    let decom_lines = if let Some(pattern_path) = config.pattern {
+        let pattern: Pattern = PatternWithCount::import(pattern_path)?.try_into()?;
+        let mut budget = SubpatternsBudget::default();
+        if let Some(max_depth) = config.max_depth {
+            budget = budget.with_max_depth(max_depth);
+        }
+        if let Some(max_patterns) = config.max_patterns {
+            budget = budget.with_max_patterns(max_patterns);
+        }
+        generate_decom_lines(&pattern, &budget)
+    } else {
+        let file = File::open(config.input.unwrap())?;
         BufReader::new(file)
+            .lines()
+            .collect::<Result<_, _>>()?
     };
     let mut output_lines = Vec::new();
-    for line in decom.lines() {
-        let line = line?;
+    for line in decom_lines {
         let pattern = parse_pattern(&pattern_meta, &line);
         let count = if let Some(index) = catalog.get_pattern_index(&pattern.encode_to()) {
             let weight = catalog.get_pattern_weight(index).unwrap();
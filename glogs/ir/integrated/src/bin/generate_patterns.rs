@@ -0,0 +1,66 @@
+//
+//! Copyright 2022 Alibaba Group Holding Limited.
+//!
+//! Licensed under the Apache License, Version 2.0 (the "License");
+//! you may not use this file except in compliance with the License.
+//! You may obtain a copy of the License at
+//!
+//! http://www.apache.org/licenses/LICENSE-2.0
+//!
+//! Unless required by applicable law or agreed to in writing, software
+//! distributed under the License is distributed on an "AS IS" BASIS,
+//! WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//! See the License for the specific language governing permissions and
+//! limitations under the License.
+//!
+
+use std::path::PathBuf;
+
+use ir_core::catalogue::pattern::generate_patterns;
+use log::info;
+use runtime_integration::{export_pattern_dir, read_pattern_meta};
+use structopt::StructOpt;
+
+#[global_allocator]
+static ALLOC: snmalloc_rs::SnMalloc = snmalloc_rs::SnMalloc;
+
+#[derive(StructOpt)]
+pub struct Config {
+    #[structopt(short = "v", long = "max-vertices", default_value = "3")]
+    max_vertices: usize,
+    #[structopt(short = "e", long = "max-edges", default_value = "3")]
+    max_edges: usize,
+    #[structopt(short = "l", long = "same-label-vertex-limit", default_value = "3")]
+    same_label_vertex_limit: usize,
+    /// Also generate the patterns made of a connected pattern and an isolated vertex
+    #[structopt(long = "disconnected")]
+    disconnected: bool,
+    /// Only generate the acyclic connected patterns
+    #[structopt(long = "trees-only")]
+    trees_only: bool,
+    #[structopt(short = "o", long = "output")]
+    output: PathBuf,
+    /// Drop the patterns isomorphic to an already written one
+    #[structopt(long = "dedup")]
+    dedup: bool,
+}
+
+fn main() -> anyhow::Result<()> {
+    env_logger::init();
+    let config = Config::from_args();
+    let pattern_meta = read_pattern_meta()?;
+    let patterns = generate_patterns(
+        &pattern_meta,
+        config.max_vertices,
+        config.max_edges,
+        config.same_label_vertex_limit,
+        !config.disconnected,
+        config.trees_only,
+    );
+    info!("generated {} patterns", patterns.len());
+    let duplicates_num = export_pattern_dir(patterns, &config.output, config.dedup)?;
+    if config.dedup {
+        info!("dropped {} duplicated patterns", duplicates_num);
+    }
+    Ok(())
+}
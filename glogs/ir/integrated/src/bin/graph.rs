@@ -0,0 +1,56 @@
+//
+//! Copyright 2022 Alibaba Group Holding Limited.
+//!
+//! Licensed under the Apache License, Version 2.0 (the "License");
+//! you may not use this file except in compliance with the License.
+//! You may obtain a copy of the License at
+//!
+//! http://www.apache.org/licenses/LICENSE-2.0
+//!
+//! Unless required by applicable law or agreed to in writing, software
+//! distributed under the License is distributed on an "AS IS" BASIS,
+//! WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//! See the License for the specific language governing permissions and
+//! limitations under the License.
+//!
+use graph_proxy::collect_degree_stats;
+use ir_core::catalogue::sparsify::GraphStats;
+use runtime_integration::read_graph;
+use structopt::StructOpt;
+
+#[global_allocator]
+static ALLOC: snmalloc_rs::SnMalloc = snmalloc_rs::SnMalloc;
+
+#[derive(StructOpt)]
+pub struct Config {
+    /// Print the average degree of vertices of each label along each edge label and direction
+    #[structopt(long = "degree-stats")]
+    degree_stats: bool,
+    /// Dump the vertex counts of each label and the edge counts of each (src label, edge label, dst label)
+    /// to the given JSON file
+    #[structopt(long = "dump-stats")]
+    dump_stats: Option<String>,
+}
+
+fn main() -> anyhow::Result<()> {
+    env_logger::init();
+    let config = Config::from_args();
+    let graph = read_graph()?;
+    if let Some(stats_path) = &config.dump_stats {
+        GraphStats::from_graph(&graph).export(stats_path)?;
+        println!("graph stats are dumped to {}", stats_path);
+    }
+    if config.degree_stats {
+        let mut degree_stats: Vec<_> = collect_degree_stats(&graph)
+            .into_iter()
+            .collect();
+        degree_stats.sort_by_key(|&((vertex_label, edge_label, direction), _)| {
+            (vertex_label, edge_label, direction as i32)
+        });
+        println!("vertex_label,edge_label,direction,avg_degree");
+        for ((vertex_label, edge_label, direction), avg_degree) in degree_stats {
+            println!("{},{},{:?},{}", vertex_label, edge_label, direction, avg_degree);
+        }
+    }
+    Ok(())
+}
@@ -14,11 +14,14 @@
 //! limitations under the License.
 //!
 use std::convert::TryInto;
+use std::fs::File;
 use std::{path::PathBuf, time::Instant};
 
 use ir_core::catalogue::catalog::Catalogue;
 use ir_core::catalogue::pattern::{Pattern, PatternWithCount};
+use ir_core::catalogue::sample::{count_pattern_exact, estimate_pattern_count_bound, GraphDegreeStats};
 use log::debug;
+use runtime_integration::{estimate_pattern_dir, read_graph, write_pattern_count};
 use structopt::StructOpt;
 
 #[global_allocator]
@@ -26,26 +29,128 @@ static ALLOC: snmalloc_rs::SnMalloc = snmalloc_rs::SnMalloc;
 
 #[derive(StructOpt)]
 pub struct Config {
-    #[structopt(short = "p", long = "pattern")]
-    pattern: PathBuf,
-    #[structopt(short = "c", long = "catalog")]
-    catalog: PathBuf,
+    #[structopt(
+        short = "p",
+        long = "pattern",
+        raw(required_unless_one = r#"&["edge_list", "pattern_dir"]"#),
+        conflicts_with = "edge_list"
+    )]
+    pattern: Option<PathBuf>,
+    /// Read the pattern from lines of `edge_id,edge_label,src_id,src_label,dst_id,dst_label` instead
+    #[structopt(long = "edge-list")]
+    edge_list: Option<PathBuf>,
+    /// Estimate all the `*.json` pattern files in the directory, and write a summary csv
+    #[structopt(long = "pattern-dir", raw(conflicts_with_all = r#"&["pattern", "edge_list"]"#))]
+    pattern_dir: Option<PathBuf>,
+    /// The path of the summary csv of --pattern-dir, which is written to stdout by default
+    #[structopt(long = "summary")]
+    summary: Option<PathBuf>,
+    #[structopt(short = "c", long = "catalog", raw(required_unless_one = r#"&["exact", "bound"]"#))]
+    catalog: Option<PathBuf>,
+    /// Load the catalog as a full catalogue saved by `Catalogue::save` (e.g., by build_ceg_catalog with
+    /// --catalog-output) instead of an exported one, so the estimated counts are reused without sampling
+    #[structopt(long = "saved", requires = "catalog")]
+    saved: bool,
+    /// Count the pattern exactly on the graph of GRAPH_PATH instead of estimating it by the catalog,
+    /// which is only feasible for small graphs
+    #[structopt(long = "exact", conflicts_with = "pattern_dir")]
+    exact: bool,
+    /// Print an upper bound of the count by the degree statistics of the graph of GRAPH_PATH instead,
+    /// which needs no sampling
+    #[structopt(long = "bound", raw(conflicts_with_all = r#"&["pattern_dir", "exact"]"#))]
+    bound: bool,
     #[structopt(short = "r", long = "random")]
     random: bool,
     #[structopt(short = "s", long = "sample", default_value = "100")]
     sample: usize,
     #[structopt(long = "seed", default_value = "12345")]
     seed: u64,
+    /// The ground-truth count of the pattern (e.g., from the count command) to report the q-error with,
+    /// which is only printed if given
+    #[structopt(long = "true-count")]
+    true_count: Option<f64>,
+    /// Also print the confidence interval `[low,high]` of the count with the given z-score (e.g., 1.96
+    /// for 95%), if the pattern's count is estimated by sampling in the catalog
+    #[structopt(long = "ci")]
+    ci: Option<f64>,
+    /// Print the count as a JSON object with the numbers of vertices and edges of the pattern, its canonical
+    /// code, the elapsed time and the q-error, instead of the plain `count,time` line
+    #[structopt(long = "json", raw(conflicts_with_all = r#"&["pattern_dir", "ci"]"#))]
+    json: bool,
+}
+
+fn read_catalog(config: &Config) -> anyhow::Result<Catalogue> {
+    let catalog_path = config.catalog.as_ref().unwrap();
+    let catalog =
+        if config.saved { Catalogue::load(catalog_path)? } else { Catalogue::import(catalog_path)? };
+    Ok(catalog)
 }
 
 fn main() -> anyhow::Result<()> {
     env_logger::init();
     let config = Config::from_args();
-    let pattern: Pattern = PatternWithCount::import(config.pattern)?.try_into()?;
-    let catalog = Catalogue::import(config.catalog)?;
+    if let Some(pattern_dir) = &config.pattern_dir {
+        let catalog = read_catalog(&config)?;
+        let estimations = estimate_pattern_dir(&catalog, pattern_dir)?;
+        let mut writer: csv::Writer<Box<dyn std::io::Write>> = match &config.summary {
+            Some(summary) => csv::Writer::from_writer(Box::new(File::create(summary)?)),
+            None => csv::Writer::from_writer(Box::new(std::io::stdout())),
+        };
+        writer.write_record(&["pattern_file", "vertices", "edges", "estimate"])?;
+        for estimation in estimations {
+            writer.write_record(&[
+                estimation.pattern_file.display().to_string(),
+                estimation.vertices_num.to_string(),
+                estimation.edges_num.to_string(),
+                estimation.estimate.to_string(),
+            ])?;
+        }
+        writer.flush()?;
+        return Ok(());
+    }
+    let pattern: Pattern = if let Some(edge_list) = config.edge_list {
+        Pattern::from_edge_list(File::open(edge_list)?)?
+    } else {
+        PatternWithCount::import(config.pattern.unwrap())?.try_into()?
+    };
+    if config.exact {
+        let graph = read_graph()?;
+        let start = Instant::now();
+        let pattern_count = count_pattern_exact(&graph, &pattern);
+        let elapsed = start.elapsed();
+        debug!("pattern: {}, exact count: {}", pattern, pattern_count);
+        write_pattern_count(
+            std::io::stdout(),
+            &pattern,
+            pattern_count as f64,
+            elapsed,
+            config.true_count,
+            config.json,
+        )?;
+        return Ok(());
+    }
+    if config.bound {
+        let graph = read_graph()?;
+        let start = Instant::now();
+        let graph_stats = GraphDegreeStats::from_graph(&graph);
+        let pattern_bound = estimate_pattern_count_bound(&pattern, &graph_stats)?;
+        let elapsed = start.elapsed();
+        debug!("pattern: {}, count bound: {}", pattern, pattern_bound);
+        write_pattern_count(
+            std::io::stdout(),
+            &pattern,
+            pattern_bound,
+            elapsed,
+            config.true_count,
+            config.json,
+        )?;
+        return Ok(());
+    }
+    let catalog = read_catalog(&config)?;
     let start = Instant::now();
     let pattern_code = pattern.encode_to();
-    let pattern_count = if let Some(pattern_index) = catalog.get_pattern_index(&pattern_code) {
+    let pattern_index = catalog.get_pattern_index(&pattern_code);
+    let pattern_count = if let Some(pattern_index) = pattern_index {
         let pattern_count = catalog
             .get_pattern_weight(pattern_index)
             .unwrap()
@@ -61,7 +166,21 @@ fn main() -> anyhow::Result<()> {
         debug!("pattern: {}, count: {}", pattern, pattern_count);
         pattern_count
     };
-    let time = start.elapsed().as_secs_f64();
-    println!("{pattern_count},{time}");
+    let elapsed = start.elapsed();
+    write_pattern_count(
+        std::io::stdout(),
+        &pattern,
+        pattern_count.into_inner(),
+        elapsed,
+        config.true_count,
+        config.json,
+    )?;
+    if let Some((low, high)) = config
+        .ci
+        .zip(pattern_index)
+        .and_then(|(z, pattern_index)| catalog.get_pattern_count_ci(pattern_index, z))
+    {
+        println!("[{low},{high}]");
+    }
     Ok(())
 }
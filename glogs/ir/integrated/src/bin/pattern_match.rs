@@ -21,7 +21,7 @@ use std::time::Instant;
 
 use graph_proxy::create_exp_store;
 use ir_core::catalogue::catalog::PatMatPlanSpace;
-use ir_core::catalogue::plan::{set_alpha, set_beta, set_w1, set_w2};
+use ir_core::catalogue::plan::{CostModel, PlanConfig};
 use ir_core::plan::logical::LogicalPlan;
 use ir_core::plan::physical::AsPhysical;
 use pegasus::{Configuration, JobConf};
@@ -101,10 +101,7 @@ fn main() -> Result<(), Box<dyn Error>> {
         "multiple" => read_patterns()?,
         _ => unreachable!(),
     };
-    set_alpha(config.alpha);
-    set_beta(config.beta);
-    set_w1(config.w1);
-    set_w2(config.w2);
+    let cost_model = CostModel::new(config.alpha, config.beta, config.w1, config.w2);
     let mut catalog = read_catalogue()?;
     for pattern in patterns {
         println!("############ Plan Generation ############");
@@ -123,7 +120,12 @@ fn main() -> Result<(), Box<dyn Error>> {
         // );
         let plan_generation_start_time = Instant::now();
         let mut pb_plan = pattern
-            .generate_optimized_match_plan(&mut catalog, &pattern_meta, config.is_distributed)
+            .generate_optimized_match_plan(
+                &mut catalog,
+                &pattern_meta,
+                config.is_distributed,
+                &PlanConfig::default().with_cost_model(cost_model),
+            )
             .expect("Failed to generate optimized pattern match plan");
         println!("generating plan time cost is: {:?} ms", plan_generation_start_time.elapsed().as_millis());
 
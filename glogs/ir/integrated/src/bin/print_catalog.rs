@@ -14,14 +14,88 @@
 //! limitations under the License.
 //!
 use std::error::Error;
+use std::path::PathBuf;
 
-use runtime_integration::read_catalogue;
+use ir_core::catalogue::catalog::Catalogue;
+use runtime_integration::{check_pattern_dir_coverage, get_workload_coverage, read_catalogue};
+use structopt::StructOpt;
 
 #[global_allocator]
 static ALLOC: snmalloc_rs::SnMalloc = snmalloc_rs::SnMalloc;
 
+#[derive(StructOpt)]
+pub struct Config {
+    /// Output format, either "text" or "dot" (a Graphviz digraph per pattern, or of the whole catalog)
+    #[structopt(short = "f", long = "format", default_value = "text")]
+    format: String,
+    /// Print the whole catalog graph of patterns and approaches instead of each pattern,
+    /// only for the "dot" format
+    #[structopt(short = "c", long = "catalog")]
+    catalog: bool,
+    /// Instead of printing the catalog, report whether each `*.json` pattern file in the directory is
+    /// covered by the catalog with its missing subpatterns, and the overall coverage of the workload
+    #[structopt(short = "w", long = "workload")]
+    workload: Option<PathBuf>,
+    /// Instead of printing the catalog, compare its estimated counts of the patterns with those in
+    /// the given catalog, sorted by the divergence of the counts
+    #[structopt(short = "d", long = "diff")]
+    diff: Option<PathBuf>,
+    /// The divergence of counts above which a pattern is flagged when comparing catalogs
+    #[structopt(short = "t", long = "threshold", default_value = "2.0")]
+    threshold: f64,
+    /// When comparing catalogs, match a pattern absent in the given catalog with its structurally
+    /// most similar pattern there, if their WL similarity is at least the given value
+    #[structopt(short = "s", long = "min_similarity")]
+    min_similarity: Option<f64>,
+}
+
 fn main() -> Result<(), Box<dyn Error>> {
+    let config = Config::from_args();
     let catalog = read_catalogue()?;
-    println!("{}", catalog);
+    if let Some(workload) = &config.workload {
+        let coverages = check_pattern_dir_coverage(&catalog, workload)?;
+        for coverage in coverages.iter() {
+            println!(
+                "{}: in catalog: {}, missing subpatterns: {}",
+                coverage.pattern_file.display(),
+                coverage.in_catalog,
+                coverage.missing_subpatterns.len()
+            );
+            for subpattern in coverage.missing_subpatterns.iter() {
+                println!("    {}", subpattern);
+            }
+        }
+        println!("coverage: {:.2}%", get_workload_coverage(&coverages) * 100.0);
+        return Ok(());
+    }
+    if let Some(other_catalog_path) = &config.diff {
+        let other_catalog = Catalogue::import(other_catalog_path)?;
+        for diff in catalog.diff(&other_catalog, config.min_similarity) {
+            println!(
+                "{}: {} vs {}, ratio: {:.4}, similarity: {:.4}{}",
+                diff.get_pattern(),
+                diff.get_count(),
+                diff.get_other_count(),
+                diff.get_ratio(),
+                diff.get_similarity(),
+                if diff.get_divergence() > config.threshold { " (diverged)" } else { "" }
+            );
+        }
+        return Ok(());
+    }
+    match config.format.as_str() {
+        "text" => println!("{}", catalog),
+        "dot" if config.catalog => println!("{}", catalog.to_dot()),
+        "dot" => {
+            for index in catalog.pattern_indices_iter() {
+                let pattern = catalog
+                    .get_pattern_weight(index)
+                    .unwrap()
+                    .get_pattern();
+                println!("{}", pattern.to_dot());
+            }
+        }
+        format => return Err(format!("unsupported format: {}", format).into()),
+    }
     Ok(())
 }
@@ -4,9 +4,10 @@ use std::path::PathBuf;
 use std::time::Instant;
 
 use anyhow::Context;
-use ir_core::catalogue::catalog::{Catalogue, PatMatPlanSpace};
+use ir_core::catalogue::catalog::{Catalogue, IntersectOrder, PatMatPlanSpace};
 use ir_core::catalogue::pattern::{Pattern, PatternWithCount};
-use ir_core::catalogue::plan::{set_alpha, set_beta, set_w1, set_w2};
+use ir_core::catalogue::plan::{get_definite_extend_steps_in_order, CostModel, PlanConfig, PlanGenerator};
+use ir_core::catalogue::PatternId;
 use ir_core::plan::logical::LogicalPlan;
 use log::warn;
 use runtime_integration::*;
@@ -32,6 +33,33 @@ pub struct Config {
     w2: f64,
     #[structopt(long = "plan_space", default_value = "hybrid")]
     plan_space: String,
+    /// The order to intersect extend edges, one of asc, desc, label and selectivity
+    #[structopt(long = "intersect_order", default_value = "asc")]
+    intersect_order: String,
+    /// Print the plan as a tree of the chosen extend/join steps
+    #[structopt(long = "explain")]
+    explain: bool,
+    /// Instead of the optimal plan, estimate the extend steps in the given order of vertex ids,
+    /// as a JSON list, e.g., "[0, 2, 1]"
+    #[structopt(long = "order")]
+    order: Option<String>,
+}
+
+fn parse_vertex_order(order: &str) -> anyhow::Result<Vec<PatternId>> {
+    let order = order.trim();
+    let items = order
+        .strip_prefix('[')
+        .and_then(|order| order.strip_suffix(']'))
+        .with_context(|| format!("extend order {} is not a JSON list", order))?;
+    items
+        .split(',')
+        .map(|item| item.trim())
+        .filter(|item| !item.is_empty())
+        .map(|item| {
+            item.parse()
+                .with_context(|| format!("invalid vertex id {} in extend order", item))
+        })
+        .collect()
 }
 
 fn main() -> anyhow::Result<()> {
@@ -49,11 +77,16 @@ fn main() -> anyhow::Result<()> {
         "join" => PatMatPlanSpace::BinaryJoin,
         _ => panic!("unsupported plan space: {}", config.plan_space),
     };
-    set_alpha(config.alpha);
-    set_beta(config.beta);
-    set_w1(config.w1);
-    set_w2(config.w2);
+    let intersect_order = match config.intersect_order.as_str() {
+        "asc" => IntersectOrder::AscendingCount,
+        "desc" => IntersectOrder::DescendingCount,
+        "label" => IntersectOrder::EdgeLabel,
+        "selectivity" => IntersectOrder::Selectivity,
+        _ => panic!("unsupported intersect order: {}", config.intersect_order),
+    };
+    let cost_model = CostModel::new(config.alpha, config.beta, config.w1, config.w2);
     let mut catalog = Catalogue::build_from_pattern(&pattern, plan_space);
+    catalog.set_intersect_order(intersect_order);
 
     let start = Instant::now();
     let mut num_updated = 0;
@@ -94,7 +127,34 @@ fn main() -> anyhow::Result<()> {
     );
 
     let start = Instant::now();
-    let pb_plan = pattern.generate_optimized_match_plan(&mut catalog, &pattern_meta, false)?;
+    if let Some(order) = &config.order {
+        let order = parse_vertex_order(order)?;
+        let (extend_steps, cost) = get_definite_extend_steps_in_order(&pattern, &order, &catalog)?;
+        println!("planning time: {} ms", start.elapsed().as_millis());
+        for extend_step in extend_steps.iter() {
+            println!(
+                "extend vertex {} with {} edges",
+                extend_step.get_target_vertex().get_id(),
+                extend_step.get_extend_edges_num()
+            );
+        }
+        println!("cost: {}", cost);
+        return Ok(());
+    }
+    if config.explain {
+        let mut plan_generator =
+            PlanGenerator::new(&pattern, &catalog, &pattern_meta, false).with_cost_model(cost_model);
+        plan_generator.generate_pattern_match_plan()?;
+        println!("planning time: {} ms", start.elapsed().as_millis());
+        println!("{}", plan_generator.explain());
+        return Ok(());
+    }
+    let pb_plan = pattern.generate_optimized_match_plan(
+        &mut catalog,
+        &pattern_meta,
+        false,
+        &PlanConfig::default().with_cost_model(cost_model),
+    )?;
     let plan: LogicalPlan = pb_plan.try_into().unwrap();
     println!("planning time: {} ms", start.elapsed().as_millis());
     println!("{}", plan);
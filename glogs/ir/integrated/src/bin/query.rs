@@ -7,7 +7,7 @@ use anyhow::Context;
 use graph_proxy::create_exp_store;
 use ir_core::catalogue::catalog::{Catalogue, PatMatPlanSpace};
 use ir_core::catalogue::pattern::{Pattern, PatternWithCount};
-use ir_core::catalogue::plan::{set_alpha, set_beta, set_w1, set_w2};
+use ir_core::catalogue::plan::{CostModel, PlanConfig};
 use ir_core::plan::logical::LogicalPlan;
 use ir_core::plan::physical::AsPhysical;
 use log::warn;
@@ -57,10 +57,7 @@ fn main() -> anyhow::Result<()> {
         "join" => PatMatPlanSpace::BinaryJoin,
         _ => panic!("unsupported plan space: {}", config.plan_space),
     };
-    set_alpha(config.alpha);
-    set_beta(config.beta);
-    set_w1(config.w1);
-    set_w2(config.w2);
+    let cost_model = CostModel::new(config.alpha, config.beta, config.w1, config.w2);
     let mut catalog = Catalogue::build_from_pattern(&pattern, plan_space);
 
     let start = Instant::now();
@@ -102,7 +99,12 @@ fn main() -> anyhow::Result<()> {
     );
 
     let start = Instant::now();
-    let pb_plan = pattern.generate_optimized_match_plan(&mut catalog, &pattern_meta, false)?;
+    let pb_plan = pattern.generate_optimized_match_plan(
+        &mut catalog,
+        &pattern_meta,
+        false,
+        &PlanConfig::default().with_cost_model(cost_model),
+    )?;
     let plan: LogicalPlan = pb_plan.try_into().unwrap();
     println!("planning time: {} ms", start.elapsed().as_millis());
     println!("{}", plan);
@@ -5,7 +5,7 @@ use dyn_type::Object;
 use graph_proxy::create_exp_store;
 use ir_core::catalogue::catalog::{Catalogue, PatMatPlanSpace};
 use ir_core::catalogue::pattern::{Pattern, PatternWithCount};
-use ir_core::catalogue::plan::{set_alpha, set_beta, set_w1, set_w2};
+use ir_core::catalogue::plan::{CostModel, PlanConfig};
 use ir_core::plan::logical::LogicalPlan;
 use ir_core::plan::physical::AsPhysical;
 use pegasus::{Configuration, JobConf};
@@ -44,10 +44,7 @@ fn main() -> anyhow::Result<()> {
     let config = Config::from_args();
     let pattern_meta = read_pattern_meta()?;
     let pattern: Pattern = PatternWithCount::import(config.pattern)?.try_into()?;
-    set_alpha(config.alpha);
-    set_beta(config.beta);
-    set_w1(config.w1);
-    set_w2(config.w2);
+    let cost_model = CostModel::new(config.alpha, config.beta, config.w1, config.w2);
     let mut plan_catalog = Catalogue::build_from_pattern(&pattern, PatMatPlanSpace::Hybrid);
     let catalog = Catalogue::import(config.catalog)?;
 
@@ -66,7 +63,12 @@ fn main() -> anyhow::Result<()> {
         plan_catalog.set_extend_count_infos(index);
     }
 
-    let pb_plan = pattern.generate_optimized_match_plan(&mut plan_catalog, &pattern_meta, false)?;
+    let pb_plan = pattern.generate_optimized_match_plan(
+        &mut plan_catalog,
+        &pattern_meta,
+        false,
+        &PlanConfig::default().with_cost_model(cost_model),
+    )?;
     let plan: LogicalPlan = pb_plan.try_into().unwrap();
 
     create_exp_store();
@@ -14,20 +14,25 @@
 //! limitations under the License.
 //!
 
+use std::collections::HashSet;
 use std::convert::TryInto;
-use std::fs::{read_dir, read_to_string, File};
-use std::path::Path;
+use std::fs::{create_dir_all, read_dir, read_to_string, File};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 
 use graph_store::config::{DIR_GRAPH_SCHEMA, FILE_SCHEMA};
 use graph_store::prelude::LargeGraphDB;
 use graph_store::prelude::{DefaultId, GraphDBConfig, InternalId};
 use ir_core::catalogue::catalog::Catalogue;
-use ir_core::catalogue::pattern::{Pattern, PatternWithCount};
+use ir_core::catalogue::pattern::{Pattern, PatternCountRecord, PatternWithCount};
 use ir_core::catalogue::pattern_meta::PatternMeta;
+use ir_core::catalogue::sample::q_error;
 use ir_core::error::IrError;
 use ir_core::plan::meta::Schema;
 use ir_core::JsonIO;
 use lazy_static::lazy_static;
+use log::warn;
 use runtime::IRJobAssembly;
 
 use crate::{InitializeJobAssembly, QueryExpGraph};
@@ -43,8 +48,16 @@ pub fn read_schema() -> anyhow::Result<Schema> {
     Ok(schema)
 }
 
+/// Read the pattern meta of the schema, whose vertex label counts are collected from the graph of
+/// GRAPH_PATH if it is given
 pub fn read_pattern_meta() -> anyhow::Result<PatternMeta> {
-    Ok(PatternMeta::from(read_schema()?))
+    let pattern_meta = PatternMeta::from(read_schema()?);
+    if std::env::var("GRAPH_PATH").is_ok() {
+        let graph = read_graph()?;
+        Ok(pattern_meta.with_vertex_label_counts(&graph))
+    } else {
+        Ok(pattern_meta)
+    }
 }
 
 pub fn read_catalogue() -> anyhow::Result<Catalogue> {
@@ -110,6 +123,167 @@ pub fn read_pattern_from_path<P: AsRef<Path>>(pattern_path: P) -> anyhow::Result
     Ok(pattern.try_into()?)
 }
 
+/// The estimated count of a pattern read from a pattern file
+#[derive(Debug, Clone)]
+pub struct PatternFileEstimation {
+    pub pattern_file: PathBuf,
+    pub vertices_num: usize,
+    pub edges_num: usize,
+    pub estimate: f64,
+}
+
+/// The `*.json` files in the directory, ordered by file path
+fn read_pattern_files<P: AsRef<Path>>(pattern_dir: P) -> anyhow::Result<Vec<PathBuf>> {
+    let mut pattern_files = vec![];
+    for entry in read_dir(pattern_dir)? {
+        let path = entry?.path();
+        if path.is_file()
+            && path
+                .extension()
+                .map_or(false, |extension| extension == "json")
+        {
+            pattern_files.push(path);
+        }
+    }
+    pattern_files.sort();
+    Ok(pattern_files)
+}
+
+/// Write the count of the pattern as the count command prints it
+///
+/// The count is written as a plain `count,time` line, followed by the q-error if the ground-truth count
+/// is given, or as a JSON line of the count with the size and canonical code of the pattern, the elapsed
+/// time and the q-error, see `PatternCountRecord`
+pub fn write_pattern_count<W: Write>(
+    mut writer: W, pattern: &Pattern, count: f64, elapsed: Duration, true_count: Option<f64>, json: bool,
+) -> io::Result<()> {
+    let q_error = true_count.map(|true_count| q_error(count, true_count));
+    if json {
+        let mut record = PatternCountRecord::new(pattern, count, elapsed);
+        if let Some(q_error) = q_error {
+            record = record.with_q_error(q_error);
+        }
+        record.into_json(&mut writer)?;
+        writeln!(writer)
+    } else {
+        let time = elapsed.as_secs_f64();
+        if let Some(q_error) = q_error {
+            writeln!(writer, "{count},{time},{q_error}")
+        } else {
+            writeln!(writer, "{count},{time}")
+        }
+    }
+}
+
+/// Estimate the patterns of all the `*.json` files in the directory by the catalog, ordered by file path
+///
+/// Files failed to be imported as patterns are logged and skipped instead of aborting the whole batch
+pub fn estimate_pattern_dir<P: AsRef<Path>>(
+    catalog: &Catalogue, pattern_dir: P,
+) -> anyhow::Result<Vec<PatternFileEstimation>> {
+    let mut estimations = vec![];
+    for pattern_file in read_pattern_files(pattern_dir)? {
+        let pattern = match read_pattern_from_path(&pattern_file) {
+            Ok(pattern) => pattern,
+            Err(e) => {
+                warn!("failed to read pattern from {:?}: {}", pattern_file, e);
+                continue;
+            }
+        };
+        let estimate = catalog
+            .estimate_pattern_count(&pattern)
+            .into_inner();
+        estimations.push(PatternFileEstimation {
+            pattern_file,
+            vertices_num: pattern.get_vertices_num(),
+            edges_num: pattern.get_edges_num(),
+            estimate,
+        });
+    }
+    Ok(estimations)
+}
+
+/// Whether a pattern read from a pattern file is covered by the catalog
+#[derive(Debug, Clone)]
+pub struct PatternFileCoverage {
+    pub pattern_file: PathBuf,
+    pub in_catalog: bool,
+    /// The subpatterns required to generate a plan for the pattern that are not in the catalog
+    pub missing_subpatterns: Vec<Pattern>,
+}
+
+impl PatternFileCoverage {
+    /// A pattern is covered if it and all its required subpatterns are in the catalog
+    pub fn is_covered(&self) -> bool {
+        self.in_catalog && self.missing_subpatterns.is_empty()
+    }
+}
+
+/// Check whether the patterns of all the `*.json` files in the directory (i.e., a workload) are covered
+/// by the catalog, ordered by file path
+///
+/// Files failed to be imported as patterns are logged and skipped as in `estimate_pattern_dir`
+pub fn check_pattern_dir_coverage<P: AsRef<Path>>(
+    catalog: &Catalogue, pattern_dir: P,
+) -> anyhow::Result<Vec<PatternFileCoverage>> {
+    let mut coverages = vec![];
+    for pattern_file in read_pattern_files(pattern_dir)? {
+        let pattern = match read_pattern_from_path(&pattern_file) {
+            Ok(pattern) => pattern,
+            Err(e) => {
+                warn!("failed to read pattern from {:?}: {}", pattern_file, e);
+                continue;
+            }
+        };
+        coverages.push(PatternFileCoverage {
+            pattern_file,
+            in_catalog: catalog
+                .get_pattern_index(&pattern.encode_to())
+                .is_some(),
+            missing_subpatterns: catalog.get_missing_subpatterns(&pattern),
+        });
+    }
+    Ok(coverages)
+}
+
+/// The fraction of the covered patterns in the workload, which is 1.0 for an empty workload
+pub fn get_workload_coverage(coverages: &[PatternFileCoverage]) -> f64 {
+    if coverages.is_empty() {
+        1.0
+    } else {
+        let covered_num = coverages
+            .iter()
+            .filter(|coverage| coverage.is_covered())
+            .count();
+        covered_num as f64 / coverages.len() as f64
+    }
+}
+
+/// Export the patterns to `{index}.json` files in the directory, where the indices are consecutive
+///
+/// If `dedup` is set, the patterns isomorphic to an exported one (i.e., with the same canonical code)
+/// are dropped instead of being exported. Return the number of the dropped duplicates.
+pub fn export_pattern_dir<P: AsRef<Path>>(
+    patterns: impl IntoIterator<Item = Pattern>, pattern_dir: P, dedup: bool,
+) -> anyhow::Result<usize> {
+    create_dir_all(&pattern_dir)?;
+    let mut pattern_codes: HashSet<Vec<u8>> = HashSet::new();
+    let mut exported_num = 0;
+    let mut duplicates_num = 0;
+    for pattern in patterns {
+        if dedup && !pattern_codes.insert(pattern.encode_to()) {
+            duplicates_num += 1;
+            continue;
+        }
+        let path = pattern_dir
+            .as_ref()
+            .join(format!("{:0>5}.json", exported_num));
+        PatternWithCount::from(pattern).export(path)?;
+        exported_num += 1;
+    }
+    Ok(duplicates_num)
+}
+
 fn initialize_job_assembly() -> IRJobAssembly {
     let query_exp_graph = QueryExpGraph::new(1);
     query_exp_graph.initialize_job_assembly()
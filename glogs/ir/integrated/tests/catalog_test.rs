@@ -19,7 +19,6 @@ mod common;
 
 #[cfg(test)]
 mod test {
-    use std::collections::HashMap;
     use std::convert::{TryFrom, TryInto};
     use std::env;
     use std::sync::Arc;
@@ -31,8 +30,8 @@ mod test {
     use ir_common::KeyId;
     use ir_core::catalogue::catalog::{Catalogue, PatMatPlanSpace};
     use ir_core::catalogue::pattern::Pattern;
-    use ir_core::catalogue::plan::get_definite_extend_steps;
-    use ir_core::catalogue::sample::{get_src_records, load_sample_graph};
+    use ir_core::catalogue::plan::{get_definite_extend_steps, PlanConfig};
+    use ir_core::catalogue::sample::{get_src_records, load_sample_graph, EstimateConfig};
     use ir_core::catalogue::{PatternDirection, PatternLabelId};
     use ir_core::error::IrResult;
     use ir_core::plan::logical::LogicalPlan;
@@ -49,7 +48,12 @@ mod test {
         println!("start generating plan...");
         let plan_generation_start_time = Instant::now();
         let pb_plan: pb::LogicalPlan = pattern
-            .generate_optimized_match_plan(&mut catalogue.clone(), &get_ldbc_pattern_meta(), is_distributed)
+            .generate_optimized_match_plan(
+                &mut catalogue.clone(),
+                &get_ldbc_pattern_meta(),
+                is_distributed,
+                &PlanConfig::default(),
+            )
             .expect("Failed to generate pattern match plan");
         println!("generating plan time cost is: {:?} ms", plan_generation_start_time.elapsed().as_millis());
         print_pb_logical_plan(&pb_plan);
@@ -125,7 +129,7 @@ mod test {
     fn generate_naive_pattern_match_plan_for_ldbc_pattern_from_pb_case1() {
         let ldbc_pattern = build_ldbc_pattern_from_pb_case1().unwrap();
         let pb_plan = ldbc_pattern
-            .generate_simple_extend_match_plan(&get_ldbc_pattern_meta(), false)
+            .generate_simple_extend_match_plan(&get_ldbc_pattern_meta(), false, &PlanConfig::default())
             .unwrap();
         print_pb_logical_plan(&pb_plan);
         execute_pb_logical_plan(pb_plan);
@@ -160,7 +164,7 @@ mod test {
             let catalog_build_start_time = Instant::now();
             let mut catalog =
                 Catalogue::build_from_pattern(&ldbc_pattern, PatMatPlanSpace::ExtendWithIntersection);
-            catalog.estimate_graph(sample_graph, 1.0, HashMap::new(), None, 8);
+            catalog.estimate_graph(sample_graph, &EstimateConfig::default().with_thread_num(8));
             println!("building catalog time cost is: {:?} s", catalog_build_start_time.elapsed().as_secs());
             println!("start executing query...");
             let query_execution_start_time = Instant::now();
@@ -178,7 +182,7 @@ mod test {
     fn generate_naive_pattern_match_plan_for_ldbc_pattern_from_pb_case2() {
         let ldbc_pattern = build_ldbc_pattern_from_pb_case2().unwrap();
         let pb_plan = ldbc_pattern
-            .generate_simple_extend_match_plan(&get_ldbc_pattern_meta(), false)
+            .generate_simple_extend_match_plan(&get_ldbc_pattern_meta(), false, &PlanConfig::default())
             .unwrap();
         print_pb_logical_plan(&pb_plan);
         execute_pb_logical_plan(pb_plan);
@@ -207,7 +211,7 @@ mod test {
     fn generate_naive_pattern_match_plan_for_ldbc_pattern_from_pb_case3() {
         let ldbc_pattern = build_ldbc_pattern_from_pb_case3().unwrap();
         let mut pb_plan = ldbc_pattern
-            .generate_simple_extend_match_plan(&get_ldbc_pattern_meta(), false)
+            .generate_simple_extend_match_plan(&get_ldbc_pattern_meta(), false, &PlanConfig::default())
             .unwrap();
         pb_plan.roots = vec![1];
         print_pb_logical_plan(&pb_plan);
@@ -237,7 +241,7 @@ mod test {
     fn generate_naive_pattern_match_plan_for_ldbc_pattern_from_pb_case4() {
         let ldbc_pattern = build_ldbc_pattern_from_pb_case4().unwrap();
         let pb_plan = ldbc_pattern
-            .generate_simple_extend_match_plan(&get_ldbc_pattern_meta(), false)
+            .generate_simple_extend_match_plan(&get_ldbc_pattern_meta(), false, &PlanConfig::default())
             .unwrap();
         print_pb_logical_plan(&pb_plan);
         execute_pb_logical_plan(pb_plan);
@@ -266,7 +270,7 @@ mod test {
     fn generate_naive_pattern_match_plan_for_ldbc_pattern_from_pb_case5() {
         let ldbc_pattern = build_ldbc_pattern_from_pb_case5().unwrap();
         let pb_plan = ldbc_pattern
-            .generate_simple_extend_match_plan(&get_ldbc_pattern_meta(), false)
+            .generate_simple_extend_match_plan(&get_ldbc_pattern_meta(), false, &PlanConfig::default())
             .unwrap();
         print_pb_logical_plan(&pb_plan);
         execute_pb_logical_plan(pb_plan);
@@ -295,7 +299,7 @@ mod test {
     fn generate_naive_pattern_match_plan_for_ldbc_pattern_from_pb_case6() {
         let ldbc_pattern = build_ldbc_pattern_from_pb_case6().unwrap();
         let pb_plan = ldbc_pattern
-            .generate_simple_extend_match_plan(&get_ldbc_pattern_meta(), false)
+            .generate_simple_extend_match_plan(&get_ldbc_pattern_meta(), false, &PlanConfig::default())
             .unwrap();
         print_pb_logical_plan(&pb_plan);
         execute_pb_logical_plan(pb_plan);
@@ -324,7 +328,7 @@ mod test {
     fn generate_naive_pattern_match_plan_for_ldbc_bi3() {
         let ldbc_pattern = build_ldbc_bi3().unwrap();
         let pb_plan = ldbc_pattern
-            .generate_simple_extend_match_plan(&get_ldbc_pattern_meta(), false)
+            .generate_simple_extend_match_plan(&get_ldbc_pattern_meta(), false, &PlanConfig::default())
             .unwrap();
         print_pb_logical_plan(&pb_plan);
         execute_pb_logical_plan(pb_plan);
@@ -353,7 +357,7 @@ mod test {
     fn generate_naive_pattern_match_plan_for_ldbc_bi4_subtask_1() {
         let ldbc_pattern = build_ldbc_bi4_subtask_1().unwrap();
         let pb_plan = ldbc_pattern
-            .generate_simple_extend_match_plan(&get_ldbc_pattern_meta(), false)
+            .generate_simple_extend_match_plan(&get_ldbc_pattern_meta(), false, &PlanConfig::default())
             .unwrap();
         print_pb_logical_plan(&pb_plan);
         execute_pb_logical_plan(pb_plan);
@@ -382,7 +386,7 @@ mod test {
     fn generate_naive_pattern_match_plan_for_ldbc_bi4_subtask_2() {
         let ldbc_pattern = build_ldbc_bi4_subtask_2().unwrap();
         let pb_plan = ldbc_pattern
-            .generate_simple_extend_match_plan(&get_ldbc_pattern_meta(), false)
+            .generate_simple_extend_match_plan(&get_ldbc_pattern_meta(), false, &PlanConfig::default())
             .unwrap();
         print_pb_logical_plan(&pb_plan);
         execute_pb_logical_plan(pb_plan);
@@ -411,7 +415,7 @@ mod test {
     fn generate_naive_pattern_match_plan_for_ldbc_bi11() {
         let ldbc_pattern = build_ldbc_bi11().unwrap();
         let pb_plan = ldbc_pattern
-            .generate_simple_extend_match_plan(&get_ldbc_pattern_meta(), false)
+            .generate_simple_extend_match_plan(&get_ldbc_pattern_meta(), false, &PlanConfig::default())
             .unwrap();
 
         print_pb_logical_plan(&pb_plan);
@@ -0,0 +1,87 @@
+//
+//! Copyright 2022 Alibaba Group Holding Limited.
+//!
+//! Licensed under the Apache License, Version 2.0 (the "License");
+//! you may not use this file except in compliance with the License.
+//! You may obtain a copy of the License at
+//!
+//! http://www.apache.org/licenses/LICENSE-2.0
+//!
+//! Unless required by applicable law or agreed to in writing, software
+//! distributed under the License is distributed on an "AS IS" BASIS,
+//! WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//! See the License for the specific language governing permissions and
+//! limitations under the License.
+//!
+//!
+
+#[cfg(test)]
+mod test {
+    use std::convert::{TryFrom, TryInto};
+    use std::time::Duration;
+
+    use ir_core::catalogue::pattern::{Pattern, PatternCountRecord, PatternEdge, PatternVertex};
+    use ir_core::JsonIO;
+    use runtime_integration::write_pattern_count;
+
+    fn build_knows_path() -> Pattern {
+        let vertices = [0, 1, 2].map(|vertex_id| PatternVertex::new(vertex_id, 0));
+        Pattern::try_from(vec![
+            PatternEdge::new(0, 0, vertices[0], vertices[1]),
+            PatternEdge::new(1, 0, vertices[1], vertices[2]),
+        ])
+        .unwrap()
+    }
+
+    #[test]
+    fn test_write_pattern_count_json() {
+        let knows_path = build_knows_path();
+        let elapsed = Duration::from_millis(5);
+        let mut plain_output = vec![];
+        write_pattern_count(&mut plain_output, &knows_path, 42.0, elapsed, Some(21.0), false).unwrap();
+        let plain_output = String::from_utf8(plain_output).unwrap();
+        let plain_fields: Vec<&str> = plain_output.trim_end().split(',').collect();
+        assert_eq!(plain_fields, vec!["42", "0.005", "2"]);
+
+        let mut json_output = vec![];
+        write_pattern_count(&mut json_output, &knows_path, 42.0, elapsed, Some(21.0), true).unwrap();
+        // a single JSON line is written
+        assert_eq!(
+            json_output
+                .iter()
+                .filter(|&&byte| byte == b'\n')
+                .count(),
+            1
+        );
+        let json: serde_json::Value = serde_json::from_slice(&json_output).unwrap();
+        assert_eq!(json["count"].as_f64(), plain_fields[0].parse::<f64>().ok());
+        assert_eq!(json["elapsed_ms"].as_f64(), Some(5.0));
+        assert_eq!(json["q_error"].as_f64(), plain_fields[2].parse::<f64>().ok());
+        assert_eq!(json["vertices"].as_u64(), Some(3));
+        assert_eq!(json["edges"].as_u64(), Some(2));
+        assert!(json["pattern_code"].is_string());
+
+        let record = PatternCountRecord::from_json(json_output.as_slice()).unwrap();
+        assert_eq!(
+            record.get_pattern_code(),
+            PatternCountRecord::new(&knows_path, 42.0, elapsed).get_pattern_code()
+        );
+        let reloaded_path: Pattern = record.into_pattern().try_into().unwrap();
+        assert_eq!(reloaded_path.encode_to(), knows_path.encode_to());
+    }
+
+    #[test]
+    fn test_write_pattern_count_without_true_count() {
+        let knows_path = build_knows_path();
+        let mut plain_output = vec![];
+        write_pattern_count(&mut plain_output, &knows_path, 42.0, Duration::from_millis(5), None, false)
+            .unwrap();
+        assert_eq!(String::from_utf8(plain_output).unwrap(), "42,0.005\n");
+
+        let mut json_output = vec![];
+        write_pattern_count(&mut json_output, &knows_path, 42.0, Duration::from_millis(5), None, true)
+            .unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&json_output).unwrap();
+        assert!(json.get("q_error").is_none());
+    }
+}
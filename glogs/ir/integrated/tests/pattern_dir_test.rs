@@ -0,0 +1,120 @@
+//
+//! Copyright 2022 Alibaba Group Holding Limited.
+//!
+//! Licensed under the Apache License, Version 2.0 (the "License");
+//! you may not use this file except in compliance with the License.
+//! You may obtain a copy of the License at
+//!
+//! http://www.apache.org/licenses/LICENSE-2.0
+//!
+//! Unless required by applicable law or agreed to in writing, software
+//! distributed under the License is distributed on an "AS IS" BASIS,
+//! WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//! See the License for the specific language governing permissions and
+//! limitations under the License.
+//!
+//!
+
+#[cfg(test)]
+mod test {
+    use std::convert::TryFrom;
+    use std::fs;
+
+    use ir_core::catalogue::catalog::{Catalogue, PatMatPlanSpace};
+    use ir_core::catalogue::pattern::{Pattern, PatternEdge, PatternVertex, PatternWithCount};
+    use runtime_integration::{
+        check_pattern_dir_coverage, estimate_pattern_dir, export_pattern_dir, get_workload_coverage,
+    };
+
+    fn build_single_edge_pattern(edge_label: i32, end_vertex_label: i32) -> Pattern {
+        let start_vertex = PatternVertex::new(0, 0);
+        let end_vertex = PatternVertex::new(1, end_vertex_label);
+        Pattern::try_from(vec![PatternEdge::new(0, edge_label, start_vertex, end_vertex)]).unwrap()
+    }
+
+    #[test]
+    fn test_estimate_pattern_dir() {
+        let knows = build_single_edge_pattern(0, 0);
+        let created = build_single_edge_pattern(1, 1);
+        let mut catalog = Catalogue::build_from_pattern(&knows, PatMatPlanSpace::ExtendWithIntersection);
+        catalog.update_catalog_by_pattern(&created);
+        assert!(catalog.set_pattern_count(&knows, 10.0.into()));
+        assert!(catalog.set_pattern_count(&created, 20.0.into()));
+        let pattern_dir = std::env::temp_dir().join("test_estimate_pattern_dir");
+        let _ = fs::remove_dir_all(&pattern_dir);
+        fs::create_dir_all(&pattern_dir).unwrap();
+        PatternWithCount::from(knows)
+            .export(pattern_dir.join("knows.json"))
+            .unwrap();
+        PatternWithCount::from(created)
+            .export(pattern_dir.join("created.json"))
+            .unwrap();
+        fs::write(pattern_dir.join("broken.json"), "not a pattern").unwrap();
+        fs::write(pattern_dir.join("notes.txt"), "not a json file").unwrap();
+        let estimations = estimate_pattern_dir(&catalog, &pattern_dir).unwrap();
+        // the broken file is skipped, and the others are ordered by their paths
+        assert_eq!(estimations.len(), 2);
+        assert_eq!(estimations[0].pattern_file, pattern_dir.join("created.json"));
+        assert_eq!(estimations[0].estimate, 20.0);
+        assert_eq!(estimations[1].pattern_file, pattern_dir.join("knows.json"));
+        assert_eq!(estimations[1].estimate, 10.0);
+        for estimation in estimations {
+            assert_eq!(estimation.vertices_num, 2);
+            assert_eq!(estimation.edges_num, 1);
+        }
+        fs::remove_dir_all(&pattern_dir).unwrap();
+    }
+
+    fn build_knows_triangle(vertex_ids: [usize; 3]) -> Pattern {
+        let vertices = vertex_ids.map(|vertex_id| PatternVertex::new(vertex_id, 0));
+        Pattern::try_from(vec![
+            PatternEdge::new(0, 0, vertices[0], vertices[1]),
+            PatternEdge::new(1, 0, vertices[1], vertices[2]),
+            PatternEdge::new(2, 0, vertices[0], vertices[2]),
+        ])
+        .unwrap()
+    }
+
+    #[test]
+    fn test_export_pattern_dir_with_dedup() {
+        let triangles = vec![build_knows_triangle([0, 1, 2]), build_knows_triangle([5, 3, 4])];
+        let pattern_dir = std::env::temp_dir().join("test_export_pattern_dir_with_dedup");
+        let _ = fs::remove_dir_all(&pattern_dir);
+        assert_eq!(export_pattern_dir(triangles.clone(), &pattern_dir, true).unwrap(), 1);
+        assert_eq!(fs::read_dir(&pattern_dir).unwrap().count(), 1);
+        fs::remove_dir_all(&pattern_dir).unwrap();
+        assert_eq!(export_pattern_dir(triangles, &pattern_dir, false).unwrap(), 0);
+        assert_eq!(fs::read_dir(&pattern_dir).unwrap().count(), 2);
+        fs::remove_dir_all(&pattern_dir).unwrap();
+    }
+
+    #[test]
+    fn test_check_pattern_dir_coverage() {
+        let knows = build_single_edge_pattern(0, 0);
+        let created = build_single_edge_pattern(1, 1);
+        let catalog = Catalogue::build_from_pattern(&knows, PatMatPlanSpace::ExtendWithIntersection);
+        let pattern_dir = std::env::temp_dir().join("test_check_pattern_dir_coverage");
+        let _ = fs::remove_dir_all(&pattern_dir);
+        fs::create_dir_all(&pattern_dir).unwrap();
+        PatternWithCount::from(knows)
+            .export(pattern_dir.join("knows.json"))
+            .unwrap();
+        PatternWithCount::from(created.clone())
+            .export(pattern_dir.join("created.json"))
+            .unwrap();
+        let coverages = check_pattern_dir_coverage(&catalog, &pattern_dir).unwrap();
+        assert_eq!(coverages.len(), 2);
+        // the created pattern and its vertex labeled 1 are missing
+        assert_eq!(coverages[0].pattern_file, pattern_dir.join("created.json"));
+        assert!(!coverages[0].in_catalog);
+        assert_eq!(coverages[0].missing_subpatterns.len(), 2);
+        assert!(coverages[0]
+            .missing_subpatterns
+            .iter()
+            .any(|subpattern| subpattern.encode_to() == created.encode_to()));
+        assert_eq!(coverages[1].pattern_file, pattern_dir.join("knows.json"));
+        assert!(coverages[1].is_covered());
+        assert_eq!(get_workload_coverage(&coverages), 0.5);
+        fs::remove_dir_all(&pattern_dir).unwrap();
+    }
+}
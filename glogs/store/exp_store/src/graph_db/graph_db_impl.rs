@@ -411,6 +411,11 @@ where
         &self.topology
     }
 
+    /// Get the graph schema. Unlike `get_schema()`, the returned schema can be shared across threads.
+    pub fn get_graph_schema(&self) -> Arc<LDBCGraphSchema> {
+        self.graph_schema.clone()
+    }
+
     pub fn into_topology(self) -> T {
         self.topology
     }
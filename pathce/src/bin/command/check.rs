@@ -4,12 +4,16 @@ use std::path::PathBuf;
 
 use clap::Args;
 use pathce::pattern::{GeneralPattern, GraphPattern};
+use pathce::schema::Schema;
 
 #[derive(Debug, Args)]
 pub struct CheckArgs {
     /// Specify the pattern path.
     #[arg(short, long, value_name = "PATTERN_FILE")]
     pattern: PathBuf,
+    /// Specify the schema path, against which the pattern is validated.
+    #[arg(short, long, value_name = "SCHEMA_FILE")]
+    schema: Option<PathBuf>,
 }
 
 pub fn check(args: CheckArgs) {
@@ -23,6 +27,13 @@ pub fn check(args: CheckArgs) {
         return;
     }
     let pattern = pattern.unwrap();
+    if let Some(schema) = args.schema {
+        let schema = Schema::import_json(schema).unwrap();
+        if let Err(err) = pattern.validate_against_schema(&schema) {
+            eprintln!("{err}");
+            std::process::exit(1);
+        }
+    }
     if pattern.vertices().len() == 1 && pattern.edges().is_empty() {
         println!("vertex");
         return;
@@ -6,7 +6,7 @@ use std::sync::Arc;
 use clap::Args;
 use pathce::counter::{PathCounter, StarCounter};
 use pathce::graph::LabeledGraph;
-use pathce::pattern::{GeneralPattern, PathPattern};
+use pathce::pattern::{GeneralPattern, PathPattern, RawPattern};
 use rayon::ThreadPoolBuilder;
 
 #[derive(Debug, Args)]
@@ -15,8 +15,17 @@ pub struct CountArgs {
     #[arg(short, long, value_name = "GRAPH_FILE")]
     graph: PathBuf,
     /// Specify the pattern path.
-    #[arg(short, long)]
-    pattern: PathBuf,
+    #[arg(
+        short,
+        long,
+        required_unless_present = "edge_list",
+        conflicts_with = "edge_list"
+    )]
+    pattern: Option<PathBuf>,
+    /// Specify the pattern path in lines of
+    /// `edge_id,edge_label,src_id,src_label,dst_id,dst_label`.
+    #[arg(long, value_name = "EDGE_LIST_FILE")]
+    edge_list: Option<PathBuf>,
     /// Specify the number of threads.
     #[arg(short, long, default_value = "4")]
     threads: usize,
@@ -36,19 +45,37 @@ pub fn count(args: CountArgs) {
     let count = match args.shape.as_str() {
         "path" => {
             let counter = PathCounter::new(graph, pool);
-            let path: PathPattern = {
-                let file = File::open(args.pattern).unwrap();
-                let reader = BufReader::new(file);
-                serde_json::from_reader(reader).unwrap()
+            let path: PathPattern = match (args.pattern, args.edge_list) {
+                (Some(pattern), _) => {
+                    let file = File::open(pattern).unwrap();
+                    let reader = BufReader::new(file);
+                    serde_json::from_reader(reader).unwrap()
+                }
+                (None, Some(edge_list)) => {
+                    RawPattern::from_edge_list(File::open(edge_list).unwrap())
+                        .unwrap()
+                        .to_path()
+                        .unwrap()
+                }
+                (None, None) => unreachable!(),
             };
             counter.count(&path)
         }
         "star" => {
             let counter = StarCounter::new(graph, pool);
-            let pattern: GeneralPattern = {
-                let file = File::open(args.pattern).unwrap();
-                let reader = BufReader::new(file);
-                serde_json::from_reader(reader).unwrap()
+            let pattern: GeneralPattern = match (args.pattern, args.edge_list) {
+                (Some(pattern), _) => {
+                    let file = File::open(pattern).unwrap();
+                    let reader = BufReader::new(file);
+                    serde_json::from_reader(reader).unwrap()
+                }
+                (None, Some(edge_list)) => {
+                    RawPattern::from_edge_list(File::open(edge_list).unwrap())
+                        .unwrap()
+                        .to_general()
+                        .unwrap()
+                }
+                (None, None) => unreachable!(),
             };
             counter.count(&pattern)
         }
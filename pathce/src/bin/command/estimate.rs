@@ -1,12 +1,12 @@
 use std::fs::File;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::time::Instant;
 
 use clap::Args;
 use pathce::catalog::DuckCatalog;
 use pathce::common::TagId;
 use pathce::estimate::CardinalityEstimator;
-use pathce::pattern::RawPattern;
+use pathce::pattern::{GeneralPattern, RawPattern};
 use log::info;
 
 #[derive(Debug, Args)]
@@ -17,6 +17,10 @@ pub struct EstimateArgs {
     /// Specify the pattern path.
     #[arg(short, long, value_name = "PATTERN_FILE")]
     patterns: Vec<PathBuf>,
+    /// Specify the pattern path in lines of
+    /// `edge_id,edge_label,src_id,src_label,dst_id,dst_label`.
+    #[arg(long, value_name = "EDGE_LIST_FILE")]
+    edge_list: Vec<PathBuf>,
     /// Specify the maximum path length.
     #[arg(long, default_value = "3")]
     max_path_length: usize,
@@ -43,6 +47,16 @@ pub struct EstimateArgs {
     order: Option<String>,
 }
 
+fn load_pattern(path: &Path, is_edge_list: bool) -> GeneralPattern {
+    let file = File::open(path).unwrap();
+    let pattern = if is_edge_list {
+        RawPattern::from_edge_list(file).unwrap()
+    } else {
+        serde_json::from_reader(file).unwrap()
+    };
+    pattern.to_general().unwrap()
+}
+
 fn parse_order(order: String) -> Vec<TagId> {
     order
         .trim()
@@ -63,26 +77,28 @@ pub fn estimate(args: EstimateArgs) {
         args.disable_prune,
         args.disable_cyclic,
     );
+    let mut patterns = args
+        .patterns
+        .iter()
+        .map(|path| (path, false))
+        .chain(args.edge_list.iter().map(|path| (path, true)));
     if let Some(order) = args.order {
         assert_eq!(
-            args.patterns.len(),
+            args.patterns.len() + args.edge_list.len(),
             1,
             "only one pattern can be estimated using predefined order"
         );
-        let pattern = args.patterns.first().unwrap();
-        let pattern: RawPattern = serde_json::from_reader(File::open(pattern).unwrap()).unwrap();
-        let pattern = pattern.to_general().unwrap();
+        let (pattern, is_edge_list) = patterns.next().unwrap();
+        let pattern = load_pattern(pattern, is_edge_list);
         let order = parse_order(order);
         let start = Instant::now();
         let card = estimator.estimate_with_order(&pattern, order).unwrap();
         let time = start.elapsed().as_secs_f64();
         println!("{},{}", card, time);
     } else {
-        for pattern in args.patterns {
+        for (pattern, is_edge_list) in patterns {
             info!("estimate {:?}", pattern);
-            let pattern: RawPattern =
-                serde_json::from_reader(File::open(pattern).unwrap()).unwrap();
-            let pattern = pattern.to_general().unwrap();
+            let pattern = load_pattern(pattern, is_edge_list);
             let start = Instant::now();
             let card = estimator.estimate(&pattern).unwrap();
             let time = start.elapsed().as_secs_f64();
@@ -3,7 +3,8 @@ use std::path::PathBuf;
 
 use clap::Args;
 use pathce::estimate::decompose::heuristic::find_candidate_paths;
-use pathce::pattern::{GraphPattern, RawPattern};
+use pathce::pattern::{GraphPattern, PatternStats, RawPattern};
+use serde::Serialize;
 
 #[derive(Debug, Args)]
 pub struct PatternStatisticsArgs {
@@ -12,6 +13,13 @@ pub struct PatternStatisticsArgs {
     pattern: PathBuf,
 }
 
+#[derive(Debug, Serialize)]
+struct PatternStatisticsOutput {
+    longest_path: usize,
+    #[serde(flatten)]
+    stats: PatternStats,
+}
+
 pub fn pattern_statistics(args: PatternStatisticsArgs) {
     let pattern: RawPattern = serde_json::from_reader(File::open(args.pattern).unwrap()).unwrap();
     let pattern = pattern.to_general().unwrap();
@@ -26,5 +34,9 @@ pub fn pattern_statistics(args: PatternStatisticsArgs) {
             .max()
             .unwrap_or_default()
     };
-    println!("{longest_path}")
+    let output = PatternStatisticsOutput {
+        longest_path,
+        stats: PatternStats::new(&pattern),
+    };
+    println!("{}", serde_json::to_string(&output).unwrap())
 }
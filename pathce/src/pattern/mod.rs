@@ -2,6 +2,7 @@ mod canonical;
 mod general;
 mod path;
 mod raw;
+mod stats;
 
 use std::cmp::Ordering;
 use std::fmt::Debug;
@@ -13,8 +14,11 @@ use itertools::Itertools;
 pub use path::*;
 pub use raw::*;
 use serde::{Deserialize, Serialize};
+pub use stats::*;
 
 use crate::common::{EdgeDirection, LabelId, TagId};
+use crate::error::{GCardError, GCardResult};
+use crate::schema::Schema;
 
 const EDGE_ENCODING_LENGTH: usize = 14;
 
@@ -185,6 +189,33 @@ pub trait GraphPattern: Debug + Clone {
         }
         deg1_count == 2 && deg1_count + deg2_count == self.vertices().len()
     }
+
+    /// Check that the pattern is realizable under the schema, i.e., every vertex label exists and
+    /// every edge connects the vertex labels of its edge label. The first offending vertex or edge
+    /// is reported in the error.
+    fn validate_against_schema(&self, schema: &Schema) -> GCardResult<()> {
+        for v in self.vertices() {
+            if schema.get_vertex(v.label_id).is_none() {
+                let err = format!("vertex {v:?} has an unknown label");
+                return Err(GCardError::Pattern(err));
+            }
+        }
+        for e in self.edges() {
+            let src_label_id = self.get_vertex(e.src).unwrap().label_id;
+            let dst_label_id = self.get_vertex(e.dst).unwrap().label_id;
+            match schema.get_edge(e.label_id) {
+                Some(schema_edge)
+                    if schema_edge.from == src_label_id && schema_edge.to == dst_label_id => {}
+                _ => {
+                    let err = format!(
+                        "edge {e:?} from label {src_label_id} to label {dst_label_id} is not in the schema"
+                    );
+                    return Err(GCardError::Pattern(err));
+                }
+            }
+        }
+        Ok(())
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
@@ -288,6 +319,8 @@ impl PatternAdjacency {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::common::EdgeCardinality;
+    use crate::schema::SchemaUnchecked;
 
     #[test]
     fn test_encode_edge() {
@@ -343,4 +376,46 @@ mod tests {
         .unwrap();
         assert!(!p3.is_path());
     }
+
+    #[test]
+    fn test_validate_against_schema() {
+        let schema: Schema = SchemaUnchecked::default()
+            .add_vertex_label("person".into(), 0)
+            .add_vertex_label("city".into(), 1)
+            .add_edge_label("knows".into(), 0)
+            .add_edge_label("isLocatedIn".into(), 1)
+            .add_vertex((0, false))
+            .add_vertex((1, true))
+            .add_edge((0, 0, 0, EdgeCardinality::ManyToMany))
+            .add_edge((0, 1, 1, EdgeCardinality::ManyToOne))
+            .try_into()
+            .unwrap();
+        let triangle = RawPattern::with_vertices_edges(
+            [(0, 0), (1, 0), (2, 0)],
+            [(0, 0, 1, 0), (1, 1, 2, 0), (2, 0, 2, 0)],
+        )
+        .to_general()
+        .unwrap();
+        assert!(triangle.validate_against_schema(&schema).is_ok());
+
+        let bogus_label_triangle = RawPattern::with_vertices_edges(
+            [(0, 0), (1, 0), (2, 0)],
+            [(0, 0, 1, 0), (1, 1, 2, 7), (2, 0, 2, 0)],
+        )
+        .to_general()
+        .unwrap();
+        assert!(bogus_label_triangle
+            .validate_against_schema(&schema)
+            .is_err());
+
+        let bogus_endpoints_triangle = RawPattern::with_vertices_edges(
+            [(0, 0), (1, 0), (2, 0)],
+            [(0, 0, 1, 0), (1, 1, 2, 1), (2, 0, 2, 0)],
+        )
+        .to_general()
+        .unwrap();
+        assert!(bogus_endpoints_triangle
+            .validate_against_schema(&schema)
+            .is_err());
+    }
 }
@@ -1,4 +1,5 @@
 use std::collections::{HashMap, HashSet, VecDeque};
+use std::io::{BufRead, BufReader, Read};
 use std::iter;
 
 use serde::{Deserialize, Serialize};
@@ -32,6 +33,51 @@ impl RawPattern {
         Self { vertices, edges }
     }
 
+    /// Read a pattern from lines of `edge_id,edge_label,src_id,src_label,dst_id,dst_label`, where
+    /// fields are separated by either comma or tab, and blank lines are skipped.
+    pub fn from_edge_list<R: Read>(reader: R) -> GCardResult<Self> {
+        let mut pattern = Self::new();
+        let mut vertex_labels = HashMap::new();
+        for (line_no, line) in BufReader::new(reader).lines().enumerate() {
+            let line = line?;
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+            let invalid_line =
+                || GCardError::Pattern(format!("invalid edge list line {}: {}", line_no + 1, line));
+            let fields = line
+                .split(|c| c == ',' || c == '\t')
+                .map(|field| field.trim().parse::<u32>().map_err(|_| invalid_line()))
+                .collect::<GCardResult<Vec<_>>>()?;
+            if fields.len() != 6 {
+                return Err(invalid_line());
+            }
+            let (edge_id, edge_label, src_id, src_label, dst_id, dst_label) = (
+                fields[0], fields[1], fields[2], fields[3], fields[4], fields[5],
+            );
+            let to_tag_id = |id: u32| TagId::try_from(id).map_err(|_| invalid_line());
+            let (edge_id, src_id, dst_id) =
+                (to_tag_id(edge_id)?, to_tag_id(src_id)?, to_tag_id(dst_id)?);
+            for (tag_id, label_id) in [(src_id, src_label), (dst_id, dst_label)] {
+                match vertex_labels.get(&tag_id) {
+                    Some(&existing_label_id) if existing_label_id != label_id => {
+                        return Err(GCardError::Pattern(format!(
+                            "vertex {tag_id} has conflicting labels {existing_label_id} and {label_id}"
+                        )));
+                    }
+                    Some(_) => {}
+                    None => {
+                        vertex_labels.insert(tag_id, label_id);
+                        pattern.push_back_vertex((tag_id, label_id));
+                    }
+                }
+            }
+            pattern.push_back_edge((edge_id, src_id, dst_id, edge_label));
+        }
+        Ok(pattern)
+    }
+
     pub fn max_vertex_tag_id(&self) -> Option<TagId> {
         self.vertices.iter().map(|v| v.tag_id).max()
     }
@@ -314,6 +360,19 @@ mod tests {
         assert!(RawPattern::new().to_general().is_ok());
     }
 
+    #[test]
+    fn test_from_edge_list() {
+        let edge_list = "0,10,0,1,1,2\n\n1\t11\t1\t2\t2\t3\n2,12,2,3,3,4\n";
+        let pattern = RawPattern::from_edge_list(edge_list.as_bytes()).unwrap();
+        assert_eq!(pattern.get_edges_num(), 3);
+        assert_eq!(pattern.get_vertices_num(), 4);
+        let path = pattern.to_path().unwrap();
+        assert_eq!(path.edges().len(), 3);
+        assert!(RawPattern::from_edge_list("0,10,0,1,1".as_bytes()).is_err());
+        assert!(RawPattern::from_edge_list("0,10,0,1,x,2".as_bytes()).is_err());
+        assert!(RawPattern::from_edge_list("0,10,0,1,1,2\n1,11,1,3,2,3".as_bytes()).is_err());
+    }
+
     #[test]
     fn test_build_path() {
         assert!(RawPattern::new()
@@ -0,0 +1,136 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use serde::Serialize;
+
+use super::GraphPattern;
+use crate::common::TagId;
+
+/// Structural statistics of a pattern.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PatternStats {
+    pub num_vertices: usize,
+    pub num_edges: usize,
+    /// The longest shortest path between any two connected vertices.
+    pub diameter: usize,
+    pub min_degree: usize,
+    pub max_degree: usize,
+    pub mean_degree: f64,
+    pub num_components: usize,
+    /// The number of independent cycles, i.e., `|E| - |V| + #components`.
+    pub num_cycles: usize,
+}
+
+impl PatternStats {
+    pub fn new<P: GraphPattern>(pattern: &P) -> Self {
+        let num_vertices = pattern.vertices().len();
+        let num_edges = pattern.edges().len();
+        let degrees = pattern
+            .vertices()
+            .iter()
+            .map(|v| pattern.get_vertex_degree(v.tag_id).unwrap())
+            .collect::<Vec<_>>();
+        let min_degree = degrees.iter().copied().min().unwrap_or_default();
+        let max_degree = degrees.iter().copied().max().unwrap_or_default();
+        let mean_degree = if num_vertices == 0 {
+            0.0
+        } else {
+            degrees.iter().sum::<usize>() as f64 / num_vertices as f64
+        };
+        let num_components = count_components(pattern);
+        Self {
+            num_vertices,
+            num_edges,
+            diameter: compute_diameter(pattern),
+            min_degree,
+            max_degree,
+            mean_degree,
+            num_components,
+            num_cycles: num_edges + num_components - num_vertices,
+        }
+    }
+}
+
+fn shortest_distances<P: GraphPattern>(pattern: &P, start: TagId) -> HashMap<TagId, usize> {
+    let mut distances = HashMap::from([(start, 0)]);
+    let mut queue = VecDeque::from([start]);
+    while let Some(tag_id) = queue.pop_front() {
+        let distance = distances[&tag_id];
+        for adj in pattern.adjacencies(tag_id).unwrap() {
+            distances.entry(adj.neighbor_tag_id).or_insert_with(|| {
+                queue.push_back(adj.neighbor_tag_id);
+                distance + 1
+            });
+        }
+    }
+    distances
+}
+
+fn compute_diameter<P: GraphPattern>(pattern: &P) -> usize {
+    pattern
+        .vertices()
+        .iter()
+        .flat_map(|v| shortest_distances(pattern, v.tag_id).into_values())
+        .max()
+        .unwrap_or_default()
+}
+
+fn count_components<P: GraphPattern>(pattern: &P) -> usize {
+    let mut visited = HashSet::new();
+    let mut num_components = 0;
+    for v in pattern.vertices() {
+        if visited.insert(v.tag_id) {
+            num_components += 1;
+            let mut stack = vec![v.tag_id];
+            while let Some(tag_id) = stack.pop() {
+                stack.extend(
+                    pattern
+                        .adjacencies(tag_id)
+                        .unwrap()
+                        .map(|adj| adj.neighbor_tag_id)
+                        .filter(|tag_id| visited.insert(*tag_id)),
+                );
+            }
+        }
+    }
+    num_components
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pattern::RawPattern;
+
+    #[test]
+    fn test_path_stats() {
+        let p = RawPattern::with_vertices_edges(
+            [(0, 1), (1, 1), (2, 1), (3, 1)],
+            [(0, 0, 1, 0), (1, 1, 2, 0), (2, 3, 2, 0)],
+        )
+        .to_general()
+        .unwrap();
+        let stats = PatternStats::new(&p);
+        assert_eq!(stats.diameter, 3);
+        assert_eq!(stats.num_cycles, 0);
+        assert_eq!(stats.num_components, 1);
+        assert_eq!(stats.min_degree, 1);
+        assert_eq!(stats.max_degree, 2);
+        assert_eq!(stats.mean_degree, 1.5);
+    }
+
+    #[test]
+    fn test_triangle_stats() {
+        let p = RawPattern::with_vertices_edges(
+            [(0, 1), (1, 1), (2, 1)],
+            [(0, 0, 1, 0), (1, 1, 2, 0), (2, 0, 2, 0)],
+        )
+        .to_general()
+        .unwrap();
+        let stats = PatternStats::new(&p);
+        assert_eq!(stats.diameter, 1);
+        assert_eq!(stats.num_cycles, 1);
+        assert_eq!(stats.num_components, 1);
+        assert_eq!(stats.min_degree, 2);
+        assert_eq!(stats.max_degree, 2);
+        assert_eq!(stats.mean_degree, 2.0);
+    }
+}